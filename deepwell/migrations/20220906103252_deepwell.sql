@@ -25,8 +25,14 @@ CREATE TABLE "user" (
     slug TEXT NOT NULL,
     name_changes_left SMALLINT NOT NULL,  -- Default set in runtime configuration.
     last_renamed_at TIMESTAMP WITH TIME ZONE,
+    name_changes_refilled_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    last_login_at TIMESTAMP WITH TIME ZONE,
+    failed_login_count INTEGER NOT NULL DEFAULT 0,  -- Since the last successful login
+    last_failed_login_at TIMESTAMP WITH TIME ZONE,
     email TEXT NOT NULL,
     email_verified_at TIMESTAMP WITH TIME ZONE,
+    pending_email TEXT,  -- Set during an email change, until it is confirmed
+    pending_email_token TEXT,
     password TEXT NOT NULL,
     multi_factor_secret TEXT,
     multi_factor_recovery_codes TEXT[],
@@ -46,6 +52,9 @@ CREATE TABLE "user" (
     -- Both MFA columns should either be set or unset
     CHECK ((multi_factor_secret IS NULL) = (multi_factor_recovery_codes IS NULL)),
 
+    -- Both pending email columns should either be set or unset
+    CHECK ((pending_email IS NULL) = (pending_email_token IS NULL)),
+
     -- Strings should either be NULL or non-empty (and within limits)
     CHECK (real_name IS NULL OR (length(real_name) > 0 AND length(real_name) < 300)),
     CHECK (gender IS NULL OR (length(gender) > 0 AND length(gender) < 100)),
@@ -54,9 +63,15 @@ CREATE TABLE "user" (
     CHECK (user_page IS NULL OR (length(user_page) > 0 AND length(user_page) < 100)),
 
     CHECK (name_changes_left >= 0),                                 -- Value cannot be negative
+    CHECK (failed_login_count >= 0),                                -- Value cannot be negative
     CHECK (avatar_s3_hash IS NULL OR length(avatar_s3_hash) = 64)   -- SHA-512 hash size (if set)
 );
 
+-- Regular accounts cannot share an email address, regardless of case.
+-- Bot accounts are allowed to have duplicate emails.
+CREATE UNIQUE INDEX user_email_unique ON "user" (lower(email))
+    WHERE user_type = 'regular' AND deleted_at IS NULL;
+
 CREATE TABLE user_bot_owner (
     bot_user_id BIGINT REFERENCES "user"(user_id),
     human_user_id BIGINT REFERENCES "user"(user_id),
@@ -71,6 +86,11 @@ CREATE TABLE user_bot_owner (
 -- Site
 --
 
+CREATE TYPE vote_type AS ENUM (
+    'ups-downs',
+    'five-star'
+);
+
 CREATE TABLE site (
     site_id BIGSERIAL PRIMARY KEY,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
@@ -83,6 +103,15 @@ CREATE TABLE site (
     description TEXT NOT NULL,
     locale TEXT NOT NULL,
     default_page TEXT NOT NULL DEFAULT 'start',
+    default_category TEXT NOT NULL DEFAULT '_default',
+    slug_allow_categories BOOLEAN NOT NULL DEFAULT true,
+    slug_pattern TEXT,
+    require_revision_comments BOOLEAN NOT NULL DEFAULT false,
+    allow_anonymous_edits BOOLEAN NOT NULL DEFAULT false,
+    vote_type vote_type NOT NULL DEFAULT 'ups-downs',
+    retain_compiled_html BOOLEAN NOT NULL DEFAULT false,
+    disabled_blocks TEXT[] NOT NULL DEFAULT '{}',  -- Block names the renderer skips
+    robots_txt TEXT,  -- NULL means use the default
     custom_domain TEXT,  -- Dependency cycle, add foreign key constraint after
 
     UNIQUE (slug, deleted_at)
@@ -92,10 +121,45 @@ CREATE TABLE site_domain (
     domain TEXT PRIMARY KEY,
     site_id BIGINT NOT NULL REFERENCES site(site_id),
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    verification_token TEXT,  -- NULL if ownership verification was never started
+    verified_at TIMESTAMP WITH TIME ZONE,
 
     CHECK (length(domain) > 0)
 );
 
+CREATE TYPE feature_flag AS ENUM (
+    'page-drafts',
+    'page-votes'
+);
+
+-- Only stores flags which have been explicitly set for a site,
+-- otherwise the flag's default is used.
+CREATE TABLE site_feature_flag (
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    flag feature_flag NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+
+    PRIMARY KEY (site_id, flag)
+);
+
+CREATE TYPE site_role AS ENUM (
+    'member',
+    'moderator',
+    'admin'
+);
+
+-- Only stores users with a role on a site, any other user is not a member.
+CREATE TABLE site_member (
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    role site_role NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+
+    PRIMARY KEY (site_id, user_id)
+);
+
 ALTER TABLE site
     ADD CONSTRAINT site_custom_domain_fk
     FOREIGN KEY (custom_domain) REFERENCES site_domain(domain);
@@ -129,9 +193,14 @@ CREATE TABLE session (
     user_id BIGINT NOT NULL REFERENCES "user"(user_id),
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     expires_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (expires_at > created_at),
+    last_active_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     ip_address TEXT NOT NULL,  -- TODO change to INET
+    country TEXT,  -- Approximate location of the IP address, if known
+    region TEXT,
     user_agent TEXT NOT NULL,
-    restricted BOOLEAN NOT NULL
+    restricted BOOLEAN NOT NULL,
+
+    CHECK (region IS NULL OR country IS NOT NULL)
 );
 
 --
@@ -148,18 +217,31 @@ CREATE TABLE page_category (
     UNIQUE (site_id, slug)
 );
 
+CREATE TYPE page_deletion_reason AS ENUM (
+    'spam',
+    'copyright',
+    'author-request',
+    'other'
+);
+
 CREATE TABLE page (
     page_id BIGSERIAL PRIMARY KEY,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     updated_at TIMESTAMP WITH TIME ZONE,
     deleted_at TIMESTAMP WITH TIME ZONE,
+    deletion_reason page_deletion_reason,  -- Only set on deleted pages, if a reason was given
     from_wikidot BOOLEAN NOT NULL DEFAULT false,
     site_id BIGINT NOT NULL REFERENCES site(site_id),
     page_category_id BIGINT NOT NULL REFERENCES page_category(category_id),
     slug TEXT NOT NULL,
+    locale TEXT, -- if NULL, uses the site's locale
     discussion_thread_id BIGINT, -- TODO: add REFERENCES to forum threads
+    keywords TEXT[] NOT NULL DEFAULT '{}', -- Extracted from the latest revision
+    publish_at TIMESTAMP WITH TIME ZONE, -- If set, hidden from non-editors until this time
+    expires_at TIMESTAMP WITH TIME ZONE, -- If set, hidden from non-admins after this time
 
-    UNIQUE (site_id, slug, deleted_at)
+    UNIQUE (site_id, slug, deleted_at),
+    CHECK (deletion_reason IS NULL OR deleted_at IS NOT NULL)
 );
 
 --
@@ -191,9 +273,10 @@ CREATE TYPE page_revision_change AS ENUM (
 -- but since we can't we'll just verify the hash length.
 CREATE TABLE text (
     hash BYTEA PRIMARY KEY,
+    hash_algorithm TEXT NOT NULL DEFAULT 'k12',
     contents TEXT COMPRESSION pglz NOT NULL,
 
-    CHECK (length(hash) = 16)  -- KangarooTwelve hash size, 128 bits
+    CHECK (length(hash) = 16)  -- KangarooTwelve hash size, 128 bits (others truncated)
 );
 
 -- Main revision table
@@ -208,7 +291,7 @@ CREATE TABLE page_revision (
     from_wikidot BOOLEAN NOT NULL DEFAULT false,
     changes TEXT[] NOT NULL, -- List of changes in this revision
     wikitext_hash BYTEA NOT NULL REFERENCES text(hash),
-    compiled_hash BYTEA NOT NULL REFERENCES text(hash),
+    compiled_hash BYTEA REFERENCES text(hash),  -- NULL if not retained, see site.retain_compiled_html
     compiled_at TIMESTAMP WITH TIME ZONE NOT NULL,
     compiled_generator TEXT NOT NULL,
     comments TEXT NOT NULL,
@@ -254,6 +337,21 @@ CREATE TABLE page_revision (
     UNIQUE (page_id, site_id, revision_number)
 );
 
+-- Unpublished edits, which do not affect the page until published
+CREATE TABLE page_draft (
+    draft_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    base_revision_id BIGINT NOT NULL REFERENCES page_revision(revision_id),
+    comments TEXT NOT NULL,
+    wikitext_hash BYTEA NOT NULL REFERENCES text(hash),
+    title TEXT NOT NULL,
+    alt_title TEXT,
+    tags TEXT[] NOT NULL DEFAULT '{}'
+);
+
 --
 -- Page metadata
 --
@@ -286,7 +384,7 @@ CREATE TABLE page_lock (
     expires_at TIMESTAMP WITH TIME ZONE,
     from_wikidot BOOLEAN NOT NULL DEFAULT false,
     -- Text enum describing what kind of lock (e.g. authors only, staff only)
-    -- Currently the only value is 'wikidot' (meaning mods+ only)
+    -- Either 'wikidot' (meaning mods+ only) or 'edit' (held while a user edits the page)
     lock_type TEXT NOT NULL,
     page_id BIGINT NOT NULL REFERENCES page(page_id),
     user_id BIGINT NOT NULL REFERENCES "user"(user_id),
@@ -295,6 +393,10 @@ CREATE TABLE page_lock (
     UNIQUE (page_id, deleted_at)
 );
 
+-- Only one active lock per page. Expired locks must be deleted before a new one is taken.
+CREATE UNIQUE INDEX page_lock_active ON page_lock (page_id)
+    WHERE deleted_at IS NULL;
+
 --
 -- Page backlinks tracking
 --
@@ -474,3 +576,29 @@ CREATE TABLE filter (
 
     UNIQUE (site_id, regex, deleted_at)
 );
+
+--
+-- Audit log
+--
+
+CREATE TYPE audit_action AS ENUM (
+    'filter-create',
+    'filter-update',
+    'filter-delete',
+    'filter-restore',
+    'site-delete',
+    'user-delete'
+);
+
+-- Records privileged actions, so they can be reviewed later.
+--
+-- The target_id column refers to the object being acted upon,
+-- which table it is in depends on the action.
+CREATE TABLE audit_log (
+    audit_log_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    actor_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    action audit_action NOT NULL,
+    target_id BIGINT NOT NULL,
+    detail JSON NOT NULL
+);
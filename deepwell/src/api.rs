@@ -29,20 +29,26 @@
 use crate::config::{Config, Secrets};
 use crate::database;
 use crate::endpoints::{
-    auth::*, category::*, file::*, file_revision::*, link::*, locale::*, misc::*,
-    page::*, page_revision::*, parent::*, site::*, text::*, user::*, user_bot::*,
-    view::*, vote::*,
+    audit::*, auth::*, category::*, file::*, file_revision::*, filter::*, link::*,
+    locale::*, misc::*, page::*, page_lock::*, page_revision::*, parent::*, site::*,
+    text::*, user::*, user_bot::*, view::*, vote::*,
 };
 use crate::locales::Localizations;
 use crate::services::blob::spawn_magic_thread;
 use crate::services::job::JobRunner;
+use crate::services::page::RecentChangeFeed;
+use crate::services::page_lock::PageEvents;
+use crate::services::session::{GeoResolver, NullGeoResolver};
 use crate::utils::error_response;
+use crate::web::{CompressionPolicy, CorsPolicy, SecurityHeaders};
 use anyhow::Result;
+use async_lock::Semaphore;
 use s3::bucket::Bucket;
 use sea_orm::DatabaseConnection;
 use std::sync::Arc;
 use std::time::Duration;
-use tide::StatusCode;
+use tide::{sse, StatusCode};
+use tide_websockets::WebSocket;
 
 pub type ApiServerState = Arc<ServerState>;
 pub type ApiServer = tide::Server<ApiServerState>;
@@ -55,6 +61,10 @@ pub struct ServerState {
     pub database: DatabaseConnection,
     pub localizations: Localizations,
     pub s3_bucket: Bucket,
+    pub geo_resolver: Box<dyn GeoResolver>,
+    pub render_semaphore: Semaphore,
+    pub page_events: PageEvents,
+    pub recent_changes: RecentChangeFeed,
 }
 
 pub async fn build_server_state(
@@ -87,15 +97,61 @@ pub async fn build_server_state(
         bucket
     };
 
+    // Limit simultaneous renders
+    let render_semaphore = Semaphore::new(config.render_concurrency);
+
     // Return server state
     Ok(Arc::new(ServerState {
         config,
         database,
         localizations,
         s3_bucket,
+        geo_resolver: Box::new(NullGeoResolver),
+        render_semaphore,
+        page_events: PageEvents::default(),
+        recent_changes: RecentChangeFeed::default(),
     }))
 }
 
+/// Builds server state around an existing database connection, for tests.
+///
+/// This is meant for a mock database, so that services can be run
+/// without PostgreSQL. The example configuration is used, and S3 is
+/// pointed at a local endpoint which is never contacted.
+#[cfg(test)]
+pub async fn build_test_server_state(database: DatabaseConnection) -> ApiServerState {
+    use s3::{creds::Credentials, region::Region};
+
+    let config = Config::example();
+
+    let localizations = Localizations::open(&config.localization_path)
+        .await
+        .expect("Unable to load localizations");
+
+    let s3_bucket = Bucket::new(
+        "deepwell-test",
+        Region::Custom {
+            region: str!("local"),
+            endpoint: str!("http://localhost:9000"),
+        },
+        Credentials::anonymous().expect("Unable to create S3 credentials"),
+    )
+    .expect("Unable to create S3 bucket");
+
+    let render_semaphore = Semaphore::new(config.render_concurrency);
+
+    Arc::new(ServerState {
+        config,
+        database,
+        localizations,
+        s3_bucket,
+        geo_resolver: Box::new(NullGeoResolver),
+        render_semaphore,
+        page_events: PageEvents::default(),
+        recent_changes: RecentChangeFeed::default(),
+    })
+}
+
 pub fn build_server(state: ApiServerState) -> ApiServer {
     macro_rules! new {
         () => {
@@ -115,6 +171,9 @@ pub fn build_server(state: ApiServerState) -> ApiServer {
     // Prefix is present to avoid ambiguity about what this
     // API is meant to be and the fact that it's not to be publicly-facing.
     let mut app = new!();
+    app.with(CorsPolicy::from_config(&state.config));
+    app.with(SecurityHeaders::from_config(&state.config));
+    app.with(CompressionPolicy::from_config(&state.config));
     app.at("/api/trusted").nest(build_routes(new!()));
     app
 }
@@ -126,6 +185,8 @@ fn build_routes(mut app: ApiServer) -> ApiServer {
     app.at("/version/full").get(full_version);
     app.at("/hostname").get(hostname);
     app.at("/config").get(config_dump);
+    app.at("/schema").get(schema_retrieve);
+    app.at("/openapi.json").get(openapi_retrieve);
     app.at("/normalize/:input").all(normalize_method);
     app.at("/teapot")
         .all(|_| async { error_response(StatusCode::ImATeapot, "🫖") });
@@ -136,6 +197,8 @@ fn build_routes(mut app: ApiServer) -> ApiServer {
 
     // Routes for web server
     app.at("/view/page").put(view_page);
+    app.at("/view/pages").put(view_pages);
+    app.at("/view/resolve").put(view_resolve);
 
     // Authentication
     app.at("/auth/login").post(auth_login);
@@ -145,6 +208,7 @@ fn build_routes(mut app: ApiServer) -> ApiServer {
 
     app.at("/auth/session/get").get(auth_session_retrieve);
     app.at("/auth/session/renew").post(auth_session_renew);
+    app.at("/auth/session/refresh").post(auth_session_refresh);
     app.at("/auth/session/others")
         .delete(auth_session_invalidate_others);
     app.at("/auth/session/others/get")
@@ -156,19 +220,38 @@ fn build_routes(mut app: ApiServer) -> ApiServer {
         .post(auth_mfa_reset_recovery);
 
     // Site
-    app.at("/site").put(site_put);
+    app.at("/site").put(site_put).delete(site_delete);
     app.at("/site/get").put(site_retrieve);
     app.at("/site/create").post(site_create);
+    app.at("/site/export").put(site_export);
+    app.at("/site/import").post(site_import);
+    app.at("/site/provenance").put(site_provenance_retrieve);
+    app.at("/site/sitemap").put(site_sitemap_retrieve);
+    app.at("/site/robots").put(site_robots_retrieve);
+    app.at("/site/feed").put(site_feed_retrieve);
+    app.at("/site/rerender").post(site_rerender);
     app.at("/site/domain/custom")
         .post(site_custom_domain_post)
         .delete(site_custom_domain_delete);
     app.at("/site/domain/custom/get")
         .get(site_custom_domain_retrieve);
+    app.at("/site/domain/custom/all")
+        .get(site_custom_domain_list_all);
+    app.at("/site/domain/custom/verification")
+        .post(site_custom_domain_verification_post);
+    app.at("/site/domain/custom/verify")
+        .put(site_custom_domain_verify);
     app.at("/site/fromDomain/:domain").get(site_get_from_domain);
+    app.at("/site/features").put(site_feature_flag_put);
+    app.at("/site/features/:site_id")
+        .get(site_feature_flags_retrieve);
+    app.at("/site/member").put(site_member_role_put);
+    app.at("/site/member/get").put(site_member_role_retrieve);
 
     // Category
     app.at("/category").get(category_get);
     app.at("/category/site").get(category_all_get);
+    app.at("/category/rebuild").put(category_rebuild);
 
     // Page
     app.at("/page").post(page_edit).delete(page_delete);
@@ -178,15 +261,40 @@ fn build_routes(mut app: ApiServer) -> ApiServer {
     app.at("/page/move").post(page_move);
     app.at("/page/rerender").put(page_rerender);
     app.at("/page/restore").post(page_restore);
+    app.at("/page/restore/slug").post(page_restore_by_slug);
+    app.at("/page/recent").put(page_recent_changes);
+    app.at("/page/recent/stream/:site_id")
+        .get(sse::endpoint(page_recent_changes_stream));
+    app.at("/page/deleted").put(page_deleted_retrieve);
+    app.at("/page/schedule").put(page_schedule);
+    app.at("/page/expiry").put(page_set_expiry);
+    app.at("/page/duplicates/:site_id")
+        .get(page_duplicates_retrieve);
+    app.at("/page/historical").put(page_historical_retrieve);
+    app.at("/page/keyword").put(page_keyword_retrieve);
+    app.at("/page/excerpt").put(page_excerpt_retrieve);
+
+    // Page locks
+    app.at("/page/lock")
+        .post(page_lock_acquire)
+        .delete(page_lock_release);
+    app.at("/page/lock/get").put(page_lock_retrieve);
+    app.at("/page/events/:page_id")
+        .get(WebSocket::new(page_events));
 
     // Page revisions
     app.at("/page/revision").put(page_revision_put);
     app.at("/page/revision/get").get(page_revision_retrieve);
     app.at("/page/revision/count").get(page_revision_count);
     app.at("/page/revision/rollback").post(page_rollback);
+    app.at("/page/revision/view").put(page_revision_view);
     app.at("/page/revision/range")
         .put(page_revision_range_retrieve);
 
+    // Page drafts
+    app.at("/page/draft").post(page_draft_create);
+    app.at("/page/draft/publish").post(page_draft_publish);
+
     // Page links
     app.at("/page/links/from").put(page_links_from_retrieve);
     app.at("/page/links/to").put(page_links_to_retrieve);
@@ -200,6 +308,7 @@ fn build_routes(mut app: ApiServer) -> ApiServer {
     app.at("/page/parent/get").put(parent_retrieve);
     app.at("/page/parent/:relationship_type")
         .put(parent_relationships_retrieve);
+    app.at("/page/children").put(parent_children_retrieve);
 
     // Files
     app.at("/file").post(file_edit).delete(file_delete);
@@ -217,15 +326,20 @@ fn build_routes(mut app: ApiServer) -> ApiServer {
 
     // Text
     app.at("/text").put(text_put);
+    app.at("/text/verify").put(text_verify_all);
+    app.at("/text/verify/:hash").get(text_verify);
     app.at("/text/:hash").get(text_get);
 
     // User
     app.at("/user").put(user_put).delete(user_delete);
     app.at("/user/get").put(user_retrieve);
+    app.at("/user/list").put(user_list_retrieve);
     app.at("/user/avatar").put(user_avatar_put);
     app.at("/user/create").post(user_create);
     app.at("/user/import").post(user_import);
     app.at("/user/addNameChange").post(user_add_name_change);
+    app.at("/user/email/change").post(user_email_change_post);
+    app.at("/user/email/confirm").post(user_email_confirm_post);
 
     // User bot information
     app.at("/user/bot/get").put(user_bot_retrieve);
@@ -241,5 +355,21 @@ fn build_routes(mut app: ApiServer) -> ApiServer {
     app.at("/vote/list").put(vote_list_retrieve);
     app.at("/vote/count").put(vote_count_retrieve);
 
+    // Filters
+    app.at("/filter")
+        .post(filter_create)
+        .put(filter_put)
+        .delete(filter_delete);
+    app.at("/filter/restore").post(filter_restore);
+    app.at("/filter/list").put(filter_list_retrieve);
+    app.at("/filter/count").put(filter_count_retrieve);
+    app.at("/filter/many").delete(filter_delete_many);
+    app.at("/filter/restore/many").post(filter_restore_many);
+    app.at("/filter/export").put(filter_export);
+    app.at("/filter/import").post(filter_import);
+
+    // Audit log
+    app.at("/audit").put(audit_log_retrieve);
+
     app
 }
@@ -19,13 +19,17 @@
  */
 
 use super::Config;
-use anyhow::Result;
+use crate::hash::TextHashAlgorithm;
+use anyhow::{ensure, Result};
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::fs::File;
 use std::io::Read;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::Duration as StdDuration;
+use tide::http::headers::{HeaderName, HeaderValue};
 use tide::log::LevelFilter;
 use time::Duration as TimeDuration;
 
@@ -47,8 +51,12 @@ pub struct ConfigFile {
     security: Security,
     locale: Locale,
     domain: Domain,
+    cors: Cors,
     job: Job,
     ftml: Ftml,
+    text: Text,
+    page: Page,
+    filter: Filter,
     user: User,
 }
 
@@ -64,6 +72,7 @@ struct Logger {
 struct Server {
     address: SocketAddr,
     pid_file: Option<PathBuf>,
+    compression_threshold_bytes: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -72,14 +81,45 @@ struct Database {
     run_migrations: bool,
     run_seeder: bool,
     seeder_path: PathBuf,
+    read_retry_attempts: u32,
+    read_retry_delay_ms: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 struct Security {
     authentication_fail_delay_ms: u64,
+    max_failed_logins: u32,
+    failed_login_lockout_minutes: u64,
     session: Session,
     mfa: Mfa,
+
+    #[serde(default)]
+    headers: SecurityHeaders,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+struct SecurityHeaders {
+    #[serde(default)]
+    all: BTreeMap<String, String>,
+
+    #[serde(default)]
+    routes: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl SecurityHeaders {
+    /// Ensures every configured header can be sent in a response,
+    /// so that mistakes are reported on startup rather than on each request.
+    fn validate(&self) -> Result<()> {
+        let route_headers = self.routes.values().flat_map(|headers| headers.iter());
+
+        for (name, value) in self.all.iter().chain(route_headers) {
+            check_header(name, value)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -89,6 +129,9 @@ struct Session {
     token_length: usize,
     duration_session_minutes: u64,
     duration_login_minutes: u64,
+
+    #[serde(default)]
+    single_session: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -105,6 +148,7 @@ struct Mfa {
 struct Job {
     delay_ms: u64,
     prune_session_secs: u64,
+    rerender_concurrency: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -117,13 +161,47 @@ struct Locale {
 #[serde(rename_all = "kebab-case")]
 struct Domain {
     main: String,
-    files: String,
+
+    #[serde(default)]
+    main_aliases: Vec<String>,
+
+    files: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Cors {
+    allowed_origins: Vec<String>,
+    allowed_methods: Vec<String>,
+    allowed_headers: Vec<String>,
+    max_age_secs: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 struct Ftml {
     render_timeout_ms: u64,
+    render_concurrency: usize,
+    max_html_size: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Text {
+    hash_algorithm: TextHashAlgorithm,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Page {
+    max_revision_comment_length: usize,
+    slug_corrections: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Filter {
+    match_timeout_ms: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -139,7 +217,8 @@ impl ConfigFile {
         let mut file = File::open(path)?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
-        let config = toml::from_str(&contents)?;
+        let config: ConfigFile = toml::from_str(&contents)?;
+        config.security.headers.validate()?;
         Ok((config, contents))
     }
 
@@ -166,22 +245,28 @@ impl ConfigFile {
                 Server {
                     address,
                     mut pid_file,
+                    compression_threshold_bytes: compression_threshold,
                 },
             database:
                 Database {
                     run_migrations,
                     run_seeder,
                     seeder_path,
+                    read_retry_attempts,
+                    read_retry_delay_ms,
                 },
             security:
                 Security {
                     authentication_fail_delay_ms,
+                    max_failed_logins,
+                    failed_login_lockout_minutes,
                     session:
                         Session {
                             token_prefix,
                             token_length,
                             duration_session_minutes,
                             duration_login_minutes,
+                            single_session,
                         },
                     mfa:
                         Mfa {
@@ -190,21 +275,53 @@ impl ConfigFile {
                             time_step,
                             time_skew,
                         },
+                    headers:
+                        SecurityHeaders {
+                            all: security_headers,
+                            routes: security_route_headers,
+                        },
                 },
             domain:
                 Domain {
-                    main: mut main_domain,
+                    main: main_domain,
+                    main_aliases: main_domain_aliases,
                     files: mut files_domain,
                 },
+            cors:
+                Cors {
+                    allowed_origins: cors_allowed_origins,
+                    allowed_methods: cors_allowed_methods,
+                    allowed_headers: cors_allowed_headers,
+                    max_age_secs: cors_max_age_secs,
+                },
             job:
                 Job {
                     delay_ms: job_delay_ms,
                     prune_session_secs,
+                    rerender_concurrency: job_rerender_concurrency,
                 },
             locale: Locale {
                 path: localization_path,
             },
-            ftml: Ftml { render_timeout_ms },
+            ftml:
+                Ftml {
+                    render_timeout_ms,
+                    render_concurrency,
+                    max_html_size,
+                },
+            text:
+                Text {
+                    hash_algorithm: text_hash_algorithm,
+                },
+            page:
+                Page {
+                    max_revision_comment_length,
+                    slug_corrections,
+                },
+            filter:
+                Filter {
+                    match_timeout_ms: filter_match_timeout_ms,
+                },
             user:
                 User {
                     default_name_changes,
@@ -215,8 +332,24 @@ impl ConfigFile {
 
         // Prefix domains with '.' so we can do easy subdomain checks
         // and concatenations.
-        prefix_domain(&mut main_domain);
-        prefix_domain(&mut files_domain);
+        let main_domains = Some(main_domain)
+            .into_iter()
+            .chain(main_domain_aliases)
+            .map(|mut domain| {
+                prefix_domain(&mut domain);
+                domain
+            })
+            .collect();
+
+        // Treats an empty files domain as absent, same as pid_file below.
+        // If there is no files domain, then user files are not served.
+        if let Some(ref mut domain) = files_domain {
+            if domain.is_empty() {
+                files_domain = None;
+            } else {
+                prefix_domain(domain);
+            }
+        }
 
         // Treats empty strings (which aren't valid paths anyways)
         // as null for the purpose of pid_file.
@@ -232,15 +365,27 @@ impl ConfigFile {
             logger_level,
             address,
             pid_file,
-            main_domain,
+            compression_threshold,
+            main_domains,
             files_domain,
+            cors_allowed_origins,
+            cors_allowed_methods,
+            cors_allowed_headers,
+            cors_max_age: StdDuration::from_secs(cors_max_age_secs),
             run_migrations,
             run_seeder,
             seeder_path,
+            read_retry_attempts,
+            read_retry_delay: StdDuration::from_millis(read_retry_delay_ms),
             localization_path,
             authentication_fail_delay: StdDuration::from_millis(
                 authentication_fail_delay_ms,
             ),
+            max_failed_logins,
+            failed_login_lockout: time_duration!(
+                from_secs,
+                failed_login_lockout_minutes * 60,
+            ),
             session_token_prefix: token_prefix,
             session_token_length: token_length,
             normal_session_duration: time_duration!(
@@ -251,13 +396,30 @@ impl ConfigFile {
                 from_secs,
                 duration_login_minutes * 60,
             ),
+            single_session,
             recovery_code_count,
             recovery_code_length,
             totp_time_step: time_step,
             totp_time_skew: time_skew,
+            security_headers: security_headers.into_iter().collect(),
+            security_route_headers: security_route_headers
+                .into_iter()
+                .map(|(prefix, headers)| (prefix, headers.into_iter().collect()))
+                .collect(),
             job_delay: StdDuration::from_millis(job_delay_ms),
             job_prune_session_period: StdDuration::from_secs(prune_session_secs),
+            job_rerender_concurrency: job_rerender_concurrency.max(1),
             render_timeout: StdDuration::from_millis(render_timeout_ms),
+            render_concurrency: render_concurrency.max(1),
+            max_html_size: if max_html_size == 0 {
+                None
+            } else {
+                Some(max_html_size)
+            },
+            text_hash_algorithm,
+            max_revision_comment_length,
+            slug_corrections: slug_corrections.into_iter().collect(),
+            filter_match_timeout: StdDuration::from_millis(filter_match_timeout_ms),
             default_name_changes: i16::from(default_name_changes),
             max_name_changes: i16::from(max_name_changes),
             refill_name_change: StdDuration::from_secs(
@@ -272,3 +434,136 @@ fn prefix_domain(domain: &mut String) {
         domain.insert(0, '.');
     }
 }
+
+#[cfg(test)]
+const EXAMPLE_CONFIG: &str = include_str!("../../config.example.toml");
+
+/// Parses configuration for tests, usually a modified `EXAMPLE_CONFIG`.
+#[cfg(test)]
+fn load_test_config(toml: &str) -> Config {
+    let config_file: ConfigFile = toml::from_str(toml).expect("Invalid config");
+    config_file.into_config(str!(toml))
+}
+
+#[test]
+fn files_domain() {
+    let config = load_test_config(EXAMPLE_CONFIG);
+    assert_eq!(config.main_domains, [".wikijump.com"]);
+    assert_eq!(config.files_domain.as_deref(), Some(".wjfiles.com"));
+
+    let toml = EXAMPLE_CONFIG.replace("files = \"wjfiles.com\"", "files = \"\"");
+    let config = load_test_config(&toml);
+    assert_eq!(config.main_domains, [".wikijump.com"]);
+    assert_eq!(config.files_domain, None);
+
+    let toml = EXAMPLE_CONFIG.replace("files = \"wjfiles.com\"", "");
+    let config = load_test_config(&toml);
+    assert_eq!(config.main_domains, [".wikijump.com"]);
+    assert_eq!(config.files_domain, None);
+}
+
+#[test]
+fn main_domains() {
+    let toml = EXAMPLE_CONFIG.replace(
+        "main-aliases = []",
+        "main-aliases = [\"wikijump.org\", \".wikijump.net\"]",
+    );
+
+    let config = load_test_config(&toml);
+    assert_eq!(
+        config.main_domains,
+        [".wikijump.com", ".wikijump.org", ".wikijump.net"],
+    );
+}
+
+/// Checks that a header name is a valid token, and its value has no control characters.
+///
+/// An empty value is permitted, since route overrides use it to remove a header.
+fn check_header(name: &str, value: &str) -> Result<()> {
+    fn is_token_char(c: char) -> bool {
+        c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+    }
+
+    ensure!(
+        !name.is_empty()
+            && name.chars().all(is_token_char)
+            && HeaderName::from_str(name).is_ok(),
+        "Invalid security header name: {name:?}",
+    );
+
+    ensure!(
+        !value.chars().any(|c| c.is_ascii_control() && c != '\t')
+            && HeaderValue::from_str(value).is_ok(),
+        "Invalid value for security header {name}: {value:?}",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn security_headers() {
+    let config = load_test_config(EXAMPLE_CONFIG);
+
+    assert!(
+        config.security_headers.iter().any(|(name, value)| {
+            name == "content-security-policy" && value.contains("default-src")
+        }),
+        "Example config has no Content-Security-Policy header",
+    );
+}
+
+#[test]
+fn invalid_security_headers() {
+    fn headers(toml: &str) -> SecurityHeaders {
+        toml::from_str(toml).expect("Invalid security headers section")
+    }
+
+    let valid = headers(
+        r#"
+        [all]
+        content-security-policy = "default-src 'none'"
+
+        [routes]
+        "/embed" = { content-security-policy = "frame-ancestors *", x-frame-options = "" }
+        "#,
+    );
+    assert!(valid.validate().is_ok());
+
+    for (name, value) in [
+        ("", "DENY"),
+        ("x frame options", "DENY"),
+        ("x-frame-options:", "DENY"),
+        ("x-frame-öptions", "DENY"),
+        ("x-frame-options", "DENY\r\nset-cookie: a=b"),
+        ("x-frame-options", "DÉNY"),
+    ] {
+        let invalid = SecurityHeaders {
+            all: BTreeMap::from([(str!(name), str!(value))]),
+            routes: BTreeMap::new(),
+        };
+        assert!(
+            invalid.validate().is_err(),
+            "Invalid header {name:?}: {value:?} was accepted",
+        );
+
+        // Also in route overrides
+        let invalid = SecurityHeaders {
+            all: BTreeMap::new(),
+            routes: BTreeMap::from([(str!("/embed"), invalid.all)]),
+        };
+        assert!(
+            invalid.validate().is_err(),
+            "Invalid route header {name:?}: {value:?} was accepted",
+        );
+    }
+}
+
+#[test]
+fn max_html_size() {
+    let config = load_test_config(EXAMPLE_CONFIG);
+    assert_eq!(config.max_html_size, Some(4194304));
+
+    let toml = EXAMPLE_CONFIG.replace("max-html-size = 4194304", "max-html-size = 0");
+    let config = load_test_config(&toml);
+    assert_eq!(config.max_html_size, None, "Zero size limit not disabled");
+}
@@ -19,6 +19,7 @@
  */
 
 use super::file::ConfigFile;
+use crate::hash::TextHashAlgorithm;
 use anyhow::Result;
 use std::env;
 use std::net::SocketAddr;
@@ -49,11 +50,32 @@ pub struct Config {
     /// The PID file (if any) to write to on boot.
     pub pid_file: Option<PathBuf>,
 
-    /// The main domain to serve sites from.
-    pub main_domain: String,
+    /// Response bodies larger than this many bytes are compressed,
+    /// if the client supports it.
+    pub compression_threshold: usize,
+
+    /// The main domains to serve sites from.
+    ///
+    /// There is always at least one, and the first is the preferred domain,
+    /// which is used when generating canonical domains for sites.
+    pub main_domains: Vec<String>,
 
     /// The files domain to serve user-generated content from.
-    pub files_domain: String,
+    /// If `None`, then user files are not served by this instance.
+    pub files_domain: Option<String>,
+
+    /// Which origins may make cross-origin requests.
+    /// If empty, then all cross-origin requests are denied.
+    pub cors_allowed_origins: Vec<String>,
+
+    /// Which HTTP methods may be used in cross-origin requests.
+    pub cors_allowed_methods: Vec<String>,
+
+    /// Which request headers may be sent in cross-origin requests.
+    pub cors_allowed_headers: Vec<String>,
+
+    /// How long browsers may cache the result of a preflight request.
+    pub cors_max_age: StdDuration,
 
     /// Whether to run migrations on startup.
     pub run_migrations: bool,
@@ -65,12 +87,25 @@ pub struct Config {
     /// The location where all the seeder files are kept.
     pub seeder_path: PathBuf,
 
+    /// How many times to attempt a read operation on transient database errors.
+    pub read_retry_attempts: u32,
+
+    /// How long to wait before the first retry of a read operation.
+    /// This is doubled after each subsequent failure.
+    pub read_retry_delay: StdDuration,
+
     /// The location where all Fluent translation files are kept.
     pub localization_path: PathBuf,
 
     /// The duration to sleep after failed authentication attempts.
     pub authentication_fail_delay: StdDuration,
 
+    /// How many failed logins in a row before a user is locked out.
+    pub max_failed_logins: u32,
+
+    /// How long a user is locked out for after their last failed login.
+    pub failed_login_lockout: TimeDuration,
+
     /// Fixed prefix for all session tokens.
     pub session_token_prefix: String,
 
@@ -83,6 +118,9 @@ pub struct Config {
     /// How long restricted sessions last before expiry.
     pub restricted_session_duration: TimeDuration,
 
+    /// Whether logging in invalidates all of the user's other sessions.
+    pub single_session: bool,
+
     /// The number of recovery codes to have per user.
     pub recovery_code_count: usize,
 
@@ -95,15 +133,45 @@ pub struct Config {
     /// How much leniency should be allowed for TOTP.
     pub totp_time_skew: i64,
 
+    /// Headers added to every response, such as `Content-Security-Policy`.
+    pub security_headers: Vec<(String, String)>,
+
+    /// Header overrides for routes starting with each path prefix.
+    /// An empty value removes that header from the route's responses.
+    pub security_route_headers: Vec<(String, Vec<(String, String)>)>,
+
     /// How long to sleep in between job loops.
     pub job_delay: StdDuration,
 
     /// How often to run the "prune expired sessions" recurring job.
     pub job_prune_session_period: StdDuration,
 
+    /// How many pages to render at once when re-rendering an entire site.
+    pub job_rerender_concurrency: usize,
+
     /// Maximum run time for a render request.
     pub render_timeout: StdDuration,
 
+    /// Maximum number of renders which may run at once.
+    pub render_concurrency: usize,
+
+    /// Maximum size of a page's rendered HTML, in bytes.
+    ///
+    /// If `None`, then there is no limit.
+    pub max_html_size: Option<usize>,
+
+    /// Which algorithm to hash newly-stored text with.
+    pub text_hash_algorithm: TextHashAlgorithm,
+
+    /// Maximum length of page revision comments, in characters.
+    pub max_revision_comment_length: usize,
+
+    /// Replacements to fix typos in page slugs, applied before normalization.
+    pub slug_corrections: Vec<(String, String)>,
+
+    /// Maximum run time for checking a string against filters.
+    pub filter_match_timeout: StdDuration,
+
     /// Default name changes per user.
     pub default_name_changes: i16,
 
@@ -115,6 +183,12 @@ pub struct Config {
 }
 
 impl Config {
+    /// The preferred main domain, used for canonical site domains.
+    #[inline]
+    pub fn main_domain(&self) -> &str {
+        &self.main_domains[0]
+    }
+
     #[inline]
     pub fn load(path: &Path) -> Result<Self> {
         let (config_file, raw_toml) = ConfigFile::load(path)?;
@@ -122,6 +196,12 @@ impl Config {
         Ok(config)
     }
 
+    /// Loads the example configuration, for tests.
+    #[cfg(test)]
+    pub fn example() -> Self {
+        Config::load(Path::new("config.example.toml")).expect("Unable to load config")
+    }
+
     pub fn log(&self) {
         #[inline]
         fn bool_str(value: bool) -> &'static str {
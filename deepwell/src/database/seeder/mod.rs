@@ -38,6 +38,13 @@ use sea_orm::{
 use std::borrow::Cow;
 
 pub async fn seed(state: &ApiServerState) -> Result<()> {
+    // Callers should already check this, but ensure that a disabled
+    // seeder never so much as opens a transaction (e.g. on read replicas).
+    if !state.config.run_seeder {
+        tide::log::warn!("Seeder is disabled in configuration, not running");
+        return Ok(());
+    }
+
     tide::log::info!("Running seeder...");
 
     // Set up context
@@ -185,7 +192,11 @@ pub async fn seed(state: &ApiServerState) -> Result<()> {
                     slug: page.slug,
                     revision_comments: str!(""),
                     user_id: SYSTEM_USER_ID,
+                    locale: None,
+                    publish_at: None,
+                    expires_at: None,
                     bypass_filter: true,
+                    reject_on_parser_error: false,
                 },
             )
             .await?;
@@ -227,6 +238,7 @@ pub async fn seed(state: &ApiServerState) -> Result<()> {
         FilterService::create(
             &ctx,
             site_id,
+            SYSTEM_USER_ID,
             CreateFilter {
                 affects_user: filter.user,
                 affects_email: filter.email,
@@ -313,3 +325,25 @@ async fn run_query(txn: &DatabaseTransaction, sql: String) -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn seeder_disabled() {
+    use crate::api::build_test_server_state;
+    use async_std::task;
+    use sea_orm::MockDatabase;
+    use std::sync::Arc;
+
+    // No query results are mocked, so any query would fail
+    let database = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+    let mut state = task::block_on(build_test_server_state(database));
+    Arc::get_mut(&mut state)
+        .expect("Server state already in use")
+        .config
+        .run_seeder = false;
+
+    task::block_on(seed(&state)).expect("Disabled seeder failed");
+
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = state.database.into_transaction_log();
+    assert!(log.is_empty(), "Disabled seeder used the database: {log:?}");
+}
@@ -0,0 +1,35 @@
+/*
+ * endpoints/audit.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::audit_log::GetAuditLog;
+
+pub async fn audit_log_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: GetAuditLog = body_json_validated(&mut req).await?;
+    tide::log::info!("Getting audit log entries");
+
+    let entries = AuditLogService::list(&ctx, input).await?;
+    let body = Body::from_json(&entries)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
@@ -38,7 +38,7 @@ pub async fn auth_login(mut req: ApiRequest) -> ApiResponse {
         authenticate,
         ip_address,
         user_agent,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     // Don't allow empty passwords.
     //
@@ -55,16 +55,22 @@ pub async fn auth_login(mut req: ApiRequest) -> ApiResponse {
     // If anything went wrong, only allow a generic backend failure
     // to avoid leaking internal state.
     //
-    // The only three possible responses to this method should be:
+    // The only four possible responses to this method should be:
     // * success
     // * invalid authentication
+    // * rate limited, after too many failed logins
     // * server error
     let result = AuthenticationService::auth_password(&ctx, authenticate).await;
     let AuthenticateUserOutput { needs_mfa, user_id } = match result {
         Ok(output) => output,
         Err(error) => {
             let status_code = match error {
-                Error::InvalidAuthentication => StatusCode::Forbidden,
+                Error::InvalidAuthentication => {
+                    // Keep the failed login count
+                    txn.commit().await?;
+                    StatusCode::Forbidden
+                }
+                Error::RateLimited => StatusCode::TooManyRequests,
                 _ => {
                     tide::log::error!(
                         "Unexpected error during user authentication: {error}",
@@ -94,6 +100,10 @@ pub async fn auth_login(mut req: ApiRequest) -> ApiResponse {
     )
     .await?;
 
+    if login_complete {
+        UserService::record_login(&ctx, user_id).await?;
+    }
+
     let body = Body::from_json(&LoginUserOutput {
         session_token,
         needs_mfa,
@@ -124,7 +134,7 @@ pub async fn auth_session_retrieve(mut req: ApiRequest) -> ApiResponse {
 pub async fn auth_session_renew(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
-    let input: RenewSession = req.body_json().await?;
+    let input: RenewSession = body_json_validated(&mut req).await?;
 
     let new_session_token = SessionService::renew(&ctx, input).await?;
 
@@ -134,6 +144,19 @@ pub async fn auth_session_renew(mut req: ApiRequest) -> ApiResponse {
     Ok(response)
 }
 
+pub async fn auth_session_refresh(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let session_token = req.body_string().await?;
+    let session = SessionService::refresh(&ctx, &session_token).await?;
+
+    let body = Body::from_json(&session)?;
+    let response = Response::builder(StatusCode::Ok).body(body).into();
+    txn.commit().await?;
+    Ok(response)
+}
+
 pub async fn auth_session_retrieve_others(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
@@ -141,7 +164,7 @@ pub async fn auth_session_retrieve_others(mut req: ApiRequest) -> ApiResponse {
     let GetOtherSessions {
         user_id,
         session_token,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     // Produce output struct, which extracts the current session and
     // places it in its own location.
@@ -176,7 +199,7 @@ pub async fn auth_session_invalidate_others(mut req: ApiRequest) -> ApiResponse
     let InvalidateOtherSessions {
         session_token,
         user_id,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     let invalidated =
         SessionService::invalidate_others(&ctx, &session_token, user_id).await?;
@@ -207,7 +230,7 @@ pub async fn auth_mfa_verify(mut req: ApiRequest) -> ApiResponse {
         totp_or_code,
         ip_address,
         user_agent,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Verifying user's MFA for login (temporary session token {session_token})"
@@ -222,6 +245,8 @@ pub async fn auth_mfa_verify(mut req: ApiRequest) -> ApiResponse {
     )
     .await?;
 
+    UserService::record_login(&ctx, user.user_id).await?;
+
     let new_session_token = SessionService::renew(
         &ctx,
         RenewSession {
@@ -243,7 +268,7 @@ pub async fn auth_mfa_setup(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let GetUser { user: reference } = req.body_json().await?;
+    let GetUser { user: reference } = body_json_validated(&mut req).await?;
     let user = UserService::get(&ctx, reference).await?;
     let output = MfaService::setup(&ctx, &user).await?;
 
@@ -260,7 +285,7 @@ pub async fn auth_mfa_disable(mut req: ApiRequest) -> ApiResponse {
     let MultiFactorConfigure {
         user_id,
         session_token,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     let user = SessionService::get_user(&ctx, &session_token, false).await?;
 
@@ -286,7 +311,7 @@ pub async fn auth_mfa_reset_recovery(mut req: ApiRequest) -> ApiResponse {
     let MultiFactorConfigure {
         user_id,
         session_token,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     let user = SessionService::get_user(&ctx, &session_token, false).await?;
 
@@ -27,7 +27,7 @@ pub async fn category_get(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let GetCategory { site, category } = req.body_json().await?;
+    let GetCategory { site, category } = body_json_validated(&mut req).await?;
     let site_id = SiteService::get_id(&ctx, site).await?;
     tide::log::info!("Getting page category {category:?} in site ID {site_id}");
 
@@ -42,7 +42,7 @@ pub async fn category_all_get(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let GetSite { site } = req.body_json().await?;
+    let GetSite { site } = body_json_validated(&mut req).await?;
     let site_id = SiteService::get_id(&ctx, site).await?;
     tide::log::info!("Getting all page categories in site ID {site_id}");
 
@@ -55,3 +55,18 @@ pub async fn category_all_get(mut req: ApiRequest) -> ApiResponse {
     let body = Body::from_json(&categories)?;
     Ok(body.into())
 }
+
+pub async fn category_rebuild(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetSite { site } = body_json_validated(&mut req).await?;
+    let site_id = SiteService::get_id(&ctx, site).await?;
+    tide::log::info!("Rebuilding all page categories in site ID {site_id}");
+
+    let output = CategoryService::rebuild(&ctx, site_id).await?;
+    txn.commit().await?;
+
+    let body = Body::from_json(&output)?;
+    Ok(body.into())
+}
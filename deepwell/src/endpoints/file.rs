@@ -34,7 +34,7 @@ pub async fn file_retrieve(mut req: ApiRequest) -> ApiResponse {
         site_id,
         page_id,
         file: file_reference,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Getting file {file_reference:?} from page ID {page_id} in site ID {site_id}",
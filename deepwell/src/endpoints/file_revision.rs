@@ -32,7 +32,7 @@ pub async fn file_revision_count(mut req: ApiRequest) -> ApiResponse {
         site_id,
         page_id,
         file: file_reference,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Getting latest revision for file ID {page_id} in site ID {site_id}",
@@ -62,7 +62,7 @@ pub async fn file_revision_retrieve(mut req: ApiRequest) -> ApiResponse {
         page_id,
         file_id,
         revision_number,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Getting file revision {revision_number} for file ID {file_id} on page ID {page_id}",
@@ -81,7 +81,7 @@ pub async fn file_revision_put(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: UpdateFileRevision = req.body_json().await?;
+    let input: UpdateFileRevision = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Editing file revision ID {} for file ID {} on page {}",
@@ -100,7 +100,7 @@ pub async fn file_revision_range_retrieve(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: GetFileRevisionRange = req.body_json().await?;
+    let input: GetFileRevisionRange = body_json_validated(&mut req).await?;
     let revisions = FileRevisionService::get_range(&ctx, input).await?;
 
     txn.commit().await?;
@@ -0,0 +1,200 @@
+/*
+ * endpoints/filter.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::filter::{
+    AddFilter, EditFilter, ExportFilters, FilterClass, GetFilterPage, ImportFilters,
+    ModifyFilter, ModifyFilters,
+};
+use crate::services::site::GetSite;
+
+pub async fn filter_create(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let AddFilter {
+        site_id,
+        actor_id,
+        filter,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Creating filter for {site_id:?}");
+
+    let filter = FilterService::create(&ctx, site_id, actor_id, filter).await?;
+    txn.commit().await?;
+
+    let body = Body::from_json(&filter)?;
+    let response = Response::builder(StatusCode::Created).body(body).into();
+    Ok(response)
+}
+
+pub async fn filter_put(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let EditFilter { actor_id, filter } = body_json_validated(&mut req).await?;
+    tide::log::info!("Updating filter ID {}", filter.filter_id);
+
+    let filter = FilterService::update(&ctx, actor_id, filter).await?;
+    let body = Body::from_json(&filter)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn filter_delete(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let ModifyFilter {
+        filter_id,
+        actor_id,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Deleting filter ID {filter_id}");
+
+    FilterService::delete(&ctx, actor_id, filter_id).await?;
+
+    txn.commit().await?;
+    Ok(Response::new(StatusCode::NoContent))
+}
+
+pub async fn filter_restore(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let ModifyFilter {
+        filter_id,
+        actor_id,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Restoring filter ID {filter_id}");
+
+    let filter = FilterService::restore(&ctx, actor_id, filter_id).await?;
+    let body = Body::from_json(&filter)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn filter_delete_many(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let ModifyFilters {
+        filter_ids,
+        actor_id,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Deleting {} filters", filter_ids.len());
+
+    let results = FilterService::delete_many(&ctx, actor_id, &filter_ids).await?;
+    let body = Body::from_json(&results)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn filter_restore_many(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let ModifyFilters {
+        filter_ids,
+        actor_id,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Restoring {} filters", filter_ids.len());
+
+    let results = FilterService::restore_many(&ctx, actor_id, &filter_ids).await?;
+    let body = Body::from_json(&results)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn filter_list_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: GetFilterPage = body_json_validated(&mut req).await?;
+    tide::log::info!("Getting filters for {:?}", input.site_id);
+
+    let output = FilterService::get_page(
+        &ctx,
+        FilterClass::from(input.site_id),
+        input.filter_type,
+        input.deleted,
+        input.order(),
+        input.pagination,
+    )
+    .await?;
+
+    let body = Body::from_json(&output)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn filter_count_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetSite { site } = body_json_validated(&mut req).await?;
+    tide::log::info!("Counting filters for site {:?}", site);
+
+    let site_id = SiteService::get_id(&ctx, site).await?;
+    let counts = FilterService::count_by_class(&ctx, site_id).await?;
+    let body = Body::from_json(&counts)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn filter_export(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let ExportFilters {
+        site_id,
+        filter_type,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Exporting filters for {site_id:?}");
+
+    let filter_class = FilterClass::from(site_id);
+    let filters = FilterService::export(&ctx, filter_class, filter_type).await?;
+    let body = Body::from_json(&filters)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn filter_import(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let ImportFilters {
+        site_id,
+        actor_id,
+        filters,
+        dry_run,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Importing filters for {site_id:?}");
+
+    let output = FilterService::import(&ctx, site_id, actor_id, filters, dry_run).await?;
+    let body = Body::from_json(&output)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
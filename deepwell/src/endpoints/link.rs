@@ -30,7 +30,7 @@ pub async fn page_links_from_retrieve(mut req: ApiRequest) -> ApiResponse {
     let GetLinksFrom {
         site_id,
         page: reference,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!("Getting page links for page {reference:?} in site ID {site_id}");
 
@@ -49,7 +49,7 @@ pub async fn page_links_to_retrieve(mut req: ApiRequest) -> ApiResponse {
     let GetLinksTo {
         site_id,
         page: reference,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!("Getting page links from page {reference:?} in site ID {site_id}");
 
@@ -65,7 +65,7 @@ pub async fn page_links_to_missing_retrieve(mut req: ApiRequest) -> ApiResponse
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let GetLinksToMissing { site_id, page_slug } = req.body_json().await?;
+    let GetLinksToMissing { site_id, page_slug } = body_json_validated(&mut req).await?;
     tide::log::info!(
         "Getting missing page links from page slug {page_slug} in site ID {site_id}",
     );
@@ -84,7 +84,7 @@ pub async fn page_links_external_from(mut req: ApiRequest) -> ApiResponse {
     let GetLinksExternalFrom {
         site_id,
         page: reference,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Getting external links from page {reference:?} in site ID {site_id}",
@@ -103,7 +103,7 @@ pub async fn page_links_external_to(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let GetLinksExternalTo { site_id, url } = req.body_json().await?;
+    let GetLinksExternalTo { site_id, url } = body_json_validated(&mut req).await?;
     tide::log::info!("Getting external links to URL {url} in site ID {site_id}");
 
     let output = LinkService::get_external_to(&ctx, site_id, &url).await?;
@@ -50,7 +50,7 @@ pub async fn locale_get(req: ApiRequest) -> ApiResponse {
 }
 
 pub async fn message_put(mut req: ApiRequest) -> ApiResponse {
-    let input: MessageArguments = req.body_json().await?;
+    let input: MessageArguments = body_json_validated(&mut req).await?;
     let locale_str = req.param("locale")?;
     let message_key = req.param("message_key")?;
     tide::log::info!("Formatting message key {message_key} in locale {locale_str}");
@@ -20,6 +20,7 @@
 
 use super::prelude::*;
 use crate::info;
+use crate::web::{input_schemas, openapi_document};
 use sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
 use wikidot_normalize::normalize;
 
@@ -62,6 +63,18 @@ pub async fn config_dump(req: ApiRequest) -> ApiResponse {
     Ok(body.into())
 }
 
+pub async fn schema_retrieve(_: ApiRequest) -> ApiResponse {
+    tide::log::info!("Getting JSON Schema for endpoint inputs");
+    let body = Body::from_json(&input_schemas())?;
+    Ok(body.into())
+}
+
+pub async fn openapi_retrieve(_: ApiRequest) -> ApiResponse {
+    tide::log::info!("Getting OpenAPI document");
+    let body = Body::from_json(&openapi_document())?;
+    Ok(body.into())
+}
+
 pub async fn normalize_method(req: ApiRequest) -> ApiResponse {
     let input = req.param("input")?;
     tide::log::info!("Running normalize as utility web method: {input}");
@@ -30,27 +30,31 @@
 mod prelude {
     pub use crate::api::{ApiRequest, ApiResponse};
     pub use crate::services::{
-        AliasService, BlobService, CategoryService, DomainService, Error as ServiceError,
-        FileRevisionService, FileService, LinkService, MfaService, PageRevisionService,
+        AliasService, AuditLogService, BlobService, CategoryService, DomainService,
+        Error as ServiceError, FeatureFlagService, FileRevisionService, FileService,
+        FilterService, LinkService, MfaService, PageLockService, PageRevisionService,
         PageService, ParentService, RenderService, RequestFetchService, ScoreService,
-        ServiceContext, SessionService, SiteService, TextService, UserService,
-        ViewService, VoteService,
+        ServiceContext, SessionService, SiteMemberService, SiteService, TextService,
+        UserService, ViewService, VoteService,
     };
     pub use crate::utils::error_response;
-    pub use crate::web::HttpUnwrap;
+    pub use crate::web::{body_json_validated, HttpUnwrap};
     pub use sea_orm::{ConnectionTrait, TransactionTrait};
     pub use std::convert::TryFrom;
     pub use tide::{Body, Error as TideError, Request, Response, StatusCode};
 }
 
+pub mod audit;
 pub mod auth;
 pub mod category;
 pub mod file;
 pub mod file_revision;
+pub mod filter;
 pub mod link;
 pub mod locale;
 pub mod misc;
 pub mod page;
+pub mod page_lock;
 pub mod page_revision;
 pub mod parent;
 pub mod site;
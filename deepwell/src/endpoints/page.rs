@@ -22,24 +22,31 @@ use super::prelude::*;
 use crate::models::page::Model as PageModel;
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::services::page::{
-    CreatePage, DeletePage, EditPage, GetPage, GetPageOutput, MovePage, RestorePage,
-    RollbackPage,
+    CreatePage, DeletePage, EditPage, FindHistoricalSlug, FindPagesByKeyword,
+    GetDeletedPages, GetPage, GetPageExcerpt, GetPageExcerptOutput, GetPageOutput,
+    GetPageRevisionView, GetRecentChanges, MovePage, RecentChange,
+    RecentChangesStreamQuery, RestorePage, RestorePageBySlug, RollbackPage, SchedulePage,
+    SetPageExpiry,
 };
 use crate::services::{Result, TextService};
 use crate::web::{PageDetailsQuery, Reference};
+use futures::stream::{self, StreamExt};
 use ref_map::*;
+use tide::sse::Sender;
 
 pub async fn page_create(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: CreatePage = req.body_json().await?;
-    tide::log::info!("Creating new page in site ID {}", input.site_id);
+    let input: CreatePage = body_json_validated(&mut req).await?;
+    let site_id = input.site_id;
+    tide::log::info!("Creating new page in site ID {site_id}");
 
-    let output = PageService::create(&ctx, input).await?;
+    let (output, recent_change) = PageService::create(&ctx, input).await?;
     let body = Body::from_json(&output)?;
     txn.commit().await?;
 
+    publish_recent_change(&req, site_id, recent_change);
     Ok(body.into())
 }
 
@@ -51,12 +58,11 @@ pub async fn page_retrieve(mut req: ApiRequest) -> ApiResponse {
     let GetPage {
         site_id,
         page: reference,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!("Getting page {reference:?} in site ID {site_id}");
-    let page = PageService::get(&ctx, site_id, reference).await?;
-
-    let revision = PageRevisionService::get_latest(&ctx, site_id, page.page_id).await?;
+    let (page, revision) =
+        PageService::get_visible_with_latest_revision(&ctx, site_id, reference).await?;
 
     let response =
         build_page_response(&ctx, &page, &revision, details, StatusCode::Ok).await?;
@@ -73,7 +79,9 @@ pub async fn page_get_direct(req: ApiRequest) -> ApiResponse {
     tide::log::info!("Getting page ID {page_id}");
 
     let details: PageDetailsQuery = req.query()?;
-    let page = PageService::get_direct(&ctx, page_id).await?;
+    let page = ctx
+        .retry_read(|| PageService::get_direct(&ctx, page_id))
+        .await?;
     let revision =
         PageRevisionService::get_latest(&ctx, page.site_id, page.page_id).await?;
 
@@ -84,34 +92,238 @@ pub async fn page_get_direct(req: ApiRequest) -> ApiResponse {
     Ok(response)
 }
 
-pub async fn page_edit(mut req: ApiRequest) -> ApiResponse {
+pub async fn page_revision_view(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: EditPage = req.body_json().await?;
-    tide::log::info!("Editing page {:?} in site ID {}", input.page, input.site_id);
+    let input: GetPageRevisionView = body_json_validated(&mut req).await?;
+    tide::log::info!(
+        "Viewing revision {} of page {:?} in site ID {}",
+        input.revision_number,
+        input.page,
+        input.site_id,
+    );
 
-    let output = PageService::edit(&ctx, input).await?;
+    let output = PageService::get_revision_view(&ctx, input).await?;
+    let body = Body::from_json(&output)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
 
+pub async fn page_recent_changes(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetRecentChanges {
+        site_id,
+        since,
+        limit,
+        include_deleted,
+        session_token,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Getting recent changes in site ID {site_id}");
+
+    if include_deleted {
+        ViewService::get_session_permissions(&ctx, site_id, session_token.as_deref())
+            .await?
+            .check_moderate()?;
+    }
+
+    let changes =
+        PageService::recent_changes(&ctx, site_id, since, limit, include_deleted).await?;
+
+    let body = Body::from_json(&changes)?;
     txn.commit().await?;
-    let body = Body::from_json(&output)?;
     Ok(body.into())
 }
 
-pub async fn page_delete(mut req: ApiRequest) -> ApiResponse {
+/// Server-sent events stream of recent changes in a site, as they happen.
+///
+/// A client reconnecting with `Last-Event-ID` is first sent any
+/// retained changes it missed. Changes to deleted pages are only
+/// sent if `includeDeleted` is set, which requires moderator permissions.
+pub async fn page_recent_changes_stream(
+    req: ApiRequest,
+    sender: Sender,
+) -> tide::Result<()> {
+    let site_id = req.param("site_id")?.parse()?;
+    let RecentChangesStreamQuery {
+        include_deleted,
+        session_token,
+    } = req.query()?;
+
+    if include_deleted {
+        let txn = req.database().begin().await?;
+        let ctx = ServiceContext::new(&req, &txn);
+        ViewService::get_session_permissions(&ctx, site_id, session_token.as_deref())
+            .await?
+            .check_moderate()?;
+        txn.commit().await?;
+    }
+
+    let last_event_id = req
+        .header("Last-Event-ID")
+        .and_then(|value| value.as_str().parse().ok());
+
+    tide::log::info!(
+        "Streaming recent changes in site ID {site_id} (last event {last_event_id:?})",
+    );
+
+    let (missed, receiver) = req.state().recent_changes.subscribe(site_id, last_event_id);
+    let mut events = stream::iter(missed).chain(receiver);
+
+    while let Some((id, change)) = events.next().await {
+        if change.page_deleted && !include_deleted {
+            continue;
+        }
+
+        let data = serde_json::to_string(&change)?;
+        if sender
+            .send("change", data, Some(&id.to_string()))
+            .await
+            .is_err()
+        {
+            // Client has disconnected
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn page_deleted_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: GetDeletedPages = body_json_validated(&mut req).await?;
+    tide::log::info!("Getting deleted pages in site ID {}", input.site_id);
+
+    let pages = PageService::get_deleted(&ctx, input).await?;
+    let body = Body::from_json(&pages)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn page_schedule(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: DeletePage = req.body_json().await?;
+    let input: SchedulePage = body_json_validated(&mut req).await?;
+    let site_id = input.site_id;
+    tide::log::info!("Scheduling page {:?} in site ID {site_id}", input.page);
+
+    let (page, recent_change) = PageService::schedule(&ctx, input).await?;
+    let body = Body::from_json(&page)?;
+    txn.commit().await?;
+
+    publish_recent_change(&req, site_id, recent_change);
+    Ok(body.into())
+}
+
+pub async fn page_set_expiry(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: SetPageExpiry = body_json_validated(&mut req).await?;
     tide::log::info!(
-        "Deleting page {:?} in site ID {}",
+        "Setting expiry for page {:?} in site ID {}",
         input.page,
         input.site_id,
     );
 
-    let output = PageService::delete(&ctx, input).await?;
+    let page = PageService::set_expiry(&ctx, input).await?;
+    let body = Body::from_json(&page)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn page_duplicates_retrieve(req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let site_id = req.param("site_id")?.parse()?;
+    tide::log::info!("Getting duplicate pages in site ID {site_id}");
+
+    let clusters = PageService::find_duplicates(&ctx, site_id).await?;
+    let body = Body::from_json(&clusters)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn page_historical_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let FindHistoricalSlug { site_id, slug } = body_json_validated(&mut req).await?;
+    tide::log::info!(
+        "Finding page with current or previous slug '{slug}' in site ID {site_id}"
+    );
+
+    let result = PageService::find_by_historical_slug(&ctx, site_id, &slug).await?;
+    let body = Body::from_json(&result)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn page_keyword_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let FindPagesByKeyword { site_id, keyword } = body_json_validated(&mut req).await?;
+    tide::log::info!("Finding pages with keyword '{keyword}' in site ID {site_id}");
+
+    let pages = PageService::find_by_keyword(&ctx, site_id, &keyword).await?;
+    let body = Body::from_json(&pages)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn page_excerpt_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetPageExcerpt {
+        site_id,
+        page_id,
+        max_chars,
+    } = body_json_validated(&mut req).await?;
+    tide::log::info!("Getting excerpt of page ID {page_id} in site ID {site_id}");
+
+    let excerpt = PageService::get_excerpt(&ctx, site_id, page_id, max_chars).await?;
+    let body = Body::from_json(&GetPageExcerptOutput { excerpt })?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn page_edit(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: EditPage = body_json_validated(&mut req).await?;
+    let site_id = input.site_id;
+    tide::log::info!("Editing page {:?} in site ID {site_id}", input.page);
+
+    let (output, recent_change) = PageService::edit(&ctx, input).await?;
 
     txn.commit().await?;
+    publish_recent_change(&req, site_id, recent_change);
+    let body = Body::from_json(&output)?;
+    Ok(body.into())
+}
+
+pub async fn page_delete(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: DeletePage = body_json_validated(&mut req).await?;
+    let site_id = input.site_id;
+    tide::log::info!("Deleting page {:?} in site ID {site_id}", input.page);
+
+    let (output, recent_change) = PageService::delete(&ctx, input).await?;
+
+    txn.commit().await?;
+    publish_recent_change(&req, site_id, Some(recent_change));
     let body = Body::from_json(&output)?;
     Ok(body.into())
 }
@@ -120,7 +332,7 @@ pub async fn page_move(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: MovePage = req.body_json().await?;
+    let input: MovePage = body_json_validated(&mut req).await?;
     tide::log::info!(
         "Moving page {:?} in site ID {} to {}",
         input.page,
@@ -153,7 +365,7 @@ pub async fn page_restore(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: RestorePage = req.body_json().await?;
+    let input: RestorePage = body_json_validated(&mut req).await?;
     tide::log::info!(
         "Un-deleting page ID {} in site ID {}",
         input.page_id,
@@ -167,11 +379,29 @@ pub async fn page_restore(mut req: ApiRequest) -> ApiResponse {
     Ok(body.into())
 }
 
+pub async fn page_restore_by_slug(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: RestorePageBySlug = body_json_validated(&mut req).await?;
+    tide::log::info!(
+        "Un-deleting page with slug '{}' in site ID {}",
+        input.slug,
+        input.site_id,
+    );
+
+    let output = PageService::restore_by_slug(&ctx, input).await?;
+
+    txn.commit().await?;
+    let body = Body::from_json(&output)?;
+    Ok(body.into())
+}
+
 pub async fn page_rollback(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: RollbackPage = req.body_json().await?;
+    let input: RollbackPage = body_json_validated(&mut req).await?;
     tide::log::info!(
         "Rolling back page {:?} in site ID {} to revision number {}",
         input.page,
@@ -186,6 +416,20 @@ pub async fn page_rollback(mut req: ApiRequest) -> ApiResponse {
     Ok(body.into())
 }
 
+/// Announces a change in the site's recent changes feed, if there is one.
+///
+/// This is only called once the transaction is committed,
+/// so subscribers never see a change which was rolled back.
+pub(super) fn publish_recent_change(
+    req: &ApiRequest,
+    site_id: i64,
+    change: Option<RecentChange>,
+) {
+    if let Some(change) = change {
+        req.state().recent_changes.publish(site_id, change);
+    }
+}
+
 async fn build_page_response(
     ctx: &ServiceContext<'_>,
     page: &PageModel,
@@ -200,8 +444,12 @@ async fn build_page_response(
 
     // Get text data, if requested
     let (wikitext, compiled_html) = try_join!(
-        TextService::get_maybe(ctx, details.wikitext, &revision.wikitext_hash),
-        TextService::get_maybe(ctx, details.compiled_html, &revision.compiled_hash),
+        TextService::get_maybe(ctx, details.wikitext, Some(&revision.wikitext_hash)),
+        TextService::get_maybe(
+            ctx,
+            details.compiled_html,
+            revision.compiled_hash.as_deref(),
+        ),
     )?;
 
     // Calculate score
@@ -213,6 +461,9 @@ async fn build_page_response(
         page_created_at: page.created_at,
         page_updated_at: page.updated_at,
         page_deleted_at: page.deleted_at,
+        page_deletion_reason: page.deletion_reason,
+        page_publish_at: page.publish_at,
+        page_expires_at: page.expires_at,
         page_revision_count: revision.revision_number + 1,
         site_id: page.site_id,
         page_category_id: category.category_id,
@@ -233,6 +484,7 @@ async fn build_page_response(
         alt_title: revision.alt_title.ref_map(|s| s.as_str()),
         slug: &revision.slug,
         tags: &revision.tags,
+        keywords: &page.keywords,
         rating,
     };
 
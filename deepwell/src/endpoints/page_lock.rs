@@ -0,0 +1,139 @@
+/*
+ * endpoints/page_lock.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::page_lock::{
+    AcquirePageLock, GetPageLock, PageEvent, PageEventsQuery, ReleasePageLock,
+};
+use async_std::task;
+use futures::StreamExt;
+use tide_websockets::WebSocketConnection;
+
+pub async fn page_lock_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetPageLock { page_id } = body_json_validated(&mut req).await?;
+    tide::log::info!("Getting lock on page ID {page_id}");
+
+    let lock = PageLockService::get(&ctx, page_id).await?;
+    let body = Body::from_json(&lock)?;
+    txn.commit().await?;
+
+    Ok(body.into())
+}
+
+pub async fn page_lock_acquire(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: AcquirePageLock = body_json_validated(&mut req).await?;
+    let lock = PageLockService::acquire(&ctx, input).await?;
+    let body = Body::from_json(&lock)?;
+    txn.commit().await?;
+
+    // Notify only once the lock is committed
+    let page_id = lock.page_id;
+    req.state()
+        .page_events
+        .publish(page_id, PageEvent::Locked { lock });
+
+    Ok(body.into())
+}
+
+pub async fn page_lock_release(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: ReleasePageLock = body_json_validated(&mut req).await?;
+    let lock = PageLockService::release(&ctx, input).await?;
+    let body = Body::from_json(&lock)?;
+    txn.commit().await?;
+
+    req.state().page_events.publish(
+        lock.page_id,
+        PageEvent::Unlocked {
+            user_id: lock.user_id,
+        },
+    );
+
+    Ok(body.into())
+}
+
+/// WebSocket connection for receiving presence and lock events on a page.
+///
+/// On connection the current lock, if any, is sent. If a session token
+/// is given, the user is announced to other subscribers while connected.
+/// Messages from the client are ignored.
+pub async fn page_events(
+    req: ApiRequest,
+    stream: WebSocketConnection,
+) -> tide::Result<()> {
+    let page_id: i64 = req.param("page_id")?.parse()?;
+    let PageEventsQuery { session_token } = req.query()?;
+
+    let (user_id, lock) = {
+        let txn = req.database().begin().await?;
+        let ctx = ServiceContext::new(&req, &txn);
+
+        let user_id = match session_token {
+            Some(token) => Some(SessionService::get(&ctx, &token).await?.user_id),
+            None => None,
+        };
+
+        let lock = PageLockService::get_optional(&ctx, page_id).await?;
+        txn.commit().await?;
+        (user_id, lock)
+    };
+
+    tide::log::info!("Subscribing to events on page ID {page_id} (user {user_id:?})");
+
+    let events = &req.state().page_events;
+    let receiver = events.subscribe(page_id);
+
+    if let Some(lock) = lock {
+        stream.send_json(&PageEvent::Locked { lock }).await?;
+    }
+
+    if let Some(user_id) = user_id {
+        events.publish(page_id, PageEvent::Joined { user_id });
+    }
+
+    // Forward events until the client disconnects
+    let sender = stream.clone();
+    let forwarder = task::spawn(async move {
+        while let Ok(event) = receiver.recv().await {
+            if sender.send_json(&event).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut stream = stream;
+    while let Some(Ok(_)) = stream.next().await {}
+    forwarder.cancel().await;
+
+    if let Some(user_id) = user_id {
+        events.publish(page_id, PageEvent::Left { user_id });
+    }
+
+    tide::log::info!("Unsubscribed from events on page ID {page_id}");
+    Ok(())
+}
@@ -18,12 +18,13 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::page::publish_recent_change;
 use super::prelude::*;
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::services::page::GetPage;
 use crate::services::page_revision::{
-    GetPageRevision, GetPageRevisionRange, PageRevisionCountOutput,
-    PageRevisionModelFiltered, UpdatePageRevision,
+    CreatePageDraft, GetPageRevision, GetPageRevisionRange, PageRevisionCountOutput,
+    PageRevisionModelFiltered, PublishPageDraft, UpdatePageRevision,
 };
 use crate::services::{Result, TextService};
 use crate::web::PageDetailsQuery;
@@ -35,7 +36,7 @@ pub async fn page_revision_count(mut req: ApiRequest) -> ApiResponse {
     let GetPage {
         site_id,
         page: reference,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Getting latest revision for page {reference:?} in site ID {site_id}",
@@ -66,7 +67,7 @@ pub async fn page_revision_retrieve(mut req: ApiRequest) -> ApiResponse {
         site_id,
         page_id,
         revision_number,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Getting revision {revision_number} for page ID {page_id} in site ID {site_id}",
@@ -87,7 +88,7 @@ pub async fn page_revision_put(mut req: ApiRequest) -> ApiResponse {
     let ctx = ServiceContext::new(&req, &txn);
 
     let details: PageDetailsQuery = req.query()?;
-    let input: UpdatePageRevision = req.body_json().await?;
+    let input: UpdatePageRevision = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Editing revision ID {} for page ID {} in site ID {}",
@@ -114,7 +115,7 @@ pub async fn page_revision_range_retrieve(mut req: ApiRequest) -> ApiResponse {
     let ctx = ServiceContext::new(&req, &txn);
 
     let details: PageDetailsQuery = req.query()?;
-    let input: GetPageRevisionRange = req.body_json().await?;
+    let input: GetPageRevisionRange = body_json_validated(&mut req).await?;
     let revisions = PageRevisionService::get_range(&ctx, input).await?;
 
     let response =
@@ -175,8 +176,8 @@ async fn filter_and_populate_revision(
 
     // Get text data, if requested
     let (wikitext, compiled_html) = try_join!(
-        TextService::get_maybe(ctx, details.wikitext, &wikitext_hash),
-        TextService::get_maybe(ctx, details.compiled_html, &compiled_hash),
+        TextService::get_maybe(ctx, details.wikitext, Some(&wikitext_hash)),
+        TextService::get_maybe(ctx, details.compiled_html, compiled_hash.as_deref()),
     )?;
 
     Ok(PageRevisionModelFiltered {
@@ -235,3 +236,39 @@ async fn build_revision_list_response(
     let response = Response::builder(status).body(body).into();
     Ok(response)
 }
+
+pub async fn page_draft_create(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: CreatePageDraft = body_json_validated(&mut req).await?;
+    tide::log::info!(
+        "Creating draft for page ID {} in site ID {}",
+        input.page_id,
+        input.site_id,
+    );
+
+    let draft = PageRevisionService::create_draft(&ctx, input).await?;
+    let body = Body::from_json(&draft)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn page_draft_publish(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: PublishPageDraft = body_json_validated(&mut req).await?;
+    let site_id = input.site_id;
+    tide::log::info!(
+        "Publishing draft ID {} in site ID {site_id}",
+        input.draft_id,
+    );
+
+    let (output, recent_change) = PageRevisionService::publish_draft(&ctx, input).await?;
+    let body = Body::from_json(&output)?;
+    txn.commit().await?;
+
+    publish_recent_change(&req, site_id, recent_change);
+    Ok(body.into())
+}
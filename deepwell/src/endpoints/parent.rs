@@ -33,7 +33,7 @@ pub async fn parent_relationships_retrieve(mut req: ApiRequest) -> ApiResponse {
     let GetPage {
         site_id,
         page: reference,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Getting all {} pages from {:?} in site ID {}",
@@ -50,11 +50,28 @@ pub async fn parent_relationships_retrieve(mut req: ApiRequest) -> ApiResponse {
     build_parent_response(&models, StatusCode::Ok)
 }
 
+pub async fn parent_children_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetPage {
+        site_id,
+        page: reference,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Getting child pages of {reference:?} in site ID {site_id}");
+
+    let pages = ParentService::get_children(&ctx, site_id, reference).await?;
+
+    txn.commit().await?;
+    build_parent_response(&pages, StatusCode::Ok)
+}
+
 pub async fn parent_retrieve(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: ParentDescription = req.body_json().await?;
+    let input: ParentDescription = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Getting parental relationship {:?} -> {:?} in site ID {}",
@@ -73,7 +90,7 @@ pub async fn parent_put(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: ParentDescription = req.body_json().await?;
+    let input: ParentDescription = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Creating parental relationship {:?} -> {:?} in site ID {}",
@@ -98,7 +115,7 @@ pub async fn parent_delete(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: ParentDescription = req.body_json().await?;
+    let input: ParentDescription = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Deleting parental relationship {:?} -> {:?} in site ID {}",
@@ -23,14 +23,23 @@ use crate::models::alias::Model as AliasModel;
 use crate::models::sea_orm_active_enums::AliasType;
 use crate::models::site::Model as SiteModel;
 use crate::models::site_domain::Model as SiteDomainModel;
-use crate::services::domain::CreateCustomDomain;
-use crate::services::site::{CreateSite, GetSite, GetSiteOutput, UpdateSite};
+use crate::services::domain::{
+    CreateCustomDomain, GenerateDomainVerification, VerifyDomainOutput,
+};
+use crate::services::feature_flag::SetFeatureFlag;
+use crate::services::site::{
+    CreateSite, DeleteSite, ExportSite, GetRecentChangesFeed, GetSite, GetSiteOutput,
+    GetSitemap, ImportSiteExport, UpdateSite,
+};
+use crate::services::site_member::{GetSiteRole, SetSiteRole};
+use crate::services::JobService;
+use crate::web::Pagination;
 
 pub async fn site_create(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: CreateSite = req.body_json().await?;
+    let input: CreateSite = body_json_validated(&mut req).await?;
     let output = SiteService::create(&ctx, input).await?;
     txn.commit().await?;
 
@@ -43,7 +52,7 @@ pub async fn site_retrieve(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let GetSite { site } = req.body_json().await?;
+    let GetSite { site } = body_json_validated(&mut req).await?;
     tide::log::info!("Getting site {:?}", site);
 
     let site = SiteService::get(&ctx, site).await?;
@@ -63,7 +72,7 @@ pub async fn site_put(mut req: ApiRequest) -> ApiResponse {
         site,
         body,
         user_id,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!("Updating site {:?}", site);
 
@@ -73,6 +82,130 @@ pub async fn site_put(mut req: ApiRequest) -> ApiResponse {
     Ok(Response::new(StatusCode::NoContent))
 }
 
+pub async fn site_delete(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let DeleteSite { site, user_id } = body_json_validated(&mut req).await?;
+    tide::log::info!("Deleting site {:?}", site);
+
+    SiteService::delete(&ctx, site, user_id).await?;
+
+    txn.commit().await?;
+    Ok(Response::new(StatusCode::NoContent))
+}
+
+pub async fn site_export(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let ExportSite {
+        site,
+        all_revisions,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Exporting site {:?}", site);
+
+    let site_id = SiteService::get_id(&ctx, site).await?;
+    let output = SiteService::export(&ctx, site_id, all_revisions).await?;
+
+    let body = Body::from_json(&output)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn site_provenance_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetSite { site } = body_json_validated(&mut req).await?;
+    tide::log::info!("Getting page provenance for site {:?}", site);
+
+    let site_id = SiteService::get_id(&ctx, site).await?;
+    let output = SiteService::count_provenance(&ctx, site_id).await?;
+
+    let body = Body::from_json(&output)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn site_feed_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetRecentChangesFeed { site, format } = body_json_validated(&mut req).await?;
+    tide::log::info!("Getting {:?} feed for site {:?}", format, site);
+
+    let site_id = SiteService::get_id(&ctx, site).await?;
+    let feed = SiteService::recent_changes_feed(&ctx, site_id, format).await?;
+    txn.commit().await?;
+
+    let response = Response::builder(StatusCode::Ok)
+        .body(Body::from_string(feed))
+        .content_type(format.content_type())
+        .into();
+
+    Ok(response)
+}
+
+pub async fn site_robots_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetSite { site } = body_json_validated(&mut req).await?;
+    tide::log::info!("Getting robots.txt for site {:?}", site);
+
+    let site_id = SiteService::get_id(&ctx, site).await?;
+    let robots_txt = SiteService::get_robots_txt(&ctx, site_id).await?;
+    txn.commit().await?;
+
+    let response = Response::builder(StatusCode::Ok)
+        .body(Body::from_string(robots_txt))
+        .content_type("text/plain")
+        .into();
+
+    Ok(response)
+}
+
+pub async fn site_sitemap_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetSitemap { site, chunk } = body_json_validated(&mut req).await?;
+    tide::log::info!("Getting sitemap for site {:?}", site);
+
+    let site_id = SiteService::get_id(&ctx, site).await?;
+    let sitemap = SiteService::generate_sitemap(&ctx, site_id, chunk).await?;
+    txn.commit().await?;
+
+    let response = Response::builder(StatusCode::Ok)
+        .body(Body::from_string(sitemap))
+        .content_type("application/xml")
+        .into();
+
+    Ok(response)
+}
+
+pub async fn site_import(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let ImportSiteExport {
+        site,
+        user_id,
+        manifest,
+    } = body_json_validated(&mut req).await?;
+
+    tide::log::info!("Importing site export into site {:?}", site);
+
+    let site_id = SiteService::get_id(&ctx, site).await?;
+    let output = SiteService::import(&ctx, site_id, user_id, manifest).await?;
+
+    let body = Body::from_json(&output)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
 pub async fn site_custom_domain_retrieve(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
@@ -89,7 +222,7 @@ pub async fn site_custom_domain_post(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: CreateCustomDomain = req.body_json().await?;
+    let input: CreateCustomDomain = body_json_validated(&mut req).await?;
     DomainService::create_custom(&ctx, input).await?;
 
     txn.commit().await?;
@@ -107,6 +240,44 @@ pub async fn site_custom_domain_delete(mut req: ApiRequest) -> ApiResponse {
     Ok(Response::new(StatusCode::NoContent))
 }
 
+pub async fn site_custom_domain_verification_post(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GenerateDomainVerification { domain, site_id } =
+        body_json_validated(&mut req).await?;
+    let output =
+        DomainService::generate_verification_token(&ctx, site_id, &domain).await?;
+
+    let body = Body::from_json(&output)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn site_custom_domain_verify(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let domain = req.body_string().await?;
+    let verified = DomainService::verify_dns(&ctx, &domain).await?;
+
+    let body = Body::from_json(&VerifyDomainOutput { verified })?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn site_custom_domain_list_all(req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let pagination: Pagination = req.query()?;
+    let domains = DomainService::list_all(&ctx, pagination).await?;
+
+    let body = Body::from_json(&domains)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
 pub async fn site_get_from_domain(req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
@@ -119,6 +290,68 @@ pub async fn site_get_from_domain(req: ApiRequest) -> ApiResponse {
     Ok(body.into())
 }
 
+pub async fn site_feature_flags_retrieve(req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let site_id = req.param("site_id")?.parse()?;
+    tide::log::info!("Getting feature flags for site ID {site_id}");
+
+    let flags = FeatureFlagService::get_all(&ctx, site_id).await?;
+    let body = Body::from_json(&flags.list())?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn site_feature_flag_put(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: SetFeatureFlag = body_json_validated(&mut req).await?;
+    FeatureFlagService::set(&ctx, input).await?;
+
+    txn.commit().await?;
+    Ok(Response::new(StatusCode::NoContent))
+}
+
+pub async fn site_member_role_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetSiteRole { site_id, user_id } = body_json_validated(&mut req).await?;
+    tide::log::info!("Getting role for user ID {user_id} in site ID {site_id}");
+
+    let role = SiteMemberService::get_role(&ctx, site_id, user_id).await?;
+    let body = Body::from_json(&role)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn site_member_role_put(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: SetSiteRole = body_json_validated(&mut req).await?;
+    SiteMemberService::set_role(&ctx, input).await?;
+
+    txn.commit().await?;
+    Ok(Response::new(StatusCode::NoContent))
+}
+
+pub async fn site_rerender(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let GetSite { site } = body_json_validated(&mut req).await?;
+    tide::log::info!("Queueing re-render of all pages in site {:?}", site);
+
+    let site_id = SiteService::get_id(&ctx, site).await?;
+    JobService::queue_rerender_site(site_id);
+
+    txn.commit().await?;
+    Ok(Response::new(StatusCode::Accepted))
+}
+
 fn build_site_response(
     site: SiteModel,
     aliases: Vec<AliasModel>,
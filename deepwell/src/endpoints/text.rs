@@ -49,6 +49,34 @@ pub async fn text_get(req: ApiRequest) -> ApiResponse {
     Ok(body.into())
 }
 
+pub async fn text_verify(req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    tide::log::info!("Verifying stored text");
+    let hash = read_hash(&req)?;
+    TextService::verify(&ctx, &hash).await?;
+    txn.commit().await?;
+
+    Ok(Response::new(StatusCode::NoContent))
+}
+
+pub async fn text_verify_all(req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    tide::log::info!("Verifying all stored text");
+    let corrupted: Vec<String> = TextService::verify_all(&ctx)
+        .await?
+        .iter()
+        .map(hex::encode)
+        .collect();
+
+    let body = Body::from_json(&corrupted)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
 fn read_hash(req: &ApiRequest) -> Result<TextHash, TideError> {
     let hash_hex = req.param("hash")?;
     tide::log::debug!("Text hash: {hash_hex}");
@@ -23,7 +23,8 @@ use crate::models::alias::Model as AliasModel;
 use crate::models::sea_orm_active_enums::AliasType;
 use crate::models::user::Model as UserModel;
 use crate::services::user::{
-    CreateUser, GetUser, GetUserOutput, UpdateUser, UpdateUserBody,
+    BeginEmailChange, ConfirmEmailChange, CreateUser, DeleteUser, GetUser, GetUserOutput,
+    UpdateUser, UpdateUserBody, UserFilter,
 };
 use crate::web::ProvidedValue;
 
@@ -32,7 +33,7 @@ pub async fn user_create(mut req: ApiRequest) -> ApiResponse {
     let ctx = ServiceContext::new(&req, &txn);
 
     tide::log::info!("Creating new regular user");
-    let input: CreateUser = req.body_json().await?;
+    let input: CreateUser = body_json_validated(&mut req).await?;
     let output = UserService::create(&ctx, input).await?;
 
     let body = Body::from_json(&output)?;
@@ -51,7 +52,7 @@ pub async fn user_retrieve(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let GetUser { user: reference } = req.body_json().await?;
+    let GetUser { user: reference } = body_json_validated(&mut req).await?;
     tide::log::info!("Getting user {:?}", reference);
 
     let user = UserService::get(&ctx, reference).await?;
@@ -61,6 +62,18 @@ pub async fn user_retrieve(mut req: ApiRequest) -> ApiResponse {
     build_user_response(user, aliases, StatusCode::Ok)
 }
 
+pub async fn user_list_retrieve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let filter: UserFilter = body_json_validated(&mut req).await?;
+    let users = UserService::get_all(&ctx, filter).await?;
+
+    txn.commit().await?;
+    let body = Body::from_json(&users)?;
+    Ok(body.into())
+}
+
 pub async fn user_put(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
@@ -68,7 +81,7 @@ pub async fn user_put(mut req: ApiRequest) -> ApiResponse {
     let UpdateUser {
         user: reference,
         body,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     tide::log::info!("Updating user {:?}", reference);
 
@@ -78,14 +91,43 @@ pub async fn user_put(mut req: ApiRequest) -> ApiResponse {
     Ok(Response::new(StatusCode::NoContent))
 }
 
+pub async fn user_email_change_post(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: BeginEmailChange = body_json_validated(&mut req).await?;
+    tide::log::info!("Beginning email change for user ID {}", input.user_id);
+
+    let output = UserService::begin_email_change(&ctx, input).await?;
+    let body = Body::from_json(&output)?;
+    txn.commit().await?;
+    Ok(body.into())
+}
+
+pub async fn user_email_confirm_post(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: ConfirmEmailChange = body_json_validated(&mut req).await?;
+    tide::log::info!("Confirming email change for user ID {}", input.user_id);
+
+    UserService::confirm_email_change(&ctx, input).await?;
+
+    txn.commit().await?;
+    Ok(Response::new(StatusCode::NoContent))
+}
+
 pub async fn user_delete(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let GetUser { user: reference } = req.body_json().await?;
+    let DeleteUser {
+        user: reference,
+        actor_id,
+    } = body_json_validated(&mut req).await?;
     tide::log::info!("Deleting user {:?}", reference);
 
-    UserService::delete(&ctx, reference).await?;
+    UserService::delete(&ctx, actor_id, reference).await?;
 
     txn.commit().await?;
     Ok(Response::new(StatusCode::NoContent))
@@ -127,7 +169,7 @@ pub async fn user_add_name_change(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let GetUser { user: reference } = req.body_json().await?;
+    let GetUser { user: reference } = body_json_validated(&mut req).await?;
     tide::log::info!("Adding user name change token to {:?}", reference);
 
     let name_changes = UserService::add_name_change_token(&ctx, reference).await?;
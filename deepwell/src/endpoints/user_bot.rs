@@ -40,7 +40,7 @@ pub async fn user_bot_create(mut req: ApiRequest) -> ApiResponse {
         owners,
         authorization_token,
         bypass_filter,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
     tide::log::info!("Creating new bot user with name '{}'", name);
 
     // TODO verify auth token
@@ -105,7 +105,7 @@ pub async fn user_bot_retrieve(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let GetUser { user: reference } = req.body_json().await?;
+    let GetUser { user: reference } = body_json_validated(&mut req).await?;
     tide::log::info!("Getting bot user {reference:?}");
 
     let user = UserService::get(&ctx, reference).await?;
@@ -138,7 +138,7 @@ pub async fn user_bot_owner_put(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: CreateBotOwner = req.body_json().await?;
+    let input: CreateBotOwner = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Adding or updating bot owner ({:?} <- {:?})",
@@ -156,7 +156,7 @@ pub async fn user_bot_owner_delete(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: DeleteBotOwner = req.body_json().await?;
+    let input: DeleteBotOwner = body_json_validated(&mut req).await?;
     tide::log::info!("Remove bot owner ({:?} <- {:?})", input.bot, input.human,);
 
     UserBotOwnerService::delete(&ctx, input).await?;
@@ -19,15 +19,60 @@
  */
 
 use super::prelude::*;
-use crate::services::view::GetPageView;
+use crate::services::view::{GetPageView, ResolveRoute};
 
 /// Returns relevant context for rendering a page from a processed web request.
 pub async fn view_page(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: GetPageView = req.body_json().await?;
-    let output = ViewService::page(&ctx, input).await?;
+    let input: GetPageView = body_json_validated(&mut req).await?;
+    let output = match ViewService::page(&ctx, input).await {
+        Ok(output) => output,
+        Err(error) => return error_view_response(error),
+    };
+
+    let body = Body::from_json(&output)?;
+    Ok(body.into())
+}
+
+/// Returns the context for rendering several pages, such as for prefetching.
+///
+/// Each item is either the page view, or an error view if it could not be fetched.
+pub async fn view_pages(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let inputs: Vec<GetPageView> = body_json_validated(&mut req).await?;
+    let output = ViewService::pages(&ctx, inputs).await?;
+
     let body = Body::from_json(&output)?;
     Ok(body.into())
 }
+
+/// Resolves a request host and path into a site, slug, and any redirects.
+pub async fn view_resolve(mut req: ApiRequest) -> ApiResponse {
+    let txn = req.database().begin().await?;
+    let ctx = ServiceContext::new(&req, &txn);
+
+    let input: ResolveRoute = body_json_validated(&mut req).await?;
+    let output = match ViewService::resolve(&ctx, input).await {
+        Ok(output) => output,
+        Err(error) => return error_view_response(error),
+    };
+
+    let body = Body::from_json(&output)?;
+    Ok(body.into())
+}
+
+/// Converts expected view errors into a payload for rendering an error page.
+fn error_view_response(error: ServiceError) -> ApiResponse {
+    match ViewService::error_view(&error) {
+        Some(view) => {
+            let body = Body::from_json(&view)?;
+            let response = Response::builder(view.status).body(body).into();
+            Ok(response)
+        }
+        None => Err(error.into_tide_error()),
+    }
+}
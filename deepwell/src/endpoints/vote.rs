@@ -28,7 +28,7 @@ pub async fn vote_retrieve(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: GetVote = req.body_json().await?;
+    let input: GetVote = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Getting vote cast by {} on page {}",
@@ -45,7 +45,7 @@ pub async fn vote_put(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: CreateVote = req.body_json().await?;
+    let input: CreateVote = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Casting vote cast by {} on page {}",
@@ -65,7 +65,7 @@ pub async fn vote_delete(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: GetVote = req.body_json().await?;
+    let input: GetVote = body_json_validated(&mut req).await?;
 
     tide::log::info!(
         "Removing vote cast by {} on page {}",
@@ -87,7 +87,7 @@ pub async fn vote_action(mut req: ApiRequest) -> ApiResponse {
         user_id,
         enable,
         acting_user_id,
-    } = req.body_json().await?;
+    } = body_json_validated(&mut req).await?;
 
     let key = GetVote { page_id, user_id };
     let vote = VoteService::action(&ctx, key, enable, acting_user_id).await?;
@@ -100,7 +100,7 @@ pub async fn vote_list_retrieve(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: GetVoteHistory = req.body_json().await?;
+    let input: GetVoteHistory = body_json_validated(&mut req).await?;
     let votes = VoteService::get_history(&ctx, input).await?;
 
     txn.commit().await?;
@@ -111,7 +111,7 @@ pub async fn vote_count_retrieve(mut req: ApiRequest) -> ApiResponse {
     let txn = req.database().begin().await?;
     let ctx = ServiceContext::new(&req, &txn);
 
-    let input: CountVoteHistory = req.body_json().await?;
+    let input: CountVoteHistory = body_json_validated(&mut req).await?;
     let count = VoteService::count_history(&ctx, input).await?;
 
     txn.commit().await?;
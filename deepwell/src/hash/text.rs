@@ -18,11 +18,13 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use sha2::{Digest, Sha512};
 use tiny_keccak::{Hasher, KangarooTwelve};
 
 /// The expected length of a text hash digest.
 ///
 /// This is the standard output length for KangarooTwelve in bytes.
+/// All other algorithms produce digests truncated to this length.
 pub const TEXT_HASH_LENGTH: usize = 16;
 
 /// The array type for a text hash digest;
@@ -36,3 +38,78 @@ pub fn k12_hash(data: &[u8]) -> TextHash {
     hasher.finalize(&mut bytes);
     bytes
 }
+
+/// Produces a byte array containing the SHA-512 hash for the given data,
+/// truncated to the text hash length.
+pub fn sha512_truncated_hash(data: &[u8]) -> TextHash {
+    let result = Sha512::digest(data);
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&result[..16]);
+    bytes
+}
+
+/// Which algorithm is used to hash stored text.
+///
+/// The algorithm used is recorded with each text entry, so that the
+/// configured algorithm can be changed without invalidating prior entries.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextHashAlgorithm {
+    #[default]
+    K12,
+    Sha512Truncated,
+}
+
+impl TextHashAlgorithm {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "k12" => Some(TextHashAlgorithm::K12),
+            "sha512-truncated" => Some(TextHashAlgorithm::Sha512Truncated),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TextHashAlgorithm::K12 => "k12",
+            TextHashAlgorithm::Sha512Truncated => "sha512-truncated",
+        }
+    }
+
+    pub fn hash(self, data: &[u8]) -> TextHash {
+        match self {
+            TextHashAlgorithm::K12 => k12_hash(data),
+            TextHashAlgorithm::Sha512Truncated => sha512_truncated_hash(data),
+        }
+    }
+}
+
+#[test]
+fn text_hash_algorithms() {
+    const ALGORITHMS: [TextHashAlgorithm; 2] =
+        [TextHashAlgorithm::K12, TextHashAlgorithm::Sha512Truncated];
+
+    let data = b"**Item #:** SCP-001";
+
+    for algorithm in ALGORITHMS {
+        assert_eq!(
+            TextHashAlgorithm::from_name(algorithm.name()),
+            Some(algorithm),
+            "Algorithm name doesn't round trip",
+        );
+
+        assert_eq!(
+            algorithm.hash(data),
+            algorithm.hash(data),
+            "Algorithm is not deterministic",
+        );
+    }
+
+    assert_eq!(TextHashAlgorithm::K12.hash(data), k12_hash(data));
+    assert_ne!(
+        TextHashAlgorithm::K12.hash(data),
+        TextHashAlgorithm::Sha512Truncated.hash(data),
+        "Different algorithms produced the same hash",
+    );
+    assert_eq!(TextHashAlgorithm::from_name("md5"), None);
+}
@@ -18,6 +18,7 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::web::Validate;
 use fluent::{FluentArgs, FluentValue};
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -28,6 +29,8 @@ pub struct MessageArguments<'a> {
     inner: HashMap<Cow<'a, str>, MessageValue<'a>>,
 }
 
+impl Validate for MessageArguments<'_> {}
+
 impl<'a> MessageArguments<'a> {
     pub fn into_fluent_args(self) -> FluentArgs<'a> {
         let mut args = FluentArgs::new();
@@ -97,6 +97,9 @@ async fn main() -> Result<()> {
     let app_state = api::build_server_state(config, secrets).await?;
 
     // Run seeder, if enabled
+    //
+    // This is checked here so that nothing touches the database
+    // for seeding purposes unless it is enabled.
     if run_seeder {
         database::seed(&app_state).await?;
     }
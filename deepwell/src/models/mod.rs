@@ -3,6 +3,7 @@
 pub mod prelude;
 
 pub mod alias;
+pub mod audit_log;
 pub mod file;
 pub mod file_revision;
 pub mod filter;
@@ -11,6 +12,7 @@ pub mod page_attribution;
 pub mod page_category;
 pub mod page_connection;
 pub mod page_connection_missing;
+pub mod page_draft;
 pub mod page_link;
 pub mod page_lock;
 pub mod page_parent;
@@ -21,6 +23,8 @@ pub mod session;
 pub mod site;
 pub mod site_alias;
 pub mod site_domain;
+pub mod site_feature_flag;
+pub mod site_member;
 pub mod text;
 pub mod user;
 pub mod user_bot_owner;
@@ -1,5 +1,6 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.10.0
 
+use super::sea_orm_active_enums::PageDeletionReason;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
@@ -13,12 +14,18 @@ pub struct Model {
     pub created_at: OffsetDateTime,
     pub updated_at: Option<OffsetDateTime>,
     pub deleted_at: Option<OffsetDateTime>,
+    pub deletion_reason: Option<PageDeletionReason>,
     pub from_wikidot: bool,
     pub site_id: i64,
     pub page_category_id: i64,
     #[sea_orm(column_type = "Text")]
     pub slug: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub locale: Option<String>,
     pub discussion_thread_id: Option<i64>,
+    pub keywords: Vec<String>,
+    pub publish_at: Option<OffsetDateTime>,
+    pub expires_at: Option<OffsetDateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -45,6 +52,8 @@ pub enum Relation {
     PageAttribution,
     #[sea_orm(has_many = "super::page_lock::Entity")]
     PageLock,
+    #[sea_orm(has_many = "super::page_draft::Entity")]
+    PageDraft,
     #[sea_orm(has_many = "super::page_link::Entity")]
     PageLink,
     #[sea_orm(has_many = "super::page_vote::Entity")]
@@ -85,6 +94,12 @@ impl Related<super::page_lock::Entity> for Entity {
     }
 }
 
+impl Related<super::page_draft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageDraft.def()
+    }
+}
+
 impl Related<super::page_link::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageLink.def()
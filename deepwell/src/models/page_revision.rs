@@ -20,7 +20,7 @@ pub struct Model {
     pub from_wikidot: bool,
     pub changes: Vec<String>,
     pub wikitext_hash: Vec<u8>,
-    pub compiled_hash: Vec<u8>,
+    pub compiled_hash: Option<Vec<u8>>,
     pub compiled_at: OffsetDateTime,
     #[sea_orm(column_type = "Text")]
     pub compiled_generator: String,
@@ -1,6 +1,7 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.10.0
 
 pub use super::alias::Entity as Alias;
+pub use super::audit_log::Entity as AuditLog;
 pub use super::file::Entity as File;
 pub use super::file_revision::Entity as FileRevision;
 pub use super::filter::Entity as Filter;
@@ -9,6 +10,7 @@ pub use super::page_attribution::Entity as PageAttribution;
 pub use super::page_category::Entity as PageCategory;
 pub use super::page_connection::Entity as PageConnection;
 pub use super::page_connection_missing::Entity as PageConnectionMissing;
+pub use super::page_draft::Entity as PageDraft;
 pub use super::page_link::Entity as PageLink;
 pub use super::page_lock::Entity as PageLock;
 pub use super::page_parent::Entity as PageParent;
@@ -16,6 +18,8 @@ pub use super::page_revision::Entity as PageRevision;
 pub use super::page_vote::Entity as PageVote;
 pub use super::session::Entity as Session;
 pub use super::site::Entity as Site;
+pub use super::site_feature_flag::Entity as SiteFeatureFlag;
+pub use super::site_member::Entity as SiteMember;
 pub use super::text::Entity as Text;
 pub use super::user::Entity as User;
 pub use super::user_bot_owner::Entity as UserBotOwner;
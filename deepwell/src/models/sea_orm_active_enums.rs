@@ -15,6 +15,47 @@ pub enum AliasType {
     User,
 }
 
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "audit_action")]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditAction {
+    #[sea_orm(string_value = "filter-create")]
+    FilterCreate,
+    #[sea_orm(string_value = "filter-update")]
+    FilterUpdate,
+    #[sea_orm(string_value = "filter-delete")]
+    FilterDelete,
+    #[sea_orm(string_value = "filter-restore")]
+    FilterRestore,
+    #[sea_orm(string_value = "site-delete")]
+    SiteDelete,
+    #[sea_orm(string_value = "user-delete")]
+    UserDelete,
+}
+
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumIter,
+    DeriveActiveEnum,
+    Serialize,
+    Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "feature_flag")]
+#[serde(rename_all = "kebab-case")]
+pub enum FeatureFlag {
+    #[sea_orm(string_value = "page-drafts")]
+    PageDrafts,
+    #[sea_orm(string_value = "page-votes")]
+    PageVotes,
+}
+
 #[derive(
     Debug, Copy, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
 )]
@@ -30,6 +71,26 @@ pub enum FileRevisionType {
     #[sea_orm(string_value = "update")]
     Update,
 }
+
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "page_deletion_reason"
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum PageDeletionReason {
+    #[sea_orm(string_value = "spam")]
+    Spam,
+    #[sea_orm(string_value = "copyright")]
+    Copyright,
+    #[sea_orm(string_value = "author-request")]
+    AuthorRequest,
+    #[sea_orm(string_value = "other")]
+    Other,
+}
 #[derive(
     Debug, Copy, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
 )]
@@ -47,6 +108,20 @@ pub enum PageRevisionType {
     #[sea_orm(string_value = "undelete")]
     Undelete,
 }
+
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "site_role")]
+#[serde(rename_all = "kebab-case")]
+pub enum SiteRole {
+    #[sea_orm(string_value = "member")]
+    Member,
+    #[sea_orm(string_value = "moderator")]
+    Moderator,
+    #[sea_orm(string_value = "admin")]
+    Admin,
+}
 #[derive(
     Debug, Copy, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
 )]
@@ -60,3 +135,15 @@ pub enum UserType {
     #[sea_orm(string_value = "system")]
     System,
 }
+
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "vote_type")]
+#[serde(rename_all = "camelCase")]
+pub enum VoteType {
+    #[sea_orm(string_value = "ups-downs")]
+    UpsDowns,
+    #[sea_orm(string_value = "five-star")]
+    FiveStar,
+}
@@ -13,7 +13,12 @@ pub struct Model {
     pub user_id: i64,
     pub created_at: OffsetDateTime,
     pub expires_at: OffsetDateTime,
+    pub last_active_at: OffsetDateTime,
     pub ip_address: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub country: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub region: Option<String>,
     #[sea_orm(column_type = "Text")]
     pub user_agent: String,
     pub restricted: bool,
@@ -1,5 +1,6 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.10.0
 
+use super::sea_orm_active_enums::VoteType;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
@@ -27,6 +28,18 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub default_page: String,
     #[sea_orm(column_type = "Text")]
+    pub default_category: String,
+    pub slug_allow_categories: bool,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub slug_pattern: Option<String>,
+    pub require_revision_comments: bool,
+    pub allow_anonymous_edits: bool,
+    pub vote_type: VoteType,
+    pub retain_compiled_html: bool,
+    pub disabled_blocks: Vec<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub robots_txt: Option<String>,
+    #[sea_orm(column_type = "Text")]
     pub custom_domain: Option<String>,
 }
 
@@ -42,6 +55,8 @@ pub enum Relation {
     Filter,
     #[sea_orm(has_many = "super::site_domain::Entity")]
     SiteDomain,
+    #[sea_orm(has_many = "super::site_feature_flag::Entity")]
+    SiteFeatureFlag,
 }
 
 impl Related<super::page_category::Entity> for Entity {
@@ -74,4 +89,10 @@ impl Related<super::site_domain::Entity> for Entity {
     }
 }
 
+impl Related<super::site_feature_flag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteFeatureFlag.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -12,6 +12,9 @@ pub struct Model {
     pub domain: String,
     pub site_id: i64,
     pub created_at: OffsetDateTime,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub verification_token: Option<String>,
+    pub verified_at: Option<OffsetDateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -10,6 +10,8 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub hash: Vec<u8>,
     #[sea_orm(column_type = "Text")]
+    pub hash_algorithm: String,
+    #[sea_orm(column_type = "Text")]
     pub contents: String,
 }
 
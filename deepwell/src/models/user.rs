@@ -22,9 +22,17 @@ pub struct Model {
     pub slug: String,
     pub name_changes_left: i16,
     pub last_renamed_at: Option<OffsetDateTime>,
+    pub name_changes_refilled_at: OffsetDateTime,
+    pub last_login_at: Option<OffsetDateTime>,
+    pub failed_login_count: i32,
+    pub last_failed_login_at: Option<OffsetDateTime>,
     #[sea_orm(column_type = "Text")]
     pub email: String,
     pub email_verified_at: Option<OffsetDateTime>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub pending_email: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub pending_email_token: Option<String>,
     #[sea_orm(column_type = "Text")]
     pub password: String,
     #[sea_orm(column_type = "Text", nullable)]
@@ -1,5 +1,5 @@
 /*
- * services/score/impls/null.rs
+ * services/audit_log/mod.rs
  *
  * DEEPWELL - Wikijump API provider and database manager
  * Copyright (C) 2019-2023 Wikijump Team
@@ -18,25 +18,13 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::prelude::*;
-
-#[derive(Debug)]
-pub struct NullScorer;
-
-#[async_trait]
-impl Scorer for NullScorer {
-    #[inline]
-    fn score_type(&self) -> ScoreType {
-        ScoreType::Null
-    }
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
 
-    #[inline]
-    fn accepts_vote_type(&self, _: VoteType) -> bool {
-        true
-    }
+mod service;
+mod structs;
 
-    #[inline]
-    async fn score(&self, _: &DatabaseTransaction, _: Condition) -> Result<ScoreValue> {
-        Ok(ScoreValue::Integer(0))
-    }
-}
+pub use self::service::AuditLogService;
+pub use self::structs::*;
@@ -0,0 +1,138 @@
+/*
+ * services/audit_log/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Service for the audit log of privileged actions.
+//!
+//! Services performing sensitive operations (such as changing filters)
+//! record an entry here, naming the user responsible, so that
+//! administrators can review them later.
+
+use super::prelude::*;
+use crate::models::audit_log::{self, Entity as AuditLog, Model as AuditLogModel};
+use time::OffsetDateTime;
+
+#[derive(Debug)]
+pub struct AuditLogService;
+
+impl AuditLogService {
+    pub async fn record(
+        ctx: &ServiceContext<'_>,
+        entry: RecordAuditEntry,
+    ) -> Result<AuditLogModel> {
+        tide::log::info!(
+            "Recording audit log entry for {:?} on ID {} by user ID {}",
+            entry.action,
+            entry.target_id,
+            entry.actor_id,
+        );
+
+        let txn = ctx.transaction();
+        let model = audit_log_model(entry, ctx.now());
+        let audit_log = model.insert(txn).await?;
+        Ok(audit_log)
+    }
+
+    /// Gets audit log entries matching the given filters, newest first.
+    pub async fn list(
+        ctx: &ServiceContext<'_>,
+        input: GetAuditLog,
+    ) -> Result<Vec<AuditLogModel>> {
+        tide::log::info!("Getting audit log entries matching {input:?}");
+
+        let txn = ctx.transaction();
+        let entries = AuditLog::find()
+            .filter(input.to_condition())
+            .order_by_desc(audit_log::Column::CreatedAt)
+            .order_by_desc(audit_log::Column::AuditLogId)
+            .limit(input.limit)
+            .all(txn)
+            .await?;
+
+        Ok(entries)
+    }
+}
+
+/// Builds the model to insert for an audit log entry.
+fn audit_log_model(
+    RecordAuditEntry {
+        actor_id,
+        action,
+        target_id,
+        detail,
+    }: RecordAuditEntry,
+    created_at: OffsetDateTime,
+) -> audit_log::ActiveModel {
+    audit_log::ActiveModel {
+        created_at: Set(created_at),
+        actor_id: Set(actor_id),
+        action: Set(action),
+        target_id: Set(target_id),
+        detail: Set(detail),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn audit_log_entries() {
+    use crate::models::sea_orm_active_enums::AuditAction;
+    use sea_orm::{DbBackend, QueryTrait};
+    use serde_json::json;
+
+    let created_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let entry = RecordAuditEntry {
+        actor_id: 4,
+        action: AuditAction::FilterDelete,
+        target_id: 12,
+        detail: json!({ "regex": "spam" }),
+    };
+
+    let model = audit_log_model(entry, created_at);
+    assert_eq!(model.created_at, Set(created_at));
+    assert_eq!(model.actor_id, Set(4));
+    assert_eq!(model.action, Set(AuditAction::FilterDelete));
+    assert_eq!(model.target_id, Set(12));
+    assert_eq!(model.detail, Set(json!({ "regex": "spam" })));
+    assert!(model.audit_log_id.is_not_set());
+
+    // Listing entries by actor and action
+    let input = GetAuditLog {
+        actor_id: Some(4),
+        action: Some(AuditAction::FilterDelete),
+        ..Default::default()
+    };
+
+    let sql = AuditLog::find()
+        .filter(input.to_condition())
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains(r#""audit_log"."actor_id" = 4"#),
+        "Actor missing: {sql}",
+    );
+    assert!(
+        sql.contains(r#""audit_log"."action" = CAST('filter-delete' AS audit_action)"#),
+        "Action missing: {sql}",
+    );
+    assert!(
+        !sql.contains(r#""audit_log"."target_id" ="#),
+        "Target included when not requested: {sql}",
+    );
+}
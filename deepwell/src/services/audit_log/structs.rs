@@ -0,0 +1,75 @@
+/*
+ * services/audit_log/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::audit_log;
+use crate::models::sea_orm_active_enums::AuditAction;
+use crate::web::Validate;
+use sea_orm::{ColumnTrait, Condition};
+use serde_json::Value as JsonValue;
+use time::OffsetDateTime;
+
+/// A privileged action to be recorded in the audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordAuditEntry {
+    /// The user who performed the action.
+    pub actor_id: i64,
+    pub action: AuditAction,
+
+    /// The ID of the object acted upon, for instance the filter ID.
+    pub target_id: i64,
+
+    /// Additional information about the action, such as the state of the object.
+    pub detail: JsonValue,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GetAuditLog {
+    pub actor_id: Option<i64>,
+    pub action: Option<AuditAction>,
+    pub target_id: Option<i64>,
+    pub since: Option<OffsetDateTime>,
+    pub limit: Option<u64>,
+}
+
+impl Validate for GetAuditLog {}
+
+impl GetAuditLog {
+    /// Converts these filters into a condition which can be used for queries.
+    pub fn to_condition(&self) -> Condition {
+        Condition::all()
+            .add_option(
+                self.actor_id
+                    .map(|actor_id| audit_log::Column::ActorId.eq(actor_id)),
+            )
+            .add_option(
+                self.action
+                    .map(|action| audit_log::Column::Action.eq(action)),
+            )
+            .add_option(
+                self.target_id
+                    .map(|target_id| audit_log::Column::TargetId.eq(target_id)),
+            )
+            .add_option(
+                self.since
+                    .map(|since| audit_log::Column::CreatedAt.gte(since)),
+            )
+    }
+}
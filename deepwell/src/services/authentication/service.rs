@@ -20,7 +20,8 @@
 
 use super::prelude::*;
 use crate::models::user::{self, Entity as User, Model as UserModel};
-use crate::services::{MfaService, PasswordService, SessionService};
+use crate::services::{MfaService, PasswordService, SessionService, UserService};
+use time::OffsetDateTime;
 
 #[derive(Debug)]
 pub struct AuthenticationService;
@@ -36,13 +37,33 @@ impl AuthenticationService {
         }: AuthenticateUser,
     ) -> Result<AuthenticateUserOutput> {
         let auth = Self::get_user_auth(ctx, &name_or_email).await?;
-        PasswordService::verify(ctx, &password, &auth.password_hash).await?;
+        let result = PasswordService::verify(ctx, &password, &auth.password_hash).await;
 
         // User not found, return authentication failure
         if !auth.valid {
             return Err(Error::InvalidAuthentication);
         }
 
+        // Too many failed logins, refuse until the lockout has passed.
+        //
+        // This is checked after the password, so that it takes the same
+        // amount of time. These attempts are not counted as failures,
+        // otherwise the lockout would be extended by each one.
+        if is_locked_out(ctx.config(), &auth, ctx.now()) {
+            tide::log::warn!(
+                "User ID {} is locked out after too many failed logins",
+                auth.user_id,
+            );
+            return Err(Error::RateLimited);
+        }
+
+        // Incorrect password, count it against the user
+        if let Err(Error::InvalidAuthentication) = result {
+            UserService::record_failed_login(ctx, auth.user_id).await?;
+        }
+
+        result?;
+
         Ok(AuthenticateUserOutput {
             needs_mfa: auth.multi_factor_secret.is_some(),
             user_id: auth.user_id,
@@ -119,3 +140,45 @@ impl AuthenticationService {
         }
     }
 }
+
+/// Determines if a user may not log in, due to too many recent failed logins.
+fn is_locked_out(config: &Config, auth: &UserAuthInfo, now: OffsetDateTime) -> bool {
+    match auth.last_failed_login_at {
+        Some(last_failed_at) => {
+            i64::from(auth.failed_login_count) >= i64::from(config.max_failed_logins)
+                && now < last_failed_at + config.failed_login_lockout
+        }
+        None => false,
+    }
+}
+
+#[test]
+fn login_lockout() {
+    use crate::services::fixtures;
+    use time::Duration;
+
+    let config = Config::example();
+    let now = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let auth = |failed_login_count, last_failed_login_at| {
+        UserAuthInfo::valid(UserModel {
+            failed_login_count,
+            last_failed_login_at,
+            ..fixtures::user(4, "test")
+        })
+    };
+
+    let max = config.max_failed_logins as i32;
+    let recently = Some(now - Duration::MINUTE);
+    let long_ago = Some(now - config.failed_login_lockout - Duration::MINUTE);
+
+    // Users who haven't failed enough times may log in
+    assert!(!is_locked_out(&config, &auth(0, None), now));
+    assert!(!is_locked_out(&config, &auth(max - 1, recently), now));
+
+    // But not after too many failures
+    assert!(is_locked_out(&config, &auth(max, recently), now));
+    assert!(is_locked_out(&config, &auth(max + 5, recently), now));
+
+    // Until the lockout has passed
+    assert!(!is_locked_out(&config, &auth(max, long_ago), now));
+}
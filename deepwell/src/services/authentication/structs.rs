@@ -19,7 +19,9 @@
  */
 
 use crate::models::user::Model as UserModel;
+use crate::web::{check_not_blank, Validate, ValidationResult};
 use std::net::IpAddr;
+use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -45,6 +47,12 @@ pub struct LoginUser {
     pub authenticate: AuthenticateUser,
 }
 
+impl Validate for LoginUser {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("name_or_email", &self.authenticate.name_or_email)
+    }
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct LoginUserOutput {
@@ -67,6 +75,14 @@ pub struct LoginUserMfa {
     pub user_agent: String,
 }
 
+impl Validate for LoginUserMfa {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("session_token", &self.session_token)?;
+        check_not_blank("totp_or_code", &self.totp_or_code)?;
+        Ok(())
+    }
+}
+
 /// Password hash to compute against when a user does not exist.
 ///
 /// It has similar settings to other passwords on Wikijump, but
@@ -82,6 +98,8 @@ pub struct UserAuthInfo {
     pub user_id: i64,
     pub password_hash: String,
     pub multi_factor_secret: Option<String>,
+    pub failed_login_count: i32,
+    pub last_failed_login_at: Option<OffsetDateTime>,
     pub valid: bool,
 }
 
@@ -91,6 +109,8 @@ impl UserAuthInfo {
             user_id: user.user_id,
             password_hash: user.password,
             multi_factor_secret: user.multi_factor_secret,
+            failed_login_count: user.failed_login_count,
+            last_failed_login_at: user.last_failed_login_at,
             valid: true,
         }
     }
@@ -101,6 +121,8 @@ impl UserAuthInfo {
             user_id: 0,
             password_hash: str!(INVALID_PASSWORD_HASH),
             multi_factor_secret: None,
+            failed_login_count: 0,
+            last_failed_login_at: None,
             valid: false,
         }
     }
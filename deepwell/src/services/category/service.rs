@@ -19,9 +19,14 @@
  */
 
 use super::prelude::*;
+use crate::models::page::{self, Model as PageModel};
 use crate::models::page_category::{
     self, Entity as PageCategory, Model as PageCategoryModel,
 };
+use crate::services::{PageService, SiteService};
+use crate::utils::get_category_name;
+use crate::web::PageOrder;
+use std::collections::HashSet;
 
 #[derive(Debug)]
 pub struct CategoryService;
@@ -80,11 +85,23 @@ impl CategoryService {
         find_or_error(Self::get_optional(ctx, site_id, reference)).await
     }
 
+    /// Gets the category with this slug, creating it if it doesn't exist.
+    ///
+    /// If the slug is `_default`, then the site's configured
+    /// default category is used instead.
     pub async fn get_or_create(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         slug: &str,
     ) -> Result<PageCategoryModel> {
+        let site;
+        let slug = if slug == "_default" {
+            site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+            site.default_category.as_str()
+        } else {
+            slug
+        };
+
         let category =
             match Self::get_optional(ctx, site_id, Reference::from(slug)).await? {
                 Some(category) => category,
@@ -108,4 +125,111 @@ impl CategoryService {
 
         Ok(categories)
     }
+
+    /// Rebuilds the category assignments for all pages in a site.
+    ///
+    /// Each page's category is derived from its slug, creating the category
+    /// if necessary, and the page is reassigned if it was in a different
+    /// category. Afterwards, any categories without pages are deleted.
+    ///
+    /// This is a maintenance operation, for use if category rows have drifted
+    /// from page slugs, for instance after manual database edits.
+    pub async fn rebuild(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<RebuildCategoriesOutput> {
+        tide::log::info!("Rebuilding page categories for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let pages =
+            PageService::get_all(ctx, site_id, None, None, PageOrder::default()).await?;
+
+        let mut output = RebuildCategoriesOutput::default();
+        let mut used_categories = HashSet::new();
+
+        // Ensure each page is in the correct category
+        for page in pages {
+            let PageCategoryModel { category_id, .. } =
+                Self::get_or_create(ctx, site_id, get_category_name(&page.slug)).await?;
+
+            used_categories.insert(category_id);
+
+            if page.page_category_id != category_id {
+                tide::log::debug!(
+                    "Moving page ID {} from category ID {} to {}",
+                    page.page_id,
+                    page.page_category_id,
+                    category_id,
+                );
+
+                let model = page::ActiveModel {
+                    page_id: Set(page.page_id),
+                    page_category_id: Set(category_id),
+                    ..Default::default()
+                };
+                model.update(txn).await?;
+                ctx.invalidate_model::<PageModel>(page.page_id);
+                output.updated_pages.push(page.page_id);
+            }
+        }
+
+        // Delete any categories which are no longer used
+        let categories = Self::get_all(ctx, site_id).await?;
+        for category in orphaned_categories(categories, &used_categories) {
+            tide::log::debug!(
+                "Deleting orphaned category '{}' (ID {})",
+                category.slug,
+                category.category_id,
+            );
+
+            PageCategory::delete_by_id(category.category_id)
+                .exec(txn)
+                .await?;
+
+            output.deleted_categories.push(category.slug);
+        }
+
+        Ok(output)
+    }
+}
+
+/// Gets the categories which are not used by any page.
+fn orphaned_categories(
+    categories: Vec<PageCategoryModel>,
+    used_categories: &HashSet<i64>,
+) -> Vec<PageCategoryModel> {
+    categories
+        .into_iter()
+        .filter(|category| !used_categories.contains(&category.category_id))
+        .collect()
+}
+
+#[test]
+fn orphaned_category_cleanup() {
+    use crate::services::fixtures;
+
+    let category = |category_id, slug| fixtures::page_category(1, category_id, slug);
+
+    let categories = vec![
+        category(1, "_default"),
+        category(2, "fragment"),
+        category(3, "system"),
+        category(4, "old"),
+    ];
+
+    // Pages "start", "system:members", and "system:join" remain
+    let used = [1, 3].into_iter().collect();
+    let orphans: Vec<_> = orphaned_categories(categories.clone(), &used)
+        .into_iter()
+        .map(|category| category.slug)
+        .collect();
+
+    assert_eq!(orphans, ["fragment", "old"], "Wrong categories deleted");
+
+    // All categories in use
+    let used = [1, 2, 3, 4].into_iter().collect();
+    assert!(
+        orphaned_categories(categories, &used).is_empty(),
+        "Used categories deleted",
+    );
 }
@@ -19,7 +19,7 @@
  */
 
 use crate::models::page_category::Model as PageCategoryModel;
-use crate::web::Reference;
+use crate::web::{Reference, Validate};
 use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug)]
@@ -29,6 +29,8 @@ pub struct GetCategory<'a> {
     pub category: Reference<'a>,
 }
 
+impl Validate for GetCategory<'_> {}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CategoryOutput {
@@ -59,3 +61,10 @@ impl From<PageCategoryModel> for CategoryOutput {
         }
     }
 }
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RebuildCategoriesOutput {
+    pub updated_pages: Vec<i64>,
+    pub deleted_categories: Vec<String>,
+}
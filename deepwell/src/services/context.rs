@@ -18,16 +18,28 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::feature_flag::FeatureFlags;
+use super::model_cache::{self, CachedModel, ModelCache};
+use super::session::GeoResolver;
+use super::Result;
 use crate::api::{ApiRequest, ApiServerState};
 use crate::config::Config;
+use crate::utils::{retry_with_backoff, Clock};
+use async_lock::Semaphore;
 use s3::bucket::Bucket;
-use sea_orm::DatabaseTransaction;
-use std::sync::Arc;
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseTransaction, Statement};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
 
 #[derive(Debug)]
 pub struct ServiceContext<'txn> {
     state: ApiServerState,
     transaction: &'txn DatabaseTransaction,
+    clock: Clock,
+    feature_flags: Mutex<HashMap<i64, FeatureFlags>>,
+    models: Mutex<ModelCache>,
 }
 
 impl<'txn> ServiceContext<'txn> {
@@ -43,9 +55,20 @@ impl<'txn> ServiceContext<'txn> {
         ServiceContext {
             state: Arc::clone(state),
             transaction,
+            clock: Clock::system(),
+            feature_flags: Mutex::new(HashMap::new()),
+            models: Mutex::new(ModelCache::default()),
         }
     }
 
+    /// Replaces the clock used by services, such as a fixed one for tests.
+    #[inline]
+    #[cfg(test)]
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     // Getters
     #[inline]
     pub fn config(&self) -> &Config {
@@ -57,8 +80,130 @@ impl<'txn> ServiceContext<'txn> {
         &self.state.s3_bucket
     }
 
+    #[inline]
+    pub fn geo_resolver(&self) -> &dyn GeoResolver {
+        self.state.geo_resolver.as_ref()
+    }
+
+    /// Gets the semaphore limiting how many renders may run at once.
+    #[inline]
+    pub fn render_semaphore(&self) -> &Semaphore {
+        &self.state.render_semaphore
+    }
+
     #[inline]
     pub fn transaction(&self) -> &'txn DatabaseTransaction {
         self.transaction
     }
+
+    /// Gets the current time, according to this context's clock.
+    ///
+    /// Services with time-dependent behavior should use this
+    /// rather than `now()`, so that it can be tested.
+    #[inline]
+    pub fn now(&self) -> OffsetDateTime {
+        self.clock.now()
+    }
+
+    // Feature flags
+
+    pub fn cached_feature_flags(&self, site_id: i64) -> Option<FeatureFlags> {
+        let cache = self
+            .feature_flags
+            .lock()
+            .expect("Feature flag cache poisoned");
+        cache.get(&site_id).cloned()
+    }
+
+    pub fn cache_feature_flags(&self, site_id: i64, flags: FeatureFlags) {
+        let mut cache = self
+            .feature_flags
+            .lock()
+            .expect("Feature flag cache poisoned");
+        cache.insert(site_id, flags);
+    }
+
+    pub fn invalidate_feature_flags(&self, site_id: i64) {
+        let mut cache = self
+            .feature_flags
+            .lock()
+            .expect("Feature flag cache poisoned");
+        cache.remove(&site_id);
+    }
+
+    // Model cache
+
+    /// Gets a model by ID, only loading it if it hasn't been already in this request.
+    ///
+    /// Services which write to a cached model must call `invalidate_model()`.
+    /// See the `model_cache` module for more information.
+    #[inline]
+    pub async fn cached_model<M, F, Fut>(&self, id: i64, load: F) -> Result<Option<M>>
+    where
+        M: CachedModel,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<M>>>,
+    {
+        model_cache::get_or_load(&self.models, id, load).await
+    }
+
+    #[inline]
+    pub fn invalidate_model<M: CachedModel>(&self, id: i64) {
+        model_cache::lock(&self.models).remove::<M>(id);
+    }
+
+    /// Invalidates all cached models of a type, for writes affecting many rows.
+    #[inline]
+    pub fn invalidate_models<M: CachedModel>(&self) {
+        model_cache::lock(&self.models).clear::<M>();
+    }
+
+    // Helpers
+
+    /// Runs a read operation, retrying on transient database errors.
+    ///
+    /// The number of attempts and initial backoff delay are set in the
+    /// configuration. Only idempotent operations should be passed here,
+    /// writes must never be retried this way.
+    ///
+    /// Each attempt runs inside a savepoint, which is rolled back if it fails.
+    /// Otherwise a failed attempt would leave the transaction aborted, and
+    /// every retry after it would fail too.
+    pub async fn retry_read<F, Fut, T>(&self, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let config = self.config();
+        let txn = self.transaction;
+        retry_with_backoff(config.read_retry_attempts, config.read_retry_delay, || {
+            with_savepoint(txn, operation())
+        })
+        .await
+    }
+}
+
+async fn with_savepoint<Fut, T>(txn: &DatabaseTransaction, attempt: Fut) -> Result<T>
+where
+    Fut: Future<Output = Result<T>>,
+{
+    async fn run(txn: &DatabaseTransaction, sql: &str) -> Result<()> {
+        txn.execute(Statement::from_string(DatabaseBackend::Postgres, str!(sql)))
+            .await?;
+
+        Ok(())
+    }
+
+    run(txn, "SAVEPOINT retry_read").await?;
+
+    match attempt.await {
+        Ok(value) => {
+            run(txn, "RELEASE SAVEPOINT retry_read").await?;
+            Ok(value)
+        }
+        Err(error) => {
+            run(txn, "ROLLBACK TO SAVEPOINT retry_read").await?;
+            Err(error)
+        }
+    }
 }
@@ -0,0 +1,76 @@
+/*
+ * services/domain/dns.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Resolution of DNS records, used to verify ownership of custom domains.
+//!
+//! This is abstracted behind a trait so that lookups can be replaced in tests.
+
+use super::prelude::*;
+use async_std_resolver::{resolver_from_system_conf, AsyncStdResolver};
+use tide::utils::async_trait;
+use trust_dns_resolver::error::ResolveErrorKind;
+
+#[async_trait]
+pub trait DnsResolver: Sync {
+    /// Gets the contents of all TXT records for the given name.
+    ///
+    /// If there are no such records, or the name does not exist,
+    /// this returns an empty list rather than an error.
+    async fn txt_records(&self, name: &str) -> Result<Vec<String>>;
+}
+
+/// Resolver which performs lookups using the system's DNS configuration.
+#[derive(Debug)]
+pub struct SystemDnsResolver {
+    resolver: AsyncStdResolver,
+}
+
+impl SystemDnsResolver {
+    pub async fn new() -> Result<Self> {
+        match resolver_from_system_conf().await {
+            Ok(resolver) => Ok(SystemDnsResolver { resolver }),
+            Err(error) => {
+                tide::log::error!("Unable to create DNS resolver: {error}");
+                Err(Error::RemoteOperationFailed)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DnsResolver for SystemDnsResolver {
+    async fn txt_records(&self, name: &str) -> Result<Vec<String>> {
+        tide::log::debug!("Looking up TXT records for '{name}'");
+
+        match self.resolver.txt_lookup(name).await {
+            // Multiple strings in a single record are concatenated
+            Ok(lookup) => Ok(lookup.iter().map(|txt| txt.to_string()).collect()),
+            Err(error) => match error.kind() {
+                ResolveErrorKind::NoRecordsFound { .. } => Ok(vec![]),
+                _ => {
+                    tide::log::error!(
+                        "Unable to look up TXT records for '{name}': {error}"
+                    );
+                    Err(Error::RemoteOperationFailed)
+                }
+            },
+        }
+    }
+}
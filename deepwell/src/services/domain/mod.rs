@@ -23,8 +23,10 @@ mod prelude {
     pub use super::structs::*;
 }
 
+mod dns;
 mod service;
 mod structs;
 
+pub use self::dns::{DnsResolver, SystemDnsResolver};
 pub use self::service::DomainService;
 pub use self::structs::*;
@@ -25,12 +25,24 @@
 
 // TODO disallow custom domains that are subdomains of the main domain or files domain
 
+use super::dns::{DnsResolver, SystemDnsResolver};
 use super::prelude::*;
 use crate::models::site::{self, Entity as Site, Model as SiteModel};
 use crate::models::site_domain::{self, Entity as SiteDomain, Model as SiteDomainModel};
 use crate::services::SiteService;
+use crate::utils::assert_is_csprng;
+use crate::web::Pagination;
+use rand::distributions::{Alphanumeric, DistString};
+use rand::thread_rng;
+use sea_orm::Select;
 use std::borrow::Cow;
 
+/// The prefix for the TXT record used to verify custom domain ownership.
+const VERIFICATION_RECORD_PREFIX: &str = "_wikijump-verification.";
+
+/// The length of generated custom domain verification tokens.
+const VERIFICATION_TOKEN_LENGTH: usize = 32;
+
 #[derive(Debug)]
 pub struct DomainService;
 
@@ -43,6 +55,7 @@ impl DomainService {
         tide::log::info!("Creating custom domain '{domain}' (site ID {site_id})");
 
         let txn = ctx.transaction();
+        let domain = Self::normalize_domain(&domain)?;
         if Self::custom_domain_exists(ctx, &domain).await? {
             tide::log::error!("Custom domain already exists, cannot create");
             return Err(Error::Conflict);
@@ -51,7 +64,8 @@ impl DomainService {
         let model = site_domain::ActiveModel {
             domain: Set(domain),
             site_id: Set(site_id),
-            created_at: Set(now()),
+            created_at: Set(ctx.now()),
+            ..Default::default()
         };
         model.insert(txn).await?;
         Ok(())
@@ -64,6 +78,7 @@ impl DomainService {
         tide::log::info!("Deleting custom domain '{domain}'");
 
         let txn = ctx.transaction();
+        let domain = Self::normalize_domain(&domain)?;
         let DeleteResult { rows_affected, .. } =
             SiteDomain::delete_by_id(domain).exec(txn).await?;
 
@@ -74,12 +89,112 @@ impl DomainService {
         }
     }
 
+    /// Generates a token to verify ownership of a custom domain via DNS.
+    ///
+    /// The token must be placed in a TXT record at the returned name.
+    /// Any previously generated token is replaced, and the domain
+    /// is no longer considered verified until it is checked again.
+    pub async fn generate_verification_token(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        domain: &str,
+    ) -> Result<GenerateDomainVerificationOutput> {
+        tide::log::info!(
+            "Generating verification token for custom domain '{domain}' (site ID {site_id})",
+        );
+
+        let txn = ctx.transaction();
+        let domain = Self::normalize_domain(domain)?;
+        let model = Self::get_custom(ctx, &domain).await?;
+        if model.site_id != site_id {
+            tide::log::error!(
+                "Custom domain belongs to site ID {}, not {site_id}",
+                model.site_id,
+            );
+            return Err(Error::NotFound);
+        }
+
+        let token = {
+            let mut rng = thread_rng();
+            assert_is_csprng(&rng);
+            Alphanumeric.sample_string(&mut rng, VERIFICATION_TOKEN_LENGTH)
+        };
+
+        let model = site_domain::ActiveModel {
+            domain: Set(domain),
+            verification_token: Set(Some(token.clone())),
+            verified_at: Set(None),
+            ..Default::default()
+        };
+        let SiteDomainModel { domain, .. } = model.update(txn).await?;
+
+        Ok(GenerateDomainVerificationOutput {
+            record_name: verification_record_name(&domain),
+            token,
+        })
+    }
+
+    /// Checks the DNS records of a custom domain for its verification token.
+    ///
+    /// If a matching TXT record is found, the domain is marked as verified.
+    /// Yields `Error::BadRequest` if no token was ever generated.
+    #[inline]
+    pub async fn verify_dns(ctx: &ServiceContext<'_>, domain: &str) -> Result<bool> {
+        let resolver = SystemDnsResolver::new().await?;
+        Self::verify_dns_with(ctx, domain, &resolver).await
+    }
+
+    /// Version of `verify_dns()` which uses the given DNS resolver.
+    pub async fn verify_dns_with(
+        ctx: &ServiceContext<'_>,
+        domain: &str,
+        resolver: &dyn DnsResolver,
+    ) -> Result<bool> {
+        tide::log::info!("Verifying DNS ownership of custom domain '{domain}'");
+
+        let txn = ctx.transaction();
+        let domain = Self::normalize_domain(domain)?;
+        let token = match Self::get_custom(ctx, &domain).await?.verification_token {
+            Some(token) => token,
+            None => {
+                tide::log::error!("No verification token generated for '{domain}'");
+                return Err(Error::BadRequest);
+            }
+        };
+
+        if !dns_token_matches(resolver, &domain, &token).await? {
+            tide::log::warn!("Verification token not found in DNS for '{domain}'");
+            return Ok(false);
+        }
+
+        let model = site_domain::ActiveModel {
+            domain: Set(domain),
+            verified_at: Set(Some(ctx.now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+        Ok(true)
+    }
+
+    /// Gets the model for a custom domain, which must already be normalized.
+    async fn get_custom(
+        ctx: &ServiceContext<'_>,
+        domain: &str,
+    ) -> Result<SiteDomainModel> {
+        let txn = ctx.transaction();
+        let model = SiteDomain::find_by_id(str!(domain)).one(txn).await?;
+        model.ok_or(Error::NotFound)
+    }
+
     pub async fn site_from_custom_domain_optional(
         ctx: &ServiceContext<'_>,
         domain: &str,
     ) -> Result<Option<SiteModel>> {
         tide::log::info!("Getting site for custom domain '{domain}'");
 
+        // Use the same form as stored, so Unicode and punycode hosts match.
+        let domain = Self::normalize_domain(domain)?;
+
         // Join with the site table so we can get that data, rather than just the ID.
         let txn = ctx.transaction();
         let model = Site::find()
@@ -118,7 +233,10 @@ impl DomainService {
     ) -> Result<Option<SiteModel>> {
         tide::log::info!("Getting site for domain '{domain}'");
 
-        match Self::parse_canonical(ctx.config(), domain) {
+        // Hostnames are case-insensitive
+        let domain = domain.to_ascii_lowercase();
+
+        match Self::parse_canonical(ctx.config(), &domain) {
             // Normal canonical domain, return from site slug fetch.
             Some(subdomain) => {
                 tide::log::debug!("Found canonical domain with slug '{subdomain}'");
@@ -128,7 +246,7 @@ impl DomainService {
             // Not canonical, try custom domain.
             None => {
                 tide::log::debug!("Not found, checking if it's a custom domain");
-                Self::site_from_custom_domain_optional(ctx, domain).await
+                Self::site_from_custom_domain_optional(ctx, &domain).await
             }
         }
     }
@@ -147,10 +265,35 @@ impl DomainService {
         find_or_error(Self::site_from_domain_optional(ctx, domain)).await
     }
 
+    /// Converts a domain into its ASCII form, for storage and comparison.
+    ///
+    /// Internationalized domain names are converted to punycode, so that a
+    /// Unicode domain and its punycode equivalent are treated identically.
+    /// The result is always lowercase, since hostnames are case-insensitive.
+    pub fn normalize_domain(domain: &str) -> Result<String> {
+        match idna::domain_to_ascii(domain) {
+            Ok(mut domain) => {
+                domain.make_ascii_lowercase();
+                Ok(domain)
+            }
+            Err(error) => {
+                tide::log::error!("Invalid domain name '{domain}': {error:?}");
+                Err(Error::BadRequest)
+            }
+        }
+    }
+
     /// If this domain is canonical domain, extract the site slug.
+    ///
+    /// Each of the configured main domains is checked in turn.
     pub fn parse_canonical<'a>(config: &Config, domain: &'a str) -> Option<&'a str> {
-        let main_domain = &config.main_domain;
+        config
+            .main_domains
+            .iter()
+            .find_map(|main_domain| Self::parse_canonical_for(main_domain, domain))
+    }
 
+    fn parse_canonical_for<'a>(main_domain: &str, domain: &'a str) -> Option<&'a str> {
         // Special case, see if it's the root domain (i.e. 'wikijump.com')
         {
             // This slice is safe, we know the first character of 'main_domain'
@@ -185,7 +328,20 @@ impl DomainService {
     #[inline]
     pub fn get_canonical(config: &Config, site_slug: &str) -> String {
         // 'main_domain' is already prefixed with .
-        format!("{}{}", site_slug, config.main_domain)
+        format!("{}{}", site_slug, config.main_domain())
+    }
+
+    /// Gets the files domain for this instance, which begins with a `.`.
+    ///
+    /// Yields `Error::NotFound` if the instance does not serve user files.
+    pub fn files_domain(config: &Config) -> Result<&str> {
+        match config.files_domain {
+            Some(ref domain) => Ok(domain),
+            None => {
+                tide::log::warn!("No files domain configured, user files are disabled");
+                Err(Error::NotFound)
+            }
+        }
     }
 
     /// Gets the preferred domain for the given site.
@@ -210,7 +366,7 @@ impl DomainService {
     /// slug is an internal detail.
     fn www_domain(config: &Config) -> Cow<'static, str> {
         // This starts with . so we remove it and return
-        let mut main_domain = str!(config.main_domain);
+        let mut main_domain = str!(config.main_domain());
         debug_assert_eq!(main_domain.remove(0), '.');
         Cow::Owned(main_domain)
     }
@@ -230,4 +386,331 @@ impl DomainService {
 
         Ok(models)
     }
+
+    /// Gets one page of custom domains across all sites, along with their sites.
+    ///
+    /// Domains are in alphabetical order. Those for deleted sites are excluded.
+    /// A page size of zero is rejected as a bad request.
+    pub async fn list_all(
+        ctx: &ServiceContext<'_>,
+        pagination: Pagination,
+    ) -> Result<Vec<CustomDomainExport>> {
+        tide::log::info!(
+            "Getting page {} of all custom domains (page size {})",
+            pagination.page,
+            pagination.page_size,
+        );
+
+        if pagination.page_size == 0 {
+            tide::log::error!("Cannot get custom domains with a page size of zero");
+            return Err(Error::BadRequest);
+        }
+
+        let txn = ctx.transaction();
+        let domains = build_domain_export_query(pagination)
+            .into_model::<CustomDomainExport>()
+            .all(txn)
+            .await?;
+
+        Ok(domains)
+    }
+}
+
+fn build_domain_export_query(pagination: Pagination) -> Select<SiteDomain> {
+    let query = SiteDomain::find()
+        .select_only()
+        .column(site_domain::Column::Domain)
+        .column(site_domain::Column::SiteId)
+        .column_as(site::Column::Slug, "site_slug")
+        .column_as(site_domain::Column::VerifiedAt.is_not_null(), "verified")
+        .join(JoinType::InnerJoin, site_domain::Relation::Site.def())
+        .filter(site::Column::DeletedAt.is_null())
+        .order_by_asc(site_domain::Column::Domain);
+
+    pagination.apply(query)
+}
+
+/// Gets the name of the TXT record used to verify a custom domain.
+#[inline]
+fn verification_record_name(domain: &str) -> String {
+    format!("{VERIFICATION_RECORD_PREFIX}{domain}")
+}
+
+/// Determines if the verification token is present in the domain's TXT records.
+async fn dns_token_matches(
+    resolver: &dyn DnsResolver,
+    domain: &str,
+    token: &str,
+) -> Result<bool> {
+    let name = verification_record_name(domain);
+    let records = resolver.txt_records(&name).await?;
+    Ok(records.iter().any(|record| record.trim() == token))
+}
+
+#[test]
+fn normalize_domain() {
+    macro_rules! check {
+        ($input:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                DomainService::normalize_domain($input)
+                    .expect("Unable to normalize domain")
+                    .as_str(),
+                $expected,
+                "Normalized domain doesn't match expected",
+            );
+        };
+    }
+
+    check!("scpwiki.com", "scpwiki.com");
+    check!("SCPWiki.com", "scpwiki.com");
+    check!("Example.COM", "example.com");
+    check!("Bücher.Example", "xn--bcher-kva.example");
+    check!("bücher.example", "xn--bcher-kva.example");
+    check!("xn--bcher-kva.example", "xn--bcher-kva.example");
+    check!("例え.テスト", "xn--r8jz45g.xn--zckzah");
+    check!("xn--r8jz45g.xn--zckzah", "xn--r8jz45g.xn--zckzah");
+}
+
+#[test]
+fn files_domain() {
+    let mut config = Config::example();
+
+    assert_eq!(
+        DomainService::files_domain(&config).ok(),
+        Some(".wjfiles.com"),
+        "Files domain not present in example config",
+    );
+
+    config.files_domain = None;
+    assert!(
+        matches!(DomainService::files_domain(&config), Err(Error::NotFound)),
+        "Absent files domain didn't yield not found",
+    );
+}
+
+#[test]
+fn parse_canonical() {
+    let mut config = Config::example();
+
+    config.main_domains = vec![str!(".wikijump.com"), str!(".wikijump.org")];
+
+    macro_rules! check {
+        ($domain:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                DomainService::parse_canonical(&config, $domain),
+                $expected,
+                "Parsed canonical site slug doesn't match expected",
+            );
+        };
+    }
+
+    check!("scp-wiki.wikijump.com", Some("scp-wiki"));
+    check!("scp-wiki.wikijump.org", Some("scp-wiki"));
+    check!("wikijump.com", Some("www"));
+    check!("wikijump.org", Some("www"));
+    check!("scp-wiki.wikijump.net", None);
+    check!("foo.scp-wiki.wikijump.org", None);
+    check!("scpwiki.com", None);
+
+    assert_eq!(
+        DomainService::get_canonical(&config, "scp-wiki"),
+        "scp-wiki.wikijump.com",
+        "Canonical domain doesn't use preferred main domain",
+    );
+}
+
+#[test]
+fn domain_case() {
+    let mut config = Config::example();
+
+    config.main_domains = vec![str!(".wikijump.com")];
+
+    // Canonical domains are lowercased before being parsed
+    for domain in ["scp-wiki.wikijump.com", "SCP-Wiki.WikiJump.com"] {
+        let domain = domain.to_ascii_lowercase();
+        assert_eq!(
+            DomainService::parse_canonical(&config, &domain),
+            Some("scp-wiki"),
+            "Canonical domain with different case not matched",
+        );
+    }
+
+    // Custom domains are stored and looked up in the same form
+    let stored = DomainService::normalize_domain("scpwiki.com").unwrap();
+    for domain in ["SCPWiki.com", "scpwiki.COM", "ScPwIkI.CoM"] {
+        assert_eq!(
+            DomainService::normalize_domain(domain).unwrap(),
+            stored,
+            "Custom domain with different case not matched",
+        );
+    }
+}
+
+#[test]
+fn dns_verification() {
+    use async_std::task;
+    use std::collections::HashMap;
+    use tide::utils::async_trait;
+
+    #[derive(Debug, Default)]
+    struct MockResolver {
+        records: HashMap<String, Vec<String>>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl DnsResolver for MockResolver {
+        async fn txt_records(&self, name: &str) -> Result<Vec<String>> {
+            if self.fail {
+                return Err(Error::RemoteOperationFailed);
+            }
+
+            Ok(self.records.get(name).cloned().unwrap_or_default())
+        }
+    }
+
+    const TOKEN: &str = "b4Z1xIVVxqmzE2xTYvPcgq8Ic6p8ESBy";
+
+    macro_rules! check {
+        ($resolver:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                task::block_on(dns_token_matches(&$resolver, "scpwiki.com", TOKEN))
+                    .expect("Unable to check DNS records"),
+                $expected,
+                "DNS verification result doesn't match expected",
+            );
+        };
+    }
+
+    let mut resolver = MockResolver::default();
+    assert_eq!(
+        verification_record_name("scpwiki.com"),
+        "_wikijump-verification.scpwiki.com",
+    );
+
+    // No records
+    check!(resolver, false);
+
+    // Non-matching records
+    resolver.records.insert(
+        str!("_wikijump-verification.scpwiki.com"),
+        vec![
+            str!("v=spf1 -all"),
+            str!("b4Z1xIVVxqmzE2xTYvPcgq8Ic6p8ESBz"),
+        ],
+    );
+    check!(resolver, false);
+
+    // Matching record, but on the wrong name
+    resolver.records.clear();
+    resolver
+        .records
+        .insert(str!("scpwiki.com"), vec![str!(TOKEN)]);
+    check!(resolver, false);
+
+    // Matching record, among others
+    resolver.records.insert(
+        str!("_wikijump-verification.scpwiki.com"),
+        vec![str!("v=spf1 -all"), format!(" {TOKEN} ")],
+    );
+    check!(resolver, true);
+
+    // Resolution failure
+    resolver.fail = true;
+    let result = task::block_on(dns_token_matches(&resolver, "scpwiki.com", TOKEN));
+    assert!(result.is_err(), "DNS resolution failure not reported");
+}
+
+#[test]
+fn domain_export() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let pagination = Pagination {
+        page: 2,
+        page_size: 50,
+    };
+
+    let sql = build_domain_export_query(pagination)
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    // Domains from every site, with the site slug from a join
+    let (_, filter) = sql.split_once(" WHERE ").expect("No filter in query");
+    assert!(
+        !filter.contains(r#""site_domain"."site_id""#),
+        "Domains limited to one site: {sql}",
+    );
+    assert!(
+        sql.contains(
+            r#"INNER JOIN "site" ON "site_domain"."site_id" = "site"."site_id""#,
+        ),
+        "Site not joined: {sql}",
+    );
+    assert!(
+        sql.contains(r#""site"."slug" AS "site_slug""#),
+        "Site slug missing: {sql}",
+    );
+    assert!(
+        sql.contains(r#""site_domain"."verified_at" IS NOT NULL AS "verified""#),
+        "Verification status missing: {sql}",
+    );
+    assert!(
+        sql.contains(r#""site"."deleted_at" IS NULL"#),
+        "Deleted sites included: {sql}",
+    );
+    assert!(
+        sql.ends_with(r#"ORDER BY "site_domain"."domain" ASC LIMIT 50 OFFSET 100"#),
+        "Wrong order or pagination: {sql}",
+    );
+}
+
+#[test]
+fn created_at_clock() {
+    use crate::api::build_test_server_state;
+    use crate::utils::Clock;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait, Value};
+    use std::sync::Arc;
+    use time::OffsetDateTime;
+
+    // Far enough in the past that it can't be the system time
+    let created_at = OffsetDateTime::from_unix_timestamp(1000000000).unwrap();
+
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        // No site has this domain yet
+        .append_query_results([Vec::<SiteModel>::new()])
+        // The inserted domain
+        .append_query_results([[SiteDomainModel {
+            domain: str!("scpwiki.com"),
+            site_id: 1,
+            created_at,
+            verification_token: None,
+            verified_at: None,
+        }]])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx =
+            ServiceContext::from_raw(&state, &txn).with_clock(Clock::fixed(created_at));
+        let input = CreateCustomDomain {
+            domain: str!("scpwiki.com"),
+            site_id: 1,
+        };
+
+        DomainService::create_custom(&ctx, input).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(())
+    })
+    .expect("Unable to create custom domain");
+
+    // The domain is stored with the time from the context's clock
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    let stored = format!("{:?}", Value::from(created_at));
+    assert!(
+        log.contains(&stored),
+        "Stored creation time isn't from the clock: {log}",
+    );
 }
@@ -18,9 +18,57 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::web::{check_not_blank, Validate, ValidationResult};
+use sea_orm::FromQueryResult;
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateCustomDomain {
     pub domain: String,
     pub site_id: i64,
 }
+
+impl Validate for CreateCustomDomain {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("domain", &self.domain)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateDomainVerification {
+    pub domain: String,
+    pub site_id: i64,
+}
+
+impl Validate for GenerateDomainVerification {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("domain", &self.domain)
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateDomainVerificationOutput {
+    /// The name of the TXT record to create, e.g. `_wikijump-verification.scpwiki.com`.
+    pub record_name: String,
+
+    /// The value to place in the TXT record.
+    pub token: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyDomainOutput {
+    pub verified: bool,
+}
+
+/// A custom domain along with its site, for export to DNS automation.
+#[derive(FromQueryResult, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomDomainExport {
+    pub domain: String,
+    pub site_id: i64,
+    pub site_slug: String,
+    pub verified: bool,
+}
@@ -19,9 +19,13 @@
  */
 
 use crate::locales::LocalizationTranslateError;
+use crate::services::filter::FilterMatch;
 use filemagic::FileMagicError;
+use ftml::render::html::HtmlSizeError;
 use s3::error::S3Error;
-use sea_orm::error::DbErr;
+use sea_orm::error::{DbErr, RuntimeErr};
+use sqlx::Error as SqlxError;
+use std::borrow::Cow;
 use thiserror::Error as ThisError;
 use tide::{Error as TideError, StatusCode};
 
@@ -72,18 +76,33 @@ pub enum Error {
     #[error("Attempting to perform a wikitext parse and render has timed out")]
     RenderTimeout,
 
+    #[error("Rendered wikitext is too large: {0}")]
+    RenderTooLarge(#[from] HtmlSizeError),
+
+    #[error("The operation took longer than its allotted time")]
+    Timeout,
+
     #[error("The user cannot rename as they do not have enough name change tokens")]
     InsufficientNameChanges,
 
     #[error("Invalid username, password, or TOTP code")]
     InvalidAuthentication,
 
+    #[error("The user does not have permission to perform this action")]
+    InsufficientPermissions,
+
+    #[error("Too many requests have been made, try again later")]
+    RateLimited,
+
     #[error("The request is in some way malformed or incorrect")]
     BadRequest,
 
     #[error("The request conflicts with data already present")]
     Conflict,
 
+    #[error("The request conflicted with a concurrent transaction, retry it")]
+    ConcurrentConflict,
+
     #[error("The requested data exists, when it was expected to be missing")]
     Exists,
 
@@ -91,13 +110,29 @@ pub enum Error {
     NotFound,
 
     #[error("The request violates a configured content filter")]
-    FilterViolation,
+    FilterViolation(Vec<FilterMatch>),
 
     #[error("Cannot hide the wikitext for the latest page revision")]
     CannotHideLatestRevision,
+
+    #[error("The wikitext has parser errors, and was requested to be rejected")]
+    ParserErrors,
+
+    #[error("The requested feature is disabled for this site")]
+    FeatureDisabled,
 }
 
 impl Error {
+    /// Determines if this error is transient, and so the operation may be retried.
+    ///
+    /// This covers cases such as temporary connection failures.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::Database(DbErr::ConnectionAcquire | DbErr::Conn(_)),
+        )
+    }
+
     pub fn into_tide_error(self) -> TideError {
         match self {
             Error::Cryptography(_) => {
@@ -116,23 +151,38 @@ impl Error {
             Error::InvalidEnumValue | Error::Inconsistent => {
                 TideError::from_str(StatusCode::InternalServerError, "")
             }
-            Error::RemoteOperationFailed | Error::RenderTimeout => {
+            Error::RemoteOperationFailed | Error::RenderTimeout | Error::Timeout => {
                 TideError::from_str(StatusCode::InternalServerError, "")
             }
+            Error::RenderTooLarge(inner) => {
+                TideError::new(StatusCode::PayloadTooLarge, inner)
+            }
             Error::InsufficientNameChanges => {
                 TideError::from_str(StatusCode::PaymentRequired, "")
             }
-            Error::InvalidAuthentication => {
+            Error::InvalidAuthentication | Error::InsufficientPermissions => {
                 TideError::from_str(StatusCode::Forbidden, "")
             }
+            Error::RateLimited => TideError::from_str(StatusCode::TooManyRequests, ""),
             Error::BadRequest => TideError::from_str(StatusCode::BadRequest, ""),
             Error::Exists | Error::Conflict => {
                 TideError::from_str(StatusCode::Conflict, "")
             }
+            Error::ConcurrentConflict => {
+                TideError::from_str(StatusCode::Conflict, "retry")
+            }
             Error::NotFound => TideError::from_str(StatusCode::NotFound, ""),
-            Error::FilterViolation | Error::CannotHideLatestRevision => {
+            Error::FilterViolation(matches) => {
+                // Describe the tripped filters, and where they matched if known
+                match serde_json::to_string(&matches) {
+                    Ok(body) => TideError::from_str(StatusCode::BadRequest, body),
+                    Err(_) => TideError::from_str(StatusCode::BadRequest, ""),
+                }
+            }
+            Error::CannotHideLatestRevision | Error::ParserErrors => {
                 TideError::from_str(StatusCode::BadRequest, "")
             }
+            Error::FeatureDisabled => TideError::from_str(StatusCode::Forbidden, ""),
         }
     }
 }
@@ -156,14 +206,127 @@ impl From<DbErr> for Error {
     fn from(error: DbErr) -> Error {
         match error {
             DbErr::RecordNotFound(_) => Error::NotFound,
+            _ if matches!(sqlstate(&error), Some(code) if is_retry_sqlstate(&code)) => {
+                tide::log::warn!("Transaction failed due to concurrency, retry: {error}");
+                Error::ConcurrentConflict
+            }
             _ => Error::Database(error),
         }
     }
 }
 
+/// Gets the Postgres SQLSTATE code for a database error, if it has one.
+fn sqlstate(error: &DbErr) -> Option<Cow<'_, str>> {
+    match error {
+        DbErr::Conn(RuntimeErr::SqlxError(SqlxError::Database(error)))
+        | DbErr::Exec(RuntimeErr::SqlxError(SqlxError::Database(error)))
+        | DbErr::Query(RuntimeErr::SqlxError(SqlxError::Database(error))) => error.code(),
+        _ => None,
+    }
+}
+
+/// Determines if this database error is a unique constraint violation.
+///
+/// Useful for inserts which can race with another transaction,
+/// where the violation means that transaction won.
+pub fn is_unique_violation(error: &DbErr) -> bool {
+    sqlstate(error).as_deref() == Some("23505")
+}
+
+/// Determines if this SQLSTATE means the transaction may succeed if retried.
+///
+/// These are:
+/// * `40001` -- `serialization_failure`
+/// * `40P01` -- `deadlock_detected`
+///
+/// See <https://www.postgresql.org/docs/current/errcodes-appendix.html>
+fn is_retry_sqlstate(code: &str) -> bool {
+    matches!(code, "40001" | "40P01")
+}
+
 impl From<TideError> for Error {
     #[inline]
     fn from(error: TideError) -> Error {
         Error::Web(error)
     }
 }
+
+#[test]
+fn concurrency_errors() {
+    assert!(is_retry_sqlstate("40001"));
+    assert!(is_retry_sqlstate("40P01"));
+    assert!(!is_retry_sqlstate("23505")); // unique_violation
+    assert!(!is_retry_sqlstate("42P01")); // undefined_table
+
+    macro_rules! check {
+        ($error:expr, $pattern:pat $(,)?) => {
+            assert!(
+                matches!(Error::from($error), $pattern),
+                "Converted database error doesn't match expected",
+            )
+        };
+    }
+
+    check!(DbErr::RecordNotFound(str!("page")), Error::NotFound);
+    check!(DbErr::ConnectionAcquire, Error::Database(_));
+    check!(
+        DbErr::Query(RuntimeErr::Internal(str!("40001"))),
+        Error::Database(_),
+    );
+
+    // Errors as returned by Postgres
+    check!(
+        postgres_error(DbErr::Exec, "40001"),
+        Error::ConcurrentConflict,
+    );
+    check!(
+        postgres_error(DbErr::Query, "40P01"),
+        Error::ConcurrentConflict,
+    );
+    check!(postgres_error(DbErr::Query, "23505"), Error::Database(_));
+}
+
+#[test]
+fn unique_violations() {
+    assert!(is_unique_violation(&postgres_error(DbErr::Exec, "23505")));
+    assert!(is_unique_violation(&postgres_error(DbErr::Query, "23505")));
+    assert!(!is_unique_violation(&postgres_error(DbErr::Exec, "40001")));
+    assert!(!is_unique_violation(&DbErr::RecordNotFound(str!(
+        "page_lock"
+    ))));
+}
+
+#[cfg(test)]
+pub fn postgres_error(kind: fn(RuntimeErr) -> DbErr, code: &'static str) -> DbErr {
+    use sqlx::error::DatabaseError;
+    use std::error::Error as StdError;
+
+    #[derive(ThisError, Debug)]
+    #[error("database error with SQLSTATE {0}")]
+    struct PostgresError(&'static str);
+
+    impl DatabaseError for PostgresError {
+        fn message(&self) -> &str {
+            "database error"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.0))
+        }
+
+        fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+            self
+        }
+    }
+
+    let error = SqlxError::Database(Box::new(PostgresError(code)));
+    kind(RuntimeErr::SqlxError(error))
+}
@@ -0,0 +1,106 @@
+/*
+ * services/feature_flag/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Service for per-site feature flags.
+//!
+//! These gate features which may be enabled or disabled for each site,
+//! and are checked by the services implementing those features.
+//! Flags are cached in the `ServiceContext` once read.
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::FeatureFlag;
+use crate::models::site_feature_flag::{
+    self, Entity as SiteFeatureFlag, Model as SiteFeatureFlagModel,
+};
+
+#[derive(Debug)]
+pub struct FeatureFlagService;
+
+impl FeatureFlagService {
+    /// Gets the state of all feature flags for a site.
+    pub async fn get_all(ctx: &ServiceContext<'_>, site_id: i64) -> Result<FeatureFlags> {
+        if let Some(flags) = ctx.cached_feature_flags(site_id) {
+            return Ok(flags);
+        }
+
+        tide::log::info!("Getting feature flags for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let flags: FeatureFlags = SiteFeatureFlag::find()
+            .filter(site_feature_flag::Column::SiteId.eq(site_id))
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|SiteFeatureFlagModel { flag, enabled, .. }| (flag, enabled))
+            .collect();
+
+        ctx.cache_feature_flags(site_id, flags.clone());
+        Ok(flags)
+    }
+
+    /// Ensures the given feature is enabled for a site.
+    ///
+    /// Yields `Error::FeatureDisabled` if it is not.
+    #[inline]
+    pub async fn check(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        flag: FeatureFlag,
+    ) -> Result<()> {
+        let flags = Self::get_all(ctx, site_id).await?;
+        flags.check(flag)
+    }
+
+    /// Enables or disables a feature for a site.
+    pub async fn set(
+        ctx: &ServiceContext<'_>,
+        SetFeatureFlag {
+            site_id,
+            flag,
+            enabled,
+        }: SetFeatureFlag,
+    ) -> Result<()> {
+        tide::log::info!(
+            "Setting feature flag {flag:?} for site ID {site_id} to {enabled}"
+        );
+
+        let txn = ctx.transaction();
+        let model = site_feature_flag::ActiveModel {
+            site_id: Set(site_id),
+            flag: Set(flag),
+            enabled: Set(enabled),
+            updated_at: Set(ctx.now()),
+        };
+
+        let exists = SiteFeatureFlag::find_by_id((site_id, flag))
+            .one(txn)
+            .await?
+            .is_some();
+
+        if exists {
+            model.update(txn).await?;
+        } else {
+            model.insert(txn).await?;
+        }
+
+        ctx.invalidate_feature_flags(site_id);
+        Ok(())
+    }
+}
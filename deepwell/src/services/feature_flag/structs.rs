@@ -0,0 +1,146 @@
+/*
+ * services/feature_flag/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::FeatureFlag;
+use crate::services::{Error, Result};
+use crate::web::Validate;
+use sea_orm::Iterable;
+use std::collections::HashMap;
+
+impl FeatureFlag {
+    /// Whether this feature is enabled for sites which have not set this flag.
+    pub fn default_enabled(self) -> bool {
+        match self {
+            FeatureFlag::PageDrafts => true,
+            FeatureFlag::PageVotes => true,
+        }
+    }
+}
+
+/// The state of all feature flags for a site.
+///
+/// Only flags which have been explicitly set for the site are stored,
+/// any others have their default value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureFlags {
+    overrides: HashMap<FeatureFlag, bool>,
+}
+
+impl FeatureFlags {
+    pub fn is_enabled(&self, flag: FeatureFlag) -> bool {
+        match self.overrides.get(&flag) {
+            Some(&enabled) => enabled,
+            None => flag.default_enabled(),
+        }
+    }
+
+    /// Ensures the given feature is enabled.
+    ///
+    /// Yields `Error::FeatureDisabled` if it is not.
+    pub fn check(&self, flag: FeatureFlag) -> Result<()> {
+        if self.is_enabled(flag) {
+            Ok(())
+        } else {
+            tide::log::error!("Feature {flag:?} is disabled for this site");
+            Err(Error::FeatureDisabled)
+        }
+    }
+
+    /// Lists the state of every feature flag, including those which were not set.
+    pub fn list(&self) -> Vec<FeatureFlagState> {
+        FeatureFlag::iter()
+            .map(|flag| FeatureFlagState {
+                flag,
+                enabled: self.is_enabled(flag),
+            })
+            .collect()
+    }
+}
+
+impl FromIterator<(FeatureFlag, bool)> for FeatureFlags {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (FeatureFlag, bool)>,
+    {
+        FeatureFlags {
+            overrides: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlagState {
+    pub flag: FeatureFlag,
+    pub enabled: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SetFeatureFlag {
+    pub site_id: i64,
+    pub flag: FeatureFlag,
+    pub enabled: bool,
+}
+
+impl Validate for SetFeatureFlag {}
+
+#[test]
+fn feature_flags() {
+    // No flags set, so all are at their defaults
+    let flags = FeatureFlags::default();
+    for flag in FeatureFlag::iter() {
+        assert_eq!(flags.is_enabled(flag), flag.default_enabled());
+    }
+
+    // Gated feature is rejected when its flag is off
+    let flags: FeatureFlags = [(FeatureFlag::PageDrafts, false)].into_iter().collect();
+    assert!(!flags.is_enabled(FeatureFlag::PageDrafts));
+    assert!(
+        matches!(
+            flags.check(FeatureFlag::PageDrafts),
+            Err(Error::FeatureDisabled),
+        ),
+        "Disabled feature was not rejected",
+    );
+
+    // Other features are unaffected
+    assert!(flags.check(FeatureFlag::PageVotes).is_ok());
+
+    // Explicitly enabled
+    let flags: FeatureFlags = [(FeatureFlag::PageDrafts, true)].into_iter().collect();
+    assert!(flags.check(FeatureFlag::PageDrafts).is_ok());
+
+    // Listing includes every flag
+    let flags: FeatureFlags = [(FeatureFlag::PageVotes, false)].into_iter().collect();
+    assert_eq!(
+        flags.list(),
+        [
+            FeatureFlagState {
+                flag: FeatureFlag::PageDrafts,
+                enabled: true,
+            },
+            FeatureFlagState {
+                flag: FeatureFlag::PageVotes,
+                enabled: false,
+            },
+        ],
+    );
+}
@@ -26,7 +26,7 @@ use crate::services::file_revision::{
     CreateResurrectionFileRevision, CreateTombstoneFileRevision, FileBlob,
 };
 use crate::services::filter::{FilterClass, FilterType};
-use crate::services::{BlobService, FileRevisionService, FilterService};
+use crate::services::{BlobService, DomainService, FileRevisionService, FilterService};
 
 #[derive(Debug)]
 pub struct FileService;
@@ -167,7 +167,7 @@ impl FileService {
         // Update file metadata
         let model = file::ActiveModel {
             file_id: Set(file_id),
-            updated_at: Set(Some(now())),
+            updated_at: Set(Some(ctx.now())),
             ..Default::default()
         };
         model.update(txn).await?;
@@ -232,7 +232,7 @@ impl FileService {
         // Update file metadata
         let model = file::ActiveModel {
             file_id: Set(file_id),
-            updated_at: Set(Some(now())),
+            updated_at: Set(Some(ctx.now())),
             name: Set(name),
             page_id: Set(destination_page_id),
             ..Default::default()
@@ -304,7 +304,7 @@ impl FileService {
         // Set deletion flag
         let model = file::ActiveModel {
             file_id: Set(file_id),
-            deleted_at: Set(Some(now())),
+            deleted_at: Set(Some(ctx.now())),
             ..Default::default()
         };
         model.update(txn).await?;
@@ -399,6 +399,11 @@ impl FileService {
         page_id: i64,
         reference: Reference<'_>,
     ) -> Result<Option<FileModel>> {
+        // Without a files domain, files cannot be served at all
+        if DomainService::files_domain(ctx.config()).is_err() {
+            return Ok(None);
+        }
+
         let txn = ctx.transaction();
         let file = {
             let condition = match reference {
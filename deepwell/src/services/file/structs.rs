@@ -22,7 +22,7 @@ use crate::models::sea_orm_active_enums::FileRevisionType;
 use crate::services::file_revision::{
     CreateFileRevisionOutput, CreateFirstFileRevisionOutput,
 };
-use crate::web::{ProvidedValue, Reference};
+use crate::web::{ProvidedValue, Reference, Validate};
 use serde_json::Value as JsonValue;
 use time::OffsetDateTime;
 
@@ -48,6 +48,8 @@ pub struct GetFile<'a> {
     pub file: Reference<'a>,
 }
 
+impl Validate for GetFile<'_> {}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateFile {
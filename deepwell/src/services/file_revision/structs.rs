@@ -20,7 +20,7 @@
 
 use super::prelude::*;
 use crate::services::page_revision::PageRevisionCountOutput;
-use crate::web::FetchDirection;
+use crate::web::{FetchDirection, Validate};
 
 #[derive(Debug)]
 pub struct CreateFileRevision {
@@ -102,6 +102,8 @@ pub struct GetFileRevision {
     pub revision_number: i32,
 }
 
+impl Validate for GetFileRevision {}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateFileRevision {
@@ -112,6 +114,8 @@ pub struct UpdateFileRevision {
     pub hidden: Vec<String>,
 }
 
+impl Validate for UpdateFileRevision {}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetFileRevisionRange {
@@ -122,4 +126,6 @@ pub struct GetFileRevisionRange {
     pub limit: u64,
 }
 
+impl Validate for GetFileRevisionRange {}
+
 pub type FileRevisionCountOutput = PageRevisionCountOutput;
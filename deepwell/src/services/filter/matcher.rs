@@ -19,7 +19,12 @@
  */
 
 use super::prelude::*;
-use regex::RegexSet;
+use async_std::future::timeout;
+use async_std::task::spawn_blocking;
+use regex::{Regex, RegexSet};
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Describes one filter which a `FilterMatcher` can verify against.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -28,13 +33,30 @@ pub struct FilterSummary {
     pub description: String,
 }
 
+/// Describes one filter tripped by a string, and where in it the filter matched.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterMatch {
+    pub filter_id: i64,
+    pub description: String,
+
+    /// The byte ranges within the string which the filter matched.
+    ///
+    /// This is `None` if the matcher was built without span information.
+    pub spans: Option<Vec<Range<usize>>>,
+}
+
 /// Wrapper structure which determines which filter(s) a string violates.
 ///
 /// Internally uses `RegexSet` for performance, and has fragments describing
 /// each filter flagged by the given string.
+///
+/// Optionally, it can also hold each filter's individual `Regex`,
+/// which is needed to find where in the string a filter matched.
 #[derive(Debug)]
 pub struct FilterMatcher {
     regex_set: RegexSet,
+    regexes: Option<Arc<[Regex]>>,
     filter_data: Vec<FilterSummary>,
 }
 
@@ -43,33 +65,262 @@ impl FilterMatcher {
     pub fn new(regex_set: RegexSet, filter_data: Vec<FilterSummary>) -> Self {
         FilterMatcher {
             regex_set,
+            regexes: None,
             filter_data,
         }
     }
 
+    /// Creates a matcher which is also able to produce match spans.
+    ///
+    /// The regular expressions must be in the same order as those in the set.
+    pub fn with_spans(
+        regex_set: RegexSet,
+        regexes: Vec<Regex>,
+        filter_data: Vec<FilterSummary>,
+    ) -> Result<Self> {
+        if regex_set.len() != regexes.len() {
+            tide::log::error!(
+                "Regex set has {} patterns, but {} individual regexes were given",
+                regex_set.len(),
+                regexes.len(),
+            );
+
+            return Err(Error::Inconsistent);
+        }
+
+        Ok(FilterMatcher {
+            regex_set,
+            regexes: Some(regexes.into()),
+            filter_data,
+        })
+    }
+
     /// Verifies that the given string does not trip any filters of this type.
     ///
     /// For any filter violations, they are logged and an error is returned.
+    /// The error holds each filter tripped, so the offending portions of the
+    /// string can be shown if this matcher was created with `with_spans()`.
     pub async fn verify(&self, ctx: &ServiceContext<'_>, text: &str) -> Result<()> {
-        let matches = self.regex_set.matches(text);
-        if !matches.matched_any() {
+        let matches = self.find_matches(ctx, text).await?;
+
+        if matches.is_empty() {
             tide::log::info!("String passed all filters, is clear");
             return Ok(());
         }
 
-        for index in matches {
-            let description = &self.filter_data[index];
+        for filter_match in &matches {
             tide::log::error!(
                 "String failed filter ID {}: {}",
-                description.filter_id,
-                description.description,
+                filter_match.filter_id,
+                filter_match.description,
             );
 
             // TODO audit log, with contextual data (what it's checking)
             //      (will need to add extra args)
-            let _ = ctx;
         }
 
-        Err(Error::FilterViolation)
+        Err(Error::FilterViolation(matches))
+    }
+
+    /// Finds all filters the given string trips, along with where each one matched.
+    ///
+    /// Spans are only populated if this matcher was created with `with_spans()`.
+    pub async fn find_matches(
+        &self,
+        ctx: &ServiceContext<'_>,
+        text: &str,
+    ) -> Result<Vec<FilterMatch>> {
+        self.match_spans(text, ctx.config().filter_match_timeout)
+            .await
+    }
+
+    /// Finds all filters the given string matches, and the spans of each match.
+    async fn match_spans(
+        &self,
+        text: &str,
+        budget: Duration,
+    ) -> Result<Vec<FilterMatch>> {
+        let regex_set = self.regex_set.clone();
+        let regexes = self.regexes.clone();
+        let text = str!(text);
+
+        let matches: Vec<(usize, Option<Vec<Range<usize>>>)> =
+            run_with_budget(budget, move || {
+                regex_set
+                    .matches(&text)
+                    .into_iter()
+                    .map(|index| {
+                        let spans = regexes.as_ref().map(|regexes| {
+                            regexes[index].find_iter(&text).map(|m| m.range()).collect()
+                        });
+
+                        (index, spans)
+                    })
+                    .collect()
+            })
+            .await?;
+
+        let matches = matches
+            .into_iter()
+            .map(|(index, spans)| {
+                let FilterSummary {
+                    filter_id,
+                    description,
+                } = self.filter_data[index].clone();
+
+                FilterMatch {
+                    filter_id,
+                    description,
+                    spans,
+                }
+            })
+            .collect();
+
+        Ok(matches)
     }
 }
+
+/// Runs a matching operation within the given time budget.
+///
+/// Matching is run on a separate blocking thread, so that it can be
+/// cut off if it exceeds the given time budget. In that case, this
+/// fails with `Error::Timeout`, though the thread itself will run
+/// to completion in the background.
+async fn run_with_budget<F, T>(budget: Duration, f: F) -> Result<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    timeout(budget, spawn_blocking(f)).await.map_err(|_| {
+        tide::log::error!("Matching string against filters exceeded {budget:?}");
+        Error::Timeout
+    })
+}
+
+#[test]
+fn matcher_timeout() {
+    use async_std::task::block_on;
+
+    let matcher = FilterMatcher::new(
+        RegexSet::new([r"(?i)\bspam\b", r"(\w+\s+)+forbidden"])
+            .expect("Unable to compile regex set"),
+        vec![
+            FilterSummary {
+                filter_id: 1,
+                description: str!("spam"),
+            },
+            FilterSummary {
+                filter_id: 2,
+                description: str!("forbidden"),
+            },
+        ],
+    );
+
+    let matches = block_on(matcher.match_spans("buy SPAM now", Duration::from_secs(10)))
+        .expect("Matching timed out");
+    assert_eq!(
+        matches,
+        [FilterMatch {
+            filter_id: 1,
+            description: str!("spam"),
+            spans: None,
+        }],
+        "Matched filters don't match expected",
+    );
+
+    let matches = block_on(matcher.match_spans("nothing here", Duration::from_secs(10)))
+        .expect("Matching timed out");
+    assert!(matches.is_empty(), "Filters matched clean string");
+
+    let input = "lorem ipsum ".repeat(1_000_000);
+    let result = block_on(matcher.match_spans(&input, Duration::from_nanos(1)));
+    assert!(
+        matches!(result, Err(Error::Timeout)),
+        "Matching large input did not time out",
+    );
+}
+
+#[test]
+fn matcher_spans() {
+    use async_std::task::block_on;
+
+    let patterns = [r"(?i)\bspam\b", r"forbidden", r"\d{3}-\d{4}"];
+    let filter_data = vec![
+        FilterSummary {
+            filter_id: 1,
+            description: str!("spam"),
+        },
+        FilterSummary {
+            filter_id: 2,
+            description: str!("forbidden"),
+        },
+        FilterSummary {
+            filter_id: 3,
+            description: str!("phone number"),
+        },
+    ];
+
+    let regex_set = RegexSet::new(patterns).expect("Unable to compile regex set");
+    let regexes: Vec<Regex> = patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).expect("Unable to compile regex"))
+        .collect();
+
+    // The individual regexes must correspond to the set
+    assert!(matches!(
+        FilterMatcher::with_spans(
+            regex_set.clone(),
+            regexes[1..].to_vec(),
+            filter_data.clone(),
+        ),
+        Err(Error::Inconsistent),
+    ));
+
+    let matcher = FilterMatcher::with_spans(regex_set, regexes, filter_data.clone())
+        .expect("Regex set and individual regexes differ");
+
+    let text = "Buy SPAM now! Call 555-1234, spam is great.";
+    let matches = block_on(matcher.match_spans(text, Duration::from_secs(10)))
+        .expect("Matching timed out");
+
+    assert_eq!(matches.len(), 2, "Unexpected number of filter matches");
+    assert_eq!(matches[0].filter_id, 1);
+    assert_eq!(matches[0].description, "spam");
+    assert_eq!(matches[1].filter_id, 3);
+    assert_eq!(matches[1].description, "phone number");
+
+    let matched = |index: usize| {
+        matches[index]
+            .spans
+            .as_ref()
+            .expect("No spans produced")
+            .iter()
+            .map(|span| &text[span.clone()])
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        matched(0),
+        ["SPAM", "spam"],
+        "Spans don't cover matched text"
+    );
+    assert_eq!(matched(1), ["555-1234"], "Spans don't cover matched text");
+
+    let matches = block_on(matcher.match_spans("nothing here", Duration::from_secs(10)))
+        .expect("Matching timed out");
+    assert!(matches.is_empty(), "Filters matched clean string");
+
+    // Without individual regexes, filters are still found but have no spans
+    let matcher = FilterMatcher::new(
+        RegexSet::new(patterns).expect("Unable to compile regex set"),
+        filter_data,
+    );
+
+    let matches = block_on(matcher.match_spans(text, Duration::from_secs(10)))
+        .expect("Matching timed out");
+    assert_eq!(matches.len(), 2, "Unexpected number of filter matches");
+    assert!(
+        matches.iter().all(|m| m.spans.is_none()),
+        "Spans produced without individual regexes",
+    );
+}
@@ -29,7 +29,7 @@
 
 mod prelude {
     pub use super::super::prelude::*;
-    pub use super::matcher::{FilterMatcher, FilterSummary};
+    pub use super::matcher::{FilterMatch, FilterMatcher, FilterSummary};
     pub use super::structs::*;
 }
 
@@ -37,6 +37,6 @@ mod matcher;
 mod service;
 mod structs;
 
-pub use self::matcher::{FilterMatcher, FilterSummary};
+pub use self::matcher::{FilterMatch, FilterMatcher, FilterSummary};
 pub use self::service::FilterService;
 pub use self::structs::*;
@@ -20,7 +20,13 @@
 
 use super::prelude::*;
 use crate::models::filter::{self, Entity as Filter, Model as FilterModel};
+use crate::models::sea_orm_active_enums::AuditAction;
+use crate::services::audit_log::RecordAuditEntry;
+use crate::services::AuditLogService;
+use crate::web::Pagination;
 use regex::{Regex, RegexSet};
+use sea_orm::{FromQueryResult, Select};
+use std::collections::HashSet;
 
 #[derive(Debug)]
 pub struct FilterService;
@@ -29,6 +35,7 @@ impl FilterService {
     pub async fn create(
         ctx: &ServiceContext<'_>,
         site_id: Option<i64>,
+        actor_id: i64,
         CreateFilter {
             affects_user,
             affects_email,
@@ -64,12 +71,99 @@ impl FilterService {
             ..Default::default()
         };
         let filter = model.insert(txn).await?;
+        AuditLogService::record(
+            ctx,
+            filter_audit_entry(actor_id, AuditAction::FilterCreate, &filter),
+        )
+        .await?;
+
         Ok(filter)
     }
 
-    #[allow(dead_code)] // TEMP
+    /// Exports filters of a type into a list which can be imported elsewhere.
+    ///
+    /// Only extant filters are exported. For the `filter_class` and `filter_type`
+    /// arguments, see `get_all()`.
+    pub async fn export(
+        ctx: &ServiceContext<'_>,
+        filter_class: FilterClass,
+        filter_type: Option<FilterType>,
+    ) -> Result<Vec<CreateFilter>> {
+        tide::log::info!("Exporting {} filters", filter_class.name());
+
+        let filters = Self::get_all(
+            ctx,
+            filter_class,
+            filter_type,
+            Some(false),
+            FilterOrder::default(),
+        )
+        .await?;
+        let exported = filters.into_iter().map(export_filter).collect();
+        Ok(exported)
+    }
+
+    /// Imports a list of filters, such as one produced by `export()`.
+    ///
+    /// Filters which conflict with an existing filter are skipped,
+    /// and their regular expressions are listed in the output.
+    ///
+    /// If `dry_run` is true, then no filters are created, and the output
+    /// only reports which filters would have been skipped.
+    pub async fn import(
+        ctx: &ServiceContext<'_>,
+        site_id: Option<i64>,
+        actor_id: i64,
+        filters: Vec<CreateFilter>,
+        dry_run: bool,
+    ) -> Result<ImportFilterOutput> {
+        tide::log::info!(
+            "Importing {} filters for {site_id:?}{}",
+            filters.len(),
+            if dry_run { " (dry run)" } else { "" },
+        );
+
+        let mut output = ImportFilterOutput::default();
+        if dry_run {
+            let mut checked = HashSet::new();
+            let mut conflicting = HashSet::new();
+
+            for CreateFilter { regex, .. } in &filters {
+                if !checked.insert(regex.as_str()) {
+                    continue;
+                }
+
+                match Self::check_conflicts(ctx, site_id, regex, "import").await {
+                    Ok(()) => (),
+                    Err(Error::Conflict) => {
+                        conflicting.insert(regex.as_str());
+                    }
+                    Err(error) => return Err(error),
+                }
+            }
+
+            output.skipped = dry_run_skipped(&filters, &conflicting);
+            return Ok(output);
+        }
+
+        for filter in filters {
+            let regex = filter.regex.clone();
+            match Self::create(ctx, site_id, actor_id, filter).await {
+                Ok(model) => output.created.push(model),
+                Err(Error::Conflict) => {
+                    tide::log::warn!("Skipping duplicate filter '{regex}'");
+                    output.skipped.push(regex);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(output)
+    }
+
     pub async fn update(
         ctx: &ServiceContext<'_>,
+        actor_id: i64,
         UpdateFilter {
             filter_id,
             affects_user,
@@ -87,46 +181,35 @@ impl FilterService {
 
         let mut model = filter::ActiveModel {
             filter_id: Set(filter_id),
-            updated_at: Set(Some(now())),
+            updated_at: Set(Some(ctx.now())),
             ..Default::default()
         };
 
         // Set fields
-        if let ProvidedValue::Set(affects) = affects_user {
-            model.affects_user = Set(affects);
-        }
-
-        if let ProvidedValue::Set(affects) = affects_email {
-            model.affects_email = Set(affects);
-        }
-
-        if let ProvidedValue::Set(affects) = affects_page {
-            model.affects_page = Set(affects);
-        }
-
-        if let ProvidedValue::Set(affects) = affects_file {
-            model.affects_file = Set(affects);
-        }
-
-        if let ProvidedValue::Set(affects) = affects_forum {
-            model.affects_forum = Set(affects);
-        }
-
-        if let ProvidedValue::Set(regex) = regex {
-            model.regex = Set(regex);
-        }
-
-        if let ProvidedValue::Set(description) = description {
-            model.description = Set(description);
-        }
+        affects_user.update_field(&mut model.affects_user);
+        affects_email.update_field(&mut model.affects_email);
+        affects_page.update_field(&mut model.affects_page);
+        affects_file.update_field(&mut model.affects_file);
+        affects_forum.update_field(&mut model.affects_forum);
+        regex.update_field(&mut model.regex);
+        description.update_field(&mut model.description);
 
         // Perform update
         let filter = model.update(txn).await?;
+        AuditLogService::record(
+            ctx,
+            filter_audit_entry(actor_id, AuditAction::FilterUpdate, &filter),
+        )
+        .await?;
+
         Ok(filter)
     }
 
-    #[allow(dead_code)] // TEMP
-    pub async fn delete(ctx: &ServiceContext<'_>, filter_id: i64) -> Result<()> {
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        actor_id: i64,
+        filter_id: i64,
+    ) -> Result<()> {
         let txn = ctx.transaction();
 
         tide::log::info!("Deleting filter with ID {filter_id}");
@@ -141,17 +224,23 @@ impl FilterService {
         // Delete the filter
         let model = filter::ActiveModel {
             filter_id: Set(filter_id),
-            deleted_at: Set(Some(now())),
+            deleted_at: Set(Some(ctx.now())),
             ..Default::default()
         };
         model.update(txn).await?;
+        AuditLogService::record(
+            ctx,
+            filter_audit_entry(actor_id, AuditAction::FilterDelete, &filter),
+        )
+        .await?;
+
         Ok(())
     }
 
     /// Restores a filter, causing it to be undeleted.
-    #[allow(dead_code)] // TEMP
     pub async fn restore(
         ctx: &ServiceContext<'_>,
+        actor_id: i64,
         filter_id: i64,
     ) -> Result<FilterModel> {
         let txn = ctx.transaction();
@@ -174,9 +263,119 @@ impl FilterService {
             ..Default::default()
         };
         let filter = model.update(txn).await?;
+        AuditLogService::record(
+            ctx,
+            filter_audit_entry(actor_id, AuditAction::FilterRestore, &filter),
+        )
+        .await?;
+
         Ok(filter)
     }
 
+    /// Deletes several filters at once.
+    ///
+    /// Filters which are missing or already deleted are skipped,
+    /// and the outcome for each filter ID is reported in order.
+    pub async fn delete_many(
+        ctx: &ServiceContext<'_>,
+        actor_id: i64,
+        filter_ids: &[i64],
+    ) -> Result<Vec<BulkFilterResult>> {
+        let txn = ctx.transaction();
+
+        tide::log::info!("Deleting {} filters", filter_ids.len());
+
+        let mut results = Vec::with_capacity(filter_ids.len());
+        for &filter_id in filter_ids {
+            let filter = Self::get_optional(ctx, filter_id).await?;
+            let status = match (bulk_skip_status(filter.as_ref(), true), filter) {
+                (Some(status), _) => status,
+                (None, Some(filter)) => {
+                    let model = filter::ActiveModel {
+                        filter_id: Set(filter_id),
+                        deleted_at: Set(Some(ctx.now())),
+                        ..Default::default()
+                    };
+                    model.update(txn).await?;
+                    AuditLogService::record(
+                        ctx,
+                        filter_audit_entry(actor_id, AuditAction::FilterDelete, &filter),
+                    )
+                    .await?;
+
+                    BulkFilterStatus::Deleted
+                }
+                (None, None) => unreachable!("Missing filter not skipped"),
+            };
+
+            results.push(BulkFilterResult { filter_id, status });
+        }
+
+        Ok(results)
+    }
+
+    /// Restores several filters at once.
+    ///
+    /// Filters which are missing, not deleted, or which would conflict with
+    /// an extant filter are skipped, and the outcome for each filter ID
+    /// is reported in order.
+    pub async fn restore_many(
+        ctx: &ServiceContext<'_>,
+        actor_id: i64,
+        filter_ids: &[i64],
+    ) -> Result<Vec<BulkFilterResult>> {
+        let txn = ctx.transaction();
+
+        tide::log::info!("Undeleting {} filters", filter_ids.len());
+
+        let mut results = Vec::with_capacity(filter_ids.len());
+        for &filter_id in filter_ids {
+            let filter = Self::get_optional(ctx, filter_id).await?;
+            let status = match (bulk_skip_status(filter.as_ref(), false), filter) {
+                (Some(status), _) => status,
+                (None, Some(filter)) => {
+                    // Ensure it doesn't conflict with a since-added filter
+                    let result = Self::check_conflicts(
+                        ctx,
+                        filter.site_id,
+                        &filter.regex,
+                        "restore",
+                    )
+                    .await;
+
+                    match result {
+                        Err(Error::Conflict) => BulkFilterStatus::Conflict,
+                        Err(error) => return Err(error),
+                        Ok(()) => {
+                            let model = filter::ActiveModel {
+                                filter_id: Set(filter_id),
+                                deleted_at: Set(None),
+                                ..Default::default()
+                            };
+                            let filter = model.update(txn).await?;
+                            AuditLogService::record(
+                                ctx,
+                                filter_audit_entry(
+                                    actor_id,
+                                    AuditAction::FilterRestore,
+                                    &filter,
+                                ),
+                            )
+                            .await?;
+
+                            BulkFilterStatus::Restored
+                        }
+                    }
+                }
+                (None, None) => unreachable!("Missing filter not skipped"),
+            };
+
+            results.push(BulkFilterResult { filter_id, status });
+        }
+
+        Ok(results)
+    }
+
     #[inline]
     pub async fn get(ctx: &ServiceContext<'_>, filter_id: i64) -> Result<FilterModel> {
         find_or_error(Self::get_optional(ctx, filter_id)).await
@@ -205,36 +404,117 @@ impl FilterService {
     /// * If it is `Some(true)`, then it only returns filters which have been deleted.
     /// * If it is `Some(false)`, then it only returns filters which are extant.
     /// * If it is `None`, then it returns all filters regardless of deletion status.
+    ///
+    /// For the `order` argument, see `FilterOrder`.
     pub async fn get_all(
         ctx: &ServiceContext<'_>,
         filter_class: FilterClass,
         filter_type: Option<FilterType>,
         deleted: Option<bool>,
+        order: FilterOrder,
     ) -> Result<Vec<FilterModel>> {
         let txn = ctx.transaction();
 
         tide::log::info!("Getting all {} filters", filter_class.name());
 
-        let filter_condition =
-            filter_type.map(|filter_type| filter_type.into_column().eq(true));
+        let filters = build_filter_query(filter_class, filter_type, deleted, order)
+            .all(txn)
+            .await?;
+
+        Ok(filters)
+    }
 
-        let deleted_condition = match deleted {
-            Some(true) => Some(filter::Column::DeletedAt.is_not_null()),
-            Some(false) => Some(filter::Column::DeletedAt.is_null()),
-            None => None,
-        };
+    /// Get one page of filters of a type, along with the total count.
+    ///
+    /// For all arguments other than `pagination`, see `get_all()`.
+    /// A page size of zero is rejected as a bad request.
+    pub async fn get_page(
+        ctx: &ServiceContext<'_>,
+        filter_class: FilterClass,
+        filter_type: Option<FilterType>,
+        deleted: Option<bool>,
+        order: FilterOrder,
+        pagination: Pagination,
+    ) -> Result<FilterPageOutput> {
+        let txn = ctx.transaction();
 
-        let filters = Filter::find()
-            .filter(
-                Condition::all()
-                    .add(filter_class.to_condition())
-                    .add_option(filter_condition)
-                    .add_option(deleted_condition),
-            )
+        tide::log::info!(
+            "Getting page {} of {} filters (page size {})",
+            pagination.page,
+            filter_class.name(),
+            pagination.page_size,
+        );
+
+        if pagination.page_size == 0 {
+            tide::log::error!("Cannot get filters with a page size of zero");
+            return Err(Error::BadRequest);
+        }
+
+        let total_count = Self::count(ctx, filter_class, filter_type, deleted).await?;
+        let query = build_filter_query(filter_class, filter_type, deleted, order);
+        let filters = pagination.apply(query).all(txn).await?;
+
+        Ok(FilterPageOutput {
+            filters,
+            total_count,
+        })
+    }
+
+    /// Counts the filters of a type.
+    ///
+    /// For the arguments, see `get_all()`.
+    pub async fn count(
+        ctx: &ServiceContext<'_>,
+        filter_class: FilterClass,
+        filter_type: Option<FilterType>,
+        deleted: Option<bool>,
+    ) -> Result<u64> {
+        let txn = ctx.transaction();
+        let count = build_filter_query(
+            filter_class,
+            filter_type,
+            deleted,
+            FilterOrder::default(),
+        )
+        .count(txn)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Counts the extant platform filters and filters for a site, by type.
+    pub async fn count_by_class(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<FilterClassCounts> {
+        let txn = ctx.transaction();
+
+        tide::log::info!("Counting platform and site filters for site ID {site_id}");
+
+        // As raw SQL:
+        //
+        // SELECT site_id IS NULL AS platform,
+        //        affects_user, affects_email, affects_page,
+        //        affects_file, affects_forum,
+        //        COUNT(filter_id) AS count
+        // FROM filter
+        // WHERE (site_id IS NULL OR site_id = $1)
+        // AND deleted_at IS NULL
+        // GROUP BY site_id IS NULL,
+        //          affects_user, affects_email, affects_page,
+        //          affects_file, affects_forum;
+
+        let rows = build_filter_count_query(site_id)
+            .into_model::<FilterCountRow>()
             .all(txn)
             .await?;
 
-        Ok(filters)
+        let mut counts = FilterClassCounts::default();
+        for row in rows {
+            add_filter_counts(&mut counts, row);
+        }
+
+        Ok(counts)
     }
 
     /// Get all filters of a type, specifically extracting the regular expressions.
@@ -247,14 +527,42 @@ impl FilterService {
         ctx: &ServiceContext<'_>,
         filter_class: FilterClass,
         filter_type: FilterType,
+    ) -> Result<FilterMatcher> {
+        Self::build_matcher(ctx, filter_class, filter_type, false).await
+    }
+
+    /// Like `get_matcher()`, but the matcher also records where each filter matched.
+    ///
+    /// This requires compiling each regular expression individually in addition
+    /// to the `RegexSet`, so it should only be used when the match spans are needed,
+    /// such as highlighting the offending portions of rejected content.
+    pub async fn get_matcher_with_spans(
+        ctx: &ServiceContext<'_>,
+        filter_class: FilterClass,
+        filter_type: FilterType,
+    ) -> Result<FilterMatcher> {
+        Self::build_matcher(ctx, filter_class, filter_type, true).await
+    }
+
+    async fn build_matcher(
+        ctx: &ServiceContext<'_>,
+        filter_class: FilterClass,
+        filter_type: FilterType,
+        with_spans: bool,
     ) -> Result<FilterMatcher> {
         tide::log::info!(
             "Compiling regex set for {} filters for {filter_type:?}",
             filter_class.name(),
         );
 
-        let filters =
-            Self::get_all(ctx, filter_class, Some(filter_type), Some(false)).await?;
+        let filters = Self::get_all(
+            ctx,
+            filter_class,
+            Some(filter_type),
+            Some(false),
+            FilterOrder::default(),
+        )
+        .await?;
 
         let mut regexes = Vec::new();
         let mut filter_data = Vec::new();
@@ -273,15 +581,26 @@ impl FilterService {
             });
         }
 
-        let regex_set = RegexSet::new(regexes).map_err(|error| {
+        let invalid_regex = |error: regex::Error| {
             tide::log::error!(
                 "Invalid regular expression found in the database: {error}",
             );
 
             Error::Inconsistent
-        })?;
+        };
 
-        Ok(FilterMatcher::new(regex_set, filter_data))
+        let regex_set = RegexSet::new(&regexes).map_err(invalid_regex)?;
+
+        if !with_spans {
+            return Ok(FilterMatcher::new(regex_set, filter_data));
+        }
+
+        let regexes = regexes
+            .iter()
+            .map(|regex| Regex::new(regex).map_err(invalid_regex))
+            .collect::<Result<Vec<_>>>()?;
+
+        FilterMatcher::with_spans(regex_set, regexes, filter_data)
     }
 
     /// Checks if creating / reinstating this filter would cause constraint violations.
@@ -314,3 +633,682 @@ impl FilterService {
         }
     }
 }
+
+fn export_filter(
+    FilterModel {
+        affects_user,
+        affects_email,
+        affects_page,
+        affects_file,
+        affects_forum,
+        regex,
+        description,
+        ..
+    }: FilterModel,
+) -> CreateFilter {
+    CreateFilter {
+        affects_user,
+        affects_email,
+        affects_page,
+        affects_file,
+        affects_forum,
+        regex,
+        description,
+    }
+}
+
+/// Determines which filters an import would skip, without creating any.
+///
+/// Filters conflicting with existing ones are skipped, as are repeats of a
+/// regular expression earlier in the list, since nothing is inserted during
+/// a dry run for those to conflict with.
+fn dry_run_skipped(filters: &[CreateFilter], conflicting: &HashSet<&str>) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    filters
+        .iter()
+        .filter(|filter| {
+            let regex = filter.regex.as_str();
+            !seen.insert(regex) || conflicting.contains(regex)
+        })
+        .map(|filter| filter.regex.clone())
+        .collect()
+}
+
+/// Determines if a filter should be skipped in a bulk operation, and why.
+///
+/// If `deleting` is true, the filter is being deleted, otherwise it is being
+/// restored. Returns `None` if the operation should proceed for this filter.
+fn bulk_skip_status(
+    filter: Option<&FilterModel>,
+    deleting: bool,
+) -> Option<BulkFilterStatus> {
+    let filter = match filter {
+        Some(filter) => filter,
+        None => return Some(BulkFilterStatus::NotFound),
+    };
+
+    match (filter.deleted_at.is_some(), deleting) {
+        (true, true) => Some(BulkFilterStatus::AlreadyDeleted),
+        (false, false) => Some(BulkFilterStatus::AlreadyExtant),
+        _ => None,
+    }
+}
+
+/// Builds the audit log entry for an action taken on a filter.
+fn filter_audit_entry(
+    actor_id: i64,
+    action: AuditAction,
+    filter: &FilterModel,
+) -> RecordAuditEntry {
+    RecordAuditEntry {
+        actor_id,
+        action,
+        target_id: filter.filter_id,
+        detail: serde_json::json!({
+            "siteId": filter.site_id,
+            "regex": filter.regex,
+            "description": filter.description,
+        }),
+    }
+}
+
+/// One group of filters with the same class and types, as counted by the database.
+#[derive(FromQueryResult, Debug)]
+struct FilterCountRow {
+    platform: bool,
+    affects_user: bool,
+    affects_email: bool,
+    affects_page: bool,
+    affects_file: bool,
+    affects_forum: bool,
+    count: i64,
+}
+
+fn build_filter_count_query(site_id: i64) -> Select<Filter> {
+    let is_platform = filter::Column::SiteId.is_null();
+
+    Filter::find()
+        .select_only()
+        .column_as(is_platform.clone(), "platform")
+        .column(filter::Column::AffectsUser)
+        .column(filter::Column::AffectsEmail)
+        .column(filter::Column::AffectsPage)
+        .column(filter::Column::AffectsFile)
+        .column(filter::Column::AffectsForum)
+        .column_as(filter::Column::FilterId.count(), "count")
+        .filter(
+            Condition::all()
+                .add(FilterClass::PlatformAndSite(site_id).to_condition())
+                .add(filter::Column::DeletedAt.is_null()),
+        )
+        .group_by(is_platform)
+        .group_by(filter::Column::AffectsUser)
+        .group_by(filter::Column::AffectsEmail)
+        .group_by(filter::Column::AffectsPage)
+        .group_by(filter::Column::AffectsFile)
+        .group_by(filter::Column::AffectsForum)
+}
+
+/// Adds a group of filters to the counts for its class.
+fn add_filter_counts(counts: &mut FilterClassCounts, row: FilterCountRow) {
+    let class_counts = if row.platform {
+        &mut counts.platform
+    } else {
+        &mut counts.site
+    };
+
+    // COUNT() is never negative
+    let count = u64::try_from(row.count).unwrap_or(0);
+
+    macro_rules! add {
+        ($affects:expr, $field:ident) => {
+            if $affects {
+                class_counts.$field += count;
+            }
+        };
+    }
+
+    class_counts.total += count;
+    add!(row.affects_user, user);
+    add!(row.affects_email, email);
+    add!(row.affects_page, page);
+    add!(row.affects_file, file);
+    add!(row.affects_forum, forum);
+}
+
+/// Builds the query for filters of a type, in the given order.
+///
+/// For the arguments, see `FilterService::get_all()`.
+fn build_filter_query(
+    filter_class: FilterClass,
+    filter_type: Option<FilterType>,
+    deleted: Option<bool>,
+    order: FilterOrder,
+) -> Select<Filter> {
+    let filter_condition =
+        filter_type.map(|filter_type| filter_type.into_column().eq(true));
+
+    let deleted_condition = match deleted {
+        Some(true) => Some(filter::Column::DeletedAt.is_not_null()),
+        Some(false) => Some(filter::Column::DeletedAt.is_null()),
+        None => None,
+    };
+
+    let mut query = Filter::find()
+        .filter(
+            Condition::all()
+                .add(filter_class.to_condition())
+                .add_option(filter_condition)
+                .add_option(deleted_condition),
+        )
+        .order_by(order.column.into_column(), order.direction.clone());
+
+    // Break ties by ID, so pages never overlap
+    if order.column != FilterOrderColumn::Id {
+        query = query.order_by(filter::Column::FilterId, order.direction);
+    }
+
+    query
+}
+
+#[test]
+fn filter_page() {
+    use crate::api::build_test_server_state;
+    use async_std::task;
+    use sea_orm::query::Order;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait, Value};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use time::OffsetDateTime;
+
+    let filter = |filter_id, description: &str| FilterModel {
+        filter_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: None,
+        site_id: Some(4),
+        affects_user: false,
+        affects_email: false,
+        affects_page: true,
+        affects_file: false,
+        affects_forum: false,
+        regex: format!("spam{filter_id}"),
+        description: str!(description),
+    };
+
+    // The total count, then the filters on the requested page
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[BTreeMap::from([("num_items", Value::from(23i64))])]])
+        .append_query_results([[filter(7, "Spam"), filter(3, "Eggs")]])
+        .into_connection();
+
+    let order = FilterOrder {
+        column: FilterOrderColumn::Description,
+        direction: Order::Desc,
+    };
+
+    let state = task::block_on(build_test_server_state(database));
+    let output = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+
+        // Empty pages are rejected before querying
+        let result = FilterService::get_page(
+            &ctx,
+            FilterClass::Site(4),
+            None,
+            Some(false),
+            order.clone(),
+            Pagination {
+                page: 0,
+                page_size: 0,
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(Error::BadRequest)));
+
+        let output = FilterService::get_page(
+            &ctx,
+            FilterClass::Site(4),
+            None,
+            Some(false),
+            order,
+            Pagination {
+                page: 2,
+                page_size: 10,
+            },
+        )
+        .await?;
+
+        txn.commit().await?;
+        Ok::<_, Error>(output)
+    })
+    .expect("Unable to get page of filters");
+
+    assert_eq!(output.total_count, 23);
+    assert_eq!(output.filters.len(), 2);
+    assert_eq!(output.filters[0].description, "Spam");
+
+    // The page is ordered by description, with ties broken by ID
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    let order_by = format!(
+        "{:?}",
+        r#"ORDER BY "filter"."description" DESC, "filter"."filter_id" DESC"#,
+    );
+    assert!(
+        log.contains(order_by.trim_matches('"')),
+        "Filter query has wrong order: {log}",
+    );
+
+    // And limited to the third page
+    let offset = format!("{:?}", Value::from(20u64));
+    assert!(log.contains("LIMIT"), "Filter query not limited: {log}");
+    assert!(
+        log.contains(&offset),
+        "Filter query has wrong offset: {log}"
+    );
+}
+
+#[test]
+fn bulk_filter_delete() {
+    use crate::api::build_test_server_state;
+    use crate::models::audit_log::Model as AuditLogModel;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait};
+    use std::sync::Arc;
+    use time::OffsetDateTime;
+
+    let filter = |filter_id, deleted: bool| FilterModel {
+        filter_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: deleted.then_some(OffsetDateTime::UNIX_EPOCH),
+        site_id: None,
+        affects_user: false,
+        affects_email: false,
+        affects_page: true,
+        affects_file: false,
+        affects_forum: false,
+        regex: format!("spam{filter_id}"),
+        description: str!("Spam"),
+    };
+    let audit_entry = |target_id| AuditLogModel {
+        audit_log_id: target_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        actor_id: 4,
+        action: AuditAction::FilterDelete,
+        target_id,
+        detail: serde_json::json!({}),
+    };
+
+    // Results for each query, in the order FilterService::delete_many() makes them.
+    // Filter 2 is already deleted, and filter 4 doesn't exist.
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[filter(1, false)], [filter(1, true)]])
+        .append_query_results([[audit_entry(1)]])
+        .append_query_results([[filter(2, true)], [filter(3, false)], [filter(3, true)]])
+        .append_query_results([[audit_entry(3)]])
+        .append_query_results([Vec::<FilterModel>::new()])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let results = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let results = FilterService::delete_many(&ctx, 4, &[1, 2, 3, 4]).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(results)
+    })
+    .expect("Unable to delete filters");
+
+    let statuses: Vec<_> = results
+        .iter()
+        .map(|result| (result.filter_id, result.status))
+        .collect();
+
+    assert_eq!(
+        statuses,
+        [
+            (1, BulkFilterStatus::Deleted),
+            (2, BulkFilterStatus::AlreadyDeleted),
+            (3, BulkFilterStatus::Deleted),
+            (4, BulkFilterStatus::NotFound),
+        ],
+        "Bulk delete skipped the wrong filters",
+    );
+
+    // Only the deleted filters are updated and audited
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    assert_eq!(
+        log.matches("UPDATE").count(),
+        2,
+        "Wrong filters updated: {log}"
+    );
+    assert_eq!(
+        log.matches("INSERT INTO").count(),
+        2,
+        "Wrong audit entries: {log}"
+    );
+}
+
+#[test]
+fn bulk_filter_restore() {
+    use crate::api::build_test_server_state;
+    use crate::models::audit_log::Model as AuditLogModel;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait};
+    use time::OffsetDateTime;
+
+    let filter = |filter_id, deleted: bool| FilterModel {
+        filter_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: deleted.then_some(OffsetDateTime::UNIX_EPOCH),
+        site_id: None,
+        affects_user: false,
+        affects_email: false,
+        affects_page: true,
+        affects_file: false,
+        affects_forum: false,
+        regex: str!("spam"),
+        description: str!("Spam"),
+    };
+
+    // Results for each query, in the order FilterService::restore_many() makes them.
+    // Filter 2 has since been replaced by filter 5, and filter 3 is not deleted.
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![filter(1, true)], vec![], vec![filter(1, false)]])
+        .append_query_results([[AuditLogModel {
+            audit_log_id: 1,
+            created_at: OffsetDateTime::UNIX_EPOCH,
+            actor_id: 4,
+            action: AuditAction::FilterRestore,
+            target_id: 1,
+            detail: serde_json::json!({}),
+        }]])
+        .append_query_results([[filter(2, true)], [filter(5, false)], [filter(3, false)]])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let results = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let results = FilterService::restore_many(&ctx, 4, &[1, 2, 3]).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(results)
+    })
+    .expect("Unable to restore filters");
+
+    let statuses: Vec<_> = results.iter().map(|result| result.status).collect();
+    assert_eq!(
+        statuses,
+        [
+            BulkFilterStatus::Restored,
+            BulkFilterStatus::Conflict,
+            BulkFilterStatus::AlreadyExtant,
+        ],
+        "Bulk restore skipped the wrong filters",
+    );
+}
+
+#[test]
+fn filter_audit_entries() {
+    use crate::services::fixtures;
+    use time::OffsetDateTime;
+
+    let filter = FilterModel {
+        deleted_at: Some(OffsetDateTime::UNIX_EPOCH),
+        site_id: Some(3),
+        description: str!("Spam"),
+        ..fixtures::filter(12, "spam")
+    };
+
+    // Deleting a filter records who deleted it
+    let entry = filter_audit_entry(4, AuditAction::FilterDelete, &filter);
+    assert_eq!(entry.actor_id, 4);
+    assert_eq!(entry.action, AuditAction::FilterDelete);
+    assert_eq!(entry.target_id, 12);
+    assert_eq!(entry.detail["siteId"], 3);
+    assert_eq!(entry.detail["regex"], "spam");
+    assert_eq!(entry.detail["description"], "Spam");
+}
+
+#[test]
+fn filter_class_counts() {
+    use crate::api::build_test_server_state;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait, Value};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    // Mixed groups of filters, as returned by the query
+    let row =
+        |platform: bool, [user, email, page, file, forum]: [bool; 5], count: i64| {
+            BTreeMap::from([
+                ("platform", Value::from(platform)),
+                ("affects_user", Value::from(user)),
+                ("affects_email", Value::from(email)),
+                ("affects_page", Value::from(page)),
+                ("affects_file", Value::from(file)),
+                ("affects_forum", Value::from(forum)),
+                ("count", Value::from(count)),
+            ])
+        };
+
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([vec![
+            row(true, [true, true, false, false, false], 30),
+            row(true, [false, false, true, true, true], 10),
+            row(true, [false, false, false, false, true], 2),
+            row(false, [false, false, true, false, false], 5),
+            row(false, [true, false, true, false, true], 2),
+        ]])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let counts = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let counts = FilterService::count_by_class(&ctx, 4).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(counts)
+    })
+    .expect("Unable to count filters");
+
+    assert_eq!(
+        counts,
+        FilterClassCounts {
+            platform: FilterCounts {
+                total: 42,
+                user: 30,
+                email: 30,
+                page: 10,
+                file: 10,
+                forum: 12,
+            },
+            site: FilterCounts {
+                total: 7,
+                user: 2,
+                email: 0,
+                page: 7,
+                file: 0,
+                forum: 2,
+            },
+        },
+    );
+
+    // Only extant platform filters and those for the site are counted
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    let escaped = |sql: &str| format!("{sql:?}").trim_matches('"').to_owned();
+    assert!(
+        log.contains(&escaped(
+            r#""filter"."site_id" IS NULL OR "filter"."site_id" = $1"#
+        )),
+        "Filter class missing: {log}",
+    );
+    assert!(
+        log.contains(&escaped(r#""filter"."deleted_at" IS NULL"#)),
+        "Deleted filters included: {log}",
+    );
+    assert!(log.contains("GROUP BY"), "Filters not grouped: {log}");
+}
+
+#[test]
+fn filter_export_import() {
+    use crate::api::build_test_server_state;
+    use crate::models::audit_log::Model as AuditLogModel;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait, Value};
+    use std::sync::Arc;
+    use time::OffsetDateTime;
+
+    let filter = |filter_id, site_id, regex: &str| FilterModel {
+        filter_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: None,
+        site_id: Some(site_id),
+        affects_user: true,
+        affects_email: false,
+        affects_page: true,
+        affects_file: false,
+        affects_forum: false,
+        regex: str!(regex),
+        description: format!("No {regex}"),
+    };
+    let audit_entry = AuditLogModel {
+        audit_log_id: 1,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        actor_id: 4,
+        action: AuditAction::FilterCreate,
+        target_id: 20,
+        detail: serde_json::json!({}),
+    };
+
+    // Results for each query, in the order they are made.
+    // The second site already has the "eggs" filter.
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        // Export from the first site
+        .append_query_results([[filter(10, 1, "spam"), filter(11, 1, "eggs")]])
+        // Import of "spam": no conflict, inserted filter, audit entry
+        .append_query_results([Vec::<FilterModel>::new()])
+        .append_query_results([[filter(20, 2, "spam")]])
+        .append_query_results([[audit_entry]])
+        // Import of "eggs": conflict
+        .append_query_results([[filter(12, 2, "eggs")]])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let (exported, output) = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let exported = FilterService::export(&ctx, FilterClass::Site(1), None).await?;
+        let output =
+            FilterService::import(&ctx, Some(2), 4, exported.clone(), false).await?;
+        txn.commit().await?;
+        Ok::<_, Error>((exported, output))
+    })
+    .expect("Unable to export and import filters");
+
+    // Exported filters have no site-specific fields
+    assert_eq!(exported.len(), 2);
+    assert_eq!(exported[0].regex, "spam");
+    assert_eq!(exported[0].description, "No spam");
+    assert!(exported[0].affects_user);
+    assert!(!exported[0].affects_email);
+
+    let json = serde_json::to_value(&exported[0]).expect("Unable to serialize filter");
+    assert!(json.get("filter_id").is_none(), "Filter ID exported");
+    assert!(json.get("site_id").is_none(), "Site ID exported");
+
+    // The duplicate is skipped rather than inserted
+    assert_eq!(output.created.len(), 1);
+    assert_eq!(output.created[0].regex, "spam");
+    assert_eq!(output.skipped, vec![str!("eggs")]);
+
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    let site_id = format!("{:?}", Value::from(Some(2i64)));
+    assert_eq!(
+        log.matches("INSERT INTO").count(),
+        2,
+        "Not one filter and audit entry inserted: {log}",
+    );
+    assert!(
+        log.contains(&site_id),
+        "Filter not inserted into site: {log}"
+    );
+}
+
+#[test]
+fn filter_import_dry_run() {
+    use crate::api::build_test_server_state;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait};
+    use std::sync::Arc;
+    use time::OffsetDateTime;
+
+    fn filter(regex: &str) -> CreateFilter {
+        CreateFilter {
+            affects_user: false,
+            affects_email: false,
+            affects_page: true,
+            affects_file: false,
+            affects_forum: false,
+            regex: str!(regex),
+            description: String::new(),
+        }
+    }
+
+    let existing = FilterModel {
+        filter_id: 12,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: None,
+        site_id: Some(2),
+        affects_user: false,
+        affects_email: false,
+        affects_page: true,
+        affects_file: false,
+        affects_forum: false,
+        regex: str!("ham"),
+        description: String::new(),
+    };
+
+    // Each distinct regular expression is checked once, in order.
+    // Only "ham" already exists on the site.
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<FilterModel>::new(), Vec::new()])
+        .append_query_results([[existing]])
+        .into_connection();
+
+    let filters = vec![
+        filter("spam"),
+        filter("eggs"),
+        filter("spam"),
+        filter("ham"),
+        filter("eggs"),
+    ];
+
+    let state = task::block_on(build_test_server_state(database));
+    let output = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let output = FilterService::import(&ctx, Some(2), 4, filters, true).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(output)
+    })
+    .expect("Unable to import filters");
+
+    // Conflicts and repeats within the list are reported, and nothing is created
+    assert!(output.created.is_empty());
+    assert_eq!(output.skipped, ["spam", "ham", "eggs"]);
+
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    assert!(!log.contains("INSERT INTO"), "Dry run inserted rows: {log}");
+}
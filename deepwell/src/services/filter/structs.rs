@@ -18,8 +18,11 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::models::filter;
-use crate::web::ProvidedValue;
+use crate::models::filter::{self, Model as FilterModel};
+use crate::web::{
+    check_not_blank, Pagination, ProvidedValue, Validate, ValidationResult,
+};
+use sea_orm::query::Order;
 use sea_orm::{ColumnTrait, Condition};
 
 /// Denotes what class of filter is being selected.
@@ -148,7 +151,131 @@ impl From<FilterType> for filter::Column {
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+/// Describes what order filters should be retrieved in.
+///
+/// Like `PageOrder`, it is composed of the column to order by,
+/// and whether the order should be ascending or descending.
+///
+/// Filters with equal values in that column are ordered by ID,
+/// so that the order is always stable between pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterOrder {
+    pub column: FilterOrderColumn,
+    pub direction: Order,
+}
+
+impl Default for FilterOrder {
+    #[inline]
+    fn default() -> Self {
+        FilterOrder {
+            column: FilterOrderColumn::default(),
+            direction: Order::Asc,
+        }
+    }
+}
+
+/// Describes what column that filters should be ordered by.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FilterOrderColumn {
+    /// Requests filters in filter ID order.
+    /// This is the default.
+    #[default]
+    Id,
+
+    /// Requests filters in filter creation order.
+    Creation,
+
+    /// Requests filters in description order.
+    Description,
+}
+
+impl FilterOrderColumn {
+    #[inline]
+    pub fn into_column(self) -> filter::Column {
+        self.into()
+    }
+}
+
+impl From<FilterOrderColumn> for filter::Column {
+    #[inline]
+    fn from(order: FilterOrderColumn) -> filter::Column {
+        match order {
+            FilterOrderColumn::Id => filter::Column::FilterId,
+            FilterOrderColumn::Creation => filter::Column::CreatedAt,
+            FilterOrderColumn::Description => filter::Column::Description,
+        }
+    }
+}
+
+/// The number of extant platform and site filters, by type.
+#[derive(Serialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterClassCounts {
+    pub platform: FilterCounts,
+    pub site: FilterCounts,
+}
+
+/// The number of filters in a class, by type.
+///
+/// Since a filter may affect several types, the sum
+/// of the type counts may be greater than the total.
+#[derive(Serialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterCounts {
+    pub total: u64,
+    pub user: u64,
+    pub email: u64,
+    pub page: u64,
+    pub file: u64,
+    pub forum: u64,
+}
+
+/// Which page of filters to get, see `FilterService::get_page()`.
+///
+/// If `site_id` is `None`, then platform filters are listed.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GetFilterPage {
+    pub site_id: Option<i64>,
+    pub filter_type: Option<FilterType>,
+    pub deleted: Option<bool>,
+
+    #[serde(default)]
+    pub order_by: FilterOrderColumn,
+
+    #[serde(default)]
+    pub descending: bool,
+
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
+impl Validate for GetFilterPage {}
+
+impl GetFilterPage {
+    pub fn order(&self) -> FilterOrder {
+        FilterOrder {
+            column: self.order_by,
+            direction: if self.descending {
+                Order::Desc
+            } else {
+                Order::Asc
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterPageOutput {
+    pub filters: Vec<FilterModel>,
+
+    /// The total number of filters across all pages.
+    pub total_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreateFilter {
     pub affects_user: bool,
     pub affects_email: bool,
@@ -159,6 +286,12 @@ pub struct CreateFilter {
     pub description: String,
 }
 
+impl Validate for CreateFilter {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("regex", &self.regex)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct UpdateFilter {
     pub filter_id: i64,
@@ -170,3 +303,121 @@ pub struct UpdateFilter {
     pub regex: ProvidedValue<String>,
     pub description: ProvidedValue<String>,
 }
+
+/// A filter to create, with the user creating it.
+///
+/// If `site_id` is `None`, then this is a platform filter.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddFilter {
+    pub site_id: Option<i64>,
+    pub actor_id: i64,
+    pub filter: CreateFilter,
+}
+
+impl Validate for AddFilter {
+    fn validate(&self) -> ValidationResult {
+        self.filter.validate()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EditFilter {
+    pub actor_id: i64,
+    pub filter: UpdateFilter,
+}
+
+impl Validate for EditFilter {
+    fn validate(&self) -> ValidationResult {
+        if let Some(regex) = self.filter.regex.to_option() {
+            check_not_blank("regex", regex)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A filter to delete or restore, with the user doing so.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifyFilter {
+    pub filter_id: i64,
+    pub actor_id: i64,
+}
+
+impl Validate for ModifyFilter {}
+
+/// Several filters to delete or restore, with the user doing so.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifyFilters {
+    pub filter_ids: Vec<i64>,
+    pub actor_id: i64,
+}
+
+impl Validate for ModifyFilters {}
+
+/// Which filters to export, see `FilterService::export()`.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportFilters {
+    pub site_id: Option<i64>,
+    pub filter_type: Option<FilterType>,
+}
+
+impl Validate for ExportFilters {}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFilters {
+    pub site_id: Option<i64>,
+    pub actor_id: i64,
+    pub filters: Vec<CreateFilter>,
+
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl Validate for ImportFilters {
+    fn validate(&self) -> ValidationResult {
+        self.filters.validate()
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ImportFilterOutput {
+    pub created: Vec<FilterModel>,
+    pub skipped: Vec<String>,
+}
+
+/// The result of a bulk filter operation for one filter.
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkFilterResult {
+    pub filter_id: i64,
+    pub status: BulkFilterStatus,
+}
+
+/// What happened to a filter during a bulk delete or restore.
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BulkFilterStatus {
+    /// The filter was deleted.
+    Deleted,
+
+    /// The filter was restored.
+    Restored,
+
+    /// The filter was skipped, since it was already deleted.
+    AlreadyDeleted,
+
+    /// The filter was skipped, since it was not deleted.
+    AlreadyExtant,
+
+    /// The filter was skipped, since restoring it would conflict with an extant filter.
+    Conflict,
+
+    /// No filter with this ID exists.
+    NotFound,
+}
@@ -0,0 +1,201 @@
+/*
+ * services/fixtures.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Model fixtures for tests.
+//!
+//! Each function builds a model with neutral default values, which tests
+//! customize using struct update syntax. This way adding a column only
+//! requires updating the fixture here, rather than every test.
+
+use crate::models::filter::Model as FilterModel;
+use crate::models::page::Model as PageModel;
+use crate::models::page_category::Model as PageCategoryModel;
+use crate::models::page_lock::Model as PageLockModel;
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::sea_orm_active_enums::{PageRevisionType, UserType, VoteType};
+use crate::models::session::Model as SessionModel;
+use crate::models::site::Model as SiteModel;
+use crate::models::user::Model as UserModel;
+use time::OffsetDateTime;
+
+pub fn site(site_id: i64) -> SiteModel {
+    SiteModel {
+        site_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: None,
+        from_wikidot: false,
+        slug: str!("test"),
+        name: str!("Test Wiki"),
+        tagline: String::new(),
+        description: String::new(),
+        locale: str!("en"),
+        default_page: str!("start"),
+        default_category: str!("_default"),
+        slug_allow_categories: true,
+        slug_pattern: None,
+        require_revision_comments: false,
+        allow_anonymous_edits: false,
+        vote_type: VoteType::UpsDowns,
+        retain_compiled_html: true,
+        disabled_blocks: vec![],
+        robots_txt: None,
+        custom_domain: None,
+    }
+}
+
+pub fn page(site_id: i64, page_id: i64, slug: &str) -> PageModel {
+    PageModel {
+        page_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: None,
+        deletion_reason: None,
+        from_wikidot: false,
+        site_id,
+        page_category_id: 1,
+        slug: str!(slug),
+        locale: None,
+        discussion_thread_id: None,
+        keywords: vec![],
+        publish_at: None,
+        expires_at: None,
+    }
+}
+
+pub fn page_category(site_id: i64, category_id: i64, slug: &str) -> PageCategoryModel {
+    PageCategoryModel {
+        category_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        site_id,
+        slug: str!(slug),
+    }
+}
+
+pub fn page_revision(
+    site_id: i64,
+    page_id: i64,
+    revision_id: i64,
+    revision_number: i32,
+) -> PageRevisionModel {
+    PageRevisionModel {
+        revision_id,
+        revision_type: PageRevisionType::Regular,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        revision_number,
+        page_id,
+        site_id,
+        user_id: 1,
+        from_wikidot: false,
+        changes: vec![],
+        wikitext_hash: vec![],
+        compiled_hash: None,
+        compiled_at: OffsetDateTime::UNIX_EPOCH,
+        compiled_generator: String::new(),
+        comments: String::new(),
+        hidden: vec![],
+        title: String::new(),
+        alt_title: None,
+        slug: format!("page-{page_id}"),
+        tags: vec![],
+    }
+}
+
+pub fn page_lock(page_lock_id: i64, page_id: i64, user_id: i64) -> PageLockModel {
+    PageLockModel {
+        page_lock_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: None,
+        expires_at: None,
+        from_wikidot: false,
+        lock_type: str!("edit"),
+        page_id,
+        user_id,
+        reason: String::new(),
+    }
+}
+
+pub fn user(user_id: i64, name: &str) -> UserModel {
+    UserModel {
+        user_id,
+        user_type: UserType::Regular,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: None,
+        from_wikidot: false,
+        name: str!(name),
+        slug: name.to_lowercase(),
+        name_changes_left: 2,
+        last_renamed_at: None,
+        name_changes_refilled_at: OffsetDateTime::UNIX_EPOCH,
+        last_login_at: None,
+        failed_login_count: 0,
+        last_failed_login_at: None,
+        email: format!("{}@example.com", name.to_lowercase()),
+        email_verified_at: None,
+        pending_email: None,
+        pending_email_token: None,
+        password: String::new(),
+        multi_factor_secret: None,
+        multi_factor_recovery_codes: None,
+        locale: str!("en"),
+        avatar_s3_hash: None,
+        real_name: None,
+        gender: None,
+        birthday: None,
+        location: None,
+        biography: None,
+        user_page: None,
+    }
+}
+
+pub fn session(session_token: &str, user_id: i64) -> SessionModel {
+    SessionModel {
+        session_token: str!(session_token),
+        user_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        expires_at: OffsetDateTime::UNIX_EPOCH,
+        last_active_at: OffsetDateTime::UNIX_EPOCH,
+        ip_address: str!("::1"),
+        country: None,
+        region: None,
+        user_agent: str!("test"),
+        restricted: false,
+    }
+}
+
+pub fn filter(filter_id: i64, regex: &str) -> FilterModel {
+    FilterModel {
+        filter_id,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: None,
+        site_id: None,
+        affects_user: false,
+        affects_email: false,
+        affects_page: true,
+        affects_file: false,
+        affects_forum: false,
+        regex: str!(regex),
+        description: String::new(),
+    }
+}
@@ -20,13 +20,22 @@
 
 use super::prelude::*;
 use crate::api::ApiServerState;
-use crate::services::{PageRevisionService, SessionService};
+use crate::services::{PageRevisionService, PageService, SessionService, UserService};
+use crate::web::PageOrder;
 use async_std::task;
 use crossfire::mpsc;
+use futures::stream::{self, StreamExt};
 use sea_orm::TransactionTrait;
 use std::sync::Arc;
+use std::time::Duration as StdDuration;
 use void::Void;
 
+/// How often to check for users due a name change token refill.
+const NAME_CHANGE_REFILL_PERIOD: StdDuration = StdDuration::from_secs(60 * 60);
+
+/// How often to check for scheduled pages which are due to be published.
+const PUBLISH_SCHEDULED_PAGES_PERIOD: StdDuration = StdDuration::from_secs(60);
+
 lazy_static! {
     static ref QUEUE: (mpsc::TxUnbounded<Job>, mpsc::RxUnbounded<Job>) =
         mpsc::unbounded_future();
@@ -61,10 +70,25 @@ impl JobService {
         Self::queue_job(Job::RerenderPageId { site_id, page_id });
     }
 
+    pub fn queue_rerender_site(site_id: i64) {
+        tide::log::debug!("Queueing all pages in site ID {site_id} for rerendering");
+        Self::queue_job(Job::RerenderSite { site_id });
+    }
+
     pub fn queue_prune_sessions() {
         tide::log::debug!("Queueing sessions list for pruning");
         Self::queue_job(Job::PruneSessions);
     }
+
+    pub fn queue_refill_name_changes() {
+        tide::log::debug!("Queueing users for name change token refills");
+        Self::queue_job(Job::RefillNameChanges);
+    }
+
+    pub fn queue_publish_scheduled_pages() {
+        tide::log::debug!("Queueing scheduled pages for publishing");
+        Self::queue_job(Job::PublishScheduledPages);
+    }
 }
 
 #[derive(Debug)]
@@ -91,8 +115,21 @@ impl JobRunner {
             }
         });
 
-        // TODO job that checks hourly for users who can get a name change token refill
-        //      see config.refill_name_change
+        task::spawn(async move {
+            loop {
+                tide::log::trace!("Running repeat job: refill name change tokens");
+                JobService::queue_refill_name_changes();
+                task::sleep(NAME_CHANGE_REFILL_PERIOD).await;
+            }
+        });
+
+        task::spawn(async move {
+            loop {
+                tide::log::trace!("Running repeat job: publish scheduled pages");
+                JobService::queue_publish_scheduled_pages();
+                task::sleep(PUBLISH_SCHEDULED_PAGES_PERIOD).await;
+            }
+        });
     }
 
     async fn main_loop(mut self) -> Void {
@@ -119,19 +156,103 @@ impl JobRunner {
     }
 
     async fn process_job(&mut self, job: Job) -> Result<()> {
+        // Site re-renders use a transaction for each page, see rerender_site()
+        if let Job::RerenderSite { site_id } = job {
+            self.rerender_site(site_id).await?;
+            return Ok(());
+        }
+
         let txn = self.state.database.begin().await?;
         let ctx = &ServiceContext::from_raw(&self.state, &txn);
+        let mut recent_changes = Vec::new();
 
         match job {
             Job::RerenderPageId { site_id, page_id } => {
                 PageRevisionService::rerender(ctx, site_id, page_id).await?;
             }
+            Job::RerenderSite { .. } => unreachable!("Site re-render handled above"),
             Job::PruneSessions => {
                 SessionService::prune(ctx).await?;
             }
+            Job::RefillNameChanges => {
+                UserService::refill_name_changes(ctx).await?;
+            }
+            Job::PublishScheduledPages => {
+                recent_changes = PageService::publish_due(ctx).await?;
+            }
         }
 
         txn.commit().await?;
+
+        // Announce published pages only once committed
+        for (site_id, change) in recent_changes {
+            self.state.recent_changes.publish(site_id, change);
+        }
+
         Ok(())
     }
+
+    /// Re-renders every extant page in a site.
+    ///
+    /// This is for after changes which affect how many pages render, such as
+    /// to a site-wide filter or template. Like `PageRevisionService::rerender()`,
+    /// only the compiled HTML of each page's latest revision is replaced,
+    /// no new revisions are created. Since every page is re-rendered, pages
+    /// which depend on each are not outdated.
+    ///
+    /// Each page is re-rendered in its own transaction, so that up to
+    /// `job_rerender_concurrency` pages are actually rendered at once,
+    /// and progress is logged as each completes. Pages which time out are skipped.
+    async fn rerender_site(&self, site_id: i64) -> Result<RerenderSiteOutput> {
+        let page_ids: Vec<i64> = {
+            let txn = self.state.database.begin().await?;
+            let ctx = &ServiceContext::from_raw(&self.state, &txn);
+            let pages = PageService::get_all(
+                ctx,
+                site_id,
+                None,
+                Some(false),
+                PageOrder::default(),
+            )
+            .await?;
+
+            txn.commit().await?;
+            pages.into_iter().map(|page| page.page_id).collect()
+        };
+
+        let concurrency = self.state.config.job_rerender_concurrency;
+        tide::log::info!(
+            "Re-rendering {} pages in site ID {site_id} ({concurrency} at a time)",
+            page_ids.len(),
+        );
+
+        let state = &self.state;
+        let mut output = RerenderSiteOutput::new(page_ids.len());
+        let mut renders = stream::iter(page_ids)
+            .map(|page_id| async move {
+                let result = rerender_page(state, site_id, page_id).await;
+                (page_id, result)
+            })
+            .buffer_unordered(concurrency);
+
+        while let Some((page_id, result)) = renders.next().await {
+            output.record(page_id, result)?;
+            tide::log::info!(
+                "Re-rendered {} of {} pages in site ID {site_id}",
+                output.completed(),
+                output.total,
+            );
+        }
+
+        Ok(output)
+    }
+}
+
+/// Re-renders a single page of a site re-render, in its own transaction.
+async fn rerender_page(state: &ApiServerState, site_id: i64, page_id: i64) -> Result<()> {
+    let txn = state.database.begin().await?;
+    let ctx = &ServiceContext::from_raw(state, &txn);
+    PageRevisionService::rerender_latest(ctx, site_id, page_id).await?;
+    txn.commit().await?;
+    Ok(())
 }
@@ -18,8 +18,56 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::prelude::*;
+
 #[derive(Debug, Clone)]
 pub enum Job {
     RerenderPageId { site_id: i64, page_id: i64 },
+    RerenderSite { site_id: i64 },
     PruneSessions,
+    RefillNameChanges,
+    PublishScheduledPages,
+}
+
+/// The outcome of re-rendering all the pages in a site.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RerenderSiteOutput {
+    pub total: usize,
+    pub rerendered: usize,
+
+    /// Pages which could not be rendered in time, and were skipped.
+    pub timed_out: Vec<i64>,
+}
+
+impl RerenderSiteOutput {
+    #[inline]
+    pub fn new(total: usize) -> Self {
+        RerenderSiteOutput {
+            total,
+            rerendered: 0,
+            timed_out: Vec::new(),
+        }
+    }
+
+    /// Records the result of re-rendering a page.
+    ///
+    /// Pages which time out are skipped, any other error is returned.
+    pub fn record(&mut self, page_id: i64, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => self.rerendered += 1,
+            Err(Error::RenderTimeout) => {
+                tide::log::warn!("Render timed out for page ID {page_id}, skipping");
+                self.timed_out.push(page_id);
+            }
+            Err(error) => return Err(error),
+        }
+
+        Ok(())
+    }
+
+    /// The number of pages processed so far, whether or not they succeeded.
+    #[inline]
+    pub fn completed(&self) -> usize {
+        self.rerendered + self.timed_out.len()
+    }
 }
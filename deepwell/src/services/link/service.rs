@@ -305,7 +305,7 @@ async fn update_connections(
                 Some(count) => {
                     let mut model: page_connection::ActiveModel = connection.into();
                     model.count = Set(count);
-                    model.updated_at = Set(Some(now()));
+                    model.updated_at = Set(Some(ctx.now()));
                     model.update(txn).await?;
                 }
 
@@ -326,7 +326,7 @@ async fn update_connections(
                 from_page_id: Set(from_page_id),
                 to_page_id: Set(to_page_id),
                 connection_type: Set(str!(connection_type.name())),
-                created_at: Set(now()),
+                created_at: Set(ctx.now()),
                 updated_at: Set(None),
                 count: Set(*count),
             },
@@ -369,7 +369,7 @@ async fn update_connections_missing(
                     let mut model: page_connection_missing::ActiveModel =
                         connection.into();
                     model.count = Set(count);
-                    model.updated_at = Set(Some(now()));
+                    model.updated_at = Set(Some(ctx.now()));
                     model.update(txn).await?;
                 }
 
@@ -392,7 +392,7 @@ async fn update_connections_missing(
                     to_site_id: Set(to_site_id),
                     to_page_slug: Set(str!(to_page_slug)),
                     connection_type: Set(str!(connection_type.name())),
-                    created_at: Set(now()),
+                    created_at: Set(ctx.now()),
                     updated_at: Set(None),
                     count: Set(*count),
                 }
@@ -433,7 +433,7 @@ async fn update_external_links(
                 Some(count) => {
                     let mut model: page_link::ActiveModel = link.into();
                     model.count = Set(count);
-                    model.updated_at = Set(Some(now()));
+                    model.updated_at = Set(Some(ctx.now()));
                     model.update(txn).await?;
                 }
 
@@ -452,7 +452,7 @@ async fn update_external_links(
         .map(|(ref url, count)| page_link::ActiveModel {
             page_id: Set(from_page_id),
             url: Set(str!(url)),
-            created_at: Set(now()),
+            created_at: Set(ctx.now()),
             updated_at: Set(None),
             count: Set(*count),
         })
@@ -21,7 +21,7 @@
 use crate::models::page_connection::Model as PageConnectionModel;
 use crate::models::page_connection_missing::Model as PageConnectionMissingModel;
 use crate::models::page_link::Model as PageLinkModel;
-use crate::web::Reference;
+use crate::web::{check_not_blank, Reference, Validate, ValidationResult};
 use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug)]
@@ -31,6 +31,8 @@ pub struct GetLinksFrom<'a> {
     pub page: Reference<'a>,
 }
 
+impl Validate for GetLinksFrom<'_> {}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetLinksFromOutput {
@@ -46,6 +48,8 @@ pub struct GetLinksTo<'a> {
     pub page: Reference<'a>,
 }
 
+impl Validate for GetLinksTo<'_> {}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetLinksToOutput {
@@ -59,6 +63,12 @@ pub struct GetLinksToMissing {
     pub page_slug: String,
 }
 
+impl Validate for GetLinksToMissing {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("page_slug", &self.page_slug)
+    }
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetLinksToMissingOutput {
@@ -79,6 +89,8 @@ pub struct GetLinksExternalFrom<'a> {
     pub page: Reference<'a>,
 }
 
+impl Validate for GetLinksExternalFrom<'_> {}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetLinksExternalFromOutput {
@@ -92,6 +104,12 @@ pub struct GetLinksExternalTo {
     pub url: String,
 }
 
+impl Validate for GetLinksExternalTo {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("url", &self.url)
+    }
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetLinksExternalToOutput {
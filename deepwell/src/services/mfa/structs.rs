@@ -21,6 +21,7 @@
 use super::prelude::*;
 use crate::services::PasswordService;
 use crate::utils::assert_is_csprng;
+use crate::web::{check_not_blank, Validate, ValidationResult};
 use data_encoding::BASE32_NOPAD;
 use rand::distributions::{Alphanumeric, DistString};
 use rand::{thread_rng, Rng};
@@ -91,6 +92,12 @@ pub struct MultiFactorConfigure {
     pub session_token: String,
 }
 
+impl Validate for MultiFactorConfigure {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("session_token", &self.session_token)
+    }
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct MultiFactorSetupOutput {
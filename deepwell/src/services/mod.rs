@@ -38,7 +38,7 @@ mod prelude {
     pub use super::context::ServiceContext;
     pub use super::error::*;
     pub use crate::config::Config;
-    pub use crate::utils::{find_or_error, now};
+    pub use crate::utils::find_or_error;
     pub use crate::web::{ProvidedValue, Reference};
     pub use sea_orm::{
         ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DeleteResult,
@@ -49,13 +49,19 @@ mod prelude {
 
 mod context;
 mod error;
+mod model_cache;
+
+#[cfg(test)]
+mod fixtures;
 
 pub mod alias;
+pub mod audit_log;
 pub mod authentication;
 pub mod blob;
 pub mod category;
 pub mod domain;
 // TODO pub mod email;
+pub mod feature_flag;
 pub mod file;
 pub mod file_revision;
 pub mod filter;
@@ -65,6 +71,7 @@ pub mod link;
 pub mod mfa;
 pub mod outdate;
 pub mod page;
+pub mod page_lock;
 pub mod page_revision;
 pub mod parent;
 pub mod password;
@@ -72,6 +79,7 @@ pub mod render;
 pub mod score;
 pub mod session;
 pub mod site;
+pub mod site_member;
 pub mod text;
 pub mod user;
 pub mod user_bot_owner;
@@ -82,12 +90,14 @@ use crate::api::ApiRequest;
 use sea_orm::DatabaseConnection;
 
 pub use self::alias::AliasService;
+pub use self::audit_log::AuditLogService;
 pub use self::authentication::AuthenticationService;
 pub use self::blob::BlobService;
 pub use self::category::CategoryService;
 pub use self::context::ServiceContext;
 pub use self::domain::DomainService;
 pub use self::error::*;
+pub use self::feature_flag::FeatureFlagService;
 pub use self::file::FileService;
 pub use self::file_revision::FileRevisionService;
 pub use self::filter::FilterService;
@@ -96,6 +106,7 @@ pub use self::link::LinkService;
 pub use self::mfa::MfaService;
 pub use self::outdate::OutdateService;
 pub use self::page::PageService;
+pub use self::page_lock::PageLockService;
 pub use self::page_revision::PageRevisionService;
 pub use self::parent::ParentService;
 pub use self::password::PasswordService;
@@ -103,6 +114,7 @@ pub use self::render::RenderService;
 pub use self::score::ScoreService;
 pub use self::session::SessionService;
 pub use self::site::SiteService;
+pub use self::site_member::SiteMemberService;
 pub use self::text::TextService;
 pub use self::user::UserService;
 pub use self::user_bot_owner::UserBotOwnerService;
@@ -0,0 +1,177 @@
+/*
+ * services/model_cache.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Per-request cache of models, to avoid repeated lookups of the same rows.
+//!
+//! Within one request, the same site, user, or page is often fetched by
+//! several services. Since a `ServiceContext` lasts for a single transaction,
+//! rows fetched by ID can be kept and reused until the request finishes.
+//!
+//! Any service which writes to a cached entity must invalidate it
+//! afterwards, otherwise later lookups in the request will be stale.
+
+use super::Result;
+use crate::models::page::Model as PageModel;
+use crate::models::site::Model as SiteModel;
+use crate::models::user::Model as UserModel;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard};
+
+/// A model which can be cached for the duration of a request, keyed by ID.
+pub trait CachedModel: Clone {
+    /// Gets the map of cached models of this type.
+    fn cache_map(cache: &mut ModelCache) -> &mut HashMap<i64, Self>;
+}
+
+impl CachedModel for SiteModel {
+    #[inline]
+    fn cache_map(cache: &mut ModelCache) -> &mut HashMap<i64, Self> {
+        &mut cache.sites
+    }
+}
+
+impl CachedModel for UserModel {
+    #[inline]
+    fn cache_map(cache: &mut ModelCache) -> &mut HashMap<i64, Self> {
+        &mut cache.users
+    }
+}
+
+impl CachedModel for PageModel {
+    #[inline]
+    fn cache_map(cache: &mut ModelCache) -> &mut HashMap<i64, Self> {
+        &mut cache.pages
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ModelCache {
+    sites: HashMap<i64, SiteModel>,
+    users: HashMap<i64, UserModel>,
+    pages: HashMap<i64, PageModel>,
+}
+
+impl ModelCache {
+    #[inline]
+    pub fn get<M: CachedModel>(&mut self, id: i64) -> Option<M> {
+        M::cache_map(self).get(&id).cloned()
+    }
+
+    #[inline]
+    pub fn insert<M: CachedModel>(&mut self, id: i64, model: M) {
+        M::cache_map(self).insert(id, model);
+    }
+
+    #[inline]
+    pub fn remove<M: CachedModel>(&mut self, id: i64) {
+        M::cache_map(self).remove(&id);
+    }
+
+    #[inline]
+    pub fn clear<M: CachedModel>(&mut self) {
+        M::cache_map(self).clear();
+    }
+}
+
+/// Gets a model from the cache, or loads and caches it if it isn't present.
+///
+/// Rows which don't exist are not cached, so that if
+/// one is created later in the request, it will be found.
+pub async fn get_or_load<M, F, Fut>(
+    cache: &Mutex<ModelCache>,
+    id: i64,
+    load: F,
+) -> Result<Option<M>>
+where
+    M: CachedModel,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Option<M>>>,
+{
+    // The lock cannot be held across the load, so it's taken twice
+    if let Some(model) = lock(cache).get(id) {
+        return Ok(Some(model));
+    }
+
+    let model = load().await?;
+    if let Some(ref model) = model {
+        lock(cache).insert(id, model.clone());
+    }
+
+    Ok(model)
+}
+
+#[inline]
+pub fn lock(cache: &Mutex<ModelCache>) -> MutexGuard<'_, ModelCache> {
+    cache.lock().expect("Model cache poisoned")
+}
+
+#[test]
+fn model_cache() {
+    use crate::services::fixtures;
+    use async_std::task;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn site(site_id: i64, name: &str) -> SiteModel {
+        SiteModel {
+            name: str!(name),
+            ..fixtures::site(site_id)
+        }
+    }
+
+    let cache = Mutex::new(ModelCache::default());
+    let queries = &AtomicUsize::new(0);
+
+    // Simulates SiteService::get(), counting database queries
+    let get_site = |site_id: i64, name: &'static str| {
+        task::block_on(get_or_load(&cache, site_id, move || async move {
+            queries.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(site(site_id, name)))
+        }))
+        .expect("Unable to get site")
+        .expect("Site not found")
+    };
+
+    // Second lookup of the same site hits the cache
+    assert_eq!(get_site(1, "Apple").name, "Apple");
+    assert_eq!(queries.load(Ordering::SeqCst), 1);
+    assert_eq!(get_site(1, "Apple").name, "Apple");
+    assert_eq!(queries.load(Ordering::SeqCst), 1);
+
+    // A different site does not
+    assert_eq!(get_site(2, "Banana").name, "Banana");
+    assert_eq!(queries.load(Ordering::SeqCst), 2);
+
+    // After a write, the site is fetched again
+    lock(&cache).remove::<SiteModel>(1);
+    assert_eq!(get_site(1, "Cherry").name, "Cherry");
+    assert_eq!(queries.load(Ordering::SeqCst), 3);
+    assert_eq!(get_site(2, "Durian").name, "Banana");
+    assert_eq!(queries.load(Ordering::SeqCst), 3);
+
+    // Missing rows are not cached
+    let missing = task::block_on(get_or_load(&cache, 3, move || async move {
+        queries.fetch_add(1, Ordering::SeqCst);
+        Ok(None::<SiteModel>)
+    }))
+    .expect("Unable to get site");
+    assert!(missing.is_none());
+    assert!(lock(&cache).get::<SiteModel>(3).is_none());
+}
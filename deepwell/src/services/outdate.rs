@@ -136,7 +136,7 @@ impl OutdateService {
     ) -> Result<()> {
         // If a template page has been updated,
         // we need to recompile everything in that category.
-        if page_slug == "_template" {
+        if let Some(category_slug) = template_category(category_slug, page_slug) {
             let category_select = if category_slug == "_default" {
                 // If the category is _default, we need to recompile everything.
                 // All other categories may inherit from _default.
@@ -166,3 +166,15 @@ impl OutdateService {
         Ok(())
     }
 }
+
+/// Gets the category whose pages are rendered with this page as their template.
+///
+/// If this page is not a category's `_template` page, then `None` is returned.
+/// See `PageService::apply_template()`.
+pub fn template_category<'a>(category_slug: &'a str, page_slug: &str) -> Option<&'a str> {
+    if page_slug == "_template" {
+        Some(category_slug)
+    } else {
+        None
+    }
+}
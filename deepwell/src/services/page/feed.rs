@@ -0,0 +1,159 @@
+/*
+ * services/page/feed.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! In-process feed of recent changes for each site, for streaming to clients.
+//!
+//! Each change is given an ID, sequential per site, so that a client which
+//! reconnects can resume from the last change it saw. Only the most recent
+//! changes are retained for this, and IDs do not persist across restarts.
+
+use super::structs::RecentChange;
+use async_std::channel::{self, Receiver, Sender};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// How many past changes per site are kept for clients resuming the feed.
+pub const RECENT_CHANGE_BACKLOG: usize = 100;
+
+/// A recent change, along with its ID in the site's feed.
+pub type RecentChangeEvent = (u64, RecentChange);
+
+#[derive(Debug, Default)]
+struct SiteFeed {
+    last_id: u64,
+    backlog: VecDeque<RecentChangeEvent>,
+    subscribers: Vec<Sender<RecentChangeEvent>>,
+}
+
+/// Broadcasts recent changes to each subscriber of that site.
+#[derive(Debug, Default)]
+pub struct RecentChangeFeed {
+    sites: Mutex<HashMap<i64, SiteFeed>>,
+}
+
+impl RecentChangeFeed {
+    /// Subscribes to all future changes for the given site.
+    ///
+    /// If `last_event_id` is given, any retained changes after it are
+    /// also returned, so they can be sent before any new ones.
+    pub fn subscribe(
+        &self,
+        site_id: i64,
+        last_event_id: Option<u64>,
+    ) -> (Vec<RecentChangeEvent>, Receiver<RecentChangeEvent>) {
+        tide::log::debug!(
+            "Adding subscriber for recent changes on site ID {site_id} (last event {last_event_id:?})",
+        );
+
+        let (sender, receiver) = channel::unbounded();
+        let mut sites = self.sites.lock().expect("Recent change feed poisoned");
+        let feed = sites.entry(site_id).or_default();
+
+        let missed = match last_event_id {
+            None => Vec::new(),
+            Some(last_event_id) => feed
+                .backlog
+                .iter()
+                .filter(|(id, _)| *id > last_event_id)
+                .cloned()
+                .collect(),
+        };
+
+        feed.subscribers.push(sender);
+        (missed, receiver)
+    }
+
+    /// Adds a change to the site's feed, sending it to all current subscribers.
+    pub fn publish(&self, site_id: i64, change: RecentChange) {
+        let mut sites = self.sites.lock().expect("Recent change feed poisoned");
+        let feed = sites.entry(site_id).or_default();
+
+        feed.last_id += 1;
+        let event = (feed.last_id, change);
+        tide::log::debug!("Publishing recent change {} for site ID {site_id}", event.0);
+
+        // Sending to an unbounded channel only fails once it is closed
+        feed.subscribers
+            .retain(|sender| sender.try_send(event.clone()).is_ok());
+
+        if feed.backlog.len() >= RECENT_CHANGE_BACKLOG {
+            feed.backlog.pop_front();
+        }
+
+        feed.backlog.push_back(event);
+    }
+}
+
+#[test]
+fn recent_change_feed() {
+    use crate::models::sea_orm_active_enums::PageRevisionType;
+    use time::OffsetDateTime;
+
+    fn change(revision_number: i32) -> RecentChange {
+        RecentChange {
+            page_id: 1,
+            page_deleted: false,
+            revision_id: 100 + i64::from(revision_number),
+            revision_type: PageRevisionType::Regular,
+            revision_number,
+            created_at: OffsetDateTime::from_unix_timestamp(1675166400).unwrap(),
+            user_id: 5,
+            comments: String::new(),
+            title: str!("Page"),
+            slug: str!("page"),
+        }
+    }
+
+    fn revision_numbers(events: &[RecentChangeEvent]) -> Vec<(u64, i32)> {
+        events
+            .iter()
+            .map(|(id, change)| (*id, change.revision_number))
+            .collect()
+    }
+
+    let feed = RecentChangeFeed::default();
+    let (missed, subscriber) = feed.subscribe(1, None);
+    let (_, other_site) = feed.subscribe(2, None);
+    assert!(missed.is_empty());
+
+    // An edit is sent to subscribers of the site
+    feed.publish(1, change(1));
+    let event = subscriber.try_recv().expect("No event for edit");
+    assert_eq!(revision_numbers(&[event]), [(1, 1)]);
+    assert!(
+        other_site.try_recv().is_err(),
+        "Other site received the event"
+    );
+
+    // Reconnecting with the last event ID resumes after it
+    feed.publish(1, change(2));
+    feed.publish(1, change(3));
+    let (missed, _) = feed.subscribe(1, Some(1));
+    assert_eq!(revision_numbers(&missed), [(2, 2), (3, 3)]);
+
+    // Only a limited backlog is retained
+    for revision_number in 4..=(RECENT_CHANGE_BACKLOG as i32 + 10) {
+        feed.publish(1, change(revision_number));
+    }
+
+    let (missed, _) = feed.subscribe(1, Some(0));
+    assert_eq!(missed.len(), RECENT_CHANGE_BACKLOG);
+    assert_eq!(missed[0].0, 11);
+}
@@ -0,0 +1,186 @@
+/*
+ * services/page/keywords.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Extraction of keywords describing a page, for search and page metadata.
+//!
+//! Keywords are taken from the page's tags, then its title, then the
+//! most frequent terms in its plain text render. The output is deterministic
+//! for the same input, and is bounded in both length and work done.
+
+use std::collections::{HashMap, HashSet};
+
+/// The maximum number of keywords stored for a page.
+pub const MAX_KEYWORDS: usize = 20;
+
+/// The maximum number of keywords taken from the page's text.
+const MAX_TEXT_KEYWORDS: usize = 10;
+
+/// How many characters of the page's text are scanned for terms.
+const MAX_TEXT_CHARS: usize = 100_000;
+
+/// The minimum length of a term, in characters.
+const MIN_TERM_CHARS: usize = 3;
+
+/// The maximum length of a keyword, in characters. Longer ones are skipped.
+const MAX_KEYWORD_CHARS: usize = 64;
+
+/// Common words which are never keywords.
+const STOP_WORDS: [&str; 48] = [
+    "about", "after", "all", "also", "and", "any", "are", "been", "before", "but", "can",
+    "could", "did", "for", "from", "had", "has", "have", "her", "his", "how", "into",
+    "its", "more", "not", "one", "only", "other", "our", "out", "she", "some", "than",
+    "that", "the", "their", "them", "then", "there", "these", "they", "this", "was",
+    "were", "which", "will", "with", "you",
+];
+
+/// Extracts up to [`MAX_KEYWORDS`] keywords for a page, all lowercase.
+///
+/// Tags are used as-is, while titles and text are split into terms.
+/// Terms from the text must appear at least twice, and are ordered by
+/// how often they appear, with ties broken alphabetically.
+pub fn extract_keywords(
+    title: &str,
+    alt_title: Option<&str>,
+    tags: &[String],
+    text: &str,
+) -> Vec<String> {
+    let mut keywords = Keywords::default();
+
+    for tag in tags {
+        keywords.add(tag.to_lowercase());
+    }
+
+    for term in terms(title).chain(alt_title.into_iter().flat_map(terms)) {
+        keywords.add(term);
+    }
+
+    for term in frequent_terms(text) {
+        keywords.add(term);
+    }
+
+    keywords.list
+}
+
+#[derive(Debug, Default)]
+struct Keywords {
+    list: Vec<String>,
+    seen: HashSet<String>,
+}
+
+impl Keywords {
+    fn add(&mut self, keyword: String) {
+        if self.list.len() >= MAX_KEYWORDS
+            || keyword.is_empty()
+            || keyword.chars().count() > MAX_KEYWORD_CHARS
+            || self.seen.contains(&keyword)
+        {
+            return;
+        }
+
+        self.seen.insert(keyword.clone());
+        self.list.push(keyword);
+    }
+}
+
+/// Splits text into lowercase terms, skipping short words, numbers, and stop words.
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= MIN_TERM_CHARS)
+        .filter(|word| !word.chars().all(|c| c.is_numeric()))
+        .map(str::to_lowercase)
+        .filter(|term| !STOP_WORDS.contains(&term.as_str()))
+}
+
+/// Gets the most frequent terms in the text which appear more than once.
+fn frequent_terms(text: &str) -> Vec<String> {
+    let text = match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((index, _)) => &text[..index],
+        None => text,
+    };
+
+    let mut counts = HashMap::new();
+    for term in terms(text) {
+        *counts.entry(term).or_insert(0) += 1;
+    }
+
+    let mut counts = counts
+        .into_iter()
+        .filter(|&(_, count)| count > 1)
+        .collect::<Vec<(String, usize)>>();
+
+    counts.sort_by(|(term_a, count_a), (term_b, count_b)| {
+        count_b.cmp(count_a).then_with(|| term_a.cmp(term_b))
+    });
+
+    counts
+        .into_iter()
+        .take(MAX_TEXT_KEYWORDS)
+        .map(|(term, _)| term)
+        .collect()
+}
+
+#[test]
+fn keywords() {
+    const TEXT: &str = "\
+SCP-173 is constructed from concrete and rebar with traces of Krylon brand spray paint. \
+SCP-173 is animate and extremely hostile. The object cannot move while within a direct \
+line of sight. Line of sight must not be broken at any time with SCP-173. Personnel \
+assigned to enter the container are instructed to alert one another before blinking. \
+Object is reported to attack by snapping the neck at the base of the skull, or by \
+strangulation. The object cannot move while observed, and personnel must keep \
+the object in sight.";
+
+    let tags = vec![str!("Euclid"), str!("scp"), str!("sculpture")];
+    let keywords = extract_keywords("SCP-173", Some("The Statue"), &tags, TEXT);
+
+    assert_eq!(
+        keywords,
+        vec![
+            "euclid",
+            "scp",
+            "sculpture",
+            "statue",
+            "object",
+            "sight",
+            "cannot",
+            "line",
+            "move",
+            "must",
+            "personnel",
+            "while",
+        ],
+    );
+
+    // Deterministic
+    assert_eq!(
+        keywords,
+        extract_keywords("SCP-173", Some("The Statue"), &tags, TEXT),
+    );
+
+    // Empty
+    assert!(extract_keywords("", None, &[], "").is_empty());
+
+    // Bounded
+    let tags = (0..50).map(|n| format!("tag-{n}")).collect::<Vec<_>>();
+    let keywords = extract_keywords("Title", None, &tags, TEXT);
+    assert_eq!(keywords.len(), MAX_KEYWORDS);
+    assert_eq!(keywords[0], "tag-0");
+    assert_eq!(keywords[MAX_KEYWORDS - 1], "tag-19");
+}
@@ -23,8 +23,13 @@ mod prelude {
     pub use super::structs::*;
 }
 
+mod feed;
+mod keywords;
 mod service;
 mod structs;
 
-pub use self::service::PageService;
+pub use self::feed::*;
+pub use self::service::{
+    is_expired, is_published, published_condition, unexpired_condition, PageService,
+};
 pub use self::structs::*;
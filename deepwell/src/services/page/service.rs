@@ -18,20 +18,49 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::keywords::extract_keywords;
 use super::prelude::*;
 use crate::models::page::{self, Entity as Page, Model as PageModel};
 use crate::models::page_category::Model as PageCategoryModel;
+use crate::models::page_revision::{
+    self, Entity as PageRevision, Model as PageRevisionModel,
+};
+use crate::models::sea_orm_active_enums::PageDeletionReason;
 use crate::services::filter::{FilterClass, FilterType};
 use crate::services::page_revision::{
     CreateFirstPageRevision, CreateFirstPageRevisionOutput, CreatePageRevision,
     CreatePageRevisionBody, CreatePageRevisionOutput, CreateResurrectionPageRevision,
     CreateTombstonePageRevision,
 };
-use crate::services::{CategoryService, FilterService, PageRevisionService, TextService};
-use crate::utils::{get_category_name, trim_default};
+use crate::services::render::has_severe_errors;
+use crate::services::site::{CommentPolicy, EditPolicy, SlugPolicy};
+use crate::services::{
+    CategoryService, FilterService, PageRevisionService, SiteService, TextService,
+};
+use crate::utils::{
+    canonicalize_locale, get_category, get_category_name, split_category_name,
+    trim_category, trim_default,
+};
 use crate::web::PageOrder;
+use sea_orm::sea_query::Expr;
+use sea_orm::{DatabaseBackend, FromQueryResult, Select, SelectTwo, Statement};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use time::OffsetDateTime;
 use wikidot_normalize::normalize;
 
+/// The name of the page in each category holding its template.
+const TEMPLATE_PAGE_NAME: &str = "_template";
+
+/// The placeholder in templates which is replaced with the page's wikitext.
+const TEMPLATE_CONTENT_PLACEHOLDER: &str = "%%content%%";
+
+/// The namespace of the advisory lock taken while finding a free slug.
+///
+/// Advisory locks share a single keyspace across the database, so the
+/// two-key form is used to avoid colliding with other locks keyed by ID.
+const SLUG_LOCK_NAMESPACE: i32 = 1;
+
 #[derive(Debug)]
 pub struct PageService;
 
@@ -43,16 +72,27 @@ impl PageService {
             wikitext,
             title,
             alt_title,
-            mut slug,
-            revision_comments: comments,
+            slug,
+            revision_comments: mut comments,
             user_id,
+            mut locale,
+            publish_at,
+            expires_at,
             bypass_filter,
+            reject_on_parser_error,
         }: CreatePage,
-    ) -> Result<CreatePageOutput> {
+    ) -> Result<(CreatePageOutput, Option<RecentChange>)> {
         let txn = ctx.transaction();
+        clean_revision_comments(&mut comments, ctx.config().max_revision_comment_length)?;
 
         // Ensure row consistency
-        normalize(&mut slug);
+        if let Some(ref mut locale) = locale {
+            *locale = canonicalize_locale(locale)?;
+        }
+
+        check_expiry(publish_at, expires_at)?;
+        let slug = Self::normalize_slug(ctx, site_id, &slug).await?;
+        Self::check_slug_policy(ctx, site_id, &slug).await?;
         Self::check_conflicts(ctx, site_id, &slug, "create").await?;
 
         // Perform filter validation
@@ -77,6 +117,9 @@ impl PageService {
             site_id: Set(site_id),
             page_category_id: Set(category_id),
             slug: Set(slug.clone()),
+            locale: Set(locale),
+            publish_at: Set(publish_at),
+            expires_at: Set(expires_at),
             ..Default::default()
         };
         let page = model.insert(txn).await?;
@@ -97,13 +140,28 @@ impl PageService {
         } = PageRevisionService::create_first(ctx, site_id, page.page_id, revision_input)
             .await?;
 
+        // Reject if requested, this error rolls back the transaction
+        if reject_on_parser_error && has_severe_errors(&parser_errors) {
+            tide::log::error!("Rejecting page creation due to parser errors");
+            return Err(Error::ParserErrors);
+        }
+
+        // Scheduled pages are announced once they're published
+        let recent_change = if is_published(publish_at, ctx.now()) {
+            Some(Self::recent_change(ctx, site_id, page.page_id, false).await?)
+        } else {
+            None
+        };
+
         // Build and return
-        Ok(CreatePageOutput {
+        let output = CreatePageOutput {
             page_id: page.page_id,
             slug,
             revision_id,
             parser_errors,
-        })
+        };
+
+        Ok((output, recent_change))
     }
 
     pub async fn edit(
@@ -111,34 +169,68 @@ impl PageService {
         EditPage {
             site_id,
             page: reference,
-            revision_comments: comments,
+            revision_comments: mut comments,
             user_id,
+            reject_on_parser_error,
             body:
                 EditPageBody {
                     wikitext,
                     title,
                     alt_title,
                     tags,
+                    mut locale,
                 },
         }: EditPage<'_>,
-    ) -> Result<Option<EditPageOutput>> {
+    ) -> Result<(Option<EditPageOutput>, Option<RecentChange>)> {
         let txn = ctx.transaction();
-        let PageModel { page_id, .. } = Self::get(ctx, site_id, reference).await?;
+        clean_revision_comments(&mut comments, ctx.config().max_revision_comment_length)?;
+        Self::check_comment_policy(ctx, site_id, &comments).await?;
+        let user_id = Self::check_edit_policy(ctx, site_id, user_id).await?;
+        let PageModel {
+            page_id,
+            publish_at,
+            ..
+        } = Self::get(ctx, site_id, reference).await?;
+
+        if let ProvidedValue::Set(Some(ref mut locale)) = locale {
+            *locale = canonicalize_locale(locale)?;
+        }
 
         // Perform filter validation
+        //
+        // Only a new alt title has text to check.
+        // Clearing it (setting null) is always permitted.
+        let new_alt_title = match alt_title {
+            ProvidedValue::Set(Some(ref alt_title)) => Some(alt_title),
+            ProvidedValue::Set(None) | ProvidedValue::Unset => None,
+        };
+
         Self::run_filter(
             ctx,
             site_id,
             wikitext.to_option(),
             title.to_option(),
-            // Flatten what is essentially Option<Option<_>>
-            match alt_title {
-                ProvidedValue::Set(Some(ref alt_title)) => Some(alt_title),
-                _ => None,
-            },
+            new_alt_title,
         )
         .await?;
 
+        // Set page updated_at column.
+        //
+        // Previously this was conditional on whether a revision was actually created.
+        // But since this rerenders regardless, we need to update the page row.
+        //
+        // This is done before the revision is created so that
+        // rendering uses the new locale, if it was changed.
+        let mut model = page::ActiveModel {
+            page_id: Set(page_id),
+            updated_at: Set(Some(ctx.now())),
+            ..Default::default()
+        };
+
+        locale.update_field(&mut model.locale);
+        model.update(txn).await?;
+        ctx.invalidate_model::<PageModel>(page_id);
+
         // Get latest revision
         let last_revision =
             PageRevisionService::get_latest(ctx, site_id, page_id).await?;
@@ -169,20 +261,30 @@ impl PageService {
         )
         .await?;
 
-        // Set page updated_at column.
-        //
-        // Previously this was conditional on whether a revision was actually created.
-        // But since this rerenders regardless, we need to update the page row.
-        let model = page::ActiveModel {
-            page_id: Set(page_id),
-            updated_at: Set(Some(now())),
-            ..Default::default()
-        };
+        // Reject if requested, this error rolls back the transaction
+        if reject_on_parser_error {
+            if let Some(CreatePageRevisionOutput {
+                parser_errors: Some(ref parser_errors),
+                ..
+            }) = revision_output
+            {
+                if has_severe_errors(parser_errors) {
+                    tide::log::error!("Rejecting page edit due to parser errors");
+                    return Err(Error::ParserErrors);
+                }
+            }
+        }
 
-        model.update(txn).await?;
+        // Scheduled pages are announced once they're published
+        let recent_change =
+            if revision_output.is_some() && is_published(publish_at, ctx.now()) {
+                Some(Self::recent_change(ctx, site_id, page_id, false).await?)
+            } else {
+                None
+            };
 
         // Build and return
-        Ok(revision_output)
+        Ok((revision_output, recent_change))
     }
 
     /// Moves a page from from one slug to another.
@@ -191,12 +293,14 @@ impl PageService {
         MovePage {
             site_id,
             page: reference,
-            mut new_slug,
-            revision_comments: comments,
+            new_slug,
+            revision_comments: mut comments,
             user_id,
         }: MovePage<'_>,
     ) -> Result<MovePageOutput> {
         let txn = ctx.transaction();
+        clean_revision_comments(&mut comments, ctx.config().max_revision_comment_length)?;
+        Self::check_comment_policy(ctx, site_id, &comments).await?;
 
         let PageModel {
             page_id,
@@ -206,12 +310,13 @@ impl PageService {
 
         // Check that a move is actually taking place,
         // and that a page with that slug doesn't already exist.
-        normalize(&mut new_slug);
+        let new_slug = Self::normalize_slug(ctx, site_id, &new_slug).await?;
         if old_slug == new_slug {
             tide::log::error!("Source and destination slugs are the same: {}", old_slug);
             return Err(Error::BadRequest);
         }
 
+        Self::check_slug_policy(ctx, site_id, &new_slug).await?;
         Self::check_conflicts(ctx, site_id, &new_slug, "move").await?;
 
         // Create category if not already present
@@ -250,11 +355,12 @@ impl PageService {
             page_id: Set(page_id),
             slug: Set(new_slug.clone()),
             page_category_id: Set(category_id),
-            updated_at: Set(Some(now())),
+            updated_at: Set(Some(ctx.now())),
             ..Default::default()
         };
 
         model.update(txn).await?;
+        ctx.invalidate_model::<PageModel>(page_id);
 
         // Build and return
 
@@ -283,10 +389,13 @@ impl PageService {
             site_id,
             page: reference,
             user_id,
-            revision_comments: comments,
+            revision_comments: mut comments,
+            deletion_reason,
         }: DeletePage<'_>,
-    ) -> Result<DeletePageOutput> {
+    ) -> Result<(DeletePageOutput, RecentChange)> {
         let txn = ctx.transaction();
+        clean_revision_comments(&mut comments, ctx.config().max_revision_comment_length)?;
+        Self::check_comment_policy(ctx, site_id, &comments).await?;
         let PageModel { page_id, .. } = Self::get(ctx, site_id, reference).await?;
 
         // Get latest revision
@@ -308,15 +417,13 @@ impl PageService {
         .await?;
 
         // Set deletion flag
-        let model = page::ActiveModel {
-            page_id: Set(page_id),
-            deleted_at: Set(Some(now())),
-            ..Default::default()
-        };
+        let model = deletion_model(page_id, deletion_reason, ctx.now());
 
         // Update and return
         model.update(txn).await?;
-        Ok((output, page_id).into())
+        ctx.invalidate_model::<PageModel>(page_id);
+        let recent_change = Self::recent_change(ctx, site_id, page_id, true).await?;
+        Ok(((output, page_id).into(), recent_change))
     }
 
     /// Restore a deleted page, causing it to be undeleted.
@@ -328,11 +435,12 @@ impl PageService {
             user_id,
             slug,
             revision_comments: comments,
+            auto_slug,
         }: RestorePage,
     ) -> Result<RestorePageOutput> {
         let txn = ctx.transaction();
         let page = Self::get_direct(ctx, page_id).await?;
-        let slug = slug.unwrap_or(page.slug);
+        let mut slug = slug.unwrap_or(page.slug);
 
         // Do page checks:
         // - Site is correct
@@ -349,6 +457,12 @@ impl PageService {
             return Err(Error::BadRequest);
         }
 
+        // If requested, find a free slug by adding a numeric suffix,
+        // for instance "scp-001" -> "scp-001-2".
+        if auto_slug {
+            slug = Self::find_available_slug(ctx, site_id, &slug).await?;
+        }
+
         Self::check_conflicts(ctx, site_id, &slug, "restore").await?;
 
         // Create category if not already present
@@ -380,14 +494,36 @@ impl PageService {
             page_id: Set(page_id),
             page_category_id: Set(category.category_id),
             deleted_at: Set(None),
+            deletion_reason: Set(None),
             ..Default::default()
         };
 
         // Update and return
         model.update(txn).await?;
+        ctx.invalidate_model::<PageModel>(page_id);
         Ok((output, slug).into())
     }
 
+    /// Restore the most recently deleted page with the given slug.
+    ///
+    /// This is for when the page ID is not known. See `restore()`.
+    pub async fn restore_by_slug(
+        ctx: &ServiceContext<'_>,
+        input: RestorePageBySlug,
+    ) -> Result<RestorePageOutput> {
+        let site_id = input.site_id;
+        let slug = Self::trim_site_default(ctx, site_id, &input.slug).await?;
+
+        let page = Self::find_by_slug_including_deleted(ctx, site_id, &slug).await?;
+        let page = deleted_page_to_restore(page, &slug)?;
+        tide::log::info!(
+            "Found deleted page ID {} for slug '{slug}' in site ID {site_id}",
+            page.page_id,
+        );
+
+        Self::restore(ctx, restore_by_slug_input(input, page.page_id)).await
+    }
+
     /// Rolls back a page to be the same as it was in a previous revision.
     /// Also called "page reset".
     ///
@@ -447,11 +583,12 @@ impl PageService {
         // Set page updated_at column.
         let model = page::ActiveModel {
             page_id: Set(page_id),
-            updated_at: Set(Some(now())),
+            updated_at: Set(Some(ctx.now())),
             ..Default::default()
         };
 
         model.update(txn).await?;
+        ctx.invalidate_model::<PageModel>(page_id);
 
         // Build and return
         Ok(revision_output)
@@ -492,29 +629,236 @@ impl PageService {
         reference: Reference<'_>,
     ) -> Result<Option<PageModel>> {
         let txn = ctx.transaction();
-        let page = {
-            let condition = match reference {
-                Reference::Id(id) => page::Column::PageId.eq(id),
-                Reference::Slug(slug) => {
-                    // Trim off _default category if present
-                    page::Column::Slug.eq(trim_default(&slug))
-                }
-            };
+        let condition = Self::reference_condition(ctx, site_id, reference).await?;
+        let page = Page::find().filter(condition).one(txn).await?;
+        Ok(page)
+    }
 
-            Page::find()
-                .filter(
-                    Condition::all()
-                        .add(condition)
-                        .add(page::Column::SiteId.eq(site_id))
-                        .add(page::Column::DeletedAt.is_null()),
+    /// Gets a page as it was at a particular revision, for viewing.
+    ///
+    /// If the compiled HTML for that revision is no longer stored,
+    /// the revision is rendered again from its wikitext.
+    pub async fn get_revision_view(
+        ctx: &ServiceContext<'_>,
+        GetPageRevisionView {
+            site_id,
+            page: reference,
+            revision_number,
+        }: GetPageRevisionView<'_>,
+    ) -> Result<GetPageRevisionViewOutput> {
+        let page = Self::get(ctx, site_id, reference).await?;
+        let revision =
+            PageRevisionService::get(ctx, site_id, page.page_id, revision_number).await?;
+
+        // Old revisions may not have retained their compiled HTML
+        let compiled_hash = revision.compiled_hash.as_deref();
+        let mut hashes = vec![revision.wikitext_hash.as_slice()];
+        hashes.extend(compiled_hash);
+
+        let mut texts = TextService::get_many(ctx, &hashes).await?;
+        let (wikitext, compiled_html) =
+            take_revision_texts(&mut texts, &revision.wikitext_hash, compiled_hash)?;
+
+        let (compiled_html, rerendered) = match compiled_html {
+            Some(compiled_html) => (compiled_html, false),
+            None => {
+                let compiled_html = PageRevisionService::render_revision(
+                    ctx,
+                    &revision,
+                    wikitext.clone(),
                 )
-                .one(txn)
-                .await?
+                .await?;
+
+                (compiled_html, true)
+            }
+        };
+
+        Ok(GetPageRevisionViewOutput {
+            page,
+            revision,
+            wikitext,
+            compiled_html,
+            rerendered,
+        })
+    }
+
+    /// Gets a page along with its latest revision.
+    ///
+    /// Performs a join rather than separate fetches of the page and
+    /// then its revision, which is useful for hot paths like page views.
+    pub async fn get_with_latest_revision(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+    ) -> Result<(PageModel, PageRevisionModel)> {
+        let txn = ctx.transaction();
+        let condition = Self::reference_condition(ctx, site_id, reference).await?;
+        let (page, revision) = Self::with_latest_revision(condition)
+            .one(txn)
+            .await?
+            .ok_or(Error::NotFound)?;
+
+        // All extant pages must have at least one revision
+        let revision = revision.ok_or(Error::Inconsistent)?;
+        Ok((page, revision))
+    }
+
+    /// Gets a visible page along with its latest revision.
+    ///
+    /// Like `get_with_latest_revision()`, except that pages which are
+    /// scheduled to be published later, or which have expired, are treated
+    /// as missing. This is for callers which do not check the viewer's
+    /// permissions to see them.
+    pub async fn get_visible_with_latest_revision(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+    ) -> Result<(PageModel, PageRevisionModel)> {
+        let txn = ctx.transaction();
+        let condition = Self::reference_condition(ctx, site_id, reference)
+            .await?
+            .add(published_condition(ctx.now()))
+            .add(unexpired_condition(ctx.now()));
+
+        let (page, revision) = Self::with_latest_revision(condition)
+            .one(txn)
+            .await?
+            .ok_or(Error::NotFound)?;
+
+        // All extant pages must have at least one revision
+        let revision = revision.ok_or(Error::Inconsistent)?;
+        Ok((page, revision))
+    }
+
+    /// Builds a query for pages joined with their revisions, latest first.
+    ///
+    /// When limited to one row, this yields the same revision as
+    /// `PageRevisionService::get_latest()` does for the page.
+    fn with_latest_revision(condition: Condition) -> SelectTwo<Page, PageRevision> {
+        Page::find()
+            .find_also_related(PageRevision)
+            .filter(condition)
+            .order_by_desc(page_revision::Column::RevisionNumber)
+    }
+
+    /// Finds a slug which is not in use by any extant page.
+    ///
+    /// If the normalized base slug is free, it is returned as-is.
+    /// Otherwise numeric suffixes are appended, starting at `-2`, until
+    /// a free slug is found. For instance "scp-001" -> "scp-001-2".
+    ///
+    /// This takes a transaction-scoped lock on the site, so concurrent
+    /// callers of this method cannot be handed the same slug. The lock
+    /// is released when the transaction ends.
+    pub async fn find_available_slug(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        base_slug: &str,
+    ) -> Result<String> {
+        let txn = ctx.transaction();
+        let mut base_slug = str!(base_slug);
+        normalize(&mut base_slug);
+
+        txn.execute(slug_lock_statement(site_id)).await?;
+
+        let slug = next_free_slug(&base_slug, |slug| async move {
+            let page =
+                Self::get_optional(ctx, site_id, Reference::from(slug.as_str())).await?;
+
+            Ok(page.is_some())
+        })
+        .await?;
+
+        if slug != base_slug {
+            tide::log::info!("Slug '{base_slug}' is taken, using '{slug}' instead");
+        }
+
+        Ok(slug)
+    }
+
+    /// Builds the condition to find an extant page from a reference.
+    async fn reference_condition(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+    ) -> Result<Condition> {
+        let condition = match reference {
+            Reference::Id(id) => page::Column::PageId.eq(id),
+            Reference::Slug(slug) => {
+                // Trim off default category if present
+                let slug = Self::trim_site_default(ctx, site_id, &slug).await?;
+                page::Column::Slug.eq(slug)
+            }
         };
 
+        Ok(Condition::all()
+            .add(condition)
+            .add(page::Column::SiteId.eq(site_id))
+            .add(page::Column::DeletedAt.is_null()))
+    }
+
+    /// Finds a page by slug, regardless of whether it has been deleted.
+    ///
+    /// If an extant page has this slug, then it is returned. Otherwise,
+    /// the most recently deleted page with this slug is returned, if any.
+    /// This lets callers decide whether to restore the page or refuse.
+    pub async fn find_by_slug_including_deleted(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<Option<PageModel>> {
+        let slug = Self::trim_site_default(ctx, site_id, slug).await?;
+
+        if let Some(page) =
+            Self::get_optional(ctx, site_id, Reference::from(slug.as_str())).await?
+        {
+            return Ok(Some(page));
+        }
+
+        Self::find_deleted_by_slug(ctx, site_id, &slug).await
+    }
+
+    /// Finds the most recently deleted page with this slug, if any.
+    async fn find_deleted_by_slug(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<Option<PageModel>> {
+        let txn = ctx.transaction();
+        let page = deleted_by_slug_query(site_id, slug).one(txn).await?;
         Ok(page)
     }
 
+    /// Finds an extant page by its current slug, or by any slug it used to have.
+    ///
+    /// Previous slugs are taken from the page's revision history, so a page
+    /// which has been moved can still be found at its old location. If several
+    /// pages used to have this slug, the one which most recently had it is chosen.
+    pub async fn find_by_historical_slug(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<Option<HistoricalSlugMatch>> {
+        let txn = ctx.transaction();
+        let slug = Self::trim_site_default(ctx, site_id, slug).await?;
+
+        if let Some(page) =
+            Self::get_optional(ctx, site_id, Reference::from(slug.as_str())).await?
+        {
+            return Ok(Some(slug_match(page, &slug)));
+        }
+
+        let result = PageRevision::find()
+            .find_also_related(Page)
+            .filter(historical_slug_condition(site_id, &slug))
+            .order_by_desc(page_revision::Column::CreatedAt)
+            .one(txn)
+            .await?;
+
+        let page = result.and_then(|(_, page)| page);
+        Ok(page.map(|page| slug_match(page, &slug)))
+    }
+
     /// Gets the page ID from a reference, looking up if necessary.
     ///
     /// Convenience method since this is much more common than the optional
@@ -547,10 +891,132 @@ impl PageService {
         page_id: i64,
     ) -> Result<Option<PageModel>> {
         let txn = ctx.transaction();
-        let page = Page::find_by_id(page_id).one(txn).await?;
+        ctx.cached_model(page_id, move || async move {
+            Page::find_by_id(page_id)
+                .one(txn)
+                .await
+                .map_err(Error::from)
+        })
+        .await
+    }
+
+    /// Sets when a page is to be published.
+    ///
+    /// Until then, the page is hidden from viewers who cannot moderate the site.
+    /// It must be before the page's expiry time, if it has one.
+    /// If `publish_at` is `None`, the page is published immediately, and announced
+    /// in recent changes unless it already was published. If `publish_at` has passed,
+    /// the page is visible immediately, though it is only announced by `publish_due()`.
+    pub async fn schedule(
+        ctx: &ServiceContext<'_>,
+        SchedulePage {
+            site_id,
+            page: reference,
+            publish_at,
+        }: SchedulePage<'_>,
+    ) -> Result<(PageModel, Option<RecentChange>)> {
+        let txn = ctx.transaction();
+        let PageModel {
+            page_id,
+            publish_at: current_publish_at,
+            expires_at,
+            ..
+        } = Self::get(ctx, site_id, reference).await?;
+
+        tide::log::info!(
+            "Scheduling page ID {page_id} in site ID {site_id} for publishing at {publish_at:?}",
+        );
+
+        check_expiry(publish_at, expires_at)?;
+        let model = page::ActiveModel {
+            page_id: Set(page_id),
+            publish_at: Set(publish_at),
+            updated_at: Set(Some(ctx.now())),
+            ..Default::default()
+        };
+        let page = model.update(txn).await?;
+        ctx.invalidate_model::<PageModel>(page_id);
+
+        let recent_change =
+            if schedule_publishes_now(current_publish_at, publish_at, ctx.now()) {
+                Some(Self::recent_change(ctx, site_id, page_id, false).await?)
+            } else {
+                None
+            };
+
+        Ok((page, recent_change))
+    }
+
+    /// Sets when a page is to expire.
+    ///
+    /// After this time, the page is hidden from viewers who are not
+    /// administrators, as if it didn't exist. It must be after the
+    /// page's scheduled publishing time, if it has one.
+    pub async fn set_expiry(
+        ctx: &ServiceContext<'_>,
+        SetPageExpiry {
+            site_id,
+            page: reference,
+            expires_at,
+        }: SetPageExpiry<'_>,
+    ) -> Result<PageModel> {
+        let txn = ctx.transaction();
+        let PageModel {
+            page_id,
+            publish_at,
+            ..
+        } = Self::get(ctx, site_id, reference).await?;
+
+        tide::log::info!(
+            "Setting page ID {page_id} in site ID {site_id} to expire at {expires_at:?}",
+        );
+
+        check_expiry(publish_at, expires_at)?;
+        let model = page::ActiveModel {
+            page_id: Set(page_id),
+            expires_at: Set(expires_at),
+            updated_at: Set(Some(ctx.now())),
+            ..Default::default()
+        };
+        let page = model.update(txn).await?;
+        ctx.invalidate_model::<PageModel>(page_id);
         Ok(page)
     }
 
+    /// Publishes all scheduled pages whose time has come.
+    ///
+    /// This clears their scheduled time. Returns the site ID and recent change
+    /// for each page published, to be announced once the transaction is committed.
+    pub async fn publish_due(
+        ctx: &ServiceContext<'_>,
+    ) -> Result<Vec<(i64, RecentChange)>> {
+        let txn = ctx.transaction();
+        let pages = Page::find()
+            .filter(publish_due_condition(ctx.now()))
+            .all(txn)
+            .await?;
+
+        tide::log::info!("Publishing {} scheduled pages", pages.len());
+
+        let mut recent_changes = Vec::with_capacity(pages.len());
+        for PageModel {
+            page_id, site_id, ..
+        } in pages
+        {
+            let model = page::ActiveModel {
+                page_id: Set(page_id),
+                publish_at: Set(None),
+                ..Default::default()
+            };
+            model.update(txn).await?;
+            ctx.invalidate_model::<PageModel>(page_id);
+            let change = Self::recent_change(ctx, site_id, page_id, false).await?;
+            recent_changes.push((site_id, change));
+        }
+
+        Ok(recent_changes)
+    }
+
     /// Get all pages in a site, with potential conditions.
     ///
     /// The `category` argument:
@@ -604,6 +1070,218 @@ impl PageService {
         Ok(pages)
     }
 
+    /// Gets the deleted pages in a site, most recently deleted first.
+    ///
+    /// If `deletion_reason` is set, only pages deleted for that reason are returned.
+    pub async fn get_deleted(
+        ctx: &ServiceContext<'_>,
+        GetDeletedPages {
+            site_id,
+            deletion_reason,
+        }: GetDeletedPages,
+    ) -> Result<Vec<PageModel>> {
+        tide::log::info!(
+            "Getting deleted pages in site ID {site_id} (reason {deletion_reason:?})",
+        );
+
+        let txn = ctx.transaction();
+        let pages = Page::find()
+            .filter(deleted_pages_condition(site_id, deletion_reason))
+            .order_by_desc(page::Column::DeletedAt)
+            .all(txn)
+            .await?;
+
+        Ok(pages)
+    }
+
+    /// Builds the recent change for the page's latest revision.
+    ///
+    /// This is returned to the caller rather than published here, since
+    /// it must only be sent to the site's feed once the transaction is committed.
+    async fn recent_change(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        page_deleted: bool,
+    ) -> Result<RecentChange> {
+        let revision = PageRevisionService::get_latest(ctx, site_id, page_id).await?;
+        Ok(RecentChange::from_revision(revision, page_deleted))
+    }
+
+    /// Gets the most recently changed pages in a site, newest first.
+    ///
+    /// Each page is listed once, with its latest revision made at or after `since`.
+    /// Changes to deleted pages are only included if `include_deleted` is set,
+    /// which should only be for administrators. These are flagged in the output.
+    pub async fn recent_changes(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        since: Option<OffsetDateTime>,
+        limit: u64,
+        include_deleted: bool,
+    ) -> Result<Vec<RecentChange>> {
+        tide::log::info!(
+            "Getting up to {limit} recent changes in site ID {site_id} since {since:?}",
+        );
+
+        let txn = ctx.transaction();
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        let mut changes = Vec::new();
+        let mut seen_pages = HashSet::new();
+
+        // Revisions are newest first, so the first seen for each page is its latest.
+        let mut revision_chunks = PageRevision::find()
+            .find_also_related(Page)
+            .filter(recent_changes_condition(
+                site_id,
+                since,
+                include_deleted,
+                ctx.now(),
+            ))
+            .order_by_desc(page_revision::Column::CreatedAt)
+            .order_by_desc(page_revision::Column::RevisionId)
+            .paginate(txn, 100);
+
+        while let Some(revisions) = revision_chunks.fetch_and_next().await? {
+            let revisions = revisions.into_iter().map(|(revision, page)| {
+                let page_deleted =
+                    matches!(page, Some(page) if page.deleted_at.is_some());
+                (revision, page_deleted)
+            });
+
+            if add_recent_changes(&mut changes, &mut seen_pages, revisions, limit) {
+                break;
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Finds extant pages in a site whose latest revisions have the same wikitext.
+    ///
+    /// Since text is stored by hash, this only compares hashes rather than
+    /// the wikitext itself. Each cluster has at least two pages, and they
+    /// are ordered by the lowest page ID in each.
+    pub async fn find_duplicates(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<DuplicateCluster>> {
+        tide::log::info!("Finding pages with duplicate wikitext in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let rows =
+            DuplicatePageRow::find_by_statement(duplicate_pages_statement(site_id))
+                .all(txn)
+                .await?;
+
+        Ok(duplicate_clusters(rows))
+    }
+
+    /// Gets a plain text excerpt of a page, such as for search results or previews.
+    ///
+    /// The excerpt is at most `max_chars` characters long, with whitespace collapsed.
+    /// If it must be cut short, it is cut at a word boundary where possible.
+    pub async fn get_excerpt(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        max_chars: usize,
+    ) -> Result<String> {
+        let text = PageRevisionService::render_latest_text(ctx, site_id, page_id).await?;
+        Ok(excerpt(&text, max_chars))
+    }
+
+    /// Extracts keywords from a page's newly-rendered revision, and stores them on it.
+    ///
+    /// Keywords come from the revision's tags, titles, and plain text render.
+    /// This is called whenever the latest revision is rendered,
+    /// see `PageRevisionService::render_and_update_links()`.
+    /// See [`extract_keywords`] for details.
+    pub async fn update_keywords(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        title: &str,
+        alt_title: Option<&str>,
+        tags: &[String],
+        text: &str,
+    ) -> Result<Vec<String>> {
+        tide::log::info!("Updating keywords for page ID {page_id}");
+
+        let txn = ctx.transaction();
+        let keywords = extract_keywords(title, alt_title, tags, text);
+
+        let model = page::ActiveModel {
+            page_id: Set(page_id),
+            keywords: Set(keywords.clone()),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+        ctx.invalidate_model::<PageModel>(page_id);
+
+        Ok(keywords)
+    }
+
+    /// Gets all extant pages in a site which have the given keyword.
+    ///
+    /// Keywords are stored in lowercase, so matching is case-insensitive.
+    pub async fn find_by_keyword(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        keyword: &str,
+    ) -> Result<Vec<PageModel>> {
+        tide::log::info!("Finding pages in site ID {site_id} with keyword '{keyword}'");
+
+        let txn = ctx.transaction();
+        let pages = Page::find()
+            .filter(
+                Condition::all()
+                    .add(page::Column::SiteId.eq(site_id))
+                    .add(page::Column::DeletedAt.is_null())
+                    .add(Expr::cust_with_values(
+                        "$1 = ANY(keywords)",
+                        [keyword.to_lowercase()],
+                    )),
+            )
+            .order_by_asc(page::Column::PageId)
+            .all(txn)
+            .await?;
+
+        Ok(pages)
+    }
+
+    /// Applies the page's category template to its wikitext, if there is one.
+    ///
+    /// As on Wikidot, a category's template is its `_template` page, and each
+    /// `%%content%%` within it is replaced with the page's wikitext. Pages whose
+    /// names begin with an underscore, such as the template itself, are not
+    /// templated. If there is no template, the wikitext is returned unchanged.
+    pub async fn apply_template(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+        wikitext: String,
+    ) -> Result<String> {
+        let template_slug = match template_slug(slug) {
+            Some(template_slug) => template_slug,
+            None => return Ok(wikitext),
+        };
+
+        let template =
+            Self::get_optional(ctx, site_id, Reference::Slug(cow!(&template_slug)))
+                .await?;
+
+        let PageModel { page_id, .. } = match template {
+            Some(template) => template,
+            None => return Ok(wikitext),
+        };
+
+        tide::log::debug!("Applying template {template_slug} to page {slug}");
+
+        let revision = PageRevisionService::get_latest(ctx, site_id, page_id).await?;
+        let template = TextService::get(ctx, &revision.wikitext_hash).await?;
+        Ok(fill_template(&template, wikitext))
+    }
+
     /// Checks to see if a page already exists at the slug specified.
     ///
     /// If so, this method fails with `Error::Conflict`. Otherwise it returns nothing.
@@ -641,6 +1319,77 @@ impl PageService {
         }
     }
 
+    /// Normalizes a slug into the form pages are stored under.
+    ///
+    /// Besides regular normalization, the default category is trimmed off,
+    /// the same as it is for lookups, so that the page can be found by slug.
+    async fn normalize_slug(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<String> {
+        let mut slug = str!(slug);
+        normalize(&mut slug);
+        Self::trim_site_default(ctx, site_id, &slug).await
+    }
+
+    /// Trims off the default category from a slug, if present.
+    ///
+    /// This includes both the explicit `_default` category, and the
+    /// site's configured default category, if it is different.
+    async fn trim_site_default(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<String> {
+        let slug = trim_default(slug);
+
+        // Only need to check the site if there's still a category
+        if get_category(slug).is_none() {
+            return Ok(str!(slug));
+        }
+
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        Ok(str!(trim_category(slug, &site.default_category)))
+    }
+
+    /// Checks that the slug is permitted by the site's slug policy.
+    ///
+    /// If not, this method fails with `Error::BadRequest`.
+    async fn check_slug_policy(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<()> {
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        SlugPolicy::from(&site).check(slug)
+    }
+
+    /// Checks that the revision comments are permitted by the site's comment policy.
+    ///
+    /// If not, this method fails with `Error::BadRequest`.
+    async fn check_comment_policy(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        comments: &str,
+    ) -> Result<()> {
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        CommentPolicy::from(&site).check(comments)
+    }
+
+    /// Determines the author of an edit according to the site's edit policy.
+    ///
+    /// If the edit is anonymous and the site does not permit this,
+    /// this method fails with `Error::InvalidAuthentication`.
+    async fn check_edit_policy(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: Option<i64>,
+    ) -> Result<i64> {
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        EditPolicy::from(&site).author(user_id)
+    }
+
     async fn run_filter<S: AsRef<str>>(
         ctx: &ServiceContext<'_>,
         site_id: i64,
@@ -650,7 +1399,8 @@ impl PageService {
     ) -> Result<()> {
         tide::log::info!("Checking page data against filters...");
 
-        let filter_matcher = FilterService::get_matcher(
+        // Page content is long, so note where filters matched for the author
+        let filter_matcher = FilterService::get_matcher_with_spans(
             ctx,
             FilterClass::PlatformAndSite(site_id),
             FilterType::Page,
@@ -677,3 +1427,1290 @@ impl PageService {
         Ok(())
     }
 }
+
+/// Takes the wikitext and compiled HTML for a revision from fetched texts.
+///
+/// The wikitext must be present, but the compiled HTML may not be, either
+/// because it was not retained or no longer exists. In that case it is `None`
+/// and the revision must be rendered again.
+fn take_revision_texts(
+    texts: &mut HashMap<Vec<u8>, String>,
+    wikitext_hash: &[u8],
+    compiled_hash: Option<&[u8]>,
+) -> Result<(String, Option<String>)> {
+    // Clone rather than remove, in case both hashes are the same
+    let wikitext = texts.get(wikitext_hash).cloned().ok_or(Error::NotFound)?;
+    let compiled_html = compiled_hash.and_then(|hash| texts.remove(hash));
+    Ok((wikitext, compiled_html))
+}
+
+/// Builds the model to mark a page as deleted, with an optional reason.
+fn deletion_model(
+    page_id: i64,
+    deletion_reason: Option<PageDeletionReason>,
+    deleted_at: OffsetDateTime,
+) -> page::ActiveModel {
+    page::ActiveModel {
+        page_id: Set(page_id),
+        deleted_at: Set(Some(deleted_at)),
+        deletion_reason: Set(deletion_reason),
+        ..Default::default()
+    }
+}
+
+/// Builds the condition for deleted pages in a site, optionally with a given reason.
+fn deleted_pages_condition(
+    site_id: i64,
+    deletion_reason: Option<PageDeletionReason>,
+) -> Condition {
+    Condition::all()
+        .add(page::Column::SiteId.eq(site_id))
+        .add(page::Column::DeletedAt.is_not_null())
+        .add_option(deletion_reason.map(|reason| page::Column::DeletionReason.eq(reason)))
+}
+
+/// Builds the query for deleted pages with the given slug, newest first.
+fn deleted_by_slug_query(site_id: i64, slug: &str) -> Select<Page> {
+    Page::find()
+        .filter(
+            Condition::all()
+                .add(page::Column::SiteId.eq(site_id))
+                .add(page::Column::Slug.eq(slug))
+                .add(page::Column::DeletedAt.is_not_null()),
+        )
+        .order_by_desc(page::Column::DeletedAt)
+        .order_by_desc(page::Column::PageId)
+}
+
+/// Checks that a page found by slug is a deleted page which can be restored.
+///
+/// If an extant page has the slug, this fails with `Error::Conflict`,
+/// since restoring another page there would collide with it.
+fn deleted_page_to_restore(page: Option<PageModel>, slug: &str) -> Result<PageModel> {
+    match page {
+        None => Err(Error::NotFound),
+        Some(page) if page.deleted_at.is_none() => {
+            tide::log::error!(
+                "Page ID {} with slug '{slug}' is not deleted, cannot restore",
+                page.page_id,
+            );
+
+            Err(Error::Conflict)
+        }
+        Some(page) => Ok(page),
+    }
+}
+
+fn restore_by_slug_input(
+    RestorePageBySlug {
+        site_id,
+        slug: _,
+        revision_comments,
+        user_id,
+        auto_slug,
+    }: RestorePageBySlug,
+    page_id: i64,
+) -> RestorePage {
+    RestorePage {
+        site_id,
+        page_id,
+        revision_comments,
+        user_id,
+        slug: None,
+        auto_slug,
+    }
+}
+
+/// Builds the condition for extant pages which used to have the given slug.
+fn historical_slug_condition(site_id: i64, slug: &str) -> Condition {
+    Condition::all()
+        .add(page_revision::Column::SiteId.eq(site_id))
+        .add(page_revision::Column::Slug.eq(slug))
+        .add(page::Column::DeletedAt.is_null())
+}
+
+/// Wraps a page found by a historical slug lookup.
+///
+/// If the page's current slug differs from the one searched for,
+/// then it was found through one of its previous slugs.
+fn slug_match(page: PageModel, slug: &str) -> HistoricalSlugMatch {
+    let via_alias = page.slug != slug;
+    HistoricalSlugMatch { page, via_alias }
+}
+
+/// Strips control characters from revision comments, and checks their length.
+///
+/// Runs of control whitespace, such as tabs and newlines, become a single space
+/// so words aren't joined together, and leading or trailing whitespace is trimmed.
+/// Comments which are longer than `max_length` characters after
+/// stripping are rejected with `Error::BadRequest`.
+fn clean_revision_comments(comments: &mut String, max_length: usize) -> Result<()> {
+    let mut cleaned = String::with_capacity(comments.len());
+    let mut in_whitespace = false;
+
+    for c in comments.chars() {
+        if c.is_control() && c.is_whitespace() {
+            if !in_whitespace {
+                cleaned.push(' ');
+                in_whitespace = true;
+            }
+        } else if !c.is_control() {
+            cleaned.push(c);
+            in_whitespace = c.is_whitespace();
+        }
+    }
+
+    *comments = str!(cleaned.trim());
+
+    let length = comments.chars().count();
+    if length > max_length {
+        tide::log::error!(
+            "Revision comments are too long ({length} > {max_length} characters)",
+        );
+        return Err(Error::BadRequest);
+    }
+
+    Ok(())
+}
+
+/// Builds the condition for which page revisions appear in recent changes.
+///
+/// Pages which are scheduled to be published later or have expired are never included.
+fn recent_changes_condition(
+    site_id: i64,
+    since: Option<OffsetDateTime>,
+    include_deleted: bool,
+    now: OffsetDateTime,
+) -> Condition {
+    let since_condition = since.map(|since| page_revision::Column::CreatedAt.gte(since));
+    let deleted_condition = if include_deleted {
+        None
+    } else {
+        Some(page::Column::DeletedAt.is_null())
+    };
+
+    Condition::all()
+        .add(page_revision::Column::SiteId.eq(site_id))
+        .add_option(since_condition)
+        .add_option(deleted_condition)
+        .add(published_condition(now))
+        .add(unexpired_condition(now))
+}
+
+/// Adds revisions to the recent changes list, skipping pages already present.
+///
+/// The revisions must be ordered newest first, so that each page is
+/// represented by its latest revision. Returns `true` once the list is full.
+fn add_recent_changes<I>(
+    changes: &mut Vec<RecentChange>,
+    seen_pages: &mut HashSet<i64>,
+    revisions: I,
+    limit: usize,
+) -> bool
+where
+    I: IntoIterator<Item = (PageRevisionModel, bool)>,
+{
+    for (revision, page_deleted) in revisions {
+        if changes.len() >= limit {
+            break;
+        }
+
+        if seen_pages.insert(revision.page_id) {
+            changes.push(RecentChange::from_revision(revision, page_deleted));
+        }
+    }
+
+    changes.len() >= limit
+}
+
+/// A page whose latest revision shares its wikitext with another page.
+#[derive(FromQueryResult, Debug)]
+struct DuplicatePageRow {
+    page_id: i64,
+    slug: String,
+    wikitext_hash: Vec<u8>,
+}
+
+/// Builds the statement to find pages in a site whose latest wikitext is not unique.
+///
+/// Only the latest revision of each page is considered, so pages which merely
+/// used to be identical are not matched. Deleted pages are excluded.
+fn duplicate_pages_statement(site_id: i64) -> Statement {
+    Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        r#"
+        WITH latest AS (
+            SELECT DISTINCT ON (page_revision.page_id)
+                page_revision.page_id,
+                page_revision.slug,
+                page_revision.wikitext_hash
+            FROM page_revision
+            JOIN page ON page.page_id = page_revision.page_id
+            WHERE page_revision.site_id = $1
+            AND page.deleted_at IS NULL
+            ORDER BY page_revision.page_id, page_revision.revision_number DESC
+        ), duplicates AS (
+            SELECT wikitext_hash
+            FROM latest
+            GROUP BY wikitext_hash
+            HAVING COUNT(*) > 1
+        )
+        SELECT latest.page_id, latest.slug, latest.wikitext_hash
+        FROM latest
+        JOIN duplicates ON duplicates.wikitext_hash = latest.wikitext_hash
+        ORDER BY latest.page_id
+        "#,
+        [site_id.into()],
+    )
+}
+
+/// Groups duplicate pages into clusters by their shared wikitext.
+///
+/// The rows must be ordered by page ID, so that each cluster is too.
+fn duplicate_clusters(rows: Vec<DuplicatePageRow>) -> Vec<DuplicateCluster> {
+    let mut clusters: Vec<DuplicateCluster> = Vec::new();
+    let mut cluster_indices = HashMap::new();
+
+    for DuplicatePageRow {
+        page_id,
+        slug,
+        wikitext_hash,
+    } in rows
+    {
+        let index = *cluster_indices
+            .entry(wikitext_hash)
+            .or_insert_with_key(|hash| {
+                clusters.push(DuplicateCluster {
+                    wikitext_hash: hex::encode(hash),
+                    pages: Vec::new(),
+                });
+
+                clusters.len() - 1
+            });
+
+        clusters[index].pages.push(DuplicatePage { page_id, slug });
+    }
+
+    clusters
+}
+
+/// Collapses whitespace in text, and truncates it to at most `max_chars` characters.
+///
+/// Truncation is on a character boundary, so multibyte characters are never split.
+/// If a word would be cut in half, the excerpt ends before it instead, unless that
+/// would leave nothing at all.
+fn excerpt(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    // Find the byte index of the first character past the limit
+    let end = match text.char_indices().nth(max_chars) {
+        Some((index, _)) => index,
+        None => return text,
+    };
+
+    let mut excerpt = &text[..end];
+    let splits_word = !text[end..].starts_with(' ');
+
+    if splits_word {
+        if let Some(index) = excerpt.rfind(' ') {
+            excerpt = &excerpt[..index];
+        }
+    }
+
+    str!(excerpt.trim_end())
+}
+
+/// Determines if a page with the given publishing time is visible at `now`.
+pub fn is_published(publish_at: Option<OffsetDateTime>, now: OffsetDateTime) -> bool {
+    match publish_at {
+        None => true,
+        Some(publish_at) => publish_at <= now,
+    }
+}
+
+/// Builds the condition for pages which are published at `now`.
+///
+/// This is the query equivalent of `is_published()`.
+pub fn published_condition(now: OffsetDateTime) -> Condition {
+    Condition::any()
+        .add(page::Column::PublishAt.is_null())
+        .add(page::Column::PublishAt.lte(now))
+}
+
+/// Determines if scheduling a page publishes it now, and so it should be announced.
+///
+/// This is only when the schedule is cleared on a page which was not yet published.
+/// Times which have already passed are left for `publish_due()` to announce.
+fn schedule_publishes_now(
+    current_publish_at: Option<OffsetDateTime>,
+    publish_at: Option<OffsetDateTime>,
+    now: OffsetDateTime,
+) -> bool {
+    publish_at.is_none() && !is_published(current_publish_at, now)
+}
+
+/// Determines if a page with the given expiry time has expired at `now`.
+pub fn is_expired(expires_at: Option<OffsetDateTime>, now: OffsetDateTime) -> bool {
+    match expires_at {
+        None => false,
+        Some(expires_at) => expires_at <= now,
+    }
+}
+
+/// Builds the condition for pages which have not expired at `now`.
+///
+/// This is the query equivalent of `!is_expired()`.
+pub fn unexpired_condition(now: OffsetDateTime) -> Condition {
+    Condition::any()
+        .add(page::Column::ExpiresAt.is_null())
+        .add(page::Column::ExpiresAt.gt(now))
+}
+
+/// Ensures that a page would not expire before it is published.
+fn check_expiry(
+    publish_at: Option<OffsetDateTime>,
+    expires_at: Option<OffsetDateTime>,
+) -> Result<()> {
+    match (publish_at, expires_at) {
+        (Some(publish_at), Some(expires_at)) if expires_at <= publish_at => {
+            tide::log::error!(
+                "Page would expire at {expires_at} before it is published at {publish_at}",
+            );
+            Err(Error::BadRequest)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Builds the condition for extant pages which are due to be published.
+///
+/// Pages which expired before the job got to them are never announced.
+fn publish_due_condition(now: OffsetDateTime) -> Condition {
+    Condition::all()
+        .add(page::Column::PublishAt.lte(now))
+        .add(page::Column::DeletedAt.is_null())
+        .add(unexpired_condition(now))
+}
+
+/// Gets the slug of the template for a page, if it should have one.
+fn template_slug(slug: &str) -> Option<String> {
+    let (category, page_slug) = split_category_name(slug);
+    if page_slug.starts_with('_') {
+        return None;
+    }
+
+    Some(format!("{category}:{TEMPLATE_PAGE_NAME}"))
+}
+
+/// Substitutes the page's wikitext into its template.
+///
+/// A template without a content placeholder is malformed, so the
+/// page's wikitext is used as-is rather than being hidden.
+fn fill_template(template: &str, wikitext: String) -> String {
+    if !template.contains(TEMPLATE_CONTENT_PLACEHOLDER) {
+        tide::log::warn!("Template has no content placeholder, ignoring");
+        return wikitext;
+    }
+
+    template.replace(TEMPLATE_CONTENT_PLACEHOLDER, &wikitext)
+}
+
+/// Builds the statement to take the free slug advisory lock for a site.
+///
+/// The lock keys are 32-bit, so larger site IDs wrap around. This can only
+/// cause unrelated sites to wait on each other, never two callers to run at once.
+fn slug_lock_statement(site_id: i64) -> Statement {
+    Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        "SELECT pg_advisory_xact_lock($1, $2)",
+        [SLUG_LOCK_NAMESPACE.into(), (site_id as i32).into()],
+    )
+}
+
+/// Returns the first slug from `base`, `base-2`, `base-3`, etc. which is not taken.
+async fn next_free_slug<F, Fut>(base_slug: &str, mut is_taken: F) -> Result<String>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    let mut slug = str!(base_slug);
+    let mut suffix = 1;
+
+    while is_taken(slug.clone()).await? {
+        suffix += 1;
+        slug = format!("{base_slug}-{suffix}");
+    }
+
+    Ok(slug)
+}
+
+#[test]
+fn test_next_free_slug() {
+    use async_std::task::block_on;
+
+    macro_rules! check {
+        ($base:expr, $taken:expr, $expected:expr $(,)?) => {{
+            let taken: HashSet<&str> = $taken.into_iter().collect();
+            let taken = &taken;
+            let actual = block_on(next_free_slug($base, |slug| async move {
+                Ok(taken.contains(slug.as_str()))
+            }))
+            .expect("Unable to find free slug");
+
+            assert_eq!(actual, $expected, "Actual free slug doesn't match expected");
+        }};
+    }
+
+    check!("scp-001", [], "scp-001");
+    check!("scp-001", ["scp-002"], "scp-001");
+    check!("scp-001", ["scp-001"], "scp-001-2");
+    check!(
+        "scp-001",
+        ["scp-001", "scp-001-2", "scp-001-3"],
+        "scp-001-4"
+    );
+    check!("scp-001", ["scp-001", "scp-001-3"], "scp-001-2");
+    check!("fragment:start", ["fragment:start"], "fragment:start-2");
+}
+
+#[test]
+fn restore_auto_slug() {
+    use crate::api::build_test_server_state;
+    use crate::hash::TextHashAlgorithm;
+    use crate::models::page_connection::Model as PageConnectionModel;
+    use crate::models::page_connection_missing::Model as PageConnectionMissingModel;
+    use crate::models::page_link::Model as PageLinkModel;
+    use crate::models::text::Model as TextModel;
+    use crate::services::fixtures;
+    use async_std::task::block_on;
+    use sea_orm::{
+        DatabaseBackend, MockDatabase, MockExecResult, TransactionTrait, Value,
+    };
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    // Existing clients still fail on conflicts, rather than being moved
+    let input: RestorePage = serde_json::from_str(
+        r#"{"siteId": 1, "pageId": 8, "revisionComments": "", "userId": 4, "slug": null}"#,
+    )
+    .expect("Unable to deserialize restore request");
+    assert!(!input.auto_slug, "Auto slug enabled by default");
+
+    let input: RestorePage = serde_json::from_str(
+        r#"{"siteId": 1, "pageId": 8, "revisionComments": "", "userId": 4, "slug": null, "autoSlug": true}"#,
+    )
+    .expect("Unable to deserialize restore request");
+    assert!(input.auto_slug, "Auto slug not enabled");
+
+    // A page restored over its replacement gets the next free suffix
+    let wikitext = "**Item #:** SCP-001";
+    let wikitext_hash = TextHashAlgorithm::K12.hash(wikitext.as_bytes()).to_vec();
+    let deleted_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+
+    // Results for each query, in the order PageService::restore() makes them
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        // The deleted page, and its replacement at the original slug
+        .append_query_results([[PageModel {
+            deleted_at: Some(deleted_at),
+            ..fixtures::page(1, 8, "scp-001")
+        }]])
+        .append_query_results([[fixtures::page(1, 7, "scp-001")]])
+        .append_query_results([Vec::<PageModel>::new()])
+        .append_query_results([Vec::<PageModel>::new()])
+        .append_query_results([[fixtures::site(1)]])
+        .append_query_results([[fixtures::page_category(1, 1, "_default")]])
+        .append_query_results([[PageRevisionModel {
+            wikitext_hash: wikitext_hash.clone(),
+            ..fixtures::page_revision(1, 8, 80, 3)
+        }]])
+        // PageRevisionService::create_resurrection()
+        .append_query_results([[BTreeMap::from([("sum", Value::BigInt(None))])]])
+        .append_query_results([[TextModel {
+            hash: wikitext_hash.clone(),
+            hash_algorithm: str!(TextHashAlgorithm::K12.name()),
+            contents: str!(wikitext),
+        }]])
+        .append_query_results([Vec::<PageModel>::new()])
+        .append_query_results([Vec::<TextModel>::new()])
+        .append_query_results([Vec::<PageConnectionModel>::new()])
+        .append_query_results([Vec::<PageConnectionMissingModel>::new()])
+        .append_query_results([Vec::<PageLinkModel>::new()])
+        .append_query_results([[fixtures::page(1, 8, "scp-001-2")]])
+        .append_query_results([Vec::<PageConnectionModel>::new()])
+        .append_query_results([Vec::<PageConnectionModel>::new()])
+        .append_query_results([[fixtures::page_revision(1, 8, 81, 4)]])
+        // Undeleting the page
+        .append_query_results([[fixtures::page(1, 8, "scp-001-2")]])
+        // Taking the slug lock, and inserting the compiled HTML
+        .append_exec_results((0..2).map(|_| MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }))
+        .into_connection();
+
+    let state = block_on(build_test_server_state(database));
+    let output = block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let output = PageService::restore(&ctx, input).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(output)
+    })
+    .expect("Unable to restore page");
+
+    let json = serde_json::to_value(&output).expect("Unable to serialize output");
+    assert_eq!(json["slug"], "scp-001-2");
+
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    assert!(
+        log.contains("pg_advisory_xact_lock"),
+        "Slug lock not taken: {log}",
+    );
+    assert!(
+        log.contains(&format!("{:?}", Value::from("scp-001-2"))),
+        "Revision not created at the new slug: {log}",
+    );
+}
+
+#[test]
+fn recent_changes() {
+    use crate::services::fixtures;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    fn revision(revision_id: i64, page_id: i64, created_at: i64) -> PageRevisionModel {
+        let created_at = OffsetDateTime::from_unix_timestamp(created_at).unwrap();
+
+        PageRevisionModel {
+            created_at,
+            compiled_at: created_at,
+            comments: format!("Revision {revision_id}"),
+            ..fixtures::page_revision(1, page_id, revision_id, 0)
+        }
+    }
+
+    macro_rules! check {
+        ($revisions:expr, $limit:expr, $expected:expr $(,)?) => {{
+            let mut changes = Vec::new();
+            let mut seen_pages = HashSet::new();
+            add_recent_changes(&mut changes, &mut seen_pages, $revisions, $limit);
+
+            let actual: Vec<(i64, i64, bool)> = changes
+                .iter()
+                .map(|change| (change.page_id, change.revision_id, change.page_deleted))
+                .collect();
+
+            assert_eq!(
+                actual, $expected,
+                "Actual recent changes don't match expected"
+            );
+        }};
+    }
+
+    // Newest first, one entry per page
+    let revisions = vec![
+        (revision(5, 10, 500), false),
+        (revision(4, 11, 400), true),
+        (revision(3, 10, 300), false),
+        (revision(2, 12, 200), false),
+        (revision(1, 11, 100), true),
+    ];
+
+    check!(
+        revisions.clone(),
+        10,
+        [(10, 5, false), (11, 4, true), (12, 2, false)],
+    );
+    check!(revisions.clone(), 2, [(10, 5, false), (11, 4, true)]);
+    check!(revisions, 0, []);
+
+    // Since filter and deleted pages
+    let sql = PageRevision::find()
+        .find_also_related(Page)
+        .filter(recent_changes_condition(
+            1,
+            Some(OffsetDateTime::from_unix_timestamp(0).unwrap()),
+            false,
+            OffsetDateTime::from_unix_timestamp(1675166400).unwrap(),
+        ))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains(r#""page_revision"."created_at" >= '1970-01-01"#),
+        "No since filter: {sql}"
+    );
+    assert!(
+        sql.contains(r#""page"."deleted_at" IS NULL"#),
+        "Deleted pages included: {sql}"
+    );
+    assert!(
+        sql.contains(r#""page"."publish_at" IS NULL OR "page"."publish_at" <="#),
+        "Scheduled pages included: {sql}"
+    );
+    assert!(
+        sql.contains(r#""page"."expires_at" IS NULL OR "page"."expires_at" >"#),
+        "Expired pages included: {sql}"
+    );
+
+    let sql = PageRevision::find()
+        .find_also_related(Page)
+        .filter(recent_changes_condition(
+            1,
+            None,
+            true,
+            OffsetDateTime::from_unix_timestamp(1675166400).unwrap(),
+        ))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(!sql.contains(">="), "Unexpected since filter: {sql}");
+    assert!(
+        !sql.contains(r#""page"."deleted_at" IS NULL"#),
+        "Deleted pages excluded: {sql}"
+    );
+    assert!(
+        sql.contains(r#""page"."publish_at" IS NULL"#),
+        "Scheduled pages included: {sql}"
+    );
+}
+
+#[test]
+fn revision_comments() {
+    macro_rules! check {
+        ($input:expr, $max_length:expr, $expected:expr $(,)?) => {{
+            let mut comments = str!($input);
+            let result = clean_revision_comments(&mut comments, $max_length);
+            let expected: Option<&str> = $expected;
+
+            match expected {
+                Some(expected) => {
+                    assert!(result.is_ok(), "Comments {:?} were rejected", $input);
+                    assert_eq!(comments, expected, "Cleaned comments don't match");
+                }
+                None => assert!(result.is_err(), "Comments {:?} were accepted", $input),
+            }
+        }};
+    }
+
+    check!("", 10, Some(""));
+    check!("Fix typo", 10, Some("Fix typo"));
+    check!("Fix typos in section 2", 10, None);
+    check!("Fix\u{0}\u{1b}[31m typo\n", 20, Some("Fix[31m typo"));
+    check!("Fix\ttypo\r\n", 8, Some("Fix typo"));
+    check!("Fix\n\n\ttypos", 10, Some("Fix typos"));
+    check!("\u{7f}\u{7f}\u{7f}", 0, Some(""));
+    check!("ユーザー名を修正", 8, Some("ユーザー名を修正"));
+    check!("ユーザー名を修正しました", 8, None);
+}
+
+#[test]
+fn revision_texts() {
+    let wikitext_hash = vec![1; 16];
+    let compiled_hash = vec![2; 16];
+    let mut texts = HashMap::new();
+    texts.insert(wikitext_hash.clone(), str!("**SCP-001**"));
+    texts.insert(compiled_hash.clone(), str!("<b>SCP-001</b>"));
+
+    // Current revision, compiled HTML is stored
+    let (wikitext, compiled_html) =
+        take_revision_texts(&mut texts.clone(), &wikitext_hash, Some(&compiled_hash))
+            .expect("Unable to get revision texts");
+
+    assert_eq!(wikitext, "**SCP-001**");
+    assert_eq!(compiled_html.as_deref(), Some("<b>SCP-001</b>"));
+
+    // Old revision, compiled HTML was pruned and must be rerendered
+    texts.remove(&compiled_hash);
+    let (wikitext, compiled_html) =
+        take_revision_texts(&mut texts.clone(), &wikitext_hash, Some(&compiled_hash))
+            .expect("Unable to get revision texts");
+
+    assert_eq!(wikitext, "**SCP-001**");
+    assert_eq!(compiled_html, None, "Missing compiled HTML not reported");
+
+    // Old revision, compiled HTML was not retained
+    let (wikitext, compiled_html) =
+        take_revision_texts(&mut texts.clone(), &wikitext_hash, None)
+            .expect("Unable to get revision texts");
+
+    assert_eq!(wikitext, "**SCP-001**");
+    assert_eq!(compiled_html, None, "Unretained compiled HTML not reported");
+
+    // Same text for both
+    let (wikitext, compiled_html) =
+        take_revision_texts(&mut texts.clone(), &wikitext_hash, Some(&wikitext_hash))
+            .expect("Unable to get revision texts");
+
+    assert_eq!(wikitext, "**SCP-001**");
+    assert_eq!(compiled_html.as_deref(), Some("**SCP-001**"));
+
+    // Wikitext is missing
+    texts.clear();
+    let result = take_revision_texts(&mut texts, &wikitext_hash, Some(&compiled_hash));
+    assert!(result.is_err(), "Missing wikitext not reported");
+}
+
+#[test]
+fn deletion_reasons() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let deleted_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+
+    // Deleting with a reason records it
+    let model = deletion_model(8, Some(PageDeletionReason::Copyright), deleted_at);
+    assert_eq!(model.deleted_at, Set(Some(deleted_at)));
+    assert_eq!(
+        model.deletion_reason,
+        Set(Some(PageDeletionReason::Copyright)),
+    );
+
+    // Deleting without one leaves it empty
+    let model = deletion_model(8, None, deleted_at);
+    assert_eq!(model.deletion_reason, Set(None));
+
+    // Retrieving deleted pages by reason
+    let sql = Page::find()
+        .filter(deleted_pages_condition(
+            1,
+            Some(PageDeletionReason::Copyright),
+        ))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains(r#""page"."site_id" = 1"#),
+        "Site missing: {sql}"
+    );
+    assert!(
+        sql.contains(r#""page"."deleted_at" IS NOT NULL"#),
+        "Extant pages included: {sql}",
+    );
+    assert!(
+        sql.contains(
+            r#""page"."deletion_reason" = CAST('copyright' AS page_deletion_reason)"#,
+        ),
+        "Reason missing: {sql}",
+    );
+
+    // Retrieving all deleted pages
+    let sql = Page::find()
+        .filter(deleted_pages_condition(1, None))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        !sql.contains(r#""page"."deletion_reason" ="#),
+        "Reason included when not requested: {sql}",
+    );
+}
+
+#[test]
+fn duplicate_pages() {
+    use crate::api::build_test_server_state;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait, Value};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    fn row(
+        page_id: i64,
+        slug: &str,
+        wikitext_hash: &[u8],
+    ) -> BTreeMap<&'static str, Value> {
+        BTreeMap::from([
+            ("page_id", page_id.into()),
+            ("slug", slug.into()),
+            ("wikitext_hash", wikitext_hash.to_vec().into()),
+        ])
+    }
+
+    // Pages 1 and 3 share their latest wikitext, as do pages 2 and 4
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[
+            row(1, "scp-001", b"spam"),
+            row(2, "scp-002", b"eggs"),
+            row(3, "scp-003", b"spam"),
+            row(4, "scp-004", b"eggs"),
+        ]])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let clusters = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let clusters = PageService::find_duplicates(&ctx, 1).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(clusters)
+    })
+    .expect("Unable to find duplicates");
+
+    let page = |page_id, slug| DuplicatePage {
+        page_id,
+        slug: str!(slug),
+    };
+
+    assert_eq!(
+        clusters,
+        [
+            DuplicateCluster {
+                wikitext_hash: hex::encode(b"spam"),
+                pages: vec![page(1, "scp-001"), page(3, "scp-003")],
+            },
+            DuplicateCluster {
+                wikitext_hash: hex::encode(b"eggs"),
+                pages: vec![page(2, "scp-002"), page(4, "scp-004")],
+            },
+        ],
+    );
+
+    // Latest revisions are picked and grouped by the database
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    for clause in [
+        "DISTINCT ON (page_revision.page_id)",
+        "page_revision.revision_number DESC",
+        "page.deleted_at IS NULL",
+        "GROUP BY wikitext_hash",
+        "HAVING COUNT(*) > 1",
+    ] {
+        assert!(log.contains(clause), "Query missing {clause}: {log}");
+    }
+}
+
+#[test]
+fn historical_slugs() {
+    use crate::services::fixtures;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let page = fixtures::page(1, 1, "scp-001");
+
+    // Matching the current slug
+    let result = slug_match(page.clone(), "scp-001");
+    assert_eq!(result.page, page);
+    assert!(!result.via_alias, "Current slug matched as alias");
+
+    // Matching a slug the page used to have
+    let result = slug_match(page.clone(), "old-scp-001");
+    assert_eq!(result.page, page);
+    assert!(result.via_alias, "Historical slug not matched as alias");
+
+    // Searching revisions for the old slug
+    let sql = PageRevision::find()
+        .find_also_related(Page)
+        .filter(historical_slug_condition(1, "old-scp-001"))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains(r#""page_revision"."site_id" = 1"#),
+        "Site missing: {sql}",
+    );
+    assert!(
+        sql.contains(r#""page_revision"."slug" = 'old-scp-001'"#),
+        "Slug missing: {sql}",
+    );
+    assert!(
+        sql.contains(r#""page"."deleted_at" IS NULL"#),
+        "Deleted pages included: {sql}",
+    );
+}
+
+#[test]
+fn excerpts() {
+    macro_rules! check {
+        ($text:expr, $max_chars:expr, $expected:expr $(,)?) => {{
+            let actual = excerpt($text, $max_chars);
+            assert_eq!(
+                actual, $expected,
+                "Excerpt of {:?} at {} characters doesn't match expected",
+                $text, $max_chars,
+            );
+            assert!(
+                actual.chars().count() <= $max_chars,
+                "Excerpt longer than {} characters: {:?}",
+                $max_chars,
+                actual,
+            );
+        }};
+    }
+
+    // Short enough already
+    check!("", 10, "");
+    check!("apple banana", 12, "apple banana");
+    check!("  apple\n\n banana\t", 20, "apple banana");
+
+    // Truncating at word boundaries
+    check!("apple banana cherry", 12, "apple banana");
+    check!("apple banana cherry", 15, "apple banana");
+    check!("apple banana cherry", 5, "apple");
+    check!("apple banana cherry", 3, "app");
+    check!("apple banana cherry", 0, "");
+
+    // Multibyte characters
+    check!("日本語のテキスト", 3, "日本語");
+    check!("日本語のテキスト", 8, "日本語のテキスト");
+    check!("日本語 テキスト", 6, "日本語");
+    check!("café crème brûlée", 4, "café");
+    check!("café crème brûlée", 9, "café");
+    check!("café crème brûlée", 12, "café crème");
+    check!("🍎🍌 🍒🍇", 1, "🍎");
+    check!("🍎🍌 🍒🍇", 4, "🍎🍌");
+    check!("🍎🍌 🍒🍇", 5, "🍎🍌 🍒🍇");
+}
+
+#[test]
+fn restore_by_slug() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    // With several deleted pages with this slug, the newest is picked
+    let sql = deleted_by_slug_query(1, "scp-001")
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains(r#""page"."site_id" = 1"#),
+        "Site missing: {sql}",
+    );
+    assert!(
+        sql.contains(r#""page"."slug" = 'scp-001'"#),
+        "Slug missing: {sql}",
+    );
+    assert!(
+        sql.contains(r#""page"."deleted_at" IS NOT NULL"#),
+        "Extant pages included: {sql}",
+    );
+    assert!(
+        sql.ends_with(r#"ORDER BY "page"."deleted_at" DESC, "page"."page_id" DESC"#),
+        "Newest deleted page not first: {sql}",
+    );
+
+    // Restoring the page found, keeping the slug it had
+    let input = RestorePageBySlug {
+        site_id: 1,
+        slug: str!("scp-001"),
+        revision_comments: str!("Restoring"),
+        user_id: 4,
+        auto_slug: true,
+    };
+
+    let RestorePage {
+        site_id,
+        page_id,
+        revision_comments,
+        user_id,
+        slug,
+        auto_slug,
+    } = restore_by_slug_input(input, 8);
+
+    assert_eq!(site_id, 1);
+    assert_eq!(page_id, 8);
+    assert_eq!(revision_comments, "Restoring");
+    assert_eq!(user_id, 4);
+    assert_eq!(slug, None);
+    assert!(auto_slug);
+}
+
+#[test]
+fn find_including_deleted() {
+    use crate::api::build_test_server_state;
+    use crate::services::fixtures;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait};
+    use std::sync::Arc;
+
+    let deleted_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let deleted = PageModel {
+        deleted_at: Some(deleted_at),
+        ..fixtures::page(1, 8, "scp-001")
+    };
+
+    // No extant page has the slug, but a deleted one does
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<PageModel>::new()])
+        .append_query_results([[deleted.clone()]])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let page = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let page =
+            PageService::find_by_slug_including_deleted(&ctx, 1, "scp-001").await?;
+        txn.commit().await?;
+        Ok::<_, Error>(page)
+    })
+    .expect("Unable to find page");
+
+    assert_eq!(page, Some(deleted));
+
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    assert!(
+        log.contains(r#"\"page\".\"deleted_at\" IS NOT NULL"#),
+        "Deleted pages not searched: {log}",
+    );
+}
+
+#[test]
+fn restore_by_slug_lookup() {
+    use crate::services::fixtures;
+
+    let deleted_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let extant = fixtures::page(1, 7, "scp-001");
+    let deleted = PageModel {
+        deleted_at: Some(deleted_at),
+        ..fixtures::page(1, 8, "scp-001")
+    };
+
+    // A deleted page with the slug is restored
+    let page = deleted_page_to_restore(Some(deleted.clone()), "scp-001")
+        .expect("Deleted page not restorable");
+    assert_eq!(page, deleted);
+
+    // An extant page with the slug takes precedence, and blocks restoration
+    assert!(matches!(
+        deleted_page_to_restore(Some(extant), "scp-001"),
+        Err(Error::Conflict),
+    ));
+
+    // No page with the slug at all
+    assert!(matches!(
+        deleted_page_to_restore(None, "scp-001"),
+        Err(Error::NotFound),
+    ));
+}
+
+#[test]
+fn templates() {
+    use crate::services::outdate::template_category;
+    use crate::services::RenderService;
+    use ftml::data::PageInfo;
+    use ftml::settings::{WikitextMode, WikitextSettings};
+
+    fn render(wikitext: String) -> String {
+        let page_info = PageInfo::dummy();
+        let settings = WikitextSettings::from_mode(WikitextMode::Page);
+        let (html_output, _, _) =
+            RenderService::render_wikitext(wikitext, &page_info, &settings)
+                .expect("Unable to render wikitext");
+
+        html_output.body
+    }
+
+    // Template slugs
+    assert_eq!(template_slug("apple"), Some(str!("_default:_template")));
+    assert_eq!(
+        template_slug("_default:apple"),
+        Some(str!("_default:_template"))
+    );
+    assert_eq!(template_slug("fruit:apple"), Some(str!("fruit:_template")));
+    assert_eq!(template_slug("fruit:_template"), None);
+    assert_eq!(template_slug("_template"), None);
+    assert_eq!(template_slug("fruit:_start"), None);
+
+    // Editing a template outdates the pages it is applied to
+    for slug in ["apple", "_default:apple", "fruit:apple"] {
+        let template_slug = template_slug(slug).unwrap();
+        let (template_category_slug, template_page_slug) =
+            split_category_name(&template_slug);
+
+        assert_eq!(
+            template_category(template_category_slug, template_page_slug),
+            Some(split_category_name(slug).0),
+            "Template for {slug} would not outdate it",
+        );
+    }
+    assert_eq!(template_category("fruit", "apple"), None);
+    assert_eq!(template_category("fruit", "_start"), None);
+
+    // Filling in templates
+    let template = "[[div class=\"fruit\"]]\n%%content%%\n[[/div]]";
+    let wikitext = str!("**Apple**");
+    assert_eq!(
+        fill_template(template, wikitext.clone()),
+        "[[div class=\"fruit\"]]\n**Apple**\n[[/div]]",
+    );
+    assert_eq!(fill_template("%%content%% %%content%%", str!("A")), "A A");
+    assert_eq!(fill_template("No placeholder", wikitext.clone()), wikitext);
+
+    // Rendering with and without a template
+    let plain_html = render(wikitext.clone());
+    let templated_html = render(fill_template(template, wikitext));
+
+    assert!(!plain_html.contains("class=\"fruit\""));
+    assert!(plain_html.contains("<strong>Apple</strong>"));
+    assert!(templated_html.contains("<div class=\"fruit\">"));
+    assert!(templated_html.contains("<strong>Apple</strong>"));
+}
+
+#[test]
+fn scheduled_publishing() {
+    use sea_orm::{DbBackend, QueryTrait};
+    use time::Duration;
+
+    let now = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let earlier = now - Duration::hours(1);
+    let later = now + Duration::hours(1);
+
+    // Visibility
+    assert!(is_published(None, now));
+    assert!(is_published(Some(earlier), now));
+    assert!(is_published(Some(now), now));
+    assert!(!is_published(Some(later), now));
+    assert!(is_published(Some(later), later));
+
+    let sql = Page::find()
+        .filter(published_condition(now))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains(r#""page"."publish_at" IS NULL OR "page"."publish_at" <="#),
+        "Scheduled pages not excluded: {sql}",
+    );
+
+    // Announcements, only when the schedule is cleared on an unpublished page
+    assert!(schedule_publishes_now(Some(later), None, now));
+    assert!(!schedule_publishes_now(None, None, now));
+    assert!(!schedule_publishes_now(Some(earlier), None, now));
+    assert!(!schedule_publishes_now(Some(later), Some(earlier), now));
+    assert!(!schedule_publishes_now(None, Some(later), now));
+
+    // Job query
+    let sql = Page::find()
+        .filter(publish_due_condition(now))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(sql.contains("\"publish_at\" <="), "Due time missing: {sql}");
+    assert!(
+        sql.contains("\"deleted_at\" IS NULL"),
+        "Deleted pages included: {sql}",
+    );
+}
+
+#[test]
+fn page_expiry() {
+    use sea_orm::{DbBackend, QueryTrait};
+    use time::Duration;
+
+    let now = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let earlier = now - Duration::hours(1);
+    let later = now + Duration::hours(1);
+
+    // Expiry
+    assert!(!is_expired(None, now));
+    assert!(!is_expired(Some(later), now));
+    assert!(is_expired(Some(now), now));
+    assert!(is_expired(Some(earlier), now));
+
+    let sql = Page::find()
+        .filter(unexpired_condition(now))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains(r#""page"."expires_at" IS NULL OR "page"."expires_at" >"#),
+        "Expired pages not excluded: {sql}",
+    );
+
+    // Expired pages are not published by the job
+    let sql = Page::find()
+        .filter(publish_due_condition(now))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains(r#""page"."expires_at" IS NULL OR "page"."expires_at" >"#),
+        "Expired pages due to be published: {sql}",
+    );
+
+    // Must not expire before being published
+    assert!(check_expiry(None, None).is_ok());
+    assert!(check_expiry(Some(now), None).is_ok());
+    assert!(check_expiry(None, Some(earlier)).is_ok());
+    assert!(check_expiry(Some(now), Some(later)).is_ok());
+    assert!(matches!(
+        check_expiry(Some(now), Some(now)),
+        Err(Error::BadRequest),
+    ));
+    assert!(matches!(
+        check_expiry(Some(later), Some(earlier)),
+        Err(Error::BadRequest),
+    ));
+}
+
+#[test]
+fn latest_revision_query() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let condition = Condition::all()
+        .add(page::Column::PageId.eq(5))
+        .add(page::Column::SiteId.eq(1))
+        .add(page::Column::DeletedAt.is_null());
+
+    let sql = PageService::with_latest_revision(condition)
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    // Same page as PageService::get()
+    assert!(sql.contains(
+        r#"WHERE "page"."page_id" = 5 AND "page"."site_id" = 1 AND "page"."deleted_at" IS NULL"#,
+    ));
+
+    // Same revision as PageRevisionService::get_latest()
+    assert!(sql.contains(
+        r#"LEFT JOIN "page_revision" ON "page"."page_id" = "page_revision"."page_id""#,
+    ));
+    assert!(sql.ends_with(r#"ORDER BY "page_revision"."revision_number" DESC"#));
+}
+
+#[test]
+fn slug_lock() {
+    use sea_orm::Value;
+
+    let statement = slug_lock_statement(4);
+    assert_eq!(statement.sql, "SELECT pg_advisory_xact_lock($1, $2)");
+    assert_eq!(
+        statement.values.map(|values| values.0),
+        Some(vec![
+            Value::Int(Some(SLUG_LOCK_NAMESPACE)),
+            Value::Int(Some(4))
+        ]),
+    );
+}
+
+#[test]
+fn find_by_keyword() {
+    use crate::api::build_test_server_state;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait, Value};
+    use std::sync::Arc;
+
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([Vec::<PageModel>::new()])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let pages = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let pages = PageService::find_by_keyword(&ctx, 1, "Keter").await?;
+        txn.commit().await?;
+        Ok::<_, Error>(pages)
+    })
+    .expect("Unable to find pages by keyword");
+
+    assert!(pages.is_empty());
+
+    // Keywords are matched in lowercase, as they are stored
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    let keyword = format!("{:?}", Value::from("keter"));
+    assert!(
+        log.contains("= ANY(keywords)"),
+        "Keyword not matched: {log}"
+    );
+    assert!(log.contains(&keyword), "Keyword not lowercased: {log}");
+}
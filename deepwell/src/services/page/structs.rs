@@ -19,13 +19,17 @@
  */
 
 use super::prelude::*;
-use crate::models::sea_orm_active_enums::PageRevisionType;
+use crate::models::page::Model as PageModel;
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::sea_orm_active_enums::{PageDeletionReason, PageRevisionType};
 use crate::services::page_revision::CreatePageRevisionOutput;
+use crate::services::render::ClassifiedParseError;
 use crate::services::score::ScoreValue;
-use ftml::parsing::ParseError;
+use crate::web::{check_not_blank, Validate, ValidationResult};
+use schemars::JsonSchema;
 use time::OffsetDateTime;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CreatePage {
     pub site_id: i64,
@@ -36,8 +40,34 @@ pub struct CreatePage {
     pub revision_comments: String,
     pub user_id: i64,
 
+    /// The locale to render this page in, instead of the site's.
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    /// If set, the page is hidden from non-editors until this time.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub publish_at: Option<OffsetDateTime>,
+
+    /// If set, the page is hidden from non-administrators after this time.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub expires_at: Option<OffsetDateTime>,
+
     #[serde(default)]
     pub bypass_filter: bool,
+
+    /// Refuse to create the page if there are any error-severity parser errors.
+    #[serde(default)]
+    pub reject_on_parser_error: bool,
+}
+
+impl Validate for CreatePage {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("title", &self.title)?;
+        check_not_blank("slug", &self.slug)?;
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Debug)]
@@ -46,7 +76,7 @@ pub struct CreatePageOutput {
     pub page_id: i64,
     pub slug: String,
     pub revision_id: i64,
-    pub parser_errors: Vec<ParseError>,
+    pub parser_errors: Vec<ClassifiedParseError>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -56,6 +86,8 @@ pub struct GetPage<'a> {
     pub page: Reference<'a>,
 }
 
+impl Validate for GetPage<'_> {}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetPageOutput<'a> {
@@ -63,6 +95,9 @@ pub struct GetPageOutput<'a> {
     pub page_created_at: OffsetDateTime,
     pub page_updated_at: Option<OffsetDateTime>,
     pub page_deleted_at: Option<OffsetDateTime>,
+    pub page_deletion_reason: Option<PageDeletionReason>,
+    pub page_publish_at: Option<OffsetDateTime>,
+    pub page_expires_at: Option<OffsetDateTime>,
     pub page_revision_count: i32,
     pub site_id: i64,
     pub page_category_id: i64,
@@ -83,21 +118,64 @@ pub struct GetPageOutput<'a> {
     pub alt_title: Option<&'a str>,
     pub slug: &'a str,
     pub tags: &'a [String],
+    pub keywords: &'a [String],
     pub rating: ScoreValue,
 }
 
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPageRevisionView<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub revision_number: i32,
+}
+
+impl Validate for GetPageRevisionView<'_> {}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPageRevisionViewOutput {
+    pub page: PageModel,
+    pub revision: PageRevisionModel,
+    pub wikitext: String,
+    pub compiled_html: String,
+
+    /// Whether the compiled HTML was no longer stored, and had to be rendered again.
+    pub rerendered: bool,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct EditPage<'a> {
     pub site_id: i64,
     pub page: Reference<'a>,
     pub revision_comments: String,
-    pub user_id: i64,
+
+    /// The user making this edit.
+    ///
+    /// If not set, the edit is anonymous, which is only
+    /// permitted if the site allows anonymous edits.
+    #[serde(default)]
+    pub user_id: Option<i64>,
+
+    /// Refuse to save the edit if there are any error-severity parser errors.
+    #[serde(default)]
+    pub reject_on_parser_error: bool,
 
     #[serde(flatten)]
     pub body: EditPageBody,
 }
 
+impl Validate for EditPage<'_> {
+    fn validate(&self) -> ValidationResult {
+        if let Some(title) = self.body.title.to_option() {
+            check_not_blank("title", title)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase", default)]
 pub struct EditPageBody {
@@ -105,6 +183,7 @@ pub struct EditPageBody {
     pub title: ProvidedValue<String>,
     pub alt_title: ProvidedValue<Option<String>>,
     pub tags: ProvidedValue<Vec<String>>,
+    pub locale: ProvidedValue<Option<String>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -118,6 +197,12 @@ pub struct MovePage<'a> {
     // NOTE: slug field is a parameter, not in the body
 }
 
+impl Validate for MovePage<'_> {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("new_slug", &self.new_slug)
+    }
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct MovePageOutput {
@@ -125,7 +210,7 @@ pub struct MovePageOutput {
     pub new_slug: String,
     pub revision_id: i64,
     pub revision_number: i32,
-    pub parser_errors: Option<Vec<ParseError>>,
+    pub parser_errors: Option<Vec<ClassifiedParseError>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -135,16 +220,146 @@ pub struct DeletePage<'a> {
     pub page: Reference<'a>,
     pub revision_comments: String,
     pub user_id: i64,
+
+    /// Why the page is being deleted, for moderation purposes.
+    #[serde(default)]
+    pub deletion_reason: Option<PageDeletionReason>,
+}
+
+impl Validate for DeletePage<'_> {}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulePage<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+
+    /// When the page is to be published, or `None` to publish it now.
+    pub publish_at: Option<OffsetDateTime>,
+}
+
+impl Validate for SchedulePage<'_> {}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SetPageExpiry<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+
+    /// When the page is to expire, or `None` for it to never expire.
+    pub expires_at: Option<OffsetDateTime>,
 }
 
+impl Validate for SetPageExpiry<'_> {}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
+pub struct GetDeletedPages {
+    pub site_id: i64,
+
+    /// If set, only return pages deleted for this reason.
+    #[serde(default)]
+    pub deletion_reason: Option<PageDeletionReason>,
+}
+
+impl Validate for GetDeletedPages {}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPageExcerpt {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub max_chars: usize,
+}
+
+impl Validate for GetPageExcerpt {}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPageExcerptOutput {
+    pub excerpt: String,
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FindHistoricalSlug {
+    pub site_id: i64,
+    pub slug: String,
+}
+
+impl Validate for FindHistoricalSlug {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("slug", &self.slug)
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FindPagesByKeyword {
+    pub site_id: i64,
+    pub keyword: String,
+}
+
+impl Validate for FindPagesByKeyword {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("keyword", &self.keyword)
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoricalSlugMatch {
+    pub page: PageModel,
+
+    /// Whether the page was found by a slug it used to have.
+    pub via_alias: bool,
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct RestorePage {
     pub site_id: i64,
     pub page_id: i64,
     pub revision_comments: String,
     pub user_id: i64,
     pub slug: Option<String>,
+
+    /// If the slug is taken, add a numeric suffix rather than failing.
+    #[serde(default)]
+    pub auto_slug: bool,
+}
+
+impl Validate for RestorePage {
+    fn validate(&self) -> ValidationResult {
+        if let Some(slug) = &self.slug {
+            check_not_blank("slug", slug)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RestorePageBySlug {
+    pub site_id: i64,
+
+    /// The slug the page had when it was deleted.
+    ///
+    /// If several deleted pages had this slug, the most recently deleted is restored.
+    pub slug: String,
+    pub revision_comments: String,
+    pub user_id: i64,
+
+    /// If the slug is taken, add a numeric suffix rather than failing.
+    #[serde(default)]
+    pub auto_slug: bool,
+}
+
+impl Validate for RestorePageBySlug {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("slug", &self.slug)
+    }
 }
 
 #[derive(Serialize, Debug)]
@@ -161,7 +376,7 @@ pub struct RestorePageOutput {
     slug: String,
     revision_id: i64,
     revision_number: i32,
-    parser_errors: Vec<ParseError>,
+    parser_errors: Vec<ClassifiedParseError>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -174,6 +389,88 @@ pub struct RollbackPage<'a> {
     pub user_id: i64,
 }
 
+impl Validate for RollbackPage<'_> {}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GetRecentChanges {
+    pub site_id: i64,
+    pub since: Option<OffsetDateTime>,
+    pub limit: u64,
+
+    /// Whether to include changes to deleted pages.
+    /// This is only permitted for moderators.
+    #[serde(default)]
+    pub include_deleted: bool,
+
+    /// The session of the user requesting changes, if any.
+    #[serde(default)]
+    pub session_token: Option<String>,
+}
+
+impl Validate for GetRecentChanges {}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentChangesStreamQuery {
+    /// Whether to include changes to deleted pages.
+    /// This is only permitted for moderators.
+    #[serde(default)]
+    pub include_deleted: bool,
+
+    /// The session of the user subscribing, if any.
+    #[serde(default)]
+    pub session_token: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentChange {
+    pub page_id: i64,
+    pub page_deleted: bool,
+    pub revision_id: i64,
+    pub revision_type: PageRevisionType,
+    pub revision_number: i32,
+    pub created_at: OffsetDateTime,
+    pub user_id: i64,
+    pub comments: String,
+    pub title: String,
+    pub slug: String,
+}
+
+impl RecentChange {
+    pub fn from_revision(revision: PageRevisionModel, page_deleted: bool) -> Self {
+        RecentChange {
+            page_id: revision.page_id,
+            page_deleted,
+            revision_id: revision.revision_id,
+            revision_type: revision.revision_type,
+            revision_number: revision.revision_number,
+            created_at: revision.created_at,
+            user_id: revision.user_id,
+            comments: revision.comments,
+            title: revision.title,
+            slug: revision.slug,
+        }
+    }
+}
+
+/// A group of pages whose latest revisions have identical wikitext.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCluster {
+    /// The hex-encoded hash of the shared wikitext.
+    pub wikitext_hash: String,
+    pub pages: Vec<DuplicatePage>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicatePage {
+    pub page_id: i64,
+    pub slug: String,
+}
+
 pub type EditPageOutput = CreatePageRevisionOutput;
 
 impl From<(CreatePageRevisionOutput, i64)> for DeletePageOutput {
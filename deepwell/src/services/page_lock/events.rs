@@ -0,0 +1,128 @@
+/*
+ * services/page_lock/events.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! In-process broadcasting of page events, such as locks, to subscribed clients.
+
+use crate::models::page_lock::Model as PageLockModel;
+use async_std::channel::{self, Receiver, Sender};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A notification about a page, sent to clients subscribed to it.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum PageEvent {
+    /// A user has started viewing the page.
+    #[serde(rename_all = "camelCase")]
+    Joined { user_id: i64 },
+
+    /// A user has stopped viewing the page.
+    #[serde(rename_all = "camelCase")]
+    Left { user_id: i64 },
+
+    /// The page has been locked, or an existing lock was refreshed.
+    #[serde(rename_all = "camelCase")]
+    Locked { lock: PageLockModel },
+
+    /// The page's lock has been released.
+    #[serde(rename_all = "camelCase")]
+    Unlocked { user_id: i64 },
+}
+
+/// Broadcasts page events to each subscriber of that page.
+///
+/// Subscribers are dropped once their receiver is closed.
+#[derive(Debug, Default)]
+pub struct PageEvents {
+    subscribers: Mutex<HashMap<i64, Vec<Sender<PageEvent>>>>,
+}
+
+impl PageEvents {
+    /// Subscribes to all future events for the given page.
+    pub fn subscribe(&self, page_id: i64) -> Receiver<PageEvent> {
+        tide::log::debug!("Adding subscriber for events on page ID {page_id}");
+
+        let (sender, receiver) = channel::unbounded();
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .expect("Page event subscribers poisoned");
+
+        subscribers.entry(page_id).or_default().push(sender);
+        receiver
+    }
+
+    /// Sends an event to all current subscribers of the given page.
+    pub fn publish(&self, page_id: i64, event: PageEvent) {
+        tide::log::debug!("Publishing event for page ID {page_id}: {event:?}");
+
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .expect("Page event subscribers poisoned");
+
+        if let Some(senders) = subscribers.get_mut(&page_id) {
+            // Sending to an unbounded channel only fails once it is closed
+            senders.retain(|sender| sender.try_send(event.clone()).is_ok());
+
+            if senders.is_empty() {
+                subscribers.remove(&page_id);
+            }
+        }
+    }
+}
+
+#[test]
+fn lock_broadcast() {
+    use crate::services::fixtures;
+    use time::{Duration, OffsetDateTime};
+
+    let created_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+
+    let events = PageEvents::default();
+    let subscriber = events.subscribe(1);
+    let other_page = events.subscribe(2);
+
+    let lock = PageLockModel {
+        created_at,
+        expires_at: Some(created_at + Duration::minutes(15)),
+        ..fixtures::page_lock(100, 1, 5)
+    };
+
+    // Subscribers of the page receive the event
+    events.publish(1, PageEvent::Locked { lock: lock.clone() });
+    assert_eq!(subscriber.try_recv(), Ok(PageEvent::Locked { lock }));
+
+    // Subscribers of other pages do not
+    assert!(
+        other_page.try_recv().is_err(),
+        "Other page received the event"
+    );
+
+    // Closed subscribers are dropped
+    drop(subscriber);
+    events.publish(1, PageEvent::Unlocked { user_id: 5 });
+    let subscribers = events.subscribers.lock().unwrap();
+    assert!(
+        !subscribers.contains_key(&1),
+        "Closed subscriber was retained"
+    );
+    assert!(subscribers.contains_key(&2), "Open subscriber was dropped");
+}
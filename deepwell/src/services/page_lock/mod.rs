@@ -0,0 +1,38 @@
+/*
+ * services/page_lock/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Service for page locks, which prevent others from editing a page.
+//!
+//! Changes to locks, as well as which users are viewing the page editor,
+//! are broadcast to subscribers via `PageEvents`.
+
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::events::*;
+    pub use super::structs::*;
+}
+
+mod events;
+mod service;
+mod structs;
+
+pub use self::events::*;
+pub use self::service::PageLockService;
+pub use self::structs::*;
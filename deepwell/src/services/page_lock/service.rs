@@ -0,0 +1,215 @@
+/*
+ * services/page_lock/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_lock::{self, Entity as PageLock, Model as PageLockModel};
+use sea_orm::sea_query::Expr;
+use sea_orm::DbErr;
+use time::{Duration, OffsetDateTime};
+
+/// The kind of lock taken by a user while editing a page.
+pub const EDIT_LOCK_TYPE: &str = "edit";
+
+/// How long an edit lock lasts before it must be refreshed.
+pub const EDIT_LOCK_DURATION: Duration = Duration::minutes(15);
+
+#[derive(Debug)]
+pub struct PageLockService;
+
+impl PageLockService {
+    /// Acquires an edit lock on a page, or refreshes the user's existing one.
+    ///
+    /// Fails with `Error::Conflict` if another user holds the lock,
+    /// including if they acquired it concurrently with this call.
+    /// Subscribers of the page are not notified here, since the lock
+    /// is not visible until the transaction is committed.
+    pub async fn acquire(
+        ctx: &ServiceContext<'_>,
+        AcquirePageLock {
+            page_id,
+            user_id,
+            reason,
+        }: AcquirePageLock,
+    ) -> Result<PageLockModel> {
+        let txn = ctx.transaction();
+        let now = ctx.now();
+        let expires_at = now + EDIT_LOCK_DURATION;
+
+        tide::log::info!("Acquiring lock on page ID {page_id} for user ID {user_id}");
+
+        if let Some(lock) = Self::get_optional(ctx, page_id).await? {
+            check_holder(&lock, user_id)?;
+
+            // Same user, just extend the lock
+            let mut model = lock.into_active_model();
+            model.updated_at = Set(Some(now));
+            model.expires_at = Set(Some(expires_at));
+            let lock = model.update(txn).await?;
+            return Ok(lock);
+        }
+
+        // Expired locks still count for the page_lock_active index,
+        // so they must be deleted before a new lock can be inserted.
+        PageLock::update_many()
+            .col_expr(page_lock::Column::DeletedAt, Expr::value(Some(now)))
+            .filter(expired_condition(page_id, now))
+            .exec(txn)
+            .await?;
+
+        let model = page_lock::ActiveModel {
+            created_at: Set(now),
+            expires_at: Set(Some(expires_at)),
+            lock_type: Set(str!(EDIT_LOCK_TYPE)),
+            page_id: Set(page_id),
+            user_id: Set(user_id),
+            reason: Set(reason),
+            ..Default::default()
+        };
+
+        let lock = model.insert(txn).await.map_err(insert_error)?;
+        Ok(lock)
+    }
+
+    /// Releases a user's lock on a page.
+    pub async fn release(
+        ctx: &ServiceContext<'_>,
+        ReleasePageLock { page_id, user_id }: ReleasePageLock,
+    ) -> Result<PageLockModel> {
+        let txn = ctx.transaction();
+        tide::log::info!("Releasing lock on page ID {page_id} for user ID {user_id}");
+
+        let lock = Self::get(ctx, page_id).await?;
+        if lock.user_id != user_id {
+            tide::log::error!(
+                "Page ID {page_id} is locked by user ID {}, not {user_id}",
+                lock.user_id,
+            );
+            return Err(Error::Conflict);
+        }
+
+        let mut model = lock.into_active_model();
+        model.deleted_at = Set(Some(ctx.now()));
+        let lock = model.update(txn).await?;
+        Ok(lock)
+    }
+
+    #[inline]
+    pub async fn get(ctx: &ServiceContext<'_>, page_id: i64) -> Result<PageLockModel> {
+        find_or_error(Self::get_optional(ctx, page_id)).await
+    }
+
+    /// Gets the current lock on a page, if any.
+    ///
+    /// Locks which have expired are treated as absent.
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Option<PageLockModel>> {
+        let txn = ctx.transaction();
+        let lock = PageLock::find()
+            .filter(
+                Condition::all()
+                    .add(page_lock::Column::PageId.eq(page_id))
+                    .add(page_lock::Column::DeletedAt.is_null())
+                    .add(
+                        Condition::any()
+                            .add(page_lock::Column::ExpiresAt.is_null())
+                            .add(page_lock::Column::ExpiresAt.gt(ctx.now())),
+                    ),
+            )
+            .order_by_desc(page_lock::Column::CreatedAt)
+            .one(txn)
+            .await?;
+
+        Ok(lock)
+    }
+}
+
+/// Ensures the given user is the one holding a lock.
+fn check_holder(lock: &PageLockModel, user_id: i64) -> Result<()> {
+    if lock.user_id != user_id {
+        tide::log::error!(
+            "Page ID {} is already locked by user ID {}",
+            lock.page_id,
+            lock.user_id,
+        );
+        return Err(Error::Conflict);
+    }
+
+    Ok(())
+}
+
+/// Builds the condition for locks on a page which have expired but not been deleted.
+fn expired_condition(page_id: i64, current_time: OffsetDateTime) -> Condition {
+    Condition::all()
+        .add(page_lock::Column::PageId.eq(page_id))
+        .add(page_lock::Column::DeletedAt.is_null())
+        .add(page_lock::Column::ExpiresAt.lte(current_time))
+}
+
+/// Converts the error from inserting a lock.
+///
+/// If another transaction inserted a lock for the page first,
+/// the `page_lock_active` index is violated, and that user now holds the lock.
+fn insert_error(error: DbErr) -> Error {
+    if is_unique_violation(&error) {
+        tide::log::error!("Page lock was acquired concurrently by another user");
+        return Error::Conflict;
+    }
+
+    Error::from(error)
+}
+
+#[test]
+fn acquire_conflicts() {
+    use crate::services::fixtures;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    // Existing lock, held by user ID 3
+    let lock = fixtures::page_lock(1, 2, 3);
+    assert!(check_holder(&lock, 3).is_ok());
+    assert!(matches!(check_holder(&lock, 4), Err(Error::Conflict)));
+
+    // Lock inserted concurrently
+    assert!(matches!(
+        insert_error(postgres_error(DbErr::Exec, "23505")),
+        Error::Conflict,
+    ));
+    assert!(matches!(
+        insert_error(postgres_error(DbErr::Exec, "40001")),
+        Error::ConcurrentConflict,
+    ));
+    assert!(matches!(
+        insert_error(DbErr::ConnectionAcquire),
+        Error::Database(_),
+    ));
+
+    // Expired locks are cleared before inserting
+    let now = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let sql = PageLock::update_many()
+        .col_expr(page_lock::Column::DeletedAt, Expr::value(Some(now)))
+        .filter(expired_condition(2, now))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(sql.contains(r#""page_id" = 2"#));
+    assert!(sql.contains(r#""deleted_at" IS NULL"#));
+    assert!(sql.contains(r#""expires_at" <="#));
+}
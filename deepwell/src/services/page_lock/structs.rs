@@ -0,0 +1,57 @@
+/*
+ * services/page_lock/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::web::Validate;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AcquirePageLock {
+    pub page_id: i64,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub reason: String,
+}
+
+impl Validate for AcquirePageLock {}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleasePageLock {
+    pub page_id: i64,
+    pub user_id: i64,
+}
+
+impl Validate for ReleasePageLock {}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPageLock {
+    pub page_id: i64,
+}
+
+impl Validate for GetPageLock {}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PageEventsQuery {
+    /// The subscriber's session, if they are to be announced as present.
+    pub session_token: Option<String>,
+}
@@ -19,22 +19,30 @@
  */
 
 use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::page_draft::{self, Entity as PageDraft, Model as PageDraftModel};
 use crate::models::page_revision::{
     self, Entity as PageRevision, Model as PageRevisionModel,
 };
-use crate::models::sea_orm_active_enums::PageRevisionType;
+use crate::models::sea_orm_active_enums::{FeatureFlag, PageRevisionType};
+use crate::models::site::Model as SiteModel;
+use crate::services::page::{EditPage, EditPageBody, EditPageOutput, RecentChange};
 use crate::services::render::RenderOutput;
 use crate::services::score::ScoreValue;
 use crate::services::{
-    LinkService, OutdateService, ParentService, RenderService, ScoreService, SiteService,
-    TextService,
+    FeatureFlagService, LinkService, OutdateService, PageService, ParentService,
+    RenderService, ScoreService, SiteService, TextService,
 };
 use crate::utils::{split_category, split_category_name};
 use crate::web::FetchDirection;
 use ftml::data::PageInfo;
 use ftml::settings::{WikitextMode, WikitextSettings};
 use ref_map::*;
+use sea_orm::sea_query::Expr;
+use sea_orm::UpdateResult;
+use std::borrow::Cow;
 use std::num::NonZeroI32;
+use time::OffsetDateTime;
 
 lazy_static! {
     /// The changes for the first revision.
@@ -125,18 +133,13 @@ impl PageRevisionService {
         // We check the values so that the only listed "changes"
         // are those that actually are different.
 
-        if let ProvidedValue::Set(new_title) = body.title {
-            if title != new_title {
-                changes.push(str!("title"));
-                title = new_title;
-            }
+        if body.title.update_value(&mut title) {
+            changes.push(str!("title"));
         }
 
-        if let ProvidedValue::Set(new_alt_title) = body.alt_title {
-            if alt_title != new_alt_title {
-                changes.push(str!("alt_title"));
-                alt_title = new_alt_title;
-            }
+        // Setting null clears the alt title, leaving it unset keeps it
+        if body.alt_title.update_value(&mut alt_title) {
+            changes.push(str!("alt_title"));
         }
 
         if let ProvidedValue::Set(new_slug) = body.slug {
@@ -147,11 +150,8 @@ impl PageRevisionService {
             }
         }
 
-        if let ProvidedValue::Set(new_tags) = body.tags {
-            if tags != new_tags {
-                changes.push(str!("tags"));
-                tags = new_tags;
-            }
+        if body.tags.update_value(&mut tags) {
+            changes.push(str!("tags"));
         }
 
         // Get slug strings for the new location
@@ -216,9 +216,9 @@ impl PageRevisionService {
 
             // Update fields
             parser_errors = Some(render_output.errors);
-            replace_hash(&mut compiled_hash, &render_output.compiled_hash);
+            compiled_hash = Some(render_output.compiled_hash.to_vec());
             compiled_generator = render_output.compiled_generator;
-            compiled_at = now();
+            compiled_at = ctx.now();
         }
 
         // Perform outdating based on changes made.
@@ -292,6 +292,8 @@ impl PageRevisionService {
         };
 
         let PageRevisionModel { revision_id, .. } = model.insert(txn).await?;
+        Self::release_compiled(ctx, site_id, page_id, revision_id).await?;
+
         Ok(Some(CreatePageRevisionOutput {
             revision_id,
             revision_number,
@@ -339,6 +341,7 @@ impl PageRevisionService {
         let RenderOutput {
             // TODO: use html_output
             html_output: _,
+            text: _,
             errors,
             compiled_hash,
             compiled_generator,
@@ -357,8 +360,8 @@ impl PageRevisionService {
             user_id: Set(user_id),
             changes: Set(ALL_CHANGES.clone()),
             wikitext_hash: Set(wikitext_hash.to_vec()),
-            compiled_hash: Set(compiled_hash.to_vec()),
-            compiled_at: Set(now()),
+            compiled_hash: Set(Some(compiled_hash.to_vec())),
+            compiled_at: Set(ctx.now()),
             compiled_generator: Set(compiled_generator),
             comments: Set(comments),
             hidden: Set(vec![]),
@@ -436,6 +439,8 @@ impl PageRevisionService {
         };
 
         let PageRevisionModel { revision_id, .. } = model.insert(txn).await?;
+        Self::release_compiled(ctx, site_id, page_id, revision_id).await?;
+
         Ok(CreatePageRevisionOutput {
             revision_id,
             revision_number,
@@ -474,7 +479,6 @@ impl PageRevisionService {
 
         let PageRevisionModel {
             wikitext_hash,
-            mut compiled_hash,
             hidden,
             title,
             alt_title,
@@ -505,14 +509,13 @@ impl PageRevisionService {
         let RenderOutput {
             // TODO: use html_output
             html_output: _,
+            text: _,
             errors,
             compiled_hash: new_compiled_hash,
             compiled_generator,
         } = Self::render_and_update_links(ctx, site_id, page_id, wikitext, render_input)
             .await?;
 
-        replace_hash(&mut compiled_hash, &new_compiled_hash);
-
         // Run outdater
         OutdateService::process_page_displace(ctx, site_id, page_id, &new_slug).await?;
 
@@ -525,8 +528,8 @@ impl PageRevisionService {
             user_id: Set(user_id),
             changes: Set(changes),
             wikitext_hash: Set(wikitext_hash),
-            compiled_hash: Set(compiled_hash),
-            compiled_at: Set(now()),
+            compiled_hash: Set(Some(new_compiled_hash.to_vec())),
+            compiled_at: Set(ctx.now()),
             compiled_generator: Set(compiled_generator),
             comments: Set(comments),
             hidden: Set(hidden),
@@ -538,6 +541,8 @@ impl PageRevisionService {
         };
 
         let PageRevisionModel { revision_id, .. } = model.insert(txn).await?;
+        Self::release_compiled(ctx, site_id, page_id, revision_id).await?;
+
         Ok(CreatePageRevisionOutput {
             revision_id,
             revision_number,
@@ -545,58 +550,210 @@ impl PageRevisionService {
         })
     }
 
+    /// Drops the compiled HTML of revisions which have been superseded.
+    ///
+    /// Unless the site retains compiled HTML for every revision, only the
+    /// latest revision keeps it. Older revisions are rendered again when viewed.
+    ///
+    /// The released HTML is deleted from the text table, unless it is still
+    /// in use by another revision.
+    async fn release_compiled(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        latest_revision_id: i64,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let condition = match release_compiled_condition(
+            site.retain_compiled_html,
+            page_id,
+            latest_revision_id,
+        ) {
+            Some(condition) => condition,
+            None => return Ok(()),
+        };
+
+        let released_hashes: Vec<Vec<u8>> = PageRevision::find()
+            .filter(condition.clone())
+            .all(txn)
+            .await?
+            .into_iter()
+            .filter_map(|revision| revision.compiled_hash)
+            .collect();
+
+        if released_hashes.is_empty() {
+            return Ok(());
+        }
+
+        let UpdateResult { rows_affected } = PageRevision::update_many()
+            .col_expr(
+                page_revision::Column::CompiledHash,
+                Expr::value(Option::<Vec<u8>>::None),
+            )
+            .filter(condition)
+            .exec(txn)
+            .await?;
+
+        tide::log::debug!(
+            "Released compiled HTML for {rows_affected} revisions of page ID {page_id}",
+        );
+
+        let hashes: Vec<&[u8]> = released_hashes.iter().map(Vec::as_slice).collect();
+        TextService::delete_unreferenced(ctx, &hashes).await?;
+        Ok(())
+    }
+
     /// Helper method for performing rendering for a revision.
     ///
     /// Makes all the changes associated with rendering, such as
     /// committing the new wikitext, calling ftml, and updating
-    /// backlinks.
+    /// backlinks and keywords.
     async fn render_and_update_links(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         page_id: i64,
         wikitext: String,
-        RenderPageInfo {
-            slug,
+        render_input: RenderPageInfo<'_>,
+    ) -> Result<RenderOutput> {
+        let RenderPageInfo {
             title,
             alt_title,
-            score,
             tags,
-        }: RenderPageInfo<'_>,
-    ) -> Result<RenderOutput> {
-        // Get site
-        let site = SiteService::get(ctx, Reference::from(site_id)).await?;
+            ..
+        } = render_input;
 
-        // Set up parse context
-        let settings = WikitextSettings::from_mode(WikitextMode::Page);
-        let (category_slug, page_slug) = split_category(slug);
-        let page_info = PageInfo {
-            page: cow!(page_slug),
-            category: cow_opt!(category_slug),
-            site: cow!(&site.slug),
-            title: cow!(title),
-            alt_title: cow_opt!(alt_title),
+        let output = Self::render(ctx, site_id, page_id, wikitext, render_input).await?;
+
+        // Update backlinks
+        LinkService::update(ctx, site_id, page_id, &output.html_output.backlinks).await?;
+
+        // Update keywords, from the text render of this same wikitext
+        PageService::update_keywords(ctx, page_id, title, alt_title, tags, &output.text)
+            .await?;
+
+        Ok(output)
+    }
+
+    /// Renders an existing revision, without making any changes to the page.
+    ///
+    /// This is for viewing old revisions whose compiled HTML is no longer
+    /// available. Since the revision is not current, backlinks are not updated.
+    pub async fn render_revision(
+        ctx: &ServiceContext<'_>,
+        revision: &PageRevisionModel,
+        wikitext: String,
+    ) -> Result<String> {
+        tide::log::info!(
+            "Rendering revision {} of page ID {} for viewing",
+            revision.revision_number,
+            revision.page_id,
+        );
+
+        let score = ScoreService::score(ctx, revision.page_id).await?;
+        let render_input = RenderPageInfo {
+            slug: &revision.slug,
+            title: &revision.title,
+            alt_title: revision.alt_title.ref_map(|s| s.as_str()),
             score,
-            tags: tags.iter().map(|s| cow!(s)).collect(),
-            language: cow!(&site.locale),
+            tags: &revision.tags,
         };
 
+        let RenderOutput { html_output, .. } = Self::render(
+            ctx,
+            revision.site_id,
+            revision.page_id,
+            wikitext,
+            render_input,
+        )
+        .await?;
+
+        Ok(html_output.body)
+    }
+
+    async fn render(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        wikitext: String,
+        render_input: RenderPageInfo<'_>,
+    ) -> Result<RenderOutput> {
+        // Get site and page
+        let (site, page) = try_join!(
+            SiteService::get(ctx, Reference::from(site_id)),
+            PageService::get_direct(ctx, page_id),
+        )?;
+
+        // Apply category template, if any
+        let wikitext =
+            PageService::apply_template(ctx, site_id, render_input.slug, wikitext)
+                .await?;
+
+        // Set up parse context
+        let settings = page_settings(ctx.config(), &site);
+        let page_info = build_page_info(&site, &page, render_input);
+
         // Parse and render
-        let output = RenderService::render(ctx, wikitext, &page_info, &settings).await?;
+        RenderService::render(ctx, wikitext, &page_info, &settings).await
+    }
 
-        // Update backlinks
-        LinkService::update(ctx, site_id, page_id, &output.html_output.backlinks).await?;
+    /// Renders the latest revision of a page as plain text.
+    ///
+    /// Unlike HTML renders, this output is not stored.
+    pub async fn render_latest_text(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<String> {
+        let revision = Self::get_latest(ctx, site_id, page_id).await?;
+        let (site, page, wikitext, score) = try_join!(
+            SiteService::get(ctx, Reference::from(site_id)),
+            PageService::get_direct(ctx, page_id),
+            TextService::get(ctx, &revision.wikitext_hash),
+            ScoreService::score(ctx, page_id),
+        )?;
 
-        Ok(output)
+        let render_input = RenderPageInfo {
+            slug: &revision.slug,
+            title: &revision.title,
+            alt_title: revision.alt_title.ref_map(|s| s.as_str()),
+            score,
+            tags: &revision.tags,
+        };
+
+        let wikitext =
+            PageService::apply_template(ctx, site_id, &revision.slug, wikitext).await?;
+
+        let settings = page_settings(ctx.config(), &site);
+        let page_info = build_page_info(&site, &page, render_input);
+        RenderService::render_text(ctx, wikitext, &page_info, &settings).await
     }
 
     /// Re-renders a page.
     ///
     /// This fetches the latest revision for a page, and re-renders it.
+    /// Pages which depend on it are then updated too.
     pub async fn rerender(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         page_id: i64,
     ) -> Result<()> {
+        let revision = Self::rerender_latest(ctx, site_id, page_id).await?;
+
+        // Update descendents
+        OutdateService::process_page_edit(ctx, site_id, page_id, &revision.slug).await?;
+        Ok(())
+    }
+
+    /// Re-renders a page, without updating pages which depend on it.
+    ///
+    /// This is for re-rendering every page in a site, where
+    /// updating descendents would only re-render them again.
+    pub async fn rerender_latest(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<PageRevisionModel> {
         let txn = ctx.transaction();
         let revision = Self::get_latest(ctx, site_id, page_id).await?;
         let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
@@ -620,18 +777,14 @@ impl PageRevisionService {
         } = Self::render_and_update_links(ctx, site_id, page_id, wikitext, render_input)
             .await?;
 
-        // Update descendents
-        OutdateService::process_page_edit(ctx, site_id, page_id, &revision.slug).await?;
-
-        let model = page_revision::ActiveModel {
-            revision_id: Set(revision.revision_id),
-            compiled_hash: Set(compiled_hash.to_vec()),
-            compiled_generator: Set(compiled_generator),
-            ..Default::default()
-        };
-
+        let model = rerender_model(
+            revision.revision_id,
+            &compiled_hash,
+            compiled_generator,
+            ctx.now(),
+        );
         model.update(txn).await?;
-        Ok(())
+        Ok(revision)
     }
 
     /// Modifies an existing revision.
@@ -692,6 +845,120 @@ impl PageRevisionService {
         Ok(())
     }
 
+    /// Saves an edit to a page as a draft.
+    ///
+    /// Drafts do not create a revision, and so do not affect the page
+    /// as seen by readers until they are published.
+    pub async fn create_draft(
+        ctx: &ServiceContext<'_>,
+        CreatePageDraft {
+            site_id,
+            page_id,
+            user_id,
+            comments,
+            wikitext,
+            title,
+            alt_title,
+            mut tags,
+        }: CreatePageDraft,
+    ) -> Result<PageDraftModel> {
+        tide::log::info!("Creating draft for page ID {page_id} in site ID {site_id}");
+        FeatureFlagService::check(ctx, site_id, FeatureFlag::PageDrafts).await?;
+
+        let txn = ctx.transaction();
+        let base_revision = Self::get_latest(ctx, site_id, page_id).await?;
+        let wikitext_hash = TextService::create(ctx, wikitext).await?;
+
+        tags.sort();
+        tags.dedup();
+
+        let model = page_draft::ActiveModel {
+            page_id: Set(page_id),
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            base_revision_id: Set(base_revision.revision_id),
+            comments: Set(comments),
+            wikitext_hash: Set(wikitext_hash.to_vec()),
+            title: Set(title),
+            alt_title: Set(alt_title),
+            tags: Set(tags),
+            ..Default::default()
+        };
+
+        let draft = model.insert(txn).await?;
+        Ok(draft)
+    }
+
+    pub async fn get_draft_optional(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        draft_id: i64,
+    ) -> Result<Option<PageDraftModel>> {
+        let txn = ctx.transaction();
+        let draft = PageDraft::find()
+            .filter(
+                Condition::all()
+                    .add(page_draft::Column::SiteId.eq(site_id))
+                    .add(page_draft::Column::DraftId.eq(draft_id)),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(draft)
+    }
+
+    #[inline]
+    pub async fn get_draft(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        draft_id: i64,
+    ) -> Result<PageDraftModel> {
+        find_or_error(Self::get_draft_optional(ctx, site_id, draft_id)).await
+    }
+
+    /// Publishes a draft, making it the page's latest revision.
+    ///
+    /// This is performed as a normal page edit, after which the draft is removed.
+    /// If the page was edited since the draft was made, the draft is rejected
+    /// rather than overwriting those changes. If the draft is the same as the
+    /// latest revision, no revision is created and the draft is still removed.
+    pub async fn publish_draft(
+        ctx: &ServiceContext<'_>,
+        PublishPageDraft {
+            site_id,
+            draft_id,
+            user_id,
+        }: PublishPageDraft,
+    ) -> Result<(Option<EditPageOutput>, Option<RecentChange>)> {
+        tide::log::info!("Publishing draft ID {draft_id} in site ID {site_id}");
+        FeatureFlagService::check(ctx, site_id, FeatureFlag::PageDrafts).await?;
+
+        let txn = ctx.transaction();
+        let draft = Self::get_draft(ctx, site_id, draft_id).await?;
+        let wikitext = TextService::get(ctx, &draft.wikitext_hash).await?;
+        let page_id = draft.page_id;
+        let comments = draft.comments.clone();
+
+        let latest = Self::get_latest(ctx, site_id, page_id).await?;
+        check_draft_base(&draft, &latest)?;
+
+        let (output, recent_change) = PageService::edit(
+            ctx,
+            EditPage {
+                site_id,
+                page: Reference::Id(page_id),
+                revision_comments: comments,
+                user_id: Some(user_id),
+                reject_on_parser_error: false,
+                body: draft_edit_body(draft, wikitext),
+            },
+        )
+        .await?;
+
+        PageDraft::delete_by_id(draft_id).exec(txn).await?;
+        Ok((output, recent_change))
+    }
+
     pub async fn get_latest(
         ctx: &ServiceContext<'_>,
         site_id: i64,
@@ -761,6 +1028,26 @@ impl PageRevisionService {
         Ok(revision)
     }
 
+    /// Gets all revisions for a page, in ascending revision number order.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<Vec<PageRevisionModel>> {
+        let txn = ctx.transaction();
+        let revisions = PageRevision::find()
+            .filter(
+                Condition::all()
+                    .add(page_revision::Column::SiteId.eq(site_id))
+                    .add(page_revision::Column::PageId.eq(page_id)),
+            )
+            .order_by_asc(page_revision::Column::RevisionNumber)
+            .all(txn)
+            .await?;
+
+        Ok(revisions)
+    }
+
     pub async fn count(
         ctx: &ServiceContext<'_>,
         site_id: i64,
@@ -798,6 +1085,7 @@ impl PageRevisionService {
             revision_number,
             revision_direction,
             limit,
+            from_wikidot,
         }: GetPageRevisionRange,
     ) -> Result<Vec<PageRevisionModel>> {
         let revision_condition = {
@@ -824,7 +1112,11 @@ impl PageRevisionService {
                 Condition::all()
                     .add(page_revision::Column::SiteId.eq(site_id))
                     .add(page_revision::Column::PageId.eq(page_id))
-                    .add(revision_condition),
+                    .add(revision_condition)
+                    .add_option(
+                        from_wikidot
+                            .map(|value| page_revision::Column::FromWikidot.eq(value)),
+                    ),
             )
             .order_by_asc(page_revision::Column::RevisionNumber)
             .limit(limit)
@@ -869,3 +1161,413 @@ fn next_revision_number(previous: &PageRevisionModel, site_id: i64, page_id: i64
     // Get the new revision number
     previous.revision_number + 1
 }
+
+/// Ensures the page has not been edited since the draft was made.
+fn check_draft_base(draft: &PageDraftModel, latest: &PageRevisionModel) -> Result<()> {
+    if latest.revision_id != draft.base_revision_id {
+        tide::log::error!(
+            "Page ID {} changed since draft ID {} was made (latest revision {})",
+            draft.page_id,
+            draft.draft_id,
+            latest.revision_number,
+        );
+
+        return Err(Error::Conflict);
+    }
+
+    Ok(())
+}
+
+/// Converts a draft into the edit which would publish it.
+fn draft_edit_body(draft: PageDraftModel, wikitext: String) -> EditPageBody {
+    EditPageBody {
+        wikitext: ProvidedValue::Set(wikitext),
+        title: ProvidedValue::Set(draft.title),
+        alt_title: ProvidedValue::Set(draft.alt_title),
+        tags: ProvidedValue::Set(draft.tags),
+        locale: ProvidedValue::Unset,
+    }
+}
+
+#[test]
+fn draft_publishing() {
+    use crate::services::fixtures;
+
+    let draft = PageDraftModel {
+        draft_id: 1,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        page_id: 2,
+        site_id: 3,
+        user_id: 4,
+        base_revision_id: 5,
+        comments: str!("Draft edit"),
+        wikitext_hash: vec![0; 16],
+        title: str!("SCP-001"),
+        alt_title: None,
+        tags: vec![str!("keter"), str!("scp")],
+    };
+
+    // Only drafts made against the latest revision may be published
+    let latest = fixtures::page_revision(3, 2, 5, 1);
+    assert!(check_draft_base(&draft, &latest).is_ok());
+
+    let latest = fixtures::page_revision(3, 2, 6, 2);
+    assert!(matches!(
+        check_draft_base(&draft, &latest),
+        Err(Error::Conflict),
+    ));
+
+    let body = draft_edit_body(draft, str!("**Item #:** SCP-001"));
+    assert_eq!(
+        body.wikitext,
+        ProvidedValue::Set(str!("**Item #:** SCP-001"))
+    );
+    assert_eq!(body.title, ProvidedValue::Set(str!("SCP-001")));
+    assert_eq!(body.alt_title, ProvidedValue::Set(None));
+    assert_eq!(
+        body.tags,
+        ProvidedValue::Set(vec![str!("keter"), str!("scp")]),
+    );
+    assert_eq!(
+        body.locale,
+        ProvidedValue::Unset,
+        "Draft changed page locale"
+    );
+}
+
+/// Builds the ftml settings for rendering a page on the given site.
+fn page_settings(config: &Config, site: &SiteModel) -> WikitextSettings {
+    let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+    settings.max_html_size = config.max_html_size;
+    settings.disabled_blocks = site
+        .disabled_blocks
+        .iter()
+        .map(|name| name.to_ascii_lowercase())
+        .collect();
+    settings
+}
+
+/// Builds the ftml page information for rendering a page.
+fn build_page_info<'a>(
+    site: &'a SiteModel,
+    page: &'a PageModel,
+    RenderPageInfo {
+        slug,
+        title,
+        alt_title,
+        score,
+        tags,
+    }: RenderPageInfo<'a>,
+) -> PageInfo<'a> {
+    // Use the page's locale if set, otherwise the site's
+    let language = page.locale.as_ref().unwrap_or(&site.locale);
+    let (category_slug, page_slug) = split_category(slug);
+
+    PageInfo {
+        page: cow!(page_slug),
+        category: category_slug.map(Cow::Borrowed),
+        site: cow!(&site.slug),
+        title: cow!(title),
+        alt_title: alt_title.map(Cow::Borrowed),
+        score,
+        tags: tags.iter().map(|s| cow!(s)).collect(),
+        language: cow!(language),
+    }
+}
+
+/// Builds the model to replace a revision's compiled HTML after re-rendering.
+///
+/// Only the compiled fields are changed, the revision is otherwise left as-is.
+fn rerender_model(
+    revision_id: i64,
+    compiled_hash: &[u8],
+    compiled_generator: String,
+    compiled_at: OffsetDateTime,
+) -> page_revision::ActiveModel {
+    page_revision::ActiveModel {
+        revision_id: Set(revision_id),
+        compiled_hash: Set(Some(compiled_hash.to_vec())),
+        compiled_at: Set(compiled_at),
+        compiled_generator: Set(compiled_generator),
+        ..Default::default()
+    }
+}
+
+/// Builds the condition for revisions of a page whose compiled HTML is dropped.
+///
+/// If `retain` is set, the site keeps compiled HTML for every revision,
+/// so this returns `None`.
+fn release_compiled_condition(
+    retain: bool,
+    page_id: i64,
+    latest_revision_id: i64,
+) -> Option<Condition> {
+    if retain {
+        return None;
+    }
+
+    let condition = Condition::all()
+        .add(page_revision::Column::PageId.eq(page_id))
+        .add(page_revision::Column::RevisionId.lt(latest_revision_id))
+        .add(page_revision::Column::CompiledHash.is_not_null());
+
+    Some(condition)
+}
+
+#[test]
+fn compiled_retention() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    // Retained, so every revision keeps its compiled HTML
+    assert!(
+        release_compiled_condition(true, 10, 100).is_none(),
+        "Compiled HTML released despite retention",
+    );
+
+    // Not retained, only revisions before the latest are released
+    let condition = release_compiled_condition(false, 10, 100)
+        .expect("Compiled HTML not released without retention");
+
+    let sql = PageRevision::find()
+        .filter(condition)
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(sql.contains("\"page_id\" = 10"), "Page missing: {sql}");
+    assert!(
+        sql.contains("\"revision_id\" < 100"),
+        "Latest included: {sql}"
+    );
+    assert!(
+        sql.contains("\"compiled_hash\" IS NOT NULL"),
+        "Released revisions included: {sql}",
+    );
+}
+
+#[test]
+fn release_compiled_texts() {
+    use crate::api::build_test_server_state;
+    use crate::hash::TextHashAlgorithm;
+    use crate::models::sea_orm_active_enums::VoteType;
+    use async_std::task;
+    use sea_orm::{
+        DatabaseBackend, DatabaseConnection, MockDatabase, MockExecResult,
+        TransactionTrait, Value,
+    };
+    use std::sync::Arc;
+
+    let site = |retain_compiled_html| SiteModel {
+        site_id: 1,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        deleted_at: None,
+        from_wikidot: false,
+        slug: str!("test"),
+        name: str!("Test Wiki"),
+        tagline: String::new(),
+        description: String::new(),
+        locale: str!("en"),
+        default_page: str!("start"),
+        default_category: str!("_default"),
+        slug_allow_categories: true,
+        slug_pattern: None,
+        require_revision_comments: false,
+        allow_anonymous_edits: false,
+        vote_type: VoteType::UpsDowns,
+        retain_compiled_html,
+        disabled_blocks: vec![],
+        robots_txt: None,
+        custom_domain: None,
+    };
+
+    let algorithm = TextHashAlgorithm::default();
+    let wikitext_hash = algorithm.hash(b"Apple");
+    let compiled_hash = algorithm.hash(b"<p>Apple</p>");
+    let revision = PageRevisionModel {
+        revision_id: 99,
+        revision_type: PageRevisionType::Regular,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        revision_number: 0,
+        page_id: 10,
+        site_id: 1,
+        user_id: 1,
+        from_wikidot: false,
+        changes: vec![],
+        wikitext_hash: wikitext_hash.to_vec(),
+        compiled_hash: Some(compiled_hash.to_vec()),
+        compiled_at: OffsetDateTime::UNIX_EPOCH,
+        compiled_generator: String::new(),
+        comments: String::new(),
+        hidden: vec![],
+        title: String::new(),
+        alt_title: None,
+        slug: str!("test"),
+        tags: vec![],
+    };
+
+    let release = |database: DatabaseConnection| {
+        let state = task::block_on(build_test_server_state(database));
+        task::block_on(async {
+            let txn = state.database.begin().await?;
+            let ctx = ServiceContext::from_raw(&state, &txn);
+            PageRevisionService::release_compiled(&ctx, 1, 10, 100).await?;
+            txn.commit().await?;
+            Ok::<_, Error>(())
+        })
+        .expect("Unable to release compiled HTML");
+
+        let state = Arc::try_unwrap(state).expect("Server state still in use");
+        format!("{:?}", state.database.into_transaction_log())
+    };
+
+    // Retained, so nothing is released or deleted
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[site(true)]])
+        .into_connection();
+
+    let log = release(database);
+    assert!(!log.contains("UPDATE"), "Compiled HTML released: {log}");
+    assert!(!log.contains("DELETE"), "Texts deleted: {log}");
+
+    // Not retained, so the released HTML is deleted unless still in use
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[site(false)]])
+        .append_query_results([[revision]])
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+        ])
+        .into_connection();
+
+    let log = release(database);
+    let released = format!("{:?}", Value::from(compiled_hash.to_vec()));
+    assert!(log.contains("UPDATE"), "Compiled HTML not released: {log}");
+    assert!(
+        log.contains("DELETE FROM"),
+        "Released text not deleted: {log}"
+    );
+    assert!(log.contains(&released), "Wrong text deleted: {log}");
+    assert_eq!(
+        log.matches("NOT IN").count(),
+        3,
+        "Text deletion doesn't check references: {log}",
+    );
+}
+
+#[test]
+fn rerender_updates() {
+    use crate::hash::TextHashAlgorithm;
+
+    fn render(wikitext: &str, settings: &WikitextSettings) -> String {
+        let page_info = PageInfo::dummy();
+        let (html_output, _, _) =
+            RenderService::render_wikitext(str!(wikitext), &page_info, settings)
+                .expect("Unable to render wikitext");
+
+        html_output.body
+    }
+
+    // The same wikitext renders differently once settings change
+    let wikitext = "[[toc]]\n\n+ Heading";
+    let old_html = render(wikitext, &WikitextSettings::from_mode(WikitextMode::Draft));
+    let new_html = render(wikitext, &WikitextSettings::from_mode(WikitextMode::Page));
+    assert_ne!(old_html, new_html, "Settings change did not affect render");
+
+    let algorithm = TextHashAlgorithm::default();
+    let old_hash = algorithm.hash(old_html.as_bytes());
+    let new_hash = algorithm.hash(new_html.as_bytes());
+    assert_ne!(old_hash, new_hash);
+
+    // Re-rendering updates the compiled output in place
+    let compiled_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let model = rerender_model(100, &new_hash, str!("ftml v1.22"), compiled_at);
+    assert_eq!(model.revision_id, Set(100));
+    assert_eq!(model.compiled_hash, Set(Some(new_hash.to_vec())));
+    assert_eq!(model.compiled_at, Set(compiled_at));
+    assert_eq!(model.compiled_generator, Set(str!("ftml v1.22")));
+
+    // But does not touch anything else, nor create a new revision
+    assert!(model.revision_number.is_not_set());
+    assert!(model.wikitext_hash.is_not_set());
+    assert!(model.created_at.is_not_set());
+    assert!(model.page_id.is_not_set());
+}
+
+#[test]
+fn page_locale() {
+    use crate::services::fixtures;
+
+    let site = fixtures::site(1);
+    let tags = vec![];
+    let render_input = || RenderPageInfo {
+        slug: "test",
+        title: "Test",
+        alt_title: None,
+        score: ScoreValue::Integer(0),
+        tags: &tags,
+    };
+
+    // Pages with their own locale render in it
+    let page = PageModel {
+        locale: Some(str!("fr")),
+        ..fixtures::page(1, 100, "test")
+    };
+    let page_info = build_page_info(&site, &page, render_input());
+    assert_eq!(page_info.language, "fr");
+
+    // Otherwise they fall back to the site's
+    let page = fixtures::page(1, 101, "test");
+    let page_info = build_page_info(&site, &page, render_input());
+    assert_eq!(page_info.language, "en");
+}
+
+#[test]
+fn disabled_blocks() {
+    use crate::services::fixtures;
+    use ftml::parsing::ParseErrorKind;
+
+    let config = Config::example();
+
+    let render = |site: &SiteModel, wikitext: &str| {
+        let page_info = PageInfo::dummy();
+        let settings = page_settings(&config, site);
+        let (html_output, _, errors) =
+            RenderService::render_wikitext(str!(wikitext), &page_info, &settings)
+                .expect("Unable to render wikitext");
+
+        let warned = errors
+            .iter()
+            .any(|error| error.kind() == ParseErrorKind::BlockDisabled);
+
+        (html_output.body, warned)
+    };
+
+    let iframe = "[[iframe https://example.com/]]";
+    let div = "[[div]]\nApple\n[[/div]]";
+
+    // By default, all blocks are enabled
+    let site = fixtures::site(1);
+    let (html, warned) = render(&site, iframe);
+    assert!(html.contains("<iframe"));
+    assert!(!warned);
+
+    // Blocks the site disables are skipped with a warning, regardless of case
+    let site = SiteModel {
+        disabled_blocks: vec![str!("IFrame")],
+        ..fixtures::site(1)
+    };
+    let (html, warned) = render(&site, iframe);
+    assert!(!html.contains("<iframe"), "Disabled block was rendered");
+    assert!(warned, "No warning for disabled block");
+
+    // Others still work
+    let (html, warned) = render(&site, div);
+    assert!(html.contains("Apple"));
+    assert!(!warned);
+}
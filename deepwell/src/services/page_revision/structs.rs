@@ -20,8 +20,8 @@
 
 use super::prelude::*;
 use crate::models::sea_orm_active_enums::PageRevisionType;
-use crate::web::FetchDirection;
-use ftml::parsing::ParseError;
+use crate::services::render::ClassifiedParseError;
+use crate::web::{check_not_blank, FetchDirection, Validate, ValidationResult};
 use std::num::NonZeroI32;
 use time::OffsetDateTime;
 
@@ -45,6 +45,38 @@ pub struct CreatePageRevisionBody {
     pub tags: ProvidedValue<Vec<String>>,
 }
 
+/// An edit to a page which is saved without affecting the live page.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePageDraft {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub user_id: i64,
+    pub comments: String,
+    pub wikitext: String,
+    pub title: String,
+    pub alt_title: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl Validate for CreatePageDraft {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("title", &self.title)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishPageDraft {
+    pub site_id: i64,
+    pub draft_id: i64,
+
+    /// The user publishing the draft, who need not be its author.
+    pub user_id: i64,
+}
+
+impl Validate for PublishPageDraft {}
+
 #[derive(Debug)]
 pub struct CreateFirstPageRevision {
     pub user_id: i64,
@@ -77,14 +109,14 @@ pub struct CreateResurrectionPageRevision {
 pub struct CreatePageRevisionOutput {
     pub revision_id: i64,
     pub revision_number: i32,
-    pub parser_errors: Option<Vec<ParseError>>,
+    pub parser_errors: Option<Vec<ClassifiedParseError>>,
 }
 
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateFirstPageRevisionOutput {
     pub revision_id: i64,
-    pub parser_errors: Vec<ParseError>,
+    pub parser_errors: Vec<ClassifiedParseError>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -95,6 +127,8 @@ pub struct GetPageRevision {
     pub revision_number: i32,
 }
 
+impl Validate for GetPageRevision {}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdatePageRevision {
@@ -105,6 +139,8 @@ pub struct UpdatePageRevision {
     pub hidden: Vec<String>,
 }
 
+impl Validate for UpdatePageRevision {}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetPageRevisionRange {
@@ -113,8 +149,14 @@ pub struct GetPageRevisionRange {
     pub revision_number: i32,
     pub revision_direction: FetchDirection,
     pub limit: u64,
+
+    /// If set, only return revisions which were (or were not) imported from Wikidot.
+    #[serde(default)]
+    pub from_wikidot: Option<bool>,
 }
 
+impl Validate for GetPageRevisionRange {}
+
 /// Information about the revisions currently associated with a page.
 ///
 /// A lot of this information is not strictly necessary:
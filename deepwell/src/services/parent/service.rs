@@ -19,8 +19,11 @@
  */
 
 use super::prelude::*;
+use crate::models::page::{self, Entity as Page, Model as PageModel};
 use crate::models::page_parent::{self, Entity as PageParent, Model as PageParentModel};
 use crate::services::PageService;
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
 
 #[derive(Debug)]
 pub struct ParentService;
@@ -57,6 +60,22 @@ impl ParentService {
             return Err(Error::Conflict);
         }
 
+        // Check if this relationship would create a cycle,
+        // that is, if the child is already an ancestor of the parent
+        let is_cycle = has_ancestor(parent_page.page_id, child_page.page_id, |page_id| {
+            Self::get_parent_ids(ctx, page_id)
+        })
+        .await?;
+
+        if is_cycle {
+            tide::log::error!(
+                "Cannot parent page ID {} to page ID {}, it would create a cycle",
+                child_page.page_id,
+                parent_page.page_id,
+            );
+            return Err(Error::Conflict);
+        }
+
         // Check if this relationship already exists
         let relationship =
             PageParent::find_by_id((parent_page.page_id, child_page.page_id))
@@ -162,6 +181,71 @@ impl ParentService {
         Ok(models)
     }
 
+    /// Gets all extant child pages of the given page, ordered by slug.
+    pub async fn get_children(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+    ) -> Result<Vec<PageModel>> {
+        let txn = ctx.transaction();
+        let parent_page = PageService::get(ctx, site_id, reference).await?;
+        let children = Page::find()
+            .join(
+                JoinType::InnerJoin,
+                page_parent::Relation::Page2.def().rev(),
+            )
+            .filter(
+                Condition::all()
+                    .add(page_parent::Column::ParentPageId.eq(parent_page.page_id))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(page::Column::Slug)
+            .all(txn)
+            .await?;
+
+        Ok(children)
+    }
+
+    /// Gets the parent page used for breadcrumbs, if any.
+    ///
+    /// Pages may have several parents, in which case the one
+    /// which was added first is used. Deleted parents are ignored.
+    pub async fn get_breadcrumb_parent(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Option<PageModel>> {
+        let txn = ctx.transaction();
+        let parent = Page::find()
+            .join(
+                JoinType::InnerJoin,
+                page_parent::Relation::Page1.def().rev(),
+            )
+            .filter(
+                Condition::all()
+                    .add(page_parent::Column::ChildPageId.eq(page_id))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(page_parent::Column::CreatedAt)
+            .one(txn)
+            .await?;
+
+        Ok(parent)
+    }
+
+    /// Gets the IDs of all direct parents of the given page.
+    async fn get_parent_ids(ctx: &ServiceContext<'_>, page_id: i64) -> Result<Vec<i64>> {
+        let txn = ctx.transaction();
+        let parent_ids = PageParent::find()
+            .select_only()
+            .column(page_parent::Column::ParentPageId)
+            .filter(page_parent::Column::ChildPageId.eq(page_id))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        Ok(parent_ids)
+    }
+
     /// Removes all parent relationships involving this page.
     ///
     /// Whether this page is a parent or a child, this method
@@ -185,3 +269,84 @@ impl ParentService {
         Ok(rows_deleted)
     }
 }
+
+/// Determines if `ancestor_id` is an ancestor of `page_id`.
+///
+/// This walks up the parent graph breadth-first, using `get_parents`
+/// to fetch the direct parents of each page. Pages are only visited once,
+/// so this terminates even if the graph already contains a cycle.
+async fn has_ancestor<F, Fut>(
+    page_id: i64,
+    ancestor_id: i64,
+    mut get_parents: F,
+) -> Result<bool>
+where
+    F: FnMut(i64) -> Fut,
+    Fut: Future<Output = Result<Vec<i64>>>,
+{
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(page_id);
+
+    while let Some(current_id) = queue.pop_front() {
+        if current_id == ancestor_id {
+            return Ok(true);
+        }
+
+        if visited.insert(current_id) {
+            queue.extend(get_parents(current_id).await?);
+        }
+    }
+
+    Ok(false)
+}
+
+#[test]
+fn parent_cycles() {
+    use async_std::task::block_on;
+    use std::collections::HashMap;
+
+    macro_rules! check {
+        ($relationships:expr, $parent:expr, $child:expr, $expected:expr $(,)?) => {{
+            // Map of child ID to parent IDs
+            let mut parents: HashMap<i64, Vec<i64>> = HashMap::new();
+            for (parent_id, child_id) in $relationships {
+                parents.entry(child_id).or_default().push(parent_id);
+            }
+
+            let parents = &parents;
+            let is_cycle =
+                block_on(has_ancestor($parent, $child, |page_id| async move {
+                    Ok(parents.get(&page_id).cloned().unwrap_or_default())
+                }))
+                .expect("Unable to check for cycle");
+
+            assert_eq!(
+                is_cycle, $expected,
+                "Cycle check for parenting {} to {} doesn't match expected",
+                $child, $parent,
+            );
+        }};
+    }
+
+    // No existing relationships
+    check!([], 1, 2, false);
+
+    // Setting a parent, then listing children
+    check!([(1, 2)], 1, 3, false);
+    check!([(1, 2), (1, 3)], 2, 4, false);
+
+    // Direct cycle
+    check!([(1, 2)], 2, 1, true);
+
+    // Indirect cycle
+    check!([(1, 2), (2, 3), (3, 4)], 4, 1, true);
+    check!([(1, 2), (2, 3), (3, 4)], 4, 2, true);
+
+    // Multiple parents, diamond shape
+    check!([(1, 2), (1, 3), (2, 4), (3, 4)], 4, 1, true);
+    check!([(1, 2), (1, 3), (2, 4), (3, 4)], 4, 5, false);
+
+    // Existing cycle elsewhere in the graph still terminates
+    check!([(1, 2), (2, 1)], 2, 3, false);
+}
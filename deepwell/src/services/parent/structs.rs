@@ -19,7 +19,7 @@
  */
 
 use crate::services::Error;
-use crate::web::Reference;
+use crate::web::{Reference, Validate};
 use std::str::FromStr;
 
 #[derive(Deserialize, Debug)]
@@ -30,6 +30,8 @@ pub struct ParentDescription<'a> {
     pub child: Reference<'a>,
 }
 
+impl Validate for ParentDescription<'_> {}
+
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub enum ParentalRelationshipType {
     Parent,
@@ -27,13 +27,16 @@ mod prelude {
         info::VERSION as FTML_VERSION,
         parsing::ParseError,
         render::html::{HtmlOutput, HtmlRender},
+        render::text::TextRender,
         render::Render,
         settings::WikitextSettings,
     };
 }
 
 mod service;
+mod severity;
 mod structs;
 
 pub use self::service::RenderService;
+pub use self::severity::{has_severe_errors, ClassifiedParseError, ParseErrorSeverity};
 pub use self::structs::*;
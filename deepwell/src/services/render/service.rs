@@ -19,8 +19,11 @@
  */
 
 use super::prelude::*;
+use super::severity::ClassifiedParseError;
 use crate::services::TextService;
+use async_lock::Semaphore;
 use async_std::future::timeout;
+use std::future::Future;
 
 #[derive(Debug)]
 pub struct RenderService;
@@ -28,7 +31,7 @@ pub struct RenderService;
 impl RenderService {
     pub async fn render(
         ctx: &ServiceContext<'_>,
-        mut wikitext: String,
+        wikitext: String,
         page_info: &PageInfo<'_>,
         settings: &WikitextSettings,
     ) -> Result<RenderOutput> {
@@ -36,21 +39,22 @@ impl RenderService {
 
         // Isolate the actual render task.
         // This way we can cut it off if it times out.
+        //
+        // The timeout only starts once the render is permitted to run,
+        // time spent waiting for other renders does not count against it.
 
-        let (html_output, errors) = timeout(ctx.config().render_timeout, async {
-            // Run ftml to parse and render
-            // TODO include
-            ftml::preprocess(&mut wikitext);
-            let tokens = ftml::tokenize(&wikitext);
-            let result = ftml::parse(&tokens, page_info, settings);
-            let (tree, errors) = result.into();
-            let html_output = HtmlRender.render(&tree, page_info, settings);
-            (html_output, errors)
-        })
-        .await
-        // Not using Error::from() because timeouts could occur in other places,
-        // and this error variant is not specific to all timeouts.
-        .map_err(|_| Error::RenderTimeout)?;
+        let render = async {
+            timeout(ctx.config().render_timeout, async {
+                Self::render_wikitext(wikitext, page_info, settings)
+            })
+            .await
+        };
+
+        let (html_output, text, errors) = run_limited(ctx.render_semaphore(), render)
+            .await
+            // Not using Error::from() because timeouts could occur in other places,
+            // and this error variant is not specific to all timeouts.
+            .map_err(|_| Error::RenderTimeout)??;
 
         // Insert compiled HTML into text table
         let compiled_hash = TextService::create(ctx, html_output.body.clone()).await?;
@@ -58,9 +62,145 @@ impl RenderService {
         // Build and return
         Ok(RenderOutput {
             html_output,
-            errors,
+            text,
+            errors: errors.into_iter().map(ClassifiedParseError::from).collect(),
             compiled_hash,
             compiled_generator,
         })
     }
+
+    /// Runs ftml to parse wikitext, and render it as both HTML and plain text.
+    ///
+    /// The wikitext is only parsed once for both renders.
+    /// This is only the render itself, without the time limit
+    /// or storage of the compiled HTML which `render()` adds.
+    ///
+    /// Fails if the HTML exceeds `max_html_size` in the settings.
+    pub fn render_wikitext(
+        mut wikitext: String,
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> Result<(HtmlOutput, String, Vec<ParseError>)> {
+        // TODO include
+        ftml::preprocess(&mut wikitext);
+        let tokens = ftml::tokenize(&wikitext);
+        let result = ftml::parse(&tokens, page_info, settings);
+        let (tree, errors) = result.into();
+        let html_output = HtmlRender.try_render(&tree, page_info, settings)?;
+        let text = TextRender.render(&tree, page_info, settings);
+        Ok((html_output, text, errors))
+    }
+
+    /// Renders wikitext as plain text, such as for excerpts.
+    ///
+    /// This has the same limits as HTML rendering, but the output is not stored.
+    pub async fn render_text(
+        ctx: &ServiceContext<'_>,
+        mut wikitext: String,
+        page_info: &PageInfo<'_>,
+        settings: &WikitextSettings,
+    ) -> Result<String> {
+        let render = async {
+            timeout(ctx.config().render_timeout, async {
+                ftml::preprocess(&mut wikitext);
+                let tokens = ftml::tokenize(&wikitext);
+                let result = ftml::parse(&tokens, page_info, settings);
+                let (tree, _errors) = result.into();
+                TextRender.render(&tree, page_info, settings)
+            })
+            .await
+        };
+
+        run_limited(ctx.render_semaphore(), render)
+            .await
+            .map_err(|_| Error::RenderTimeout)
+    }
+}
+
+/// Runs the given future once the semaphore permits it.
+///
+/// If the semaphore's permits are all taken, this waits until one is released.
+async fn run_limited<F: Future>(semaphore: &Semaphore, future: F) -> F::Output {
+    let _guard = match semaphore.try_acquire() {
+        Some(guard) => guard,
+        None => {
+            tide::log::debug!("Render limit reached, waiting for another to finish");
+            semaphore.acquire().await
+        }
+    };
+
+    future.await
+}
+
+#[test]
+fn render_limit() {
+    use async_std::task;
+    use futures::future::join_all;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    const LIMIT: usize = 2;
+    const RENDERS: usize = 6;
+
+    let semaphore = Semaphore::new(LIMIT);
+    let running = AtomicUsize::new(0);
+    let max_running = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+
+    let render = || async {
+        let current = running.fetch_add(1, Ordering::SeqCst) + 1;
+        max_running.fetch_max(current, Ordering::SeqCst);
+        task::sleep(Duration::from_millis(20)).await;
+        running.fetch_sub(1, Ordering::SeqCst);
+        finished.fetch_add(1, Ordering::SeqCst);
+    };
+
+    task::block_on(join_all(
+        (0..RENDERS).map(|_| run_limited(&semaphore, render())),
+    ));
+
+    // Every render ran, but never more than the limit at once
+    assert_eq!(finished.load(Ordering::SeqCst), RENDERS);
+    assert_eq!(max_running.load(Ordering::SeqCst), LIMIT);
+    assert_eq!(running.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn render_wikitext() {
+    use ftml::settings::WikitextMode;
+
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let (html_output, text, errors) = RenderService::render_wikitext(
+        str!("+ Apples\n\nApples are **red**."),
+        &page_info,
+        &settings,
+    )
+    .expect("Unable to render wikitext");
+
+    // Both renders come from the same parse
+    assert!(errors.is_empty());
+    assert!(html_output.body.contains("<strong>red</strong>"));
+    assert!(text.contains("Apples are red."));
+    assert!(!text.contains('<'));
+}
+
+#[test]
+fn render_too_large() {
+    use ftml::settings::WikitextMode;
+
+    let page_info = PageInfo::dummy();
+    let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let wikitext = "Apples are **red**.\n\n".repeat(100);
+
+    settings.max_html_size = Some(100_000);
+    let result = RenderService::render_wikitext(str!(wikitext), &page_info, &settings);
+    assert!(result.is_ok(), "Render under the limit failed");
+
+    settings.max_html_size = Some(100);
+    let result = RenderService::render_wikitext(str!(wikitext), &page_info, &settings);
+    assert!(
+        matches!(result, Err(Error::RenderTooLarge(error)) if error.limit == 100),
+        "Render over the limit did not fail",
+    );
 }
@@ -0,0 +1,138 @@
+/*
+ * services/render/severity.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ftml::parsing::{ParseError, ParseErrorKind};
+
+/// How serious a parser error is.
+///
+/// No parser error is fatal to ftml, since it always falls back to
+/// something which can be rendered. However some errors, such as
+/// a nonexistent block, mean that the output is almost certainly
+/// not what the author intended, and so are classified as errors
+/// rather than warnings.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum ParseErrorSeverity {
+    Warning,
+    Error,
+}
+
+impl ParseErrorSeverity {
+    pub fn of(kind: ParseErrorKind) -> Self {
+        match kind {
+            ParseErrorKind::RecursionDepthExceeded
+            | ParseErrorKind::InvalidInclude
+            | ParseErrorKind::ListDepthExceeded
+            | ParseErrorKind::BlockquoteDepthExceeded
+            | ParseErrorKind::NoSuchBlock
+            | ParseErrorKind::NoSuchEmbed
+            | ParseErrorKind::NoSuchModule
+            | ParseErrorKind::NoSuchPage
+            | ParseErrorKind::BlockMissingName
+            | ParseErrorKind::ModuleMissingName
+            | ParseErrorKind::BlockMissingArguments
+            | ParseErrorKind::BlockMalformedArguments
+            | ParseErrorKind::BlockEndMismatch
+            | ParseErrorKind::InvalidUrl => ParseErrorSeverity::Error,
+            _ => ParseErrorSeverity::Warning,
+        }
+    }
+
+    #[inline]
+    pub fn of_error(error: &ParseError) -> Self {
+        Self::of(error.kind())
+    }
+}
+
+/// A parser error along with its severity, as returned to API clients.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassifiedParseError {
+    #[serde(flatten)]
+    pub error: ParseError,
+    pub severity: ParseErrorSeverity,
+}
+
+impl From<ParseError> for ClassifiedParseError {
+    #[inline]
+    fn from(error: ParseError) -> Self {
+        let severity = ParseErrorSeverity::of_error(&error);
+        ClassifiedParseError { error, severity }
+    }
+}
+
+/// Determines if any of the given parser errors have error severity.
+pub fn has_severe_errors(errors: &[ClassifiedParseError]) -> bool {
+    errors
+        .iter()
+        .any(|error| error.severity == ParseErrorSeverity::Error)
+}
+
+#[test]
+fn severity() {
+    macro_rules! check {
+        ($kind:ident, $severity:ident $(,)?) => {
+            assert_eq!(
+                ParseErrorSeverity::of(ParseErrorKind::$kind),
+                ParseErrorSeverity::$severity,
+                "Actual parser error severity doesn't match expected",
+            )
+        };
+    }
+
+    check!(RuleFailed, Warning);
+    check!(NoRulesMatch, Warning);
+    check!(EndOfInput, Warning);
+    check!(NotStartOfLine, Warning);
+    check!(ListEmpty, Warning);
+    check!(NoSuchBlock, Error);
+    check!(BlockEndMismatch, Error);
+    check!(RecursionDepthExceeded, Error);
+    check!(InvalidUrl, Error);
+
+    assert!(!has_severe_errors(&[]));
+    assert!(ParseErrorSeverity::Warning < ParseErrorSeverity::Error);
+}
+
+#[test]
+fn severity_output() {
+    use ftml::data::PageInfo;
+    use ftml::settings::{WikitextMode, WikitextSettings};
+
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let tokens = ftml::tokenize("[[nonexistent-block]]");
+    let (_, errors) = ftml::parse(&tokens, &page_info, &settings).into();
+    let errors: Vec<ClassifiedParseError> =
+        errors.into_iter().map(ClassifiedParseError::from).collect();
+
+    assert!(has_severe_errors(&errors));
+
+    // The severity is included alongside the error's own fields
+    let value = serde_json::to_value(&errors).expect("Unable to serialize errors");
+    let error = value
+        .as_array()
+        .and_then(|errors| errors.iter().find(|error| error["kind"] == "no-such-block"))
+        .expect("No error for nonexistent block");
+
+    assert_eq!(error["severity"], "error");
+    assert_eq!(error["rule"], "block");
+    assert!(error["span"].is_object());
+}
@@ -19,12 +19,17 @@
  */
 
 use super::prelude::*;
+use super::severity::ClassifiedParseError;
 use crate::hash::TextHash;
 
 #[derive(Debug)]
 pub struct RenderOutput {
     pub html_output: HtmlOutput,
-    pub errors: Vec<ParseError>,
+
+    /// Plain text render of the same wikitext, such as for extracting keywords.
+    pub text: String,
+
+    pub errors: Vec<ClassifiedParseError>,
     pub compiled_hash: TextHash,
     pub compiled_generator: String,
 }
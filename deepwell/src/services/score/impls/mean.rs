@@ -43,8 +43,8 @@ impl Scorer for MeanScorer {
     ) -> Result<ScoreValue> {
         #[derive(FromQueryResult, Debug)]
         struct MeanRow {
-            sum: u64,
-            count: u64,
+            sum: Option<i64>,
+            count: i64,
         }
 
         // Query for sum of all votes.
@@ -67,10 +67,9 @@ impl Scorer for MeanScorer {
             .await?
             .expect("No results in aggregate query");
 
-        let score = if count == 0 {
-            0.0
-        } else {
-            (sum / count) as f64
+        let score = match sum {
+            Some(sum) if count > 0 => sum as f64 / count as f64,
+            _ => 0.0,
         };
 
         Ok(ScoreValue::Float(score))
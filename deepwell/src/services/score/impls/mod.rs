@@ -21,11 +21,9 @@
 use super::prelude;
 
 mod mean;
-mod null;
 mod percent;
 mod sum;
 
 pub use self::mean::MeanScorer;
-pub use self::null::NullScorer;
 pub use self::percent::PercentScorer;
 pub use self::sum::SumScorer;
@@ -42,7 +42,7 @@ impl Scorer for SumScorer {
     ) -> Result<ScoreValue> {
         #[derive(FromQueryResult, Debug)]
         struct SumRow {
-            sum: i64,
+            sum: Option<i64>,
         }
 
         // Query for sum of all votes.
@@ -64,6 +64,7 @@ impl Scorer for SumScorer {
             .await?
             .expect("No results in aggregate query");
 
-        Ok(ScoreValue::Integer(result.sum))
+        // SUM() of no rows is NULL, not zero
+        Ok(ScoreValue::Integer(result.sum.unwrap_or(0)))
     }
 }
@@ -21,7 +21,7 @@
 use super::prelude::*;
 
 #[async_trait]
-pub trait Scorer {
+pub trait Scorer: Sync {
     /// What kind of score this scorer evaluates.
     ///
     /// There should be a 1-to-1 mapping between `Scorer`
@@ -39,8 +39,7 @@ pub trait Scorer {
     ///
     /// The process for collecting votes by group and then later iterating
     /// to sum is less efficient than having the database doing the summing,
-    /// for instance. Similarly, the `NullScorer` does not need to go to the
-    /// database at all.
+    /// for instance.
     ///
     /// In order to ensure the query is formed correctly, the `Condition` for
     /// querying active votes for a page is passed rather than the page ID.
@@ -20,6 +20,7 @@
 
 use super::impls::*;
 use super::prelude::*;
+use crate::services::VoteService;
 
 #[derive(Debug)]
 pub struct ScoreService;
@@ -35,13 +36,13 @@ impl ScoreService {
 
     /// Gets the correct `Scorer` implementation for this page.
     ///
-    /// Currently stubbed, will be implemented when relevant settings are added.
+    /// This is determined by the vote type of the page's site.
     pub async fn get_scorer(
-        _ctx: &ServiceContext<'_>,
-        _page_id: i64,
-    ) -> Result<&'static impl Scorer> {
-        // TODO
-        Ok(&NullScorer)
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<&'static dyn Scorer> {
+        let vote_type = VoteService::get_vote_type(ctx, page_id).await?;
+        Ok(scorer_for(vote_type))
     }
 
     /// Helper method for retrieving a `VoteMap` for a page.
@@ -95,3 +96,88 @@ impl ScoreService {
             .add(page_vote::Column::DisabledAt.is_null())
     }
 }
+
+/// Gets the default `Scorer` for a vote type.
+///
+/// Ups and downs are summed, whereas star ratings are averaged.
+fn scorer_for(vote_type: VoteType) -> &'static dyn Scorer {
+    match vote_type {
+        VoteType::UpsDowns => &SumScorer,
+        VoteType::FiveStar => &MeanScorer,
+    }
+}
+
+#[test]
+fn vote_type_scorers() {
+    for vote_type in [VoteType::UpsDowns, VoteType::FiveStar] {
+        let scorer = scorer_for(vote_type);
+        assert!(
+            scorer.accepts_vote_type(vote_type),
+            "Scorer {:?} doesn't accept its own vote type {:?}",
+            scorer.score_type(),
+            vote_type,
+        );
+    }
+
+    assert_eq!(scorer_for(VoteType::UpsDowns).score_type(), ScoreType::Sum);
+    assert_eq!(scorer_for(VoteType::FiveStar).score_type(), ScoreType::Mean);
+}
+
+#[test]
+fn vote_type_scores() {
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait, Value};
+    use std::collections::BTreeMap;
+
+    // Scores the page with the given aggregate row from the database
+    fn score(vote_type: VoteType, row: BTreeMap<&str, Value>) -> ScoreValue {
+        let database = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[row]])
+            .into_connection();
+
+        task::block_on(async {
+            let txn = database.begin().await?;
+            let condition = ScoreService::build_condition(10);
+            scorer_for(vote_type).score(&txn, condition).await
+        })
+        .expect("Unable to score page")
+    }
+
+    // Pages without votes aggregate to NULL, which scores as zero
+    assert_eq!(
+        score(
+            VoteType::UpsDowns,
+            BTreeMap::from([("sum", Value::BigInt(None))]),
+        ),
+        ScoreValue::Integer(0),
+    );
+    assert_eq!(
+        score(
+            VoteType::FiveStar,
+            BTreeMap::from([
+                ("sum", Value::BigInt(None)),
+                ("count", Value::BigInt(Some(0)))
+            ]),
+        ),
+        ScoreValue::Float(0.0),
+    );
+
+    // Otherwise ups and downs are summed, and stars are averaged
+    assert_eq!(
+        score(
+            VoteType::UpsDowns,
+            BTreeMap::from([("sum", Value::BigInt(Some(-3)))]),
+        ),
+        ScoreValue::Integer(-3),
+    );
+    assert_eq!(
+        score(
+            VoteType::FiveStar,
+            BTreeMap::from([
+                ("sum", Value::BigInt(Some(9))),
+                ("count", Value::BigInt(Some(2)))
+            ]),
+        ),
+        ScoreValue::Float(4.5),
+    );
+}
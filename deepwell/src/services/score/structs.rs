@@ -20,20 +20,25 @@
 
 use std::collections::BTreeMap;
 
+pub use crate::models::sea_orm_active_enums::VoteType;
 pub use crate::services::vote::VoteValue;
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-#[non_exhaustive]
-pub enum VoteType {
-    UpsDowns,
-    FiveStar,
+impl VoteType {
+    /// Whether a vote with this value can be cast under this vote type.
+    ///
+    /// * Ups and downs permits `+1` and `-1`.
+    /// * Five star permits `1` through `5`.
+    pub fn accepts_value(self, value: VoteValue) -> bool {
+        match self {
+            VoteType::UpsDowns => value == 1 || value == -1,
+            VoteType::FiveStar => (1..=5).contains(&value),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum ScoreType {
-    Null,
     Sum,
     Mean,
     Median,
@@ -89,3 +94,54 @@ impl VoteMap {
         self.inner.iter().map(|(&value, &count)| (value, count))
     }
 }
+
+#[test]
+fn vote_map() {
+    let mut votes = VoteMap::new();
+    assert!(votes.is_empty());
+    assert_eq!(votes.count(), 0);
+    assert_eq!(votes.sum(), 0);
+
+    // Cast votes
+    votes.insert(1, 5);
+    votes.insert(-1, 2);
+    assert_eq!(votes.get(1), 5);
+    assert_eq!(votes.get(-1), 2);
+    assert_eq!(votes.get(0), 0);
+    assert_eq!(votes.count(), 7);
+    assert_eq!(votes.sum(), 3);
+
+    // Change one upvote to a downvote
+    votes.insert(1, 4);
+    votes.insert(-1, 3);
+    assert_eq!(votes.count(), 7);
+    assert_eq!(votes.sum(), 1);
+}
+
+#[test]
+fn vote_values() {
+    macro_rules! check {
+        ($vote_type:expr, $value:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                $vote_type.accepts_value($value),
+                $expected,
+                "Vote value {} acceptance for {:?} doesn't match expected",
+                $value,
+                $vote_type,
+            );
+        };
+    }
+
+    check!(VoteType::UpsDowns, 1, true);
+    check!(VoteType::UpsDowns, -1, true);
+    check!(VoteType::UpsDowns, 0, false);
+    check!(VoteType::UpsDowns, 2, false);
+    check!(VoteType::UpsDowns, 5, false);
+
+    check!(VoteType::FiveStar, 1, true);
+    check!(VoteType::FiveStar, 3, true);
+    check!(VoteType::FiveStar, 5, true);
+    check!(VoteType::FiveStar, 0, false);
+    check!(VoteType::FiveStar, -1, false);
+    check!(VoteType::FiveStar, 6, false);
+}
@@ -0,0 +1,59 @@
+/*
+ * services/session/geo.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Resolution of IP addresses to coarse locations, recorded on sessions.
+//!
+//! No particular GeoIP database is used, instead a resolver implementing
+//! `GeoResolver` is provided in the server state. By default this is
+//! `NullGeoResolver`, which never finds a location.
+
+use super::prelude::*;
+use std::fmt::Debug;
+use std::net::IpAddr;
+use tide::utils::async_trait;
+
+/// The approximate location of an IP address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeoLocation {
+    /// The country, as an ISO 3166-1 alpha-2 code.
+    pub country: String,
+
+    /// The region or subdivision within the country, if known.
+    pub region: Option<String>,
+}
+
+#[async_trait]
+pub trait GeoResolver: Debug + Send + Sync {
+    /// Gets the location of the given IP address.
+    ///
+    /// If the location is not known, this returns `None` rather than an error.
+    async fn locate(&self, ip_address: IpAddr) -> Result<Option<GeoLocation>>;
+}
+
+/// Resolver which does not perform any lookups.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct NullGeoResolver;
+
+#[async_trait]
+impl GeoResolver for NullGeoResolver {
+    async fn locate(&self, _: IpAddr) -> Result<Option<GeoLocation>> {
+        Ok(None)
+    }
+}
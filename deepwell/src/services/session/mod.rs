@@ -23,8 +23,10 @@ mod prelude {
     pub use super::structs::*;
 }
 
+mod geo;
 mod service;
 mod structs;
 
+pub use self::geo::{GeoLocation, GeoResolver, NullGeoResolver};
 pub use self::service::SessionService;
 pub use self::structs::*;
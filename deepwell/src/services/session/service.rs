@@ -30,12 +30,15 @@
 //! expiry (30 minutes) which needs to be renewed by the client
 //! periodically.
 
+use super::geo::{GeoLocation, GeoResolver};
 use super::prelude::*;
 use crate::models::session::{self, Entity as Session, Model as SessionModel};
 use crate::models::user::{self, Entity as User, Model as UserModel};
 use crate::utils::assert_is_csprng;
 use rand::distributions::{Alphanumeric, DistString};
 use rand::thread_rng;
+use std::net::IpAddr;
+use time::{Duration, OffsetDateTime};
 
 #[derive(Debug)]
 pub struct SessionService;
@@ -43,17 +46,25 @@ pub struct SessionService;
 impl SessionService {
     /// Creates a new session with the given parameters.
     ///
+    /// If single sessions are enforced, then creating a full (non-restricted)
+    /// session invalidates all other sessions for the user.
+    ///
+    /// The approximate location of the IP address is looked up and stored
+    /// on the session, if the configured `GeoResolver` can find it.
+    ///
     /// # Returns
     /// The generated session token.
     pub async fn create(
         ctx: &ServiceContext<'_>,
-        CreateSession {
+        input: CreateSession,
+    ) -> Result<String> {
+        let CreateSession {
             user_id,
             ip_address,
-            user_agent,
             restricted,
-        }: CreateSession,
-    ) -> Result<String> {
+            ..
+        } = input;
+
         tide::log::info!(
             "Creating new session for user ID {user_id} (restricted: {restricted})",
         );
@@ -62,21 +73,24 @@ impl SessionService {
         let config = ctx.config();
         let token = Self::new_token(config);
         let expiry = if restricted {
-            now() + config.restricted_session_duration
+            ctx.now() + config.restricted_session_duration
         } else {
-            now() + config.normal_session_duration
+            ctx.now() + config.normal_session_duration
         };
 
-        let model = session::ActiveModel {
-            session_token: Set(token),
-            user_id: Set(user_id),
-            created_at: Set(now()),
-            expires_at: Set(expiry),
-            ip_address: Set(str!(ip_address)), // TODO inet type?
-            user_agent: Set(user_agent),
-            restricted: Set(restricted),
-        };
+        if revokes_other_sessions(config, restricted) {
+            let DeleteResult { rows_affected } = Session::delete_many()
+                .filter(other_sessions_condition(user_id, None))
+                .exec(txn)
+                .await?;
+
+            tide::log::info!(
+                "Single session enforced, invalidated {rows_affected} other sessions",
+            );
+        }
 
+        let location = resolve_location(ctx.geo_resolver(), ip_address).await;
+        let model = new_session_model(token, input, location, ctx.now(), expiry);
         let SessionModel { session_token, .. } = model.insert(txn).await?;
         tide::log::info!("Created new session token");
         Ok(session_token)
@@ -117,7 +131,7 @@ impl SessionService {
             .filter(
                 Condition::all()
                     .add(session::Column::SessionToken.eq(session_token))
-                    .add(session::Column::ExpiresAt.gt(now())),
+                    .add(session::Column::ExpiresAt.gt(ctx.now())),
             )
             .one(txn)
             .await?;
@@ -144,7 +158,7 @@ impl SessionService {
             .filter(
                 Condition::all()
                     .add(session::Column::SessionToken.eq(session_token))
-                    .add(session::Column::ExpiresAt.gt(now()))
+                    .add(session::Column::ExpiresAt.gt(ctx.now()))
                     .add(session::Column::Restricted.eq(restricted)),
             )
             .one(txn)
@@ -167,7 +181,7 @@ impl SessionService {
             .filter(
                 Condition::all()
                     .add(session::Column::UserId.eq(user_id))
-                    .add(session::Column::ExpiresAt.gt(now())),
+                    .add(session::Column::ExpiresAt.gt(ctx.now())),
             )
             .all(txn)
             .await?;
@@ -175,6 +189,43 @@ impl SessionService {
         Ok(sessions)
     }
 
+    /// Refreshes a session on activity, extending its expiry.
+    ///
+    /// Unlike `renew()`, this keeps the same session token. The session is
+    /// extended to last the configured duration from now, and is marked as
+    /// recently active. Expired or restricted sessions cannot be refreshed.
+    pub async fn refresh(
+        ctx: &ServiceContext<'_>,
+        session_token: &str,
+    ) -> Result<SessionModel> {
+        tide::log::info!("Refreshing session with token {session_token}");
+
+        let txn = ctx.transaction();
+        let session = Self::get(ctx, session_token).await?;
+        if session.restricted {
+            tide::log::error!("Cannot refresh a restricted session");
+            return Err(Error::BadRequest);
+        }
+
+        let current_time = ctx.now();
+        let expiry = refreshed_expiry(
+            &session,
+            current_time,
+            ctx.config().normal_session_duration,
+        )
+        .ok_or(Error::NotFound)?;
+
+        let model = session::ActiveModel {
+            session_token: Set(session.session_token),
+            expires_at: Set(expiry),
+            last_active_at: Set(current_time),
+            ..Default::default()
+        };
+
+        let session = model.update(txn).await?;
+        Ok(session)
+    }
+
     /// Renews a session, invalidating the old one and creating a new one.
     ///
     /// # Returns
@@ -203,21 +254,21 @@ impl SessionService {
             return Err(Error::BadRequest);
         }
 
-        // Invalid and recreate
-        let (_, session_token) = try_join!(
-            Self::invalidate(ctx, old_session_token),
-            Self::create(
-                ctx,
-                CreateSession {
-                    user_id,
-                    ip_address,
-                    user_agent,
-                    restricted: false,
-                }
-            ),
-        )?;
-
-        Ok(session_token)
+        // Invalidate and recreate
+        //
+        // This must be done in order, since with single sessions enforced,
+        // creation would remove the old session first.
+        Self::invalidate(ctx, old_session_token).await?;
+        Self::create(
+            ctx,
+            CreateSession {
+                user_id,
+                ip_address,
+                user_agent,
+                restricted: false,
+            },
+        )
+        .await
     }
 
     /// Invalidates the given session, causing it to be deleted.
@@ -268,11 +319,7 @@ impl SessionService {
 
         // Delete all sessions from user_id, except if it's this session_token
         let DeleteResult { rows_affected } = Session::delete_many()
-            .filter(
-                Condition::all()
-                    .add(session::Column::SessionToken.ne(session_token))
-                    .add(session::Column::UserId.eq(user_id)),
-            )
+            .filter(other_sessions_condition(user_id, Some(session_token)))
             .exec(txn)
             .await?;
 
@@ -291,7 +338,7 @@ impl SessionService {
 
         let txn = ctx.transaction();
         let DeleteResult { rows_affected } = Session::delete_many()
-            .filter(session::Column::ExpiresAt.lte(now()))
+            .filter(session::Column::ExpiresAt.lte(ctx.now()))
             .exec(txn)
             .await?;
 
@@ -299,3 +346,228 @@ impl SessionService {
         Ok(rows_affected)
     }
 }
+
+/// Looks up the approximate location of an IP address.
+///
+/// Failing to find the location should not prevent a login,
+/// so errors are logged and treated as an unknown location.
+async fn resolve_location(
+    resolver: &dyn GeoResolver,
+    ip_address: IpAddr,
+) -> Option<GeoLocation> {
+    match resolver.locate(ip_address).await {
+        Ok(location) => location,
+        Err(error) => {
+            tide::log::warn!("Unable to determine location of {ip_address}: {error}");
+            None
+        }
+    }
+}
+
+/// Builds the model for a newly-created session.
+fn new_session_model(
+    session_token: String,
+    CreateSession {
+        user_id,
+        ip_address,
+        user_agent,
+        restricted,
+    }: CreateSession,
+    location: Option<GeoLocation>,
+    now: OffsetDateTime,
+    expires_at: OffsetDateTime,
+) -> session::ActiveModel {
+    let (country, region) = match location {
+        Some(GeoLocation { country, region }) => (Some(country), region),
+        None => (None, None),
+    };
+
+    session::ActiveModel {
+        session_token: Set(session_token),
+        user_id: Set(user_id),
+        created_at: Set(now),
+        expires_at: Set(expires_at),
+        last_active_at: Set(now),
+        ip_address: Set(str!(ip_address)), // TODO inet type?
+        country: Set(country),
+        region: Set(region),
+        user_agent: Set(user_agent),
+        restricted: Set(restricted),
+    }
+}
+
+/// Determines if creating this session should invalidate the user's others.
+///
+/// Restricted sessions are only a step in logging in, so
+/// only creating a full session enforces single sessions.
+#[inline]
+fn revokes_other_sessions(config: &Config, restricted: bool) -> bool {
+    config.single_session && !restricted
+}
+
+/// Builds the condition for a user's sessions, except the one given (if any).
+fn other_sessions_condition(user_id: i64, except_token: Option<&str>) -> Condition {
+    Condition::all()
+        .add(session::Column::UserId.eq(user_id))
+        .add_option(except_token.map(|token| session::Column::SessionToken.ne(token)))
+}
+
+/// Determines the new expiry for a session being refreshed at `now`.
+///
+/// Returns `None` if the session has already expired. The expiry is never
+/// moved earlier than it already is.
+fn refreshed_expiry(
+    session: &SessionModel,
+    now: OffsetDateTime,
+    duration: Duration,
+) -> Option<OffsetDateTime> {
+    if session.expires_at <= now {
+        tide::log::error!("Session has already expired, cannot refresh");
+        return None;
+    }
+
+    Some(session.expires_at.max(now + duration))
+}
+
+#[test]
+fn refresh_expiry() {
+    use crate::services::fixtures;
+
+    let start = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let duration = Duration::minutes(30);
+    let session = SessionModel {
+        created_at: start,
+        expires_at: start + duration,
+        last_active_at: start,
+        ..fixtures::session("wj:test", 1)
+    };
+
+    // Extends a valid session
+    assert_eq!(
+        refreshed_expiry(&session, start + Duration::minutes(10), duration),
+        Some(start + Duration::minutes(40)),
+    );
+
+    // Never shortens a session
+    assert_eq!(
+        refreshed_expiry(&session, start, Duration::minutes(5)),
+        Some(start + duration),
+    );
+
+    // Rejects an expired session
+    assert_eq!(refreshed_expiry(&session, start + duration, duration), None);
+    assert_eq!(
+        refreshed_expiry(&session, start + Duration::hours(1), duration),
+        None,
+    );
+}
+
+#[test]
+fn single_session() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let mut config = Config::example();
+
+    // Off, sessions coexist
+    config.single_session = false;
+    assert!(!revokes_other_sessions(&config, false));
+    assert!(!revokes_other_sessions(&config, true));
+
+    // On, new logins remove prior sessions
+    config.single_session = true;
+    assert!(revokes_other_sessions(&config, false));
+    assert!(!revokes_other_sessions(&config, true));
+
+    let sql = Session::delete_many()
+        .filter(other_sessions_condition(4, None))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert_eq!(
+        sql,
+        r#"DELETE FROM "session" WHERE "session"."user_id" = 4"#,
+    );
+
+    let sql = Session::delete_many()
+        .filter(other_sessions_condition(4, Some("wj:abc")))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert_eq!(
+        sql,
+        r#"DELETE FROM "session" WHERE "session"."user_id" = 4 AND "session"."session_token" <> 'wj:abc'"#,
+    );
+}
+
+#[test]
+fn session_location() {
+    use super::geo::NullGeoResolver;
+    use async_std::task;
+    use std::net::Ipv4Addr;
+    use tide::utils::async_trait;
+
+    #[derive(Debug)]
+    struct MockResolver;
+
+    #[async_trait]
+    impl GeoResolver for MockResolver {
+        async fn locate(&self, ip_address: IpAddr) -> Result<Option<GeoLocation>> {
+            match ip_address {
+                IpAddr::V4(ip) if ip.is_documentation() => Ok(Some(GeoLocation {
+                    country: str!("NZ"),
+                    region: Some(str!("AUK")),
+                })),
+                IpAddr::V4(ip) if ip.is_loopback() => Err(Error::RemoteOperationFailed),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    let now = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let input = |ip_address| CreateSession {
+        user_id: 4,
+        ip_address,
+        user_agent: str!("test"),
+        restricted: false,
+    };
+
+    // Location is attached to the new session
+    let ip_address = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 17));
+    let location = task::block_on(resolve_location(&MockResolver, ip_address));
+    let model = new_session_model(
+        str!("wj:test"),
+        input(ip_address),
+        location,
+        now,
+        now + Duration::minutes(30),
+    );
+
+    assert_eq!(model.ip_address, Set(str!("192.0.2.17")));
+    assert_eq!(model.country, Set(Some(str!("NZ"))));
+    assert_eq!(model.region, Set(Some(str!("AUK"))));
+
+    // Unknown location
+    let ip_address = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let location = task::block_on(resolve_location(&MockResolver, ip_address));
+    let model = new_session_model(
+        str!("wj:test"),
+        input(ip_address),
+        location,
+        now,
+        now + Duration::minutes(30),
+    );
+
+    assert_eq!(model.country, Set(None));
+    assert_eq!(model.region, Set(None));
+
+    // Failed lookups do not prevent the session
+    let location = task::block_on(resolve_location(
+        &MockResolver,
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+    ));
+    assert_eq!(location, None);
+
+    // Default resolver finds nothing
+    let location = task::block_on(resolve_location(&NullGeoResolver, ip_address));
+    assert_eq!(location, None);
+}
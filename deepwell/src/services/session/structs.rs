@@ -19,6 +19,7 @@
  */
 
 use crate::models::session::Model as SessionModel;
+use crate::web::{check_not_blank, Validate, ValidationResult};
 use std::net::IpAddr;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -39,6 +40,12 @@ pub struct RenewSession {
     pub user_agent: String,
 }
 
+impl Validate for RenewSession {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("old_session_token", &self.old_session_token)
+    }
+}
+
 pub type GetOtherSessions = InvalidateOtherSessions;
 
 #[derive(Serialize, Debug, Clone)]
@@ -54,3 +61,9 @@ pub struct InvalidateOtherSessions {
     pub session_token: String,
     pub user_id: i64,
 }
+
+impl Validate for InvalidateOtherSessions {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("session_token", &self.session_token)
+    }
+}
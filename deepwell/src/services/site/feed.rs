@@ -0,0 +1,223 @@
+/*
+ * services/site/feed.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Generation of syndication feeds (Atom or RSS) for a site's recent changes.
+
+use super::prelude::*;
+use crate::utils::escape_xml;
+use time::{OffsetDateTime, UtcOffset};
+
+/// The maximum number of entries to include in a feed.
+pub const FEED_MAX_ENTRIES: u64 = 50;
+
+/// A single page change to be listed in a feed.
+#[derive(Debug, Clone)]
+pub struct FeedEntry<'a> {
+    pub title: &'a str,
+    pub slug: &'a str,
+    pub author: &'a str,
+    pub comments: &'a str,
+    pub revision_number: i32,
+    pub updated_at: OffsetDateTime,
+}
+
+/// Builds a feed in the given format listing these changes to the site at `domain`.
+///
+/// The entries should be ordered newest first.
+pub fn build_feed(
+    format: FeedFormat,
+    site_name: &str,
+    domain: &str,
+    entries: &[FeedEntry],
+) -> String {
+    match format {
+        FeedFormat::Atom => build_atom(site_name, domain, entries),
+        FeedFormat::Rss => build_rss(site_name, domain, entries),
+    }
+}
+
+fn build_atom(site_name: &str, domain: &str, entries: &[FeedEntry]) -> String {
+    let domain = escape_xml(domain);
+    let updated = entries
+        .first()
+        .map(|entry| entry.updated_at)
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH);
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    str_writeln!(&mut xml, "<title>{}</title>", escape_xml(site_name));
+    str_writeln!(&mut xml, "<id>https://{domain}/</id>");
+    str_writeln!(&mut xml, "<link href=\"https://{domain}/\"/>");
+    str_writeln!(&mut xml, "<updated>{}</updated>", rfc3339(updated));
+
+    for entry in entries {
+        let slug = escape_xml(entry.slug);
+
+        str_writeln!(
+            &mut xml,
+            "<entry><title>{}</title><id>https://{}/{}#revision-{}</id>\
+             <link href=\"https://{}/{}\"/><updated>{}</updated>\
+             <author><name>{}</name></author><summary>{}</summary></entry>",
+            escape_xml(entry.title),
+            domain,
+            slug,
+            entry.revision_number,
+            domain,
+            slug,
+            rfc3339(entry.updated_at),
+            escape_xml(entry.author),
+            escape_xml(entry.comments),
+        );
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+fn build_rss(site_name: &str, domain: &str, entries: &[FeedEntry]) -> String {
+    let domain = escape_xml(domain);
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<rss version=\"2.0\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n");
+    xml.push_str("<channel>\n");
+    str_writeln!(&mut xml, "<title>{}</title>", escape_xml(site_name));
+    str_writeln!(&mut xml, "<link>https://{domain}/</link>");
+    str_writeln!(
+        &mut xml,
+        "<description>Recent changes on {}</description>",
+        escape_xml(site_name),
+    );
+
+    for entry in entries {
+        let slug = escape_xml(entry.slug);
+
+        str_writeln!(
+            &mut xml,
+            "<item><title>{}</title><link>https://{}/{}</link>\
+             <guid isPermaLink=\"false\">https://{}/{}#revision-{}</guid>\
+             <pubDate>{}</pubDate><dc:creator>{}</dc:creator>\
+             <description>{}</description></item>",
+            escape_xml(entry.title),
+            domain,
+            slug,
+            domain,
+            slug,
+            entry.revision_number,
+            rfc2822(entry.updated_at),
+            escape_xml(entry.author),
+            escape_xml(entry.comments),
+        );
+    }
+
+    xml.push_str("</channel>\n");
+    xml.push_str("</rss>\n");
+    xml
+}
+
+/// Formats a timestamp as RFC 3339 in UTC, e.g. `2023-01-31T12:00:00Z`.
+fn rfc3339(timestamp: OffsetDateTime) -> String {
+    let timestamp = timestamp.to_offset(UtcOffset::UTC);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        timestamp.year(),
+        u8::from(timestamp.month()),
+        timestamp.day(),
+        timestamp.hour(),
+        timestamp.minute(),
+        timestamp.second(),
+    )
+}
+
+/// Formats a timestamp as RFC 2822 in UTC, e.g. `Tue, 31 Jan 2023 12:00:00 +0000`.
+fn rfc2822(timestamp: OffsetDateTime) -> String {
+    let timestamp = timestamp.to_offset(UtcOffset::UTC);
+    let weekday = timestamp.weekday().to_string();
+    let month = timestamp.month().to_string();
+
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} +0000",
+        &weekday[..3],
+        timestamp.day(),
+        &month[..3],
+        timestamp.year(),
+        timestamp.hour(),
+        timestamp.minute(),
+        timestamp.second(),
+    )
+}
+
+#[test]
+fn feed() {
+    // 2023-01-31 12:00 UTC
+    let timestamp = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+
+    let entries = [
+        FeedEntry {
+            title: "SCP-001",
+            slug: "scp-001",
+            author: "aismallard",
+            comments: "Fix <b>typo</b>",
+            revision_number: 4,
+            updated_at: timestamp,
+        },
+        FeedEntry {
+            title: "Tales & Stories",
+            slug: "tales-hub",
+            author: "Zyn",
+            comments: "",
+            revision_number: 0,
+            updated_at: timestamp - time::Duration::DAY,
+        },
+    ];
+
+    let xml = build_feed(FeedFormat::Atom, "SCP Wiki", "scpwiki.com", &entries);
+    assert_eq!(
+        xml,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+<title>SCP Wiki</title>
+<id>https://scpwiki.com/</id>
+<link href="https://scpwiki.com/"/>
+<updated>2023-01-31T12:00:00Z</updated>
+<entry><title>SCP-001</title><id>https://scpwiki.com/scp-001#revision-4</id><link href="https://scpwiki.com/scp-001"/><updated>2023-01-31T12:00:00Z</updated><author><name>aismallard</name></author><summary>Fix &lt;b&gt;typo&lt;/b&gt;</summary></entry>
+<entry><title>Tales &amp; Stories</title><id>https://scpwiki.com/tales-hub#revision-0</id><link href="https://scpwiki.com/tales-hub"/><updated>2023-01-30T12:00:00Z</updated><author><name>Zyn</name></author><summary></summary></entry>
+</feed>
+"#,
+    );
+
+    let xml = build_feed(FeedFormat::Rss, "SCP Wiki", "scpwiki.com", &entries);
+    assert_eq!(
+        xml,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">
+<channel>
+<title>SCP Wiki</title>
+<link>https://scpwiki.com/</link>
+<description>Recent changes on SCP Wiki</description>
+<item><title>SCP-001</title><link>https://scpwiki.com/scp-001</link><guid isPermaLink="false">https://scpwiki.com/scp-001#revision-4</guid><pubDate>Tue, 31 Jan 2023 12:00:00 +0000</pubDate><dc:creator>aismallard</dc:creator><description>Fix &lt;b&gt;typo&lt;/b&gt;</description></item>
+<item><title>Tales &amp; Stories</title><link>https://scpwiki.com/tales-hub</link><guid isPermaLink="false">https://scpwiki.com/tales-hub#revision-0</guid><pubDate>Mon, 30 Jan 2023 12:00:00 +0000</pubDate><dc:creator>Zyn</dc:creator><description></description></item>
+</channel>
+</rss>
+"#,
+    );
+}
@@ -23,8 +23,14 @@ mod prelude {
     pub use super::structs::*;
 }
 
+mod feed;
+mod policy;
+mod robots;
 mod service;
+mod sitemap;
 mod structs;
 
+pub use self::policy::{CommentPolicy, EditPolicy, SlugPolicy};
 pub use self::service::SiteService;
+pub use self::sitemap::SITEMAP_MAX_URLS;
 pub use self::structs::*;
@@ -0,0 +1,221 @@
+/*
+ * services/site/policy.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Per-site restrictions on page slugs and revisions.
+//!
+//! Slug restrictions are applied after normalization, and so can only
+//! further restrict what slugs are permitted, never broaden them.
+
+use super::prelude::*;
+use crate::constants::ANONYMOUS_USER_ID;
+use crate::models::site::Model as SiteModel;
+use regex::Regex;
+
+/// A site's policy on which page slugs are permitted.
+#[derive(Debug, Copy, Clone)]
+pub struct SlugPolicy<'a> {
+    /// Whether slugs may contain a category (i.e. have a `:`).
+    pub allow_categories: bool,
+
+    /// An optional regular expression that slugs must fully match.
+    pub pattern: Option<&'a str>,
+}
+
+impl<'a> SlugPolicy<'a> {
+    /// Checks that the given pattern is a valid regular expression.
+    pub fn validate_pattern(pattern: &str) -> Result<()> {
+        match Regex::new(pattern) {
+            Ok(_) => Ok(()),
+            Err(error) => {
+                tide::log::warn!("Invalid slug pattern '{pattern}': {error}");
+                Err(Error::BadRequest)
+            }
+        }
+    }
+
+    /// Checks a normalized slug against this policy.
+    ///
+    /// If the slug is not permitted, this fails with `Error::BadRequest`.
+    pub fn check(&self, slug: &str) -> Result<()> {
+        if !self.allow_categories && slug.contains(':') {
+            tide::log::error!("Slug '{slug}' has a category, which is not permitted");
+            return Err(Error::BadRequest);
+        }
+
+        if let Some(pattern) = self.pattern {
+            // Anchor so that the pattern must match the entire slug.
+            let regex = Regex::new(&format!("^(?:{pattern})$")).map_err(|error| {
+                tide::log::error!("Invalid slug pattern '{pattern}' on site: {error}");
+                Error::Inconsistent
+            })?;
+
+            if !regex.is_match(slug) {
+                tide::log::error!("Slug '{slug}' does not match pattern '{pattern}'");
+                return Err(Error::BadRequest);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> From<&'a SiteModel> for SlugPolicy<'a> {
+    #[inline]
+    fn from(site: &'a SiteModel) -> Self {
+        SlugPolicy {
+            allow_categories: site.slug_allow_categories,
+            pattern: site.slug_pattern.as_deref(),
+        }
+    }
+}
+
+/// A site's policy on revision comments.
+#[derive(Debug, Copy, Clone)]
+pub struct CommentPolicy {
+    /// Whether every edit, move, or deletion must have a non-empty comment.
+    pub required: bool,
+}
+
+impl CommentPolicy {
+    /// Checks revision comments against this policy.
+    ///
+    /// Comments consisting only of whitespace are considered empty.
+    /// If they are not permitted, this fails with `Error::BadRequest`.
+    pub fn check(&self, comments: &str) -> Result<()> {
+        if self.required && comments.trim().is_empty() {
+            tide::log::error!("Revision comments are required, but none were given");
+            return Err(Error::BadRequest);
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&SiteModel> for CommentPolicy {
+    #[inline]
+    fn from(site: &SiteModel) -> Self {
+        CommentPolicy {
+            required: site.require_revision_comments,
+        }
+    }
+}
+
+/// A site's policy on who may edit pages.
+#[derive(Debug, Copy, Clone)]
+pub struct EditPolicy {
+    /// Whether edits may be made without a user, attributed to the anonymous user.
+    pub allow_anonymous: bool,
+}
+
+impl EditPolicy {
+    /// Determines which user an edit is attributed to.
+    ///
+    /// If no user is given, the edit is anonymous. If that is not permitted,
+    /// this fails with `Error::InvalidAuthentication`.
+    pub fn author(&self, user_id: Option<i64>) -> Result<i64> {
+        match user_id {
+            Some(user_id) => Ok(user_id),
+            None if self.allow_anonymous => Ok(ANONYMOUS_USER_ID),
+            None => {
+                tide::log::error!("Anonymous edits are not permitted on this site");
+                Err(Error::InvalidAuthentication)
+            }
+        }
+    }
+}
+
+impl From<&SiteModel> for EditPolicy {
+    #[inline]
+    fn from(site: &SiteModel) -> Self {
+        EditPolicy {
+            allow_anonymous: site.allow_anonymous_edits,
+        }
+    }
+}
+
+#[test]
+fn slug_policy() {
+    macro_rules! check {
+        ($policy:expr, $slug:expr, $allowed:expr $(,)?) => {
+            assert_eq!(
+                $policy.check($slug).is_ok(),
+                $allowed,
+                "Slug policy result for {:?} doesn't match expected",
+                $slug,
+            );
+        };
+    }
+
+    let policy = SlugPolicy {
+        allow_categories: true,
+        pattern: None,
+    };
+    check!(policy, "start", true);
+    check!(policy, "system:members", true);
+    check!(policy, "deleted:scp-001", true);
+
+    let policy = SlugPolicy {
+        allow_categories: false,
+        pattern: None,
+    };
+    check!(policy, "start", true);
+    check!(policy, "scp-001", true);
+    check!(policy, "system:members", false);
+    check!(policy, "deleted:scp-001", false);
+
+    let policy = SlugPolicy {
+        allow_categories: true,
+        pattern: Some("[a-z-]+"),
+    };
+    check!(policy, "start", true);
+    check!(policy, "scp-001", false);
+    check!(policy, "system:members", false);
+    check!(policy, "some-page-start", true);
+
+    assert!(SlugPolicy::validate_pattern("[a-z]+").is_ok());
+    assert!(SlugPolicy::validate_pattern("[a-z+").is_err());
+}
+
+#[test]
+fn comment_policy() {
+    let policy = CommentPolicy { required: false };
+    assert!(policy.check("").is_ok());
+    assert!(policy.check("Fixed typo").is_ok());
+
+    let policy = CommentPolicy { required: true };
+    assert!(policy.check("").is_err());
+    assert!(policy.check("  \n").is_err());
+    assert!(policy.check("Fixed typo").is_ok());
+}
+
+#[test]
+fn edit_policy() {
+    let policy = EditPolicy {
+        allow_anonymous: true,
+    };
+    assert_eq!(policy.author(Some(4)).unwrap(), 4);
+    assert_eq!(policy.author(None).unwrap(), ANONYMOUS_USER_ID);
+
+    let policy = EditPolicy {
+        allow_anonymous: false,
+    };
+    assert_eq!(policy.author(Some(4)).unwrap(), 4);
+    assert!(policy.author(None).is_err());
+}
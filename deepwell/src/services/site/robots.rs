@@ -0,0 +1,52 @@
+/*
+ * services/site/robots.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Generation of a site's `robots.txt`, which instructs web crawlers.
+
+use std::borrow::Cow;
+
+/// Gets the `robots.txt` contents for a site on `domain`.
+///
+/// If the site has not configured its own, then a default which permits
+/// crawling everything except administrative pages is used.
+pub fn robots_txt<'a>(configured: Option<&'a str>, domain: &str) -> Cow<'a, str> {
+    match configured {
+        Some(contents) => Cow::Borrowed(contents),
+        None => Cow::Owned(format!(
+            "User-agent: *\nDisallow: /admin:\n\nSitemap: https://{domain}/sitemap.xml\n",
+        )),
+    }
+}
+
+#[test]
+fn robots() {
+    assert_eq!(
+        robots_txt(None, "scp-wiki.wikijump.com"),
+        "User-agent: *\nDisallow: /admin:\n\nSitemap: https://scp-wiki.wikijump.com/sitemap.xml\n",
+    );
+
+    assert_eq!(
+        robots_txt(
+            Some("User-agent: *\nDisallow: /\n"),
+            "scp-wiki.wikijump.com"
+        ),
+        "User-agent: *\nDisallow: /\n",
+    );
+}
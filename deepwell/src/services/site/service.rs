@@ -20,12 +20,35 @@
 
 use wikidot_normalize::normalize;
 
+use super::feed::{build_feed, FeedEntry, FEED_MAX_ENTRIES};
 use super::prelude::*;
-use crate::models::sea_orm_active_enums::AliasType;
+use super::robots::robots_txt;
+use super::sitemap::{
+    build_sitemap, build_sitemap_index, sitemap_condition, SitemapEntry,
+};
+use crate::models::page::{self, Model as PageModel};
+use crate::models::page_revision::{self, Model as PageRevisionModel};
+use crate::models::sea_orm_active_enums::{AliasType, AuditAction};
 use crate::models::site::{self, Entity as Site, Model as SiteModel};
 use crate::services::alias::CreateAlias;
-use crate::services::AliasService;
-use crate::utils::validate_locale;
+use crate::services::audit_log::RecordAuditEntry;
+use crate::services::page::{CreatePage, CreatePageOutput};
+use crate::services::site::{SlugPolicy, SITEMAP_MAX_URLS};
+use crate::services::{
+    AliasService, AuditLogService, CategoryService, DomainService, PageRevisionService,
+    PageService, TextService, UserService,
+};
+use crate::utils::canonicalize_locale;
+use crate::web::PageOrder;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use time::OffsetDateTime;
+
+/// The current version of the site export manifest format.
+///
+/// This should be incremented whenever a breaking change is made
+/// to the structure of `SiteExport`.
+pub const SITE_EXPORT_VERSION: u32 = 1;
 
 #[derive(Debug)]
 pub struct SiteService;
@@ -38,7 +61,7 @@ impl SiteService {
             name,
             tagline,
             description,
-            locale,
+            mut locale,
         }: CreateSite,
     ) -> Result<CreateSiteOutput> {
         let txn = ctx.transaction();
@@ -50,7 +73,7 @@ impl SiteService {
         Self::check_conflicts(ctx, &slug, "create").await?;
 
         // Validate locale.
-        validate_locale(&locale)?;
+        locale = canonicalize_locale(&locale)?;
 
         let model = site::ActiveModel {
             slug: Set(slug.clone()),
@@ -68,6 +91,144 @@ impl SiteService {
         })
     }
 
+    /// Counts how many pages in this site were imported from Wikidot versus created natively.
+    pub async fn count_provenance(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<SiteProvenance> {
+        let txn = ctx.transaction();
+        let counts: Vec<(bool, i64)> = page::Entity::find()
+            .select_only()
+            .column(page::Column::FromWikidot)
+            .column_as(page::Column::PageId.count(), "count")
+            .filter(
+                Condition::all()
+                    .add(page::Column::SiteId.eq(site_id))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .group_by(page::Column::FromWikidot)
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        Ok(SiteProvenance::from_counts(&counts))
+    }
+
+    /// Generates a feed of the recent changes to this site's pages.
+    pub async fn recent_changes_feed(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        format: FeedFormat,
+    ) -> Result<String> {
+        tide::log::info!("Generating {format:?} feed for site ID {site_id}");
+
+        let site = Self::get(ctx, Reference::Id(site_id)).await?;
+        let domain = DomainService::domain_for_site(ctx.config(), &site);
+        let changes =
+            PageService::recent_changes(ctx, site_id, None, FEED_MAX_ENTRIES, false)
+                .await?;
+
+        // Look up each author's name once
+        let mut authors = HashMap::new();
+        for change in &changes {
+            if let Entry::Vacant(entry) = authors.entry(change.user_id) {
+                let user = UserService::get(ctx, Reference::Id(change.user_id)).await?;
+                entry.insert(user.name);
+            }
+        }
+
+        let entries: Vec<_> = changes
+            .iter()
+            .map(|change| FeedEntry {
+                title: &change.title,
+                slug: &change.slug,
+                author: &authors[&change.user_id],
+                comments: &change.comments,
+                revision_number: change.revision_number,
+                updated_at: change.created_at,
+            })
+            .collect();
+
+        Ok(build_feed(format, &site.name, &domain, &entries))
+    }
+
+    /// Gets the `robots.txt` to serve for this site.
+    ///
+    /// This is the one configured for the site, or the default if unset.
+    pub async fn get_robots_txt(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<String> {
+        tide::log::info!("Getting robots.txt for site ID {site_id}");
+
+        let site = Self::get(ctx, Reference::Id(site_id)).await?;
+        let domain = DomainService::domain_for_site(ctx.config(), &site);
+        let contents = robots_txt(site.robots_txt.as_deref(), &domain);
+        Ok(contents.into_owned())
+    }
+
+    /// Generates an XML sitemap of this site's extant pages.
+    ///
+    /// If the site has too many pages to fit in one sitemap, then requesting
+    /// without a chunk produces a sitemap index instead, and each chunk
+    /// must be requested separately.
+    pub async fn generate_sitemap(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        chunk: Option<u64>,
+    ) -> Result<String> {
+        tide::log::info!("Generating sitemap for site ID {site_id} (chunk {chunk:?})");
+
+        let txn = ctx.transaction();
+        let now = ctx.now();
+        let site = Self::get(ctx, Reference::Id(site_id)).await?;
+        let domain = DomainService::domain_for_site(ctx.config(), &site);
+
+        let page_count = page::Entity::find()
+            .filter(sitemap_condition(site_id, now))
+            .count(txn)
+            .await?;
+
+        let chunk_count = page_count.div_ceil(SITEMAP_MAX_URLS);
+        let chunk = match chunk {
+            Some(chunk) if chunk < chunk_count.max(1) => chunk,
+            Some(chunk) => {
+                tide::log::error!(
+                    "Sitemap chunk {chunk} out of range, only {chunk_count} present",
+                );
+                return Err(Error::NotFound);
+            }
+            None if chunk_count > 1 => {
+                return Ok(build_sitemap_index(&domain, chunk_count));
+            }
+            None => 0,
+        };
+
+        let pages: Vec<(String, OffsetDateTime, Option<OffsetDateTime>)> =
+            page::Entity::find()
+                .select_only()
+                .column(page::Column::Slug)
+                .column(page::Column::CreatedAt)
+                .column(page::Column::UpdatedAt)
+                .filter(sitemap_condition(site_id, now))
+                .order_by_asc(page::Column::PageId)
+                .offset(chunk * SITEMAP_MAX_URLS)
+                .limit(SITEMAP_MAX_URLS)
+                .into_tuple()
+                .all(txn)
+                .await?;
+
+        let entries: Vec<_> = pages
+            .into_iter()
+            .map(|(slug, created_at, updated_at)| SitemapEntry {
+                slug,
+                last_modified: updated_at.unwrap_or(created_at),
+            })
+            .collect();
+
+        Ok(build_sitemap(&domain, &entries))
+    }
+
     /// Update site information.
     pub async fn update(
         ctx: &ServiceContext<'_>,
@@ -100,13 +261,59 @@ impl SiteService {
         }
 
         if let ProvidedValue::Set(locale) = input.locale {
-            validate_locale(&locale)?;
-            model.locale = Set(locale);
+            model.locale = Set(canonicalize_locale(&locale)?);
+        }
+
+        if let ProvidedValue::Set(mut default_category) = input.default_category {
+            normalize(&mut default_category);
+            if default_category.is_empty() || default_category.contains(':') {
+                tide::log::error!("Invalid default category name: '{default_category}'");
+                return Err(Error::BadRequest);
+            }
+
+            model.default_category = Set(default_category);
+        }
+
+        if let ProvidedValue::Set(allow_categories) = input.slug_allow_categories {
+            model.slug_allow_categories = Set(allow_categories);
+        }
+
+        if let ProvidedValue::Set(slug_pattern) = input.slug_pattern {
+            if let Some(ref pattern) = slug_pattern {
+                SlugPolicy::validate_pattern(pattern)?;
+            }
+
+            model.slug_pattern = Set(slug_pattern);
+        }
+
+        if let ProvidedValue::Set(required) = input.require_revision_comments {
+            model.require_revision_comments = Set(required);
+        }
+
+        if let ProvidedValue::Set(allowed) = input.allow_anonymous_edits {
+            model.allow_anonymous_edits = Set(allowed);
+        }
+
+        if let ProvidedValue::Set(vote_type) = input.vote_type {
+            model.vote_type = Set(vote_type);
+        }
+
+        if let ProvidedValue::Set(retain) = input.retain_compiled_html {
+            model.retain_compiled_html = Set(retain);
+        }
+
+        if let ProvidedValue::Set(disabled_blocks) = input.disabled_blocks {
+            model.disabled_blocks = Set(disabled_blocks);
+        }
+
+        if let ProvidedValue::Set(robots_txt) = input.robots_txt {
+            model.robots_txt = Set(robots_txt);
         }
 
         // Update site
-        model.updated_at = Set(Some(now()));
+        model.updated_at = Set(Some(ctx.now()));
         let new_site = model.update(txn).await?;
+        ctx.invalidate_model::<SiteModel>(new_site.site_id);
 
         // Run verification afterwards if the slug changed
         if site.slug != new_site.slug {
@@ -120,6 +327,55 @@ impl SiteService {
         Ok(new_site)
     }
 
+    /// Deletes a site, recording the user responsible in the audit log.
+    ///
+    /// The site row is kept, but its aliases are removed so their slugs
+    /// can be reused.
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        reference: Reference<'_>,
+        user_id: i64,
+    ) -> Result<SiteModel> {
+        let txn = ctx.transaction();
+        let site = Self::get(ctx, reference).await?;
+        tide::log::info!(
+            "Deleting site with ID {} by user ID {user_id}",
+            site.site_id,
+        );
+
+        if site.deleted_at.is_some() {
+            tide::log::error!("Attempting to delete already-deleted site");
+            return Err(Error::BadRequest);
+        }
+
+        // Delete all site aliases
+        AliasService::delete_all(ctx, AliasType::Site, site.site_id).await?;
+
+        // Set deletion flag
+        let model = site::ActiveModel {
+            site_id: Set(site.site_id),
+            deleted_at: Set(Some(ctx.now())),
+            ..Default::default()
+        };
+        let site = model.update(txn).await?;
+        ctx.invalidate_model::<SiteModel>(site.site_id);
+        AuditLogService::record(
+            ctx,
+            RecordAuditEntry {
+                actor_id: user_id,
+                action: AuditAction::SiteDelete,
+                target_id: site.site_id,
+                detail: serde_json::json!({
+                    "slug": site.slug,
+                    "name": site.name,
+                }),
+            },
+        )
+        .await?;
+
+        Ok(site)
+    }
+
     /// Updates the slug for a site, leaving behind an alias.
     ///
     /// No alias row checks are performed because of a dependency order requiring
@@ -202,7 +458,12 @@ impl SiteService {
         }
 
         let site = match reference {
-            Reference::Id(id) => Site::find_by_id(id).one(txn).await?,
+            Reference::Id(id) => {
+                ctx.cached_model(id, move || async move {
+                    Site::find_by_id(id).one(txn).await.map_err(Error::from)
+                })
+                .await?
+            }
             Reference::Slug(slug) => {
                 Site::find()
                     .filter(
@@ -247,6 +508,213 @@ impl SiteService {
         }
     }
 
+    /// Exports the contents of a site into a portable manifest.
+    ///
+    /// This currently includes the site's categories, and all extant pages
+    /// with their revisions. If `all_revisions` is false, then only the latest
+    /// revision of each page is included.
+    pub async fn export(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        all_revisions: bool,
+    ) -> Result<SiteExport> {
+        tide::log::info!(
+            "Exporting site ID {site_id} ({} revisions)",
+            if all_revisions { "all" } else { "latest" },
+        );
+
+        let site = Self::get(ctx, Reference::Id(site_id)).await?;
+        let (categories, pages) = try_join!(
+            CategoryService::get_all(ctx, site_id),
+            PageService::get_all(ctx, site_id, None, Some(false), PageOrder::default()),
+        )?;
+
+        let category_slugs = categories
+            .iter()
+            .map(|category| (category.category_id, category.slug.as_str()))
+            .collect::<HashMap<_, _>>();
+
+        let mut texts = BTreeMap::new();
+        let mut page_exports = Vec::with_capacity(pages.len());
+
+        for page in pages {
+            let revisions = if all_revisions {
+                PageRevisionService::get_all(ctx, site_id, page.page_id).await?
+            } else {
+                vec![PageRevisionService::get_latest(ctx, site_id, page.page_id).await?]
+            };
+
+            let mut revision_exports = Vec::with_capacity(revisions.len());
+            for revision in revisions {
+                let wikitext_hash = hex::encode(&revision.wikitext_hash);
+                if !texts.contains_key(&wikitext_hash) {
+                    let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+                    texts.insert(wikitext_hash.clone(), wikitext);
+                }
+
+                revision_exports.push(export_revision(revision, wikitext_hash));
+            }
+
+            let category = match category_slugs.get(&page.page_category_id) {
+                Some(slug) => str!(*slug),
+                None => {
+                    tide::log::error!(
+                        "Page ID {} has category ID {} not in site ID {}",
+                        page.page_id,
+                        page.page_category_id,
+                        site_id,
+                    );
+
+                    return Err(Error::Inconsistent);
+                }
+            };
+
+            page_exports.push(PageExport {
+                page_id: page.page_id,
+                created_at: page.created_at,
+                updated_at: page.updated_at,
+                category,
+                slug: page.slug,
+                locale: page.locale,
+                publish_at: page.publish_at,
+                expires_at: page.expires_at,
+                revisions: revision_exports,
+            });
+        }
+
+        Ok(SiteExport {
+            version: SITE_EXPORT_VERSION,
+            site: SiteExportInfo {
+                slug: site.slug,
+                name: site.name,
+                tagline: site.tagline,
+                description: site.description,
+                locale: site.locale,
+                default_page: site.default_page,
+            },
+            categories: categories
+                .into_iter()
+                .map(|category| category.slug)
+                .collect(),
+            pages: page_exports,
+            texts,
+        })
+    }
+
+    /// Imports the contents of an export manifest into an existing site.
+    ///
+    /// Each page is created from its latest revision in the manifest,
+    /// preserving creation timestamps and authors where possible. If the
+    /// author does not exist on this instance, `user_id` is used instead.
+    ///
+    /// Pages whose slugs already exist on the target site are not overwritten,
+    /// but are instead skipped and listed in the output's `conflicts` field.
+    pub async fn import(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: i64,
+        manifest: SiteExport,
+    ) -> Result<ImportSiteExportOutput> {
+        let txn = ctx.transaction();
+
+        tide::log::info!(
+            "Importing {} pages from site export '{}' into site ID {}",
+            manifest.pages.len(),
+            manifest.site.slug,
+            site_id,
+        );
+
+        check_export_version(manifest.version)?;
+
+        // Ensure the target site exists
+        Self::get(ctx, Reference::Id(site_id)).await?;
+
+        for category in &manifest.categories {
+            CategoryService::get_or_create(ctx, site_id, category).await?;
+        }
+
+        let mut output = ImportSiteExportOutput::default();
+        for page in manifest.pages {
+            let (revision, wikitext) = import_source(&manifest.texts, &page)?;
+
+            if PageService::get_optional(
+                ctx,
+                site_id,
+                Reference::from(page.slug.as_str()),
+            )
+            .await?
+            .is_some()
+            {
+                tide::log::warn!(
+                    "Page with slug '{}' already exists in site ID {}, skipping",
+                    page.slug,
+                    site_id,
+                );
+
+                output.conflicts.push(page.slug);
+                continue;
+            }
+
+            let author_id =
+                if UserService::exists(ctx, Reference::Id(revision.user_id)).await? {
+                    revision.user_id
+                } else {
+                    user_id
+                };
+
+            // Imported pages are not announced in recent changes
+            let (
+                CreatePageOutput {
+                    page_id,
+                    slug,
+                    revision_id,
+                    ..
+                },
+                _,
+            ) = PageService::create(
+                ctx,
+                CreatePage {
+                    site_id,
+                    wikitext,
+                    title: revision.title.clone(),
+                    alt_title: revision.alt_title.clone(),
+                    slug: page.slug.clone(),
+                    revision_comments: revision.comments.clone(),
+                    user_id: author_id,
+                    locale: page.locale.clone(),
+                    publish_at: page.publish_at,
+                    expires_at: page.expires_at,
+                    bypass_filter: true,
+                    reject_on_parser_error: false,
+                },
+            )
+            .await?;
+
+            // Restore original metadata, which is not set during creation.
+            let model = page::ActiveModel {
+                page_id: Set(page_id),
+                created_at: Set(page.created_at),
+                updated_at: Set(page.updated_at),
+                ..Default::default()
+            };
+            model.update(txn).await?;
+            ctx.invalidate_model::<PageModel>(page_id);
+
+            let model = page_revision::ActiveModel {
+                revision_id: Set(revision_id),
+                created_at: Set(revision.created_at),
+                from_wikidot: Set(revision.from_wikidot),
+                tags: Set(revision.tags.clone()),
+                ..Default::default()
+            };
+            model.update(txn).await?;
+
+            output.imported.push(slug);
+        }
+
+        Ok(output)
+    }
+
     /// Checks to see if a site already exists at the slug specified.
     ///
     /// If so, this method fails with `Error::Conflict`. Otherwise it returns nothing.
@@ -280,3 +748,537 @@ impl SiteService {
         }
     }
 }
+
+/// Gets the most recent revision of an exported page.
+fn latest_revision(page: &PageExport) -> Option<&PageRevisionExport> {
+    page.revisions
+        .iter()
+        .max_by_key(|revision| revision.revision_number)
+}
+
+/// Ensures a site export manifest is of a version this server can import.
+fn check_export_version(version: u32) -> Result<()> {
+    if version != SITE_EXPORT_VERSION {
+        tide::log::error!(
+            "Site export has version {version}, but only {SITE_EXPORT_VERSION} is supported",
+        );
+
+        return Err(Error::BadRequest);
+    }
+
+    Ok(())
+}
+
+/// Gets the revision and wikitext an exported page should be imported from.
+///
+/// This is the page's latest revision, and its wikitext must be present
+/// in the manifest's texts.
+fn import_source<'a>(
+    texts: &BTreeMap<String, String>,
+    page: &'a PageExport,
+) -> Result<(&'a PageRevisionExport, String)> {
+    let revision = match latest_revision(page) {
+        Some(revision) => revision,
+        None => {
+            tide::log::error!("Exported page '{}' has no revisions", page.slug);
+            return Err(Error::BadRequest);
+        }
+    };
+
+    match texts.get(&revision.wikitext_hash) {
+        Some(wikitext) => Ok((revision, wikitext.clone())),
+        None => {
+            tide::log::error!(
+                "Exported page '{}' references missing text {}",
+                page.slug,
+                revision.wikitext_hash,
+            );
+
+            Err(Error::BadRequest)
+        }
+    }
+}
+
+fn export_revision(
+    PageRevisionModel {
+        revision_number,
+        revision_type,
+        created_at,
+        user_id,
+        from_wikidot,
+        comments,
+        title,
+        alt_title,
+        slug,
+        tags,
+        ..
+    }: PageRevisionModel,
+    wikitext_hash: String,
+) -> PageRevisionExport {
+    PageRevisionExport {
+        revision_number,
+        revision_type,
+        created_at,
+        user_id,
+        from_wikidot,
+        wikitext_hash,
+        comments,
+        title,
+        alt_title,
+        slug,
+        tags,
+    }
+}
+
+#[test]
+fn provenance_counts() {
+    assert_eq!(SiteProvenance::from_counts(&[]), SiteProvenance::default());
+    assert_eq!(
+        SiteProvenance::from_counts(&[(true, 12), (false, 3)]),
+        SiteProvenance {
+            imported_pages: 12,
+            native_pages: 3,
+        },
+    );
+    assert_eq!(
+        SiteProvenance::from_counts(&[(false, 7)]),
+        SiteProvenance {
+            imported_pages: 0,
+            native_pages: 7,
+        },
+    );
+}
+
+#[test]
+fn export_two_pages() {
+    use crate::api::build_test_server_state;
+    use crate::hash::TextHashAlgorithm;
+    use crate::models::page_category::Model as PageCategoryModel;
+    use crate::models::sea_orm_active_enums::{PageRevisionType, VoteType};
+    use crate::models::text::Model as TextModel;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait};
+
+    let created_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let wikitext = "**Item #:** SCP-001";
+    let wikitext_hash = TextHashAlgorithm::K12.hash(wikitext.as_bytes()).to_vec();
+    let site = SiteModel {
+        site_id: 1,
+        created_at,
+        updated_at: None,
+        deleted_at: None,
+        from_wikidot: false,
+        slug: str!("test"),
+        name: str!("Test Wiki"),
+        tagline: str!("Secure, Contain, Protect"),
+        description: String::new(),
+        locale: str!("en"),
+        default_page: str!("start"),
+        default_category: str!("_default"),
+        slug_allow_categories: true,
+        slug_pattern: None,
+        require_revision_comments: false,
+        allow_anonymous_edits: false,
+        vote_type: VoteType::UpsDowns,
+        retain_compiled_html: false,
+        disabled_blocks: vec![],
+        robots_txt: None,
+        custom_domain: None,
+    };
+    let category = |category_id, slug| PageCategoryModel {
+        category_id,
+        created_at,
+        updated_at: None,
+        site_id: 1,
+        slug: str!(slug),
+    };
+    let page = |page_id, page_category_id, slug| PageModel {
+        page_id,
+        created_at,
+        updated_at: None,
+        deleted_at: None,
+        deletion_reason: None,
+        from_wikidot: false,
+        site_id: 1,
+        page_category_id,
+        slug: str!(slug),
+        locale: None,
+        discussion_thread_id: None,
+        keywords: vec![],
+        publish_at: None,
+        expires_at: None,
+    };
+    let revision = |page_id: i64, revision_number: i32, title: &str| PageRevisionModel {
+        revision_id: page_id * 100 + i64::from(revision_number),
+        revision_type: PageRevisionType::Regular,
+        created_at,
+        revision_number,
+        page_id,
+        site_id: 1,
+        user_id: 4,
+        from_wikidot: false,
+        changes: vec![str!("wikitext")],
+        wikitext_hash: wikitext_hash.clone(),
+        compiled_hash: Some(vec![0xcd; 16]),
+        compiled_at: created_at,
+        compiled_generator: str!("ftml"),
+        comments: String::new(),
+        hidden: vec![],
+        title: str!(title),
+        alt_title: None,
+        slug: str!("scp-001"),
+        tags: vec![str!("scp")],
+    };
+
+    // Results for each query, in the order SiteService::export() makes them.
+    // Both pages have the same wikitext, so it is only fetched once.
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[site]])
+        .append_query_results([[category(1, "_default"), category(2, "system")]])
+        .append_query_results([[page(10, 1, "scp-001"), page(11, 2, "system:join")]])
+        .append_query_results([[revision(10, 3, "SCP-001")]])
+        .append_query_results([[TextModel {
+            hash: wikitext_hash.clone(),
+            hash_algorithm: str!(TextHashAlgorithm::K12.name()),
+            contents: str!(wikitext),
+        }]])
+        .append_query_results([[revision(11, 0, "Join")]])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let manifest = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let manifest = SiteService::export(&ctx, 1, false).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(manifest)
+    })
+    .expect("Unable to export site");
+
+    assert_eq!(manifest.version, SITE_EXPORT_VERSION);
+    assert_eq!(manifest.site.slug, "test");
+    assert_eq!(manifest.site.tagline, "Secure, Contain, Protect");
+    assert_eq!(manifest.categories, vec![str!("_default"), str!("system")]);
+
+    // Pages have their categories by slug, and their latest revision
+    assert_eq!(manifest.pages.len(), 2);
+    assert_eq!(manifest.pages[0].slug, "scp-001");
+    assert_eq!(manifest.pages[0].category, "_default");
+    assert_eq!(manifest.pages[1].slug, "system:join");
+    assert_eq!(manifest.pages[1].category, "system");
+
+    let hash_hex = hex::encode(&wikitext_hash);
+    assert_eq!(
+        manifest.pages[0].revisions,
+        vec![PageRevisionExport {
+            revision_number: 3,
+            revision_type: PageRevisionType::Regular,
+            created_at,
+            user_id: 4,
+            from_wikidot: false,
+            wikitext_hash: hash_hex.clone(),
+            comments: String::new(),
+            title: str!("SCP-001"),
+            alt_title: None,
+            slug: str!("scp-001"),
+            tags: vec![str!("scp")],
+        }],
+    );
+    assert_eq!(manifest.pages[1].revisions[0].title, "Join");
+
+    // The shared wikitext is included once, keyed by its hash
+    assert_eq!(manifest.texts.len(), 1);
+    assert_eq!(manifest.texts[&hash_hex], wikitext);
+
+    // Only the wikitext is portable, compiled output is not exported
+    let json = serde_json::to_value(&manifest).expect("Unable to serialize manifest");
+    let revision = &json["pages"][0]["revisions"][0];
+    assert!(
+        revision.get("compiledHash").is_none(),
+        "Compiled hash exported"
+    );
+    assert!(revision.get("revisionId").is_none(), "Database ID exported");
+}
+
+#[test]
+fn export_import_round_trip() {
+    use crate::api::build_test_server_state;
+    use crate::hash::TextHashAlgorithm;
+    use crate::models::page_connection::Model as PageConnectionModel;
+    use crate::models::page_connection_missing::Model as PageConnectionMissingModel;
+    use crate::models::page_link::Model as PageLinkModel;
+    use crate::models::text::Model as TextModel;
+    use crate::services::fixtures;
+    use async_std::task;
+    use sea_orm::{
+        DatabaseBackend, MockDatabase, MockExecResult, TransactionTrait, Value,
+    };
+    use std::sync::Arc;
+
+    let created_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let wikitext = "**Item #:** SCP-001";
+    let wikitext_hash = TextHashAlgorithm::K12.hash(wikitext.as_bytes()).to_vec();
+    let revision = |page_id, revision_id, slug: &str| PageRevisionModel {
+        created_at,
+        wikitext_hash: wikitext_hash.clone(),
+        title: str!("SCP-001"),
+        slug: str!(slug),
+        tags: vec![str!("scp")],
+        ..fixtures::page_revision(1, page_id, revision_id, 0)
+    };
+    let page = |page_id, slug| PageModel {
+        created_at,
+        ..fixtures::page(1, page_id, slug)
+    };
+
+    // Export two pages sharing the same wikitext
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[fixtures::site(1)]])
+        .append_query_results([[fixtures::page_category(1, 1, "_default")]])
+        .append_query_results([[page(10, "scp-001"), page(11, "start")]])
+        .append_query_results([[revision(10, 100, "scp-001")]])
+        .append_query_results([[TextModel {
+            hash: wikitext_hash.clone(),
+            hash_algorithm: str!(TextHashAlgorithm::K12.name()),
+            contents: str!(wikitext),
+        }]])
+        .append_query_results([[revision(11, 110, "start")]])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let manifest = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let manifest = SiteService::export(&ctx, 1, false).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(manifest)
+    })
+    .expect("Unable to export site");
+
+    // Import into another site, where 'start' already exists.
+    // Results for each query, in the order SiteService::import() makes them.
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[fixtures::site(2)]])
+        .append_query_results([[fixtures::page_category(2, 3, "_default")]])
+        // Page 'scp-001' doesn't exist yet, and its author does
+        .append_query_results([Vec::<PageModel>::new()])
+        .append_query_results([[fixtures::user(1, "Alice")]])
+        // PageService::create()
+        .append_query_results([Vec::<PageModel>::new()])
+        .append_query_results([[fixtures::page_category(2, 3, "_default")]])
+        .append_query_results([[fixtures::page(2, 20, "scp-001")]])
+        .append_query_results([Vec::<TextModel>::new()])
+        .append_query_results([[fixtures::page(2, 20, "scp-001")]])
+        .append_query_results([[BTreeMap::from([("sum", Value::BigInt(None))])]])
+        .append_query_results([Vec::<PageModel>::new()])
+        .append_query_results([Vec::<TextModel>::new()])
+        .append_query_results([Vec::<PageConnectionModel>::new()])
+        .append_query_results([Vec::<PageConnectionMissingModel>::new()])
+        .append_query_results([Vec::<PageLinkModel>::new()])
+        .append_query_results([[fixtures::page(2, 20, "scp-001")]])
+        .append_query_results([Vec::<PageConnectionModel>::new()])
+        .append_query_results([Vec::<PageConnectionModel>::new()])
+        .append_query_results([[fixtures::page_revision(2, 20, 200, 0)]])
+        .append_query_results([[fixtures::page_revision(2, 20, 200, 0)]])
+        // Restoring the original metadata
+        .append_query_results([[fixtures::page(2, 20, "scp-001")]])
+        .append_query_results([[fixtures::page_revision(2, 20, 200, 0)]])
+        // Page 'start' already exists
+        .append_query_results([[fixtures::page(2, 21, "start")]])
+        // Inserting the wikitext and compiled HTML
+        .append_exec_results((0..2).map(|_| MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }))
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let output = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let output = SiteService::import(&ctx, 2, 1, manifest).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(output)
+    })
+    .expect("Unable to import site");
+
+    // Conflicting pages are reported rather than overwritten
+    assert_eq!(output.imported, vec![str!("scp-001")]);
+    assert_eq!(output.conflicts, vec![str!("start")]);
+
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    assert_eq!(
+        log.matches(r#"INSERT INTO \"page\" "#).count(),
+        1,
+        "Conflicting page created: {log}",
+    );
+    assert_eq!(
+        log.matches(r#"UPDATE \"page\" SET \"created_at\""#).count(),
+        1,
+        "Conflicting page updated: {log}",
+    );
+
+    // The exported wikitext and metadata are what's imported
+    let value = |value: Value| format!("{value:?}");
+    assert!(
+        log.contains(&value(Value::from(wikitext))),
+        "Wikitext not imported: {log}",
+    );
+    assert!(
+        log.contains(&value(Value::from(created_at))),
+        "Creation time not restored: {log}",
+    );
+    assert!(
+        log.contains(&value(Value::from(vec![str!("scp")]))),
+        "Tags not restored: {log}",
+    );
+}
+
+#[test]
+fn import_sources() {
+    use crate::models::sea_orm_active_enums::PageRevisionType;
+
+    fn revision(revision_number: i32, wikitext_hash: &str) -> PageRevisionExport {
+        PageRevisionExport {
+            revision_number,
+            revision_type: PageRevisionType::Regular,
+            created_at: OffsetDateTime::UNIX_EPOCH,
+            user_id: 1,
+            from_wikidot: false,
+            wikitext_hash: str!(wikitext_hash),
+            comments: String::new(),
+            title: format!("Revision {revision_number}"),
+            alt_title: None,
+            slug: str!("start"),
+            tags: vec![],
+        }
+    }
+
+    let texts: BTreeMap<String, String> =
+        [(str!("aa"), str!("first")), (str!("bb"), str!("second"))]
+            .into_iter()
+            .collect();
+
+    let mut page = PageExport {
+        page_id: 1,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        updated_at: None,
+        category: str!("_default"),
+        slug: str!("start"),
+        locale: None,
+        publish_at: None,
+        expires_at: None,
+        revisions: vec![revision(1, "bb"), revision(0, "aa")],
+    };
+
+    // Only supported manifest versions are imported
+    assert!(check_export_version(SITE_EXPORT_VERSION).is_ok());
+    assert!(matches!(
+        check_export_version(SITE_EXPORT_VERSION + 1),
+        Err(Error::BadRequest),
+    ));
+
+    // Pages are imported from their latest revision
+    let (latest, wikitext) =
+        import_source(&texts, &page).expect("Unable to get import source");
+    assert_eq!(latest.revision_number, 1);
+    assert_eq!(latest.title, "Revision 1");
+    assert_eq!(wikitext, "second");
+
+    // Revisions referring to texts not in the manifest are rejected
+    page.revisions.push(revision(2, "cc"));
+    assert!(matches!(
+        import_source(&texts, &page),
+        Err(Error::BadRequest),
+    ));
+
+    // As are pages without any revisions
+    page.revisions.clear();
+    assert!(matches!(
+        import_source(&texts, &page),
+        Err(Error::BadRequest),
+    ));
+}
+
+#[test]
+fn delete_audited() {
+    use crate::api::build_test_server_state;
+    use crate::models::audit_log::Model as AuditLogModel;
+    use crate::models::sea_orm_active_enums::VoteType;
+    use async_std::task;
+    use sea_orm::{
+        DatabaseBackend, MockDatabase, MockExecResult, TransactionTrait, Value,
+    };
+    use std::sync::Arc;
+
+    let created_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let site = SiteModel {
+        site_id: 1,
+        created_at,
+        updated_at: None,
+        deleted_at: None,
+        from_wikidot: false,
+        slug: str!("test"),
+        name: str!("Test Wiki"),
+        tagline: String::new(),
+        description: String::new(),
+        locale: str!("en"),
+        default_page: str!("start"),
+        default_category: str!("_default"),
+        slug_allow_categories: true,
+        slug_pattern: None,
+        require_revision_comments: false,
+        allow_anonymous_edits: false,
+        vote_type: VoteType::UpsDowns,
+        retain_compiled_html: false,
+        disabled_blocks: vec![],
+        robots_txt: None,
+        custom_domain: None,
+    };
+
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        // The site, then the site after deletion
+        .append_query_results([[site.clone()]])
+        .append_query_results([[SiteModel {
+            deleted_at: Some(created_at),
+            ..site
+        }]])
+        // The audit log entry
+        .append_query_results([[AuditLogModel {
+            audit_log_id: 1,
+            created_at,
+            actor_id: 4,
+            action: AuditAction::SiteDelete,
+            target_id: 1,
+            detail: serde_json::json!({}),
+        }]])
+        // The removed site aliases
+        .append_exec_results([MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 2,
+        }])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        SiteService::delete(&ctx, Reference::Id(1), 4).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(())
+    })
+    .expect("Unable to delete site");
+
+    // The deletion is recorded with the user responsible
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    let actor = format!("{:?}", Value::from(4i64));
+    assert_eq!(
+        log.matches("INSERT INTO").count(),
+        1,
+        "No audit entry: {log}"
+    );
+    assert!(log.contains("site-delete"), "Audit action missing: {log}");
+    assert!(log.contains(&actor), "Audit actor missing: {log}");
+}
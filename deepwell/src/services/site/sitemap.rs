@@ -0,0 +1,200 @@
+/*
+ * services/site/sitemap.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Generation of XML sitemaps, as described by the [sitemaps protocol].
+//!
+//! A single sitemap may have at most 50,000 URLs, so larger sites are
+//! split into several chunks which are listed in a sitemap index.
+//!
+//! [sitemaps protocol]: https://www.sitemaps.org/protocol.html
+
+use crate::models::page;
+use crate::services::page::{published_condition, unexpired_condition};
+use crate::utils::escape_xml;
+use sea_orm::{ColumnTrait, Condition};
+use time::OffsetDateTime;
+
+/// The maximum number of URLs permitted in a single sitemap.
+pub const SITEMAP_MAX_URLS: u64 = 50_000;
+
+/// Categories whose pages are never listed in sitemaps.
+const EXCLUDED_CATEGORIES: [&str; 1] = ["admin"];
+
+/// A page to be listed in a sitemap.
+#[derive(Debug, Clone)]
+pub struct SitemapEntry {
+    pub slug: String,
+    pub last_modified: OffsetDateTime,
+}
+
+/// Builds the condition for which pages in a site are listed in its sitemap.
+///
+/// This excludes deleted pages, those not yet published or already expired
+/// at `now`, and those in administrative categories.
+pub fn sitemap_condition(site_id: i64, now: OffsetDateTime) -> Condition {
+    let mut condition = Condition::all()
+        .add(page::Column::SiteId.eq(site_id))
+        .add(page::Column::DeletedAt.is_null())
+        .add(published_condition(now))
+        .add(unexpired_condition(now));
+
+    for category in EXCLUDED_CATEGORIES {
+        condition = condition.add(page::Column::Slug.not_like(&format!("{category}:%")));
+    }
+
+    condition
+}
+
+/// Builds a sitemap listing each of the given pages on `domain`.
+pub fn build_sitemap(domain: &str, entries: &[SitemapEntry]) -> String {
+    let mut xml = String::new();
+    xml.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push('\n');
+    xml.push_str(r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+    xml.push('\n');
+
+    for SitemapEntry {
+        slug,
+        last_modified,
+    } in entries
+    {
+        str_writeln!(
+            &mut xml,
+            "<url><loc>https://{}/{}</loc><lastmod>{}</lastmod></url>",
+            escape_xml(domain),
+            escape_xml(slug),
+            w3c_date(*last_modified),
+        );
+    }
+
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// Builds a sitemap index listing each chunk's sitemap on `domain`.
+pub fn build_sitemap_index(domain: &str, chunks: u64) -> String {
+    let mut xml = String::new();
+    xml.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push('\n');
+    xml.push_str(r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+    xml.push('\n');
+
+    for chunk in 0..chunks {
+        str_writeln!(
+            &mut xml,
+            "<sitemap><loc>https://{}/sitemap.xml?chunk={}</loc></sitemap>",
+            escape_xml(domain),
+            chunk,
+        );
+    }
+
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+/// Formats the date in the W3C format used by sitemaps, e.g. `2023-01-31`.
+fn w3c_date(timestamp: OffsetDateTime) -> String {
+    format!(
+        "{:04}-{:02}-{:02}",
+        timestamp.year(),
+        u8::from(timestamp.month()),
+        timestamp.day(),
+    )
+}
+
+#[test]
+fn sitemap() {
+    // 2023-01-31 12:00 UTC
+    let timestamp = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+
+    macro_rules! entry {
+        ($slug:expr, $last_modified:expr $(,)?) => {
+            SitemapEntry {
+                slug: str!($slug),
+                last_modified: $last_modified,
+            }
+        };
+    }
+
+    let entries = [
+        entry!("start", timestamp),
+        entry!("scp-001", timestamp),
+        entry!("system:recent-changes", timestamp),
+        entry!("component:license-box", timestamp),
+    ];
+
+    let xml = build_sitemap("scp-wiki.wikijump.com", &entries);
+    assert_eq!(
+        xml,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+<url><loc>https://scp-wiki.wikijump.com/start</loc><lastmod>2023-01-31</lastmod></url>
+<url><loc>https://scp-wiki.wikijump.com/scp-001</loc><lastmod>2023-01-31</lastmod></url>
+<url><loc>https://scp-wiki.wikijump.com/system:recent-changes</loc><lastmod>2023-01-31</lastmod></url>
+<url><loc>https://scp-wiki.wikijump.com/component:license-box</loc><lastmod>2023-01-31</lastmod></url>
+</urlset>
+"#,
+    );
+
+    let xml = build_sitemap_index("scpwiki.com", 2);
+    assert_eq!(
+        xml,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+<sitemap><loc>https://scpwiki.com/sitemap.xml?chunk=0</loc></sitemap>
+<sitemap><loc>https://scpwiki.com/sitemap.xml?chunk=1</loc></sitemap>
+</sitemapindex>
+"#,
+    );
+}
+
+#[test]
+fn sitemap_pages() {
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait};
+
+    let sql = page::Entity::find()
+        .filter(sitemap_condition(
+            4,
+            OffsetDateTime::from_unix_timestamp(1675166400).unwrap(),
+        ))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains(r#""page"."site_id" = 4"#),
+        "Site not filtered: {sql}"
+    );
+    assert!(
+        sql.contains(r#""page"."deleted_at" IS NULL"#),
+        "Deleted pages not excluded: {sql}",
+    );
+    assert!(
+        sql.contains(r#""page"."slug" NOT LIKE 'admin:%'"#),
+        "Admin pages not excluded: {sql}",
+    );
+    assert!(
+        sql.contains(r#""page"."publish_at" IS NULL OR "page"."publish_at" <="#),
+        "Scheduled pages not excluded: {sql}",
+    );
+    assert!(
+        sql.contains(r#""page"."expires_at" IS NULL OR "page"."expires_at" >"#),
+        "Expired pages not excluded: {sql}",
+    );
+}
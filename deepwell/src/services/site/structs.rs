@@ -19,9 +19,12 @@
  */
 
 use crate::models::alias::Model as AliasModel;
+use crate::models::sea_orm_active_enums::{PageRevisionType, VoteType};
 use crate::models::site::Model as SiteModel;
 use crate::models::site_domain::Model as SiteDomainModel;
-use crate::web::{ProvidedValue, Reference};
+use crate::web::{check_not_blank, ProvidedValue, Reference, Validate, ValidationResult};
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug)]
 pub struct CreateSite {
@@ -32,6 +35,15 @@ pub struct CreateSite {
     pub locale: String,
 }
 
+impl Validate for CreateSite {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("slug", &self.slug)?;
+        check_not_blank("name", &self.name)?;
+        check_not_blank("locale", &self.locale)?;
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateSiteOutput {
@@ -45,6 +57,47 @@ pub struct GetSite<'a> {
     pub site: Reference<'a>,
 }
 
+impl Validate for GetSite<'_> {}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GetSitemap<'a> {
+    pub site: Reference<'a>,
+
+    /// Which chunk of the sitemap to get, for sites with many pages.
+    #[serde(default)]
+    pub chunk: Option<u64>,
+}
+
+impl Validate for GetSitemap<'_> {}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GetRecentChangesFeed<'a> {
+    pub site: Reference<'a>,
+    pub format: FeedFormat,
+}
+
+impl Validate for GetRecentChangesFeed<'_> {}
+
+/// Which syndication format to produce a feed in.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    /// The MIME type for feeds in this format.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml",
+            FeedFormat::Rss => "application/rss+xml",
+        }
+    }
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetSiteOutput {
@@ -64,6 +117,33 @@ pub struct UpdateSite<'a> {
     pub body: UpdateSiteBody,
 }
 
+impl Validate for UpdateSite<'_> {
+    fn validate(&self) -> ValidationResult {
+        if let Some(name) = self.body.name.to_option() {
+            check_not_blank("name", name)?;
+        }
+
+        if let Some(slug) = self.body.slug.to_option() {
+            check_not_blank("slug", slug)?;
+        }
+
+        if let Some(locale) = self.body.locale.to_option() {
+            check_not_blank("locale", locale)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteSite<'a> {
+    pub site: Reference<'a>,
+    pub user_id: i64,
+}
+
+impl Validate for DeleteSite<'_> {}
+
 #[derive(Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase", default)]
 pub struct UpdateSiteBody {
@@ -72,4 +152,131 @@ pub struct UpdateSiteBody {
     pub tagline: ProvidedValue<String>,
     pub description: ProvidedValue<String>,
     pub locale: ProvidedValue<String>,
+    pub default_category: ProvidedValue<String>,
+    pub slug_allow_categories: ProvidedValue<bool>,
+    pub slug_pattern: ProvidedValue<Option<String>>,
+    pub require_revision_comments: ProvidedValue<bool>,
+    pub allow_anonymous_edits: ProvidedValue<bool>,
+    pub vote_type: ProvidedValue<VoteType>,
+    pub retain_compiled_html: ProvidedValue<bool>,
+    pub disabled_blocks: ProvidedValue<Vec<String>>,
+    pub robots_txt: ProvidedValue<Option<String>>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSite<'a> {
+    pub site: Reference<'a>,
+
+    #[serde(default)]
+    pub all_revisions: bool,
+}
+
+impl Validate for ExportSite<'_> {}
+
+/// A portable representation of a site's contents.
+///
+/// Text bodies (i.e. wikitext) are stored once in `texts`,
+/// keyed by their hex-encoded hash. Revisions refer to these
+/// by hash rather than embedding their contents directly.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteExport {
+    pub version: u32,
+    pub site: SiteExportInfo,
+    pub categories: Vec<String>,
+    pub pages: Vec<PageExport>,
+    pub texts: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteExportInfo {
+    pub slug: String,
+    pub name: String,
+    pub tagline: String,
+    pub description: String,
+    pub locale: String,
+    pub default_page: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PageExport {
+    pub page_id: i64,
+    pub created_at: OffsetDateTime,
+    pub updated_at: Option<OffsetDateTime>,
+    pub category: String,
+    pub slug: String,
+
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    #[serde(default)]
+    pub publish_at: Option<OffsetDateTime>,
+
+    #[serde(default)]
+    pub expires_at: Option<OffsetDateTime>,
+
+    pub revisions: Vec<PageRevisionExport>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PageRevisionExport {
+    pub revision_number: i32,
+    pub revision_type: PageRevisionType,
+    pub created_at: OffsetDateTime,
+    pub user_id: i64,
+    pub from_wikidot: bool,
+    pub wikitext_hash: String,
+    pub comments: String,
+    pub title: String,
+    pub alt_title: Option<String>,
+    pub slug: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSiteExport<'a> {
+    pub site: Reference<'a>,
+    pub user_id: i64,
+    pub manifest: SiteExport,
+}
+
+impl Validate for ImportSiteExport<'_> {}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSiteExportOutput {
+    pub imported: Vec<String>,
+    pub conflicts: Vec<String>,
+}
+
+/// How many extant pages in a site were imported from Wikidot.
+#[derive(Serialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteProvenance {
+    pub imported_pages: u64,
+    pub native_pages: u64,
+}
+
+impl SiteProvenance {
+    /// Builds the report from `(from_wikidot, count)` pairs.
+    pub fn from_counts(counts: &[(bool, i64)]) -> Self {
+        let mut provenance = SiteProvenance::default();
+
+        for &(from_wikidot, count) in counts {
+            let count = u64::try_from(count).expect("Negative page count");
+
+            if from_wikidot {
+                provenance.imported_pages += count;
+            } else {
+                provenance.native_pages += count;
+            }
+        }
+
+        provenance
+    }
 }
@@ -0,0 +1,97 @@
+/*
+ * services/site_member/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Service for the roles users have on each site.
+//!
+//! Users without a role are not members of the site, but may still
+//! view and edit it as permitted by the site's policies. The permissions
+//! each role grants are determined by `UserPermissions::for_user()`.
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::SiteRole;
+use crate::models::site_member::{self, Entity as SiteMember};
+
+#[derive(Debug)]
+pub struct SiteMemberService;
+
+impl SiteMemberService {
+    /// Gets the role a user has on a site, if they are a member.
+    pub async fn get_role(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: i64,
+    ) -> Result<Option<SiteRole>> {
+        let txn = ctx.transaction();
+        let role = SiteMember::find_by_id((site_id, user_id))
+            .one(txn)
+            .await?
+            .map(|member| member.role);
+
+        Ok(role)
+    }
+
+    /// Sets the role a user has on a site, or removes them from it.
+    pub async fn set_role(
+        ctx: &ServiceContext<'_>,
+        SetSiteRole {
+            site_id,
+            user_id,
+            role,
+        }: SetSiteRole,
+    ) -> Result<()> {
+        tide::log::info!(
+            "Setting role for user ID {user_id} in site ID {site_id} to {role:?}"
+        );
+
+        let txn = ctx.transaction();
+        let existing = SiteMember::find_by_id((site_id, user_id)).one(txn).await?;
+
+        match (existing, role) {
+            (None, None) => (),
+            (Some(member), None) => {
+                member.delete(txn).await?;
+            }
+            (None, Some(role)) => {
+                let model = site_member::ActiveModel {
+                    site_id: Set(site_id),
+                    user_id: Set(user_id),
+                    role: Set(role),
+                    created_at: Set(ctx.now()),
+                    ..Default::default()
+                };
+
+                model.insert(txn).await?;
+            }
+            (Some(_), Some(role)) => {
+                let model = site_member::ActiveModel {
+                    site_id: Set(site_id),
+                    user_id: Set(user_id),
+                    role: Set(role),
+                    updated_at: Set(Some(ctx.now())),
+                    ..Default::default()
+                };
+
+                model.update(txn).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+/*
+ * services/site_member/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::SiteRole;
+use crate::web::Validate;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GetSiteRole {
+    pub site_id: i64,
+    pub user_id: i64,
+}
+
+impl Validate for GetSiteRole {}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SetSiteRole {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    /// The user's new role, or `None` to remove them from the site.
+    pub role: Option<SiteRole>,
+}
+
+impl Validate for SetSiteRole {}
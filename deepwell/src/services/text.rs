@@ -23,10 +23,18 @@
 //!
 //! It uses content-addressable storage, meaning that data is uniquely
 //! identified by its hash.
+//!
+//! The hash algorithm is configurable, and the one used is recorded with
+//! each entry. Since entries are looked up by hash alone, text stored under
+//! a previous algorithm continues to be retrievable after it is changed.
 
 use super::prelude::*;
-use crate::hash::{k12_hash, TextHash, TEXT_HASH_LENGTH};
-use crate::models::text::{self, Entity as Text};
+use crate::hash::{TextHash, TextHashAlgorithm, TEXT_HASH_LENGTH};
+use crate::models::page_draft::{self, Entity as PageDraft};
+use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::text::{self, Entity as Text, Model as TextModel};
+use sea_orm::sea_query::Query;
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct TextService;
@@ -53,6 +61,32 @@ impl TextService {
         find_or_error(Self::get_optional(ctx, hash)).await
     }
 
+    /// Gets several texts at once, using a single query.
+    ///
+    /// The returned map is keyed by hash. Any hashes which
+    /// do not exist are absent from the map.
+    pub async fn get_many(
+        ctx: &ServiceContext<'_>,
+        hashes: &[&[u8]],
+    ) -> Result<HashMap<Vec<u8>, String>> {
+        tide::log::debug!("Getting {} texts", hashes.len());
+
+        if hashes.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let txn = ctx.transaction();
+        let contents = Text::find()
+            .filter(hashes_condition(hashes)?)
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|model| (model.hash, model.contents))
+            .collect();
+
+        Ok(contents)
+    }
+
     #[inline]
     pub async fn exists(ctx: &ServiceContext<'_>, hash: &[u8]) -> Result<bool> {
         Self::get_optional(ctx, hash)
@@ -64,29 +98,34 @@ impl TextService {
     ///
     /// This utility conditionally retrieves the
     /// text given by the specified hash only
-    /// if the flag `should_fetch` is true.
+    /// if the flag `should_fetch` is true and a hash is present.
     /// Otherwise, it does no action, returning `None`.
     pub async fn get_maybe(
         ctx: &ServiceContext<'_>,
         should_fetch: bool,
-        hash: &[u8],
+        hash: Option<&[u8]>,
     ) -> Result<Option<String>> {
-        if should_fetch {
-            let text = Self::get(ctx, hash).await?;
-            Ok(Some(text))
-        } else {
-            Ok(None)
+        match hash {
+            Some(hash) if should_fetch => {
+                let text = Self::get(ctx, hash).await?;
+                Ok(Some(text))
+            }
+            _ => Ok(None),
         }
     }
 
     /// Creates a text entry with this data, if it does not already exist.
+    ///
+    /// The text is hashed using the currently configured algorithm.
     pub async fn create(ctx: &ServiceContext<'_>, contents: String) -> Result<TextHash> {
         let txn = ctx.transaction();
-        let hash = k12_hash(contents.as_bytes());
+        let algorithm = ctx.config().text_hash_algorithm;
+        let hash = algorithm.hash(contents.as_bytes());
 
         if !Self::exists(ctx, &hash).await? {
             let model = text::ActiveModel {
                 hash: Set(hash.to_vec()),
+                hash_algorithm: Set(str!(algorithm.name())),
                 contents: Set(contents),
             };
 
@@ -96,6 +135,84 @@ impl TextService {
         Ok(hash)
     }
 
+    /// Deletes the given texts, unless something still refers to them.
+    ///
+    /// Since text is stored by hash, the same entry may be used elsewhere,
+    /// for instance by another revision with identical contents.
+    pub async fn delete_unreferenced(
+        ctx: &ServiceContext<'_>,
+        hashes: &[&[u8]],
+    ) -> Result<u64> {
+        tide::log::debug!("Deleting up to {} unreferenced texts", hashes.len());
+
+        if hashes.is_empty() {
+            return Ok(0);
+        }
+
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected } = Text::delete_many()
+            .filter(hashes_condition(hashes)?.add(unreferenced_condition()))
+            .exec(txn)
+            .await?;
+
+        tide::log::debug!("{rows_affected} unreferenced texts were deleted");
+        Ok(rows_affected)
+    }
+
+    /// Checks that the stored text matches the hash it is stored under.
+    ///
+    /// Yields `Error::Inconsistent` if the contents have been corrupted.
+    pub async fn verify(ctx: &ServiceContext<'_>, hash: &[u8]) -> Result<()> {
+        tide::log::info!("Verifying integrity of text {}", hex::encode(hash));
+
+        let txn = ctx.transaction();
+        let model = Text::find()
+            .filter(text::Column::Hash.eq(hash))
+            .one(txn)
+            .await?
+            .ok_or(Error::NotFound)?;
+
+        if is_intact(&model) {
+            Ok(())
+        } else {
+            tide::log::error!("Text {} does not match its hash", hex::encode(hash));
+            Err(Error::Inconsistent)
+        }
+    }
+
+    /// Checks the integrity of all stored text.
+    ///
+    /// This is a maintenance operation, which can take a while.
+    ///
+    /// # Returns
+    /// The stored hashes of all texts whose contents do not match.
+    /// Malformed hashes are returned as-is, since they cannot match anything.
+    pub async fn verify_all(ctx: &ServiceContext<'_>) -> Result<Vec<Vec<u8>>> {
+        tide::log::info!("Verifying integrity of all stored text");
+
+        let txn = ctx.transaction();
+        let mut corrupted = Vec::new();
+        let mut text_chunks = Text::find()
+            .order_by_asc(text::Column::Hash)
+            .paginate(txn, 100);
+
+        while let Some(texts) = text_chunks.fetch_and_next().await? {
+            for model in texts {
+                if !is_intact(&model) {
+                    tide::log::error!(
+                        "Text {} does not match its hash",
+                        hex::encode(&model.hash),
+                    );
+
+                    corrupted.push(model.hash);
+                }
+            }
+        }
+
+        tide::log::info!("Found {} corrupted texts", corrupted.len());
+        Ok(corrupted)
+    }
+
     /// Searches for any text rows which are unused.
     ///
     /// This is rare, but can happen when text is invalidated,
@@ -114,6 +231,201 @@ impl TextService {
         //     UNION
         //     SELECT compiled_hash AS hash
         //     FROM page_revision
+        //     WHERE compiled_hash IS NOT NULL
         // )
     }
 }
+
+/// Determines if the text contents still match the hash they are stored under.
+///
+/// Entries with a malformed hash or an unknown hash algorithm
+/// cannot be verified, and so fail.
+fn is_intact(model: &TextModel) -> bool {
+    let hash = match TextHash::try_from(&model.hash[..]) {
+        Ok(hash) => hash,
+        Err(_) => {
+            tide::log::error!(
+                "Text hash has invalid length {} (expected {TEXT_HASH_LENGTH})",
+                model.hash.len(),
+            );
+            return false;
+        }
+    };
+
+    match TextHashAlgorithm::from_name(&model.hash_algorithm) {
+        Some(algorithm) => algorithm.hash(model.contents.as_bytes()) == hash,
+        None => {
+            tide::log::error!("Unknown text hash algorithm '{}'", model.hash_algorithm);
+            false
+        }
+    }
+}
+
+/// Builds the condition matching texts which nothing refers to.
+///
+/// The compiled hash is nullable, so nulls must be excluded for `NOT IN`
+/// to match anything.
+fn unreferenced_condition() -> Condition {
+    let revision_wikitext = Query::select()
+        .column(page_revision::Column::WikitextHash)
+        .from(PageRevision)
+        .to_owned();
+
+    let revision_compiled = Query::select()
+        .column(page_revision::Column::CompiledHash)
+        .from(PageRevision)
+        .and_where(page_revision::Column::CompiledHash.is_not_null())
+        .to_owned();
+
+    let draft_wikitext = Query::select()
+        .column(page_draft::Column::WikitextHash)
+        .from(PageDraft)
+        .to_owned();
+
+    Condition::all()
+        .add(text::Column::Hash.not_in_subquery(revision_wikitext))
+        .add(text::Column::Hash.not_in_subquery(revision_compiled))
+        .add(text::Column::Hash.not_in_subquery(draft_wikitext))
+}
+
+/// Builds the condition matching any of the given text hashes.
+///
+/// Yields `Error::BadRequest` if any of the hashes is not of the right length.
+fn hashes_condition(hashes: &[&[u8]]) -> Result<Condition> {
+    let mut unique_hashes = Vec::with_capacity(hashes.len());
+
+    for &hash in hashes {
+        if hash.len() != TEXT_HASH_LENGTH {
+            tide::log::error!(
+                "Text hash has invalid length {} (expected {TEXT_HASH_LENGTH})",
+                hash.len(),
+            );
+            return Err(Error::BadRequest);
+        }
+
+        if !unique_hashes.contains(&hash) {
+            unique_hashes.push(hash);
+        }
+    }
+
+    Ok(Condition::all().add(text::Column::Hash.is_in(unique_hashes)))
+}
+
+#[test]
+fn get_many_condition() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let hash_1 = TextHashAlgorithm::K12.hash(b"apple");
+    let hash_2 = TextHashAlgorithm::Sha512Truncated.hash(b"banana");
+
+    let sql = Text::find()
+        .filter(hashes_condition(&[&hash_1, &hash_2, &hash_1]).unwrap())
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    // Only one query, with each hash appearing once
+    assert_eq!(sql.matches(" IN (").count(), 1, "Not a single query: {sql}");
+    assert_eq!(
+        sql.matches("x'").count(),
+        2,
+        "Hashes not deduplicated: {sql}"
+    );
+
+    // Malformed hashes are rejected rather than queried
+    assert!(matches!(
+        hashes_condition(&[&hash_1, b"not a hash"]),
+        Err(Error::BadRequest),
+    ));
+}
+
+#[test]
+fn verify_integrity() {
+    fn build(
+        algorithm: TextHashAlgorithm,
+        hash_contents: &str,
+        contents: &str,
+    ) -> TextModel {
+        TextModel {
+            hash: algorithm.hash(hash_contents.as_bytes()).to_vec(),
+            hash_algorithm: str!(algorithm.name()),
+            contents: str!(contents),
+        }
+    }
+
+    let original = "**Item #:** SCP-001";
+    let tampered = "**Item #:** SCP-002";
+
+    for algorithm in [TextHashAlgorithm::K12, TextHashAlgorithm::Sha512Truncated] {
+        assert!(
+            is_intact(&build(algorithm, original, original)),
+            "Correct text failed verification",
+        );
+        assert!(
+            !is_intact(&build(algorithm, original, tampered)),
+            "Tampered text passed verification",
+        );
+        assert!(
+            !is_intact(&build(algorithm, original, "")),
+            "Empty text passed verification",
+        );
+    }
+
+    // Recorded algorithm must match the one used
+    let mut model = build(TextHashAlgorithm::K12, original, original);
+    model.hash_algorithm = str!(TextHashAlgorithm::Sha512Truncated.name());
+    assert!(
+        !is_intact(&model),
+        "Text passed verification with wrong algorithm"
+    );
+
+    model.hash_algorithm = str!("md5");
+    assert!(
+        !is_intact(&model),
+        "Text passed verification with unknown algorithm"
+    );
+
+    // Stored hash must be of the right length
+    let mut model = build(TextHashAlgorithm::K12, original, original);
+    model.hash.truncate(TEXT_HASH_LENGTH / 2);
+    assert!(
+        !is_intact(&model),
+        "Text passed verification with truncated hash"
+    );
+}
+
+#[test]
+fn verify_all_malformed() {
+    use crate::api::build_test_server_state;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait};
+
+    let algorithm = TextHashAlgorithm::K12;
+    let contents = "**Item #:** SCP-001";
+    let intact = TextModel {
+        hash: algorithm.hash(contents.as_bytes()).to_vec(),
+        hash_algorithm: str!(algorithm.name()),
+        contents: str!(contents),
+    };
+    let truncated = TextModel {
+        hash: intact.hash[..4].to_vec(),
+        ..intact.clone()
+    };
+
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        // Only page of texts, then the end of the table
+        .append_query_results([vec![intact, truncated.clone()], vec![]])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let corrupted = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let corrupted = TextService::verify_all(&ctx).await?;
+        txn.commit().await?;
+        Ok::<_, Error>(corrupted)
+    })
+    .expect("Verification pass failed");
+
+    // The malformed row is reported rather than crashing the pass
+    assert_eq!(corrupted, vec![truncated.hash]);
+}
@@ -19,22 +19,32 @@
  */
 
 use super::prelude::*;
-use crate::models::sea_orm_active_enums::{AliasType, UserType};
+use crate::models::sea_orm_active_enums::{AliasType, AuditAction, UserType};
 use crate::models::user::{self, Entity as User, Model as UserModel};
 use crate::services::alias::CreateAlias;
+use crate::services::audit_log::RecordAuditEntry;
 use crate::services::blob::{BlobService, CreateBlobOutput};
 use crate::services::filter::{FilterClass, FilterType};
-use crate::services::{AliasService, FilterService, PasswordService};
-use crate::utils::{get_regular_slug, regex_replace_in_place};
+use crate::services::{AliasService, AuditLogService, FilterService, PasswordService};
+use crate::utils::{assert_is_csprng, get_regular_slug, regex_replace_in_place};
+use rand::distributions::{Alphanumeric, DistString};
+use rand::thread_rng;
 use regex::Regex;
-use sea_orm::ActiveValue;
+use sea_orm::sea_query::{Expr, Func};
+use sea_orm::{ActiveValue, UpdateMany, UpdateResult};
 use std::cmp;
+use std::time::Duration as StdDuration;
+use subtle::ConstantTimeEq;
+use time::OffsetDateTime;
 
 lazy_static! {
     static ref LEADING_TRAILING_CHARS: Regex =
         Regex::new(r"(^[\-\s]+)|([\-\s+]$)").unwrap();
 }
 
+/// The length of generated email change confirmation tokens.
+const EMAIL_CHANGE_TOKEN_LENGTH: usize = 32;
+
 #[derive(Debug)]
 pub struct UserService;
 
@@ -154,8 +164,13 @@ impl UserService {
             name: Set(name),
             slug: Set(slug.clone()),
             name_changes_left: Set(ctx.config().default_name_changes),
+            name_changes_refilled_at: Set(ctx.now()),
+            last_login_at: Set(None),
+            failed_login_count: Set(0),
             email: Set(email),
             email_verified_at: Set(None),
+            pending_email: Set(None),
+            pending_email_token: Set(None),
             password: Set(password),
             multi_factor_secret: Set(None),
             multi_factor_recovery_codes: Set(None),
@@ -166,7 +181,7 @@ impl UserService {
             birthday: Set(None),
             biography: Set(None),
             user_page: Set(None),
-            created_at: Set(now()),
+            created_at: Set(ctx.now()),
             updated_at: Set(None),
             deleted_at: Set(None),
             ..Default::default()
@@ -223,7 +238,12 @@ impl UserService {
         }
 
         let user = match reference {
-            Reference::Id(id) => User::find_by_id(id).one(txn).await?,
+            Reference::Id(id) => {
+                ctx.cached_model(id, move || async move {
+                    User::find_by_id(id).one(txn).await.map_err(Error::from)
+                })
+                .await?
+            }
             Reference::Slug(slug) => {
                 User::find()
                     .filter(
@@ -283,6 +303,55 @@ impl UserService {
         }
     }
 
+    /// Gets all users matching the given filter, ordered by user ID.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        filter: UserFilter,
+    ) -> Result<Vec<UserModel>> {
+        tide::log::info!("Getting all users matching {filter:?}");
+
+        let txn = ctx.transaction();
+        let users = User::find()
+            .filter(Self::build_filter_condition(&filter))
+            .order_by_asc(user::Column::UserId)
+            .limit(filter.limit)
+            .all(txn)
+            .await?;
+
+        Ok(users)
+    }
+
+    fn build_filter_condition(
+        UserFilter {
+            types,
+            include_deleted,
+            from_wikidot,
+            start_id,
+            ..
+        }: &UserFilter,
+    ) -> Condition {
+        let type_condition = if types.is_empty() {
+            None
+        } else {
+            Some(user::Column::UserType.is_in(types.iter().copied()))
+        };
+
+        let deleted_condition = if *include_deleted {
+            None
+        } else {
+            Some(user::Column::DeletedAt.is_null())
+        };
+
+        let from_wikidot_condition =
+            from_wikidot.map(|value| user::Column::FromWikidot.eq(value));
+
+        Condition::all()
+            .add(user::Column::UserId.gt(*start_id))
+            .add_option(type_condition)
+            .add_option(deleted_condition)
+            .add_option(from_wikidot_condition)
+    }
+
     pub async fn update(
         ctx: &ServiceContext<'_>,
         reference: Reference<'_>,
@@ -311,7 +380,11 @@ impl UserService {
         }
 
         if let ProvidedValue::Set(email_verified) = input.email_verified {
-            let timestamp = if email_verified { Some(now()) } else { None };
+            let timestamp = if email_verified {
+                Some(ctx.now())
+            } else {
+                None
+            };
             model.email_verified_at = Set(timestamp);
         }
 
@@ -320,33 +393,13 @@ impl UserService {
             model.password = Set(password_hash);
         }
 
-        if let ProvidedValue::Set(locale) = input.locale {
-            model.locale = Set(locale);
-        }
-
-        if let ProvidedValue::Set(real_name) = input.real_name {
-            model.real_name = Set(real_name);
-        }
-
-        if let ProvidedValue::Set(gender) = input.gender {
-            model.gender = Set(gender);
-        }
-
-        if let ProvidedValue::Set(birthday) = input.birthday {
-            model.birthday = Set(birthday);
-        }
-
-        if let ProvidedValue::Set(location) = input.location {
-            model.location = Set(location);
-        }
-
-        if let ProvidedValue::Set(biography) = input.biography {
-            model.biography = Set(biography);
-        }
-
-        if let ProvidedValue::Set(user_page) = input.user_page {
-            model.user_page = Set(user_page);
-        }
+        input.locale.update_field(&mut model.locale);
+        input.real_name.update_field(&mut model.real_name);
+        input.gender.update_field(&mut model.gender);
+        input.birthday.update_field(&mut model.birthday);
+        input.location.update_field(&mut model.location);
+        input.biography.update_field(&mut model.biography);
+        input.user_page.update_field(&mut model.user_page);
 
         if let ProvidedValue::Set(avatar) = input.avatar {
             let s3_hash = match avatar {
@@ -363,8 +416,9 @@ impl UserService {
         }
 
         // Update user
-        model.updated_at = Set(Some(now()));
+        model.updated_at = Set(Some(ctx.now()));
         let new_user = model.update(txn).await?;
+        ctx.invalidate_model::<UserModel>(new_user.user_id);
 
         // Run verification afterwards if the slug changed
         if user.slug != new_user.slug {
@@ -457,6 +511,11 @@ impl UserService {
         model.name = Set(new_name);
         model.slug = Set(new_slug);
 
+        if restarts_refill_period(user.name_changes_left, ctx.config().max_name_changes) {
+            // Refills were paused while at the cap, so the period starts from now
+            model.name_changes_refilled_at = Set(ctx.now());
+        }
+
         AliasService::create2(
             ctx,
             CreateAlias {
@@ -489,7 +548,7 @@ impl UserService {
         let model = user::ActiveModel {
             user_id: Set(user.user_id),
             name_changes_left: Set(name_changes),
-            updated_at: Set(Some(now())),
+            updated_at: Set(Some(ctx.now())),
             ..Default::default()
         };
 
@@ -502,9 +561,136 @@ impl UserService {
         );
 
         model.update(txn).await?;
+        ctx.invalidate_model::<UserModel>(user.user_id);
         Ok(name_changes)
     }
 
+    /// Begins changing a user's email address.
+    ///
+    /// The new address is stored as pending, along with a token which must be
+    /// sent to it. The current address remains in use until the change is
+    /// confirmed with `confirm_email_change()`. Beginning another change
+    /// replaces any which is still pending.
+    pub async fn begin_email_change(
+        ctx: &ServiceContext<'_>,
+        BeginEmailChange { user_id, new_email }: BeginEmailChange,
+    ) -> Result<BeginEmailChangeOutput> {
+        tide::log::info!("Beginning email change for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let user = Self::get(ctx, Reference::Id(user_id)).await?;
+        if user.email == new_email {
+            tide::log::error!("New email is the same as the current one");
+            return Err(Error::BadRequest);
+        }
+
+        Self::run_email_filter(ctx, &new_email).await?;
+
+        Self::check_email_conflict(ctx, &user, &new_email).await?;
+
+        let token = {
+            let mut rng = thread_rng();
+            assert_is_csprng(&rng);
+            Alphanumeric.sample_string(&mut rng, EMAIL_CHANGE_TOKEN_LENGTH)
+        };
+
+        let model = pending_email_model(user_id, new_email, token.clone(), ctx.now());
+        model.update(txn).await?;
+        ctx.invalidate_model::<UserModel>(user_id);
+        Ok(BeginEmailChangeOutput { token })
+    }
+
+    /// Confirms a pending email change, replacing the user's email address.
+    ///
+    /// Since the token was sent to the new address, it is considered verified.
+    pub async fn confirm_email_change(
+        ctx: &ServiceContext<'_>,
+        ConfirmEmailChange { user_id, token }: ConfirmEmailChange,
+    ) -> Result<UserModel> {
+        tide::log::info!("Confirming email change for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let user = Self::get(ctx, Reference::Id(user_id)).await?;
+        let model = confirmed_email_model(&user, &token, ctx.now())?;
+
+        // Another account may have taken the address since the change began
+        if let ActiveValue::Set(ref email) = model.email {
+            Self::check_email_conflict(ctx, &user, email).await?;
+        }
+
+        let user = model.update(txn).await?;
+        ctx.invalidate_model::<UserModel>(user_id);
+        Ok(user)
+    }
+
+    /// Gives every user who is due for one another name change token, up to the cap.
+    ///
+    /// Users are due a refill once the configured refill period has passed since
+    /// their last one (or since they were created). This is run periodically.
+    ///
+    /// # Returns
+    /// The number of users who received a name change token.
+    pub async fn refill_name_changes(ctx: &ServiceContext<'_>) -> Result<u64> {
+        let txn = ctx.transaction();
+        let config = ctx.config();
+        let current_time = ctx.now();
+
+        let cutoff = match refill_cutoff(current_time, config.refill_name_change) {
+            Some(cutoff) => cutoff,
+            None => {
+                tide::log::debug!("Name change refills are disabled");
+                return Ok(0);
+            }
+        };
+
+        tide::log::info!(
+            "Refilling name change tokens for users last refilled before {cutoff}"
+        );
+
+        let UpdateResult { rows_affected } = User::update_many()
+            .col_expr(
+                user::Column::NameChangesLeft,
+                Expr::col(user::Column::NameChangesLeft).add(1),
+            )
+            .col_expr(
+                user::Column::NameChangesRefilledAt,
+                Expr::value(current_time),
+            )
+            .col_expr(user::Column::UpdatedAt, Expr::value(Some(current_time)))
+            .filter(refill_condition(config.max_name_changes, cutoff))
+            .exec(txn)
+            .await?;
+        ctx.invalidate_models::<UserModel>();
+
+        tide::log::debug!("{rows_affected} users received a name change token");
+        Ok(rows_affected)
+    }
+
+    /// Records that a user has successfully logged in.
+    ///
+    /// This sets their last login time and resets their failed login count.
+    pub async fn record_login(ctx: &ServiceContext<'_>, user_id: i64) -> Result<()> {
+        tide::log::info!("Recording successful login for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        login_model(user_id, ctx.now()).update(txn).await?;
+        ctx.invalidate_model::<UserModel>(user_id);
+        Ok(())
+    }
+
+    /// Records that a login attempt for a user has failed.
+    pub async fn record_failed_login(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<()> {
+        tide::log::info!("Recording failed login for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        failed_login_update(user_id, ctx.now()).exec(txn).await?;
+        ctx.invalidate_model::<UserModel>(user_id);
+        Ok(())
+    }
+
     /// Set the MFA secret fields for a user.
     pub async fn set_mfa_secrets(
         ctx: &ServiceContext<'_>,
@@ -519,10 +705,11 @@ impl UserService {
             user_id: Set(user_id),
             multi_factor_secret,
             multi_factor_recovery_codes,
-            updated_at: Set(Some(now())),
+            updated_at: Set(Some(ctx.now())),
             ..Default::default()
         };
         model.update(txn).await?;
+        ctx.invalidate_model::<UserModel>(user_id);
 
         Ok(())
     }
@@ -549,10 +736,11 @@ impl UserService {
             let model = user::ActiveModel {
                 user_id: Set(user.user_id),
                 multi_factor_recovery_codes: Set(Some(updated_codes)),
-                updated_at: Set(Some(now())),
+                updated_at: Set(Some(ctx.now())),
                 ..Default::default()
             };
             model.update(txn).await?;
+            ctx.invalidate_model::<UserModel>(user.user_id);
         }
 
         Ok(())
@@ -560,11 +748,15 @@ impl UserService {
 
     pub async fn delete(
         ctx: &ServiceContext<'_>,
+        actor_id: i64,
         reference: Reference<'_>,
     ) -> Result<UserModel> {
         let txn = ctx.transaction();
         let user = Self::get(ctx, reference).await?;
-        tide::log::info!("Deleting user with ID {}", user.user_id);
+        tide::log::info!(
+            "Deleting user with ID {} by user ID {actor_id}",
+            user.user_id,
+        );
 
         // Delete all user aliases
         AliasService::delete_all(ctx, AliasType::User, user.user_id).await?;
@@ -572,12 +764,27 @@ impl UserService {
         // Set deletion flag
         let model = user::ActiveModel {
             user_id: Set(user.user_id),
-            deleted_at: Set(Some(now())),
+            deleted_at: Set(Some(ctx.now())),
             ..Default::default()
         };
 
         // Update and return
         let user = model.update(txn).await?;
+        ctx.invalidate_model::<UserModel>(user.user_id);
+        AuditLogService::record(
+            ctx,
+            RecordAuditEntry {
+                actor_id,
+                action: AuditAction::UserDelete,
+                target_id: user.user_id,
+                detail: serde_json::json!({
+                    "name": user.name,
+                    "slug": user.slug,
+                }),
+            },
+        )
+        .await?;
+
         Ok(user)
     }
 
@@ -600,6 +807,32 @@ impl UserService {
         Ok(())
     }
 
+    /// Ensures no other account is using the given email address.
+    ///
+    /// Bot accounts are allowed to have duplicate emails.
+    async fn check_email_conflict(
+        ctx: &ServiceContext<'_>,
+        user: &UserModel,
+        email: &str,
+    ) -> Result<()> {
+        if user.user_type != UserType::Regular {
+            return Ok(());
+        }
+
+        let txn = ctx.transaction();
+        let result = User::find()
+            .filter(email_conflict_condition(user.user_id, email))
+            .one(txn)
+            .await?;
+
+        if result.is_some() {
+            tide::log::error!("User with conflicting email already exists");
+            return Err(Error::Conflict);
+        }
+
+        Ok(())
+    }
+
     async fn run_email_filter(ctx: &ServiceContext<'_>, email: &str) -> Result<()> {
         tide::log::info!("Checking user email data against filters...");
 
@@ -611,3 +844,412 @@ impl UserService {
         Ok(())
     }
 }
+
+/// Builds the model to record a successful login.
+fn login_model(user_id: i64, current_time: OffsetDateTime) -> user::ActiveModel {
+    user::ActiveModel {
+        user_id: Set(user_id),
+        last_login_at: Set(Some(current_time)),
+        failed_login_count: Set(0),
+        ..Default::default()
+    }
+}
+
+/// Builds the query to record a failed login.
+///
+/// The counter is incremented in the database, so concurrent failures are all counted.
+fn failed_login_update(user_id: i64, current_time: OffsetDateTime) -> UpdateMany<User> {
+    User::update_many()
+        .col_expr(
+            user::Column::FailedLoginCount,
+            Expr::col(user::Column::FailedLoginCount).add(1),
+        )
+        .col_expr(user::Column::LastFailedLoginAt, Expr::value(current_time))
+        .filter(user::Column::UserId.eq(user_id))
+}
+
+/// Builds the condition for other regular users with the given email address.
+///
+/// Like the `user_email_unique` index, emails are compared case-insensitively.
+fn email_conflict_condition(user_id: i64, email: &str) -> Condition {
+    Condition::all()
+        .add(
+            Expr::expr(Func::lower(Expr::col(user::Column::Email)))
+                .eq(email.to_lowercase()),
+        )
+        .add(user::Column::UserId.ne(user_id))
+        .add(user::Column::UserType.eq(UserType::Regular))
+        .add(user::Column::DeletedAt.is_null())
+}
+
+/// Builds the model to store a pending email change.
+///
+/// The user's current email address is not modified.
+fn pending_email_model(
+    user_id: i64,
+    new_email: String,
+    token: String,
+    current_time: OffsetDateTime,
+) -> user::ActiveModel {
+    user::ActiveModel {
+        user_id: Set(user_id),
+        pending_email: Set(Some(new_email)),
+        pending_email_token: Set(Some(token)),
+        updated_at: Set(Some(current_time)),
+        ..Default::default()
+    }
+}
+
+/// Builds the model to complete a pending email change, if the token matches.
+///
+/// Yields `Error::NotFound` if there is no pending change,
+/// or `Error::InvalidAuthentication` if the token is incorrect.
+fn confirmed_email_model(
+    user: &UserModel,
+    token: &str,
+    current_time: OffsetDateTime,
+) -> Result<user::ActiveModel> {
+    let (pending_email, pending_token) =
+        match (&user.pending_email, &user.pending_email_token) {
+            (Some(email), Some(token)) => (email, token),
+            _ => {
+                tide::log::error!("No pending email change for user ID {}", user.user_id);
+                return Err(Error::NotFound);
+            }
+        };
+
+    // Constant-time comparison
+    if !bool::from(pending_token.as_bytes().ct_eq(token.as_bytes())) {
+        tide::log::error!("Incorrect email change token for user ID {}", user.user_id);
+        return Err(Error::InvalidAuthentication);
+    }
+
+    Ok(user::ActiveModel {
+        user_id: Set(user.user_id),
+        email: Set(pending_email.clone()),
+        email_verified_at: Set(Some(current_time)),
+        pending_email: Set(None),
+        pending_email_token: Set(None),
+        updated_at: Set(Some(current_time)),
+        ..Default::default()
+    })
+}
+
+/// Gets the latest refill time for a user to be due for another name change token.
+///
+/// Returns `None` if name change refills are disabled.
+fn refill_cutoff(
+    current_time: OffsetDateTime,
+    period: StdDuration,
+) -> Option<OffsetDateTime> {
+    if period.is_zero() {
+        None
+    } else {
+        Some(current_time - period)
+    }
+}
+
+/// Determines if spending a name change token starts a new refill period.
+///
+/// Users at the cap aren't refilled, so their last refill time may be long past.
+/// Without resetting it, the token they spend would be refilled immediately.
+fn restarts_refill_period(name_changes_left: i16, max_name_changes: i16) -> bool {
+    name_changes_left >= max_name_changes
+}
+
+/// Builds the condition for which users are due a name change token refill.
+fn refill_condition(max_name_changes: i16, cutoff: OffsetDateTime) -> Condition {
+    Condition::all()
+        .add(user::Column::NameChangesLeft.lt(max_name_changes))
+        .add(user::Column::NameChangesRefilledAt.lte(cutoff))
+        .add(user::Column::DeletedAt.is_null())
+}
+
+#[test]
+fn name_change_refill() {
+    use crate::utils::Clock;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    const DAY: u64 = 24 * 60 * 60;
+    let period = StdDuration::from_secs(90 * DAY);
+    let refilled_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let clock = Clock::fixed(refilled_at);
+
+    let is_due = |clock: &Clock| match refill_cutoff(clock.now(), period) {
+        Some(cutoff) => refilled_at <= cutoff,
+        None => false,
+    };
+
+    // Just refilled
+    assert!(!is_due(&clock));
+
+    // Not enough time has passed
+    clock.advance(StdDuration::from_secs(89 * DAY));
+    assert!(!is_due(&clock));
+
+    // Refill period has elapsed
+    clock.advance(StdDuration::from_secs(DAY));
+    assert!(is_due(&clock));
+
+    // Refills disabled
+    assert_eq!(refill_cutoff(clock.now(), StdDuration::ZERO), None);
+
+    // Spending a token at the cap restarts the period
+    assert!(restarts_refill_period(3, 3));
+    assert!(!restarts_refill_period(2, 3));
+    assert!(!restarts_refill_period(1, 3));
+
+    // Users at the cap are excluded
+    let sql = User::find()
+        .filter(refill_condition(3, refilled_at))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains("\"name_changes_left\" < 3"),
+        "Cap missing: {sql}"
+    );
+    assert!(
+        sql.contains("\"name_changes_refilled_at\" <="),
+        "Cutoff missing: {sql}",
+    );
+    assert!(
+        sql.contains("\"deleted_at\" IS NULL"),
+        "Deleted users included: {sql}"
+    );
+}
+
+#[test]
+fn name_change_refill_clock() {
+    use crate::api::build_test_server_state;
+    use crate::utils::Clock;
+    use async_std::task;
+    use sea_orm::{
+        DatabaseBackend, MockDatabase, MockExecResult, TransactionTrait, Value,
+    };
+    use std::sync::Arc;
+
+    let refilled_at = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let clock = Clock::fixed(refilled_at);
+
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            },
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+        ])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let period = state.config.refill_name_change;
+    let refill = || {
+        task::block_on(async {
+            let txn = state.database.begin().await?;
+            let ctx = ServiceContext::from_raw(&state, &txn).with_clock(clock.clone());
+            UserService::refill_name_changes(&ctx).await?;
+            txn.commit().await?;
+            Ok::<_, Error>(())
+        })
+        .expect("Unable to refill name changes");
+    };
+
+    // Just refilled, then once the refill period has elapsed
+    refill();
+    clock.advance(period);
+    refill();
+
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = state.database.into_transaction_log();
+    assert_eq!(log.len(), 2, "Wrong number of refill transactions: {log:?}");
+
+    // A user refilled at the starting time is due once the cutoff reaches it
+    let before = format!("{:?}", log[0]);
+    let after = format!("{:?}", log[1]);
+    let value = |time: OffsetDateTime| format!("{:?}", Value::from(time));
+    assert!(
+        before.contains(&value(refilled_at - period)),
+        "Cutoff isn't from the clock: {before}",
+    );
+    assert!(
+        after.contains(&value(refilled_at)),
+        "Cutoff didn't advance with the clock: {after}",
+    );
+    assert!(
+        after.contains(&value(refilled_at + period)),
+        "Refill time isn't from the clock: {after}",
+    );
+}
+
+#[test]
+fn user_filter_condition() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    macro_rules! check {
+        ($types:expr, $include_deleted:expr, $present:expr, $absent:expr $(,)?) => {{
+            let filter = UserFilter {
+                types: $types,
+                include_deleted: $include_deleted,
+                from_wikidot: None,
+                start_id: 0,
+                limit: 20,
+            };
+
+            let sql = User::find()
+                .filter(UserService::build_filter_condition(&filter))
+                .build(DbBackend::Postgres)
+                .to_string();
+
+            for part in $present {
+                assert!(sql.contains(part), "SQL {sql:?} doesn't contain {part:?}");
+            }
+
+            for part in $absent {
+                assert!(!sql.contains(part), "SQL {sql:?} contains {part:?}");
+            }
+        }};
+    }
+
+    check!(
+        vec![UserType::Regular],
+        false,
+        ["'regular'", "\"deleted_at\" IS NULL"],
+        ["'system'", "'bot'"],
+    );
+    check!(
+        vec![UserType::Bot, UserType::System],
+        true,
+        ["'bot'", "'system'"],
+        ["'regular'", "\"deleted_at\" IS NULL"],
+    );
+    check!(vec![], true, ["\"user_id\" > 0"], [" IN ("]);
+}
+
+#[test]
+fn email_change() {
+    use crate::services::fixtures;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let current_time = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let mut user = UserModel {
+        email: str!("old@example.com"),
+        email_verified_at: Some(current_time),
+        ..fixtures::user(1, "Test")
+    };
+
+    // No change pending
+    assert!(
+        matches!(
+            confirmed_email_model(&user, "token", current_time),
+            Err(Error::NotFound),
+        ),
+        "Confirmed nonexistent email change",
+    );
+
+    // Beginning the change leaves the current email untouched
+    let model = pending_email_model(
+        user.user_id,
+        str!("new@example.com"),
+        str!("token"),
+        current_time,
+    );
+
+    assert!(
+        model.email.is_not_set(),
+        "Email changed before confirmation"
+    );
+    assert!(
+        model.email_verified_at.is_not_set(),
+        "Email verification reset before confirmation",
+    );
+    assert_eq!(model.pending_email, Set(Some(str!("new@example.com"))));
+
+    user.pending_email = Some(str!("new@example.com"));
+    user.pending_email_token = Some(str!("token"));
+
+    // Incorrect token, email change does not go through
+    assert!(
+        matches!(
+            confirmed_email_model(&user, "tokem", current_time),
+            Err(Error::InvalidAuthentication),
+        ),
+        "Email change confirmed with incorrect token",
+    );
+    assert!(
+        matches!(
+            confirmed_email_model(&user, "", current_time),
+            Err(Error::InvalidAuthentication),
+        ),
+        "Email change confirmed with empty token",
+    );
+
+    // Correct token, email is replaced
+    let model = confirmed_email_model(&user, "token", current_time)
+        .expect("Unable to confirm email change");
+
+    assert_eq!(model.email, Set(str!("new@example.com")));
+    assert_eq!(model.email_verified_at, Set(Some(current_time)));
+    assert_eq!(model.pending_email, Set(None));
+    assert_eq!(model.pending_email_token, Set(None));
+
+    // Confirmation checks other regular users for the new address
+    let sql = User::find()
+        .filter(email_conflict_condition(user.user_id, "New@Example.com"))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.contains("LOWER(\"email\") = 'new@example.com'"),
+        "Email not compared case-insensitively: {sql}",
+    );
+    assert!(
+        sql.contains("\"user_id\" <> 1"),
+        "User conflicts with self: {sql}"
+    );
+    assert!(
+        sql.contains("\"user_type\" = CAST('regular' AS user_type)"),
+        "Bot accounts included: {sql}",
+    );
+    assert!(
+        sql.contains("\"deleted_at\" IS NULL"),
+        "Deleted users included: {sql}",
+    );
+}
+
+#[test]
+fn login_tracking() {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    let current_time = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+
+    // Successful login updates the timestamp and resets the counter
+    let model = login_model(4, current_time);
+    assert_eq!(model.user_id, Set(4));
+    assert_eq!(model.last_login_at, Set(Some(current_time)));
+    assert_eq!(model.failed_login_count, Set(0));
+
+    // Failed login increments the counter for only that user
+    let sql = failed_login_update(4, current_time)
+        .build(DbBackend::Postgres)
+        .to_string();
+    assert!(
+        sql.contains(r#""failed_login_count" = "failed_login_count" + 1"#),
+        "Failed login count not incremented: {sql}",
+    );
+    assert!(
+        sql.contains(r#""last_failed_login_at" = '2023-01-31 12:00:00"#),
+        "Failed login time not recorded: {sql}",
+    );
+    assert!(
+        sql.contains(r#""user"."user_id" = 4"#),
+        "Failed login not limited to user: {sql}",
+    );
+    assert!(
+        !sql.contains("last_login_at"),
+        "Failed login changed last login time: {sql}",
+    );
+}
@@ -22,6 +22,7 @@ use super::prelude::*;
 use crate::models::alias::Model as AliasModel;
 use crate::models::sea_orm_active_enums::UserType;
 use crate::models::user::Model as UserModel;
+use crate::web::{check_not_blank, Validate, ValidationResult};
 use time::Date;
 
 #[derive(Deserialize, Debug)]
@@ -37,6 +38,15 @@ pub struct CreateUser {
     pub bypass_filter: bool,
 }
 
+impl Validate for CreateUser {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("name", &self.name)?;
+        check_not_blank("email", &self.email)?;
+        check_not_blank("locale", &self.locale)?;
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateUserOutput {
@@ -50,6 +60,78 @@ pub struct GetUser<'a> {
     pub user: Reference<'a>,
 }
 
+impl Validate for GetUser<'_> {}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteUser<'a> {
+    pub user: Reference<'a>,
+
+    /// The user performing the deletion.
+    pub actor_id: i64,
+}
+
+impl Validate for DeleteUser<'_> {}
+
+/// Filter for listing users.
+///
+/// The `types` field gives which user types to return.
+/// If it is empty, then users of all types are returned.
+///
+/// The `start_id` field gives the user ID to search from, exclusive.
+/// If `0`, then means "everything".
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserFilter {
+    #[serde(default)]
+    pub types: Vec<UserType>,
+
+    #[serde(default)]
+    pub include_deleted: bool,
+
+    /// If set, only return users which were (or were not) imported from Wikidot.
+    #[serde(default)]
+    pub from_wikidot: Option<bool>,
+
+    pub start_id: i64,
+    pub limit: u64,
+}
+
+impl Validate for UserFilter {}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BeginEmailChange {
+    pub user_id: i64,
+    pub new_email: String,
+}
+
+impl Validate for BeginEmailChange {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("new_email", &self.new_email)
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BeginEmailChangeOutput {
+    /// The token to send to the new email address, to confirm the change.
+    pub token: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmEmailChange {
+    pub user_id: i64,
+    pub token: String,
+}
+
+impl Validate for ConfirmEmailChange {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("token", &self.token)
+    }
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetUserOutput {
@@ -67,6 +149,24 @@ pub struct UpdateUser<'a> {
     pub body: UpdateUserBody,
 }
 
+impl Validate for UpdateUser<'_> {
+    fn validate(&self) -> ValidationResult {
+        if let Some(name) = self.body.name.to_option() {
+            check_not_blank("name", name)?;
+        }
+
+        if let Some(email) = self.body.email.to_option() {
+            check_not_blank("email", email)?;
+        }
+
+        if let Some(locale) = self.body.locale.to_option() {
+            check_not_blank("locale", locale)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase", default)]
 pub struct UpdateUserBody {
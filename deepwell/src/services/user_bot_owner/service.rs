@@ -99,7 +99,7 @@ impl UserBotOwnerService {
 
                 let mut model = owner.into_active_model();
                 model.description = Set(description);
-                model.updated_at = Set(Some(now()));
+                model.updated_at = Set(Some(ctx.now()));
                 model.update(txn).await?
             }
 
@@ -19,7 +19,7 @@
  */
 
 use crate::models::user::Model as UserModel;
-use crate::web::Reference;
+use crate::web::{check_not_blank, Reference, Validate, ValidationResult};
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -38,6 +38,15 @@ pub struct CreateBotUser {
                                      //         S: create a site
 }
 
+impl Validate for CreateBotUser {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("name", &self.name)?;
+        check_not_blank("email", &self.email)?;
+        check_not_blank("locale", &self.locale)?;
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateBotOwner<'a> {
@@ -46,6 +55,8 @@ pub struct CreateBotOwner<'a> {
     pub description: String,
 }
 
+impl Validate for CreateBotOwner<'_> {}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct DeleteBotOwner<'a> {
@@ -53,6 +64,8 @@ pub struct DeleteBotOwner<'a> {
     pub human: Reference<'a>,
 }
 
+impl Validate for DeleteBotOwner<'_> {}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct BotOwner {
@@ -30,14 +30,28 @@
 //! requesting domain and session token into a site and user, respectively.
 
 use super::prelude::*;
+use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::site::Model as SiteModel;
 use crate::services::{
-    DomainService, PageRevisionService, PageService, SessionService, TextService,
-    UserService,
+    DomainService, PageRevisionService, PageService, ParentService, ScoreService,
+    SessionService, SiteMemberService, TextService, UserService,
 };
+use futures::stream::{self, StreamExt};
 use ref_map::*;
+use std::collections::HashSet;
+use std::future::Future;
+use tide::StatusCode;
 use wikidot_normalize::normalize;
 
+/// The maximum number of ancestors to include in a page's breadcrumbs.
+pub const BREADCRUMB_MAX_DEPTH: usize = 10;
+
+/// The maximum number of page views which can be requested in one batch.
+pub const MAX_BATCH_VIEWS: usize = 20;
+
+/// How many page views in a batch are fetched at once.
+const BATCH_VIEW_CONCURRENCY: usize = 4;
+
 #[derive(Debug)]
 pub struct ViewService;
 
@@ -48,19 +62,20 @@ impl ViewService {
             domain,
             route,
             session_token,
+            detail,
         }: GetPageView,
     ) -> Result<GetPageViewOutput> {
         tide::log::info!(
-            "Getting page view data for domain '{}', route '{:?}'",
+            "Getting page view data for domain '{}', route '{:?}' ({:?})",
             domain,
             route,
+            detail,
         );
 
-        let Viewer {
-            site,
-            redirect_site,
-            user_session,
-        } = Self::get_viewer(ctx, &domain, session_token.ref_map(|s| s.as_str())).await?;
+        let viewer =
+            Self::get_viewer(ctx, &domain, session_token.ref_map(|s| s.as_str())).await?;
+        let permissions = viewer.permissions();
+        let site = &viewer.site;
 
         // If None, means the main page for the site. Pull from site data.
         let (page_slug, page_extra): (&str, &str) = match &route {
@@ -68,35 +83,163 @@ impl ViewService {
             Some(PageRoute { slug, extra }) => (slug, extra),
         };
 
-        let redirect_page = Self::should_redirect_page(page_slug);
+        let redirect_page =
+            Self::should_redirect_page(page_slug, &ctx.config().slug_corrections);
         let options = PageOptions::parse(page_extra);
 
+        // Hide pages the viewer may not see, as if they didn't exist
+        if !permissions.can_view(page_slug) {
+            tide::log::warn!("Viewer does not have permission to see page '{page_slug}'");
+            return Err(Error::NotFound);
+        }
+
         // Get page, revision, and text fields
-        let page =
-            PageService::get(ctx, site.site_id, Reference::Slug(cow!(page_slug))).await?;
+        let (page, page_revision) = PageService::get_with_latest_revision(
+            ctx,
+            site.site_id,
+            Reference::Slug(cow!(page_slug)),
+        )
+        .await?;
+
+        if !permissions.can_view_scheduled(page.publish_at, ctx.now()) {
+            tide::log::warn!("Page '{page_slug}' is not yet published");
+            return Err(Error::NotFound);
+        }
+
+        if !permissions.can_view_expired(page.expires_at, ctx.now()) {
+            tide::log::warn!("Page '{page_slug}' has expired");
+            return Err(Error::NotFound);
+        }
 
-        let page_revision =
-            PageRevisionService::get_latest(ctx, site.site_id, page.page_id).await?;
+        // Only fetch text fields if they're wanted
+        let (wikitext, compiled_html) = match text_hashes(detail, &page_revision)? {
+            None => (None, None),
+            Some((wikitext_hash, compiled_hash)) => {
+                let mut texts =
+                    TextService::get_many(ctx, &[wikitext_hash, compiled_hash]).await?;
 
-        let (wikitext, compiled_html) = try_join!(
-            TextService::get(ctx, &page_revision.wikitext_hash),
-            TextService::get(ctx, &page_revision.compiled_hash),
-        )?;
+                // Clone rather than remove, in case both hashes are the same
+                let wikitext =
+                    texts.get(wikitext_hash).cloned().ok_or(Error::NotFound)?;
+                let compiled_html = texts.remove(compiled_hash).ok_or(Error::NotFound)?;
+
+                (Some(wikitext), Some(compiled_html))
+            }
+        };
+
+        // Pages without their own locale inherit the site's
+        let locale = page.locale.as_ref().unwrap_or(&site.locale).clone();
+
+        let breadcrumbs = Self::breadcrumbs(ctx, site.site_id, page.page_id).await?;
+        let rating = ScoreService::score(ctx, page.page_id).await?;
+        let actions = permissions.page_actions();
 
         // TODO Check if user-agent and IP match?
 
         Ok(GetPageViewOutput {
-            viewer: Viewer {
-                site,
-                redirect_site,
-                user_session,
-            },
+            viewer,
             options,
             page,
             page_revision,
+            locale,
             redirect_page,
             wikitext,
             compiled_html,
+            actions,
+            breadcrumbs,
+            rating,
+        })
+    }
+
+    /// Gets the page views for several pages at once.
+    ///
+    /// The results are in the same order as the inputs. Expected failures,
+    /// such as a missing page, are returned as an error view for that item,
+    /// whereas any other error fails the whole batch.
+    pub async fn pages(
+        ctx: &ServiceContext<'_>,
+        inputs: Vec<GetPageView>,
+    ) -> Result<Vec<PageViewResult>> {
+        tide::log::info!("Getting page view data for {} pages", inputs.len());
+
+        if inputs.len() > MAX_BATCH_VIEWS {
+            tide::log::error!(
+                "Too many page views requested ({} > {MAX_BATCH_VIEWS})",
+                inputs.len(),
+            );
+            return Err(Error::BadRequest);
+        }
+
+        let results = collect_views(inputs, BATCH_VIEW_CONCURRENCY, |input| {
+            Self::page(ctx, input)
+        })
+        .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|result| match result {
+                Ok(output) => PageViewResult::Page(Box::new(output)),
+                Err(view) => PageViewResult::Error(view),
+            })
+            .collect())
+    }
+
+    /// Gets the breadcrumbs for a page, that is, its chain of parents.
+    ///
+    /// The chain is ordered from the root page down to the immediate parent.
+    /// It ends at the first page without an (extant) parent, or after
+    /// `BREADCRUMB_MAX_DEPTH` ancestors, whichever comes first.
+    pub async fn breadcrumbs(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<Vec<Breadcrumb>> {
+        collect_breadcrumbs(page_id, BREADCRUMB_MAX_DEPTH, |page_id| async move {
+            match ParentService::get_breadcrumb_parent(ctx, page_id).await? {
+                None => Ok(None),
+                Some(parent) => {
+                    let revision =
+                        PageRevisionService::get_latest(ctx, site_id, parent.page_id)
+                            .await?;
+
+                    Ok(Some(Breadcrumb {
+                        page_id: parent.page_id,
+                        slug: parent.slug,
+                        title: revision.title,
+                    }))
+                }
+            }
+        })
+        .await
+    }
+
+    /// Resolves a request host and path into the site and page slug it refers to.
+    ///
+    /// This performs the same redirect checks as `page()`, but does not fetch
+    /// the page itself, so the router can determine where to go cheaply.
+    pub async fn resolve(
+        ctx: &ServiceContext<'_>,
+        ResolveRoute { domain, path }: ResolveRoute,
+    ) -> Result<ResolveRouteOutput> {
+        tide::log::info!("Resolving route for domain '{domain}', path '{path}'");
+
+        let SiteView {
+            site,
+            redirect_site,
+        } = Self::site_from_request(ctx, &domain).await?;
+
+        let (slug, extra) = split_route(&path, &site.default_page);
+        let redirect_page =
+            Self::should_redirect_page(slug, &ctx.config().slug_corrections);
+        let slug = str!(slug);
+        let extra = str!(extra);
+
+        Ok(ResolveRouteOutput {
+            site,
+            redirect_site,
+            slug,
+            extra,
+            redirect_page,
         })
     }
 
@@ -118,8 +261,10 @@ impl ViewService {
         tide::log::info!("Getting viewer data from domain '{domain}' and session token");
 
         // Get site data
-        let site = DomainService::site_from_domain(ctx, domain).await?;
-        let redirect_site = Self::should_redirect_site(ctx, &site, domain);
+        let SiteView {
+            site,
+            redirect_site,
+        } = Self::site_from_request(ctx, domain).await?;
 
         // Get user data from session token (if present)
         let user_session = match session_token {
@@ -128,11 +273,13 @@ impl ViewService {
             Some(token) => {
                 let session = SessionService::get(ctx, token).await?;
                 let user = UserService::get(ctx, Reference::Id(session.user_id)).await?;
+                let user_permissions =
+                    Self::get_permissions(ctx, site.site_id, Some(user.user_id)).await?;
 
                 Some(UserSession {
                     session,
                     user,
-                    user_permissions: (), // TODO add user permissions, get scheme for user and site
+                    user_permissions,
                 })
             }
         };
@@ -144,25 +291,103 @@ impl ViewService {
         })
     }
 
+    /// Gets the permissions a user has on a site, based on their role there.
+    ///
+    /// If `user_id` is `None`, then these are the permissions of an anonymous viewer.
+    pub async fn get_permissions(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: Option<i64>,
+    ) -> Result<UserPermissions> {
+        let role = match user_id {
+            Some(user_id) => SiteMemberService::get_role(ctx, site_id, user_id).await?,
+            None => None,
+        };
+
+        Ok(UserPermissions::for_user(user_id, role))
+    }
+
+    /// Gets the permissions the user of a session has on a site.
+    ///
+    /// If there is no session, then these are the permissions of an anonymous viewer.
+    pub async fn get_session_permissions(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        session_token: Option<&str>,
+    ) -> Result<UserPermissions> {
+        let user_id = match session_token {
+            None => None,
+            Some("") => None,
+            Some(token) => Some(SessionService::get(ctx, token).await?.user_id),
+        };
+
+        Self::get_permissions(ctx, site_id, user_id).await
+    }
+
+    /// Gets the site for a request domain, and whether it should be redirected.
+    ///
+    /// This is the site portion of `get_viewer()`, without any session
+    /// or user handling. It is intended for internal services which only
+    /// need to know which site a host refers to.
+    pub async fn site_from_request(
+        ctx: &ServiceContext<'_>,
+        domain: &str,
+    ) -> Result<SiteView> {
+        tide::log::info!("Getting site data from domain '{domain}'");
+
+        let site = DomainService::site_from_domain(ctx, domain).await?;
+        let redirect_site = Self::should_redirect_site(ctx, &site, domain);
+
+        Ok(SiteView {
+            site,
+            redirect_site,
+        })
+    }
+
+    /// Describes an expected failure from a view, so an error page can be shown.
+    ///
+    /// Only errors which are an ordinary part of browsing, such as a missing
+    /// page, produce a view. Any other error yields `None`, and should be
+    /// returned as-is.
+    pub fn error_view(error: &Error) -> Option<ErrorView> {
+        let (status, message_key) = match error {
+            Error::NotFound => (StatusCode::NotFound, "error-404.page"),
+            Error::InvalidAuthentication | Error::InsufficientPermissions => {
+                (StatusCode::Forbidden, "error-api.FORBIDDEN")
+            }
+            Error::RateLimited => (StatusCode::TooManyRequests, "error-api.RATE_LIMITED"),
+            _ => return None,
+        };
+
+        tide::log::debug!("Producing error view for {error}");
+        Some(ErrorView {
+            status: status as u16,
+            message_key,
+            detail: None,
+        })
+    }
+
     fn should_redirect_site(
         ctx: &ServiceContext,
         site: &SiteModel,
         domain: &str,
     ) -> Option<String> {
-        // NOTE: We have to pass an owned string here, since the Cow borrows from
-        //       SiteModel, which we are also passing in the final output struct.
         let preferred_domain = DomainService::domain_for_site(ctx.config(), site);
-        if domain == preferred_domain {
-            None
-        } else {
-            Some(preferred_domain.into_owned())
-        }
+        redirect_target(domain, &preferred_domain)
     }
 
-    fn should_redirect_page(slug: &str) -> Option<String> {
+    fn should_redirect_page(
+        slug: &str,
+        corrections: &[(String, String)],
+    ) -> Option<String> {
         // Fix typos in the page slug.
         // See https://scuttle.atlassian.net/browse/WJ-330
-        let mut target = slug.replace(';', ":");
+        let mut target = str!(slug);
+        for (pattern, replacement) in corrections {
+            if !pattern.is_empty() {
+                target = target.replace(pattern.as_str(), replacement);
+            }
+        }
 
         // Run slug normalization.
         // This also strips _default and merges multiple categories.
@@ -176,3 +401,508 @@ impl ViewService {
         }
     }
 }
+
+/// Walks up the parent chain, using `get_parent` to fetch each page's parent.
+///
+/// If the chain loops back on itself, it is cut off before the repeated page.
+async fn collect_breadcrumbs<F, Fut>(
+    page_id: i64,
+    max_depth: usize,
+    mut get_parent: F,
+) -> Result<Vec<Breadcrumb>>
+where
+    F: FnMut(i64) -> Fut,
+    Fut: Future<Output = Result<Option<Breadcrumb>>>,
+{
+    let mut breadcrumbs = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current_id = page_id;
+    visited.insert(page_id);
+
+    while breadcrumbs.len() < max_depth {
+        match get_parent(current_id).await? {
+            Some(parent) if visited.insert(parent.page_id) => {
+                current_id = parent.page_id;
+                breadcrumbs.push(parent);
+            }
+            _ => break,
+        }
+    }
+
+    breadcrumbs.reverse();
+    Ok(breadcrumbs)
+}
+
+/// Runs `get_view` on each input concurrently, preserving their order.
+///
+/// Errors which have an error view are returned in place of that item,
+/// any other error is returned for the whole batch.
+async fn collect_views<I, T, F, Fut>(
+    inputs: Vec<I>,
+    concurrency: usize,
+    get_view: F,
+) -> Result<Vec<StdResult<T, ErrorView>>>
+where
+    F: FnMut(I) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut views = stream::iter(inputs).map(get_view).buffered(concurrency);
+    let mut results = Vec::new();
+
+    while let Some(result) = views.next().await {
+        match result {
+            Ok(output) => results.push(Ok(output)),
+            Err(error) => match ViewService::error_view(&error) {
+                Some(view) => results.push(Err(view)),
+                None => return Err(error),
+            },
+        }
+    }
+
+    Ok(results)
+}
+
+/// Gets the hashes of the wikitext and compiled HTML to fetch for a view, if any.
+///
+/// With `ViewDetail::Minimal` no text is fetched, so this returns `None`.
+fn text_hashes(
+    detail: ViewDetail,
+    revision: &PageRevisionModel,
+) -> Result<Option<(&[u8], &[u8])>> {
+    if !detail.includes_text() {
+        return Ok(None);
+    }
+
+    // The latest revision always retains its compiled HTML
+    let compiled_hash = revision.compiled_hash.as_deref().ok_or(Error::NotFound)?;
+    Ok(Some((&revision.wikitext_hash, compiled_hash)))
+}
+
+/// Splits a request path into the page slug and any extra path components.
+///
+/// An empty slug, i.e. the root path, refers to the site's default page.
+fn split_route<'a>(path: &'a str, default_page: &'a str) -> (&'a str, &'a str) {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let (slug, extra) = path.split_once('/').unwrap_or((path, ""));
+
+    if slug.is_empty() {
+        (default_page, extra)
+    } else {
+        (slug, extra)
+    }
+}
+
+/// Returns the domain to redirect to, if the request wasn't for the preferred one.
+fn redirect_target(domain: &str, preferred_domain: &str) -> Option<String> {
+    // NOTE: We have to return an owned string here, since the preferred domain
+    //       may borrow from SiteModel, which we are also passing in the output.
+    if domain == preferred_domain {
+        None
+    } else {
+        Some(str!(preferred_domain))
+    }
+}
+
+#[test]
+fn site_redirect() {
+    macro_rules! check {
+        ($domain:expr, $preferred_domain:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                redirect_target($domain, $preferred_domain).as_deref(),
+                $expected,
+                "Site redirect doesn't match expected",
+            );
+        };
+    }
+
+    check!("scp-wiki.wikijump.com", "scp-wiki.wikijump.com", None);
+    check!("scpwiki.com", "scpwiki.com", None);
+    check!("scp-wiki.wikijump.com", "scpwiki.com", Some("scpwiki.com"));
+    check!("www.wikijump.com", "wikijump.com", Some("wikijump.com"));
+}
+
+#[test]
+fn error_view() {
+    macro_rules! check {
+        ($error:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                ViewService::error_view(&$error)
+                    .map(|view| (view.status, view.message_key)),
+                $expected,
+                "Error view doesn't match expected",
+            );
+        };
+    }
+
+    check!(Error::NotFound, Some((404, "error-404.page")));
+    check!(
+        Error::InvalidAuthentication,
+        Some((403, "error-api.FORBIDDEN"))
+    );
+    check!(Error::RateLimited, Some((429, "error-api.RATE_LIMITED")));
+    check!(Error::Inconsistent, None);
+    check!(Error::RenderTimeout, None);
+}
+
+#[test]
+fn breadcrumbs() {
+    use async_std::task::block_on;
+    use std::collections::HashMap;
+
+    macro_rules! check {
+        ($parents:expr, $page_id:expr, $max_depth:expr, $expected:expr $(,)?) => {{
+            // Map of child ID to parent ID
+            let parents: HashMap<i64, i64> = $parents.into_iter().collect();
+            let parents = &parents;
+            let breadcrumbs = block_on(collect_breadcrumbs(
+                $page_id,
+                $max_depth,
+                |page_id| async move {
+                    Ok(parents.get(&page_id).map(|&parent_id| Breadcrumb {
+                        page_id: parent_id,
+                        slug: format!("page-{parent_id}"),
+                        title: format!("Page {parent_id}"),
+                    }))
+                },
+            ))
+            .expect("Unable to collect breadcrumbs");
+
+            let page_ids: Vec<i64> =
+                breadcrumbs.iter().map(|crumb| crumb.page_id).collect();
+            let expected: &[i64] = &$expected;
+            assert_eq!(page_ids, expected, "Breadcrumbs don't match expected");
+        }};
+    }
+
+    // No parent
+    check!([], 1, 10, []);
+
+    // Three levels deep, full ancestor chain
+    check!([(4, 3), (3, 2), (2, 1)], 4, 10, [1, 2, 3]);
+    check!([(4, 3), (3, 2), (2, 1)], 3, 10, [1, 2]);
+
+    // Missing parent (e.g. deleted) ends the chain there
+    check!([(4, 3), (2, 1)], 4, 10, [3]);
+
+    // Depth limit keeps the closest ancestors
+    check!([(4, 3), (3, 2), (2, 1)], 4, 2, [2, 3]);
+
+    // Cycles are cut off
+    check!([(3, 2), (2, 1), (1, 3)], 3, 10, [1, 2]);
+    check!([(1, 1)], 1, 10, []);
+}
+
+#[test]
+fn resolve_route() {
+    macro_rules! check {
+        ($path:expr, $slug:expr, $extra:expr $(,)?) => {
+            assert_eq!(
+                split_route($path, "start"),
+                ($slug, $extra),
+                "Route split for {:?} doesn't match expected",
+                $path,
+            );
+        };
+    }
+
+    check!("", "start", "");
+    check!("/", "start", "");
+    check!("/scp-001", "scp-001", "");
+    check!("/scp-001/", "scp-001", "");
+    check!("/scp-001/norender/true", "scp-001", "norender/true");
+    check!("scp-001/noredirect", "scp-001", "noredirect");
+    check!("//edit/true", "start", "edit/true");
+
+    // Non-preferred host with a typo'd slug redirects both
+    let corrections = vec![(str!(";"), str!(":"))];
+    let (slug, extra) = split_route("/Component;License-Box/noredirect", "start");
+    assert_eq!(
+        redirect_target("scp-wiki.wikijump.com", "scpwiki.com").as_deref(),
+        Some("scpwiki.com"),
+    );
+    assert_eq!(
+        ViewService::should_redirect_page(slug, &corrections).as_deref(),
+        Some("component:license-box"),
+    );
+    assert_eq!(extra, "noredirect");
+
+    // Canonical host and slug do not redirect
+    let (slug, _) = split_route("/component:license-box", "start");
+    assert_eq!(redirect_target("scpwiki.com", "scpwiki.com"), None);
+    assert_eq!(ViewService::should_redirect_page(slug, &corrections), None);
+}
+
+#[test]
+fn slug_corrections() {
+    let corrections = vec![
+        (str!(";"), str!(":")),
+        (str!("\u{ff1a}"), str!(":")),
+        (str!("\u{2010}"), str!("-")),
+    ];
+
+    macro_rules! check {
+        ($slug:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                ViewService::should_redirect_page($slug, &corrections).as_deref(),
+                $expected,
+                "Page redirect for {:?} doesn't match expected",
+                $slug,
+            );
+        };
+    }
+
+    // Already correct
+    check!("scp-001", None);
+    check!("component:license-box", None);
+
+    // Configured corrections
+    check!("component;license-box", Some("component:license-box"));
+    check!(
+        "component\u{ff1a}license-box",
+        Some("component:license-box")
+    );
+    check!("scp\u{2010}001", Some("scp-001"));
+
+    // Normalization still runs afterwards
+    check!("Component;License Box", Some("component:license-box"));
+    check!("_default;Start", Some("start"));
+    check!("SCP-001", Some("scp-001"));
+
+    // No corrections configured
+    assert_eq!(
+        ViewService::should_redirect_page("component;license-box", &[]).as_deref(),
+        Some("component-license-box"),
+    );
+}
+
+#[test]
+fn view_detail() {
+    use crate::models::sea_orm_active_enums::PageRevisionType;
+    use crate::services::fixtures;
+    use crate::services::score::ScoreValue;
+
+    // Full is the default, for existing callers
+    let input: GetPageView =
+        serde_json::from_str(r#"{"domain": "test.wikijump.com", "sessionToken": null}"#)
+            .expect("Unable to deserialize view request");
+    assert_eq!(input.detail, ViewDetail::Full);
+
+    let input: GetPageView = serde_json::from_str(
+        r#"{"domain": "test.wikijump.com", "sessionToken": null, "detail": "minimal"}"#,
+    )
+    .expect("Unable to deserialize view request");
+    assert_eq!(input.detail, ViewDetail::Minimal);
+
+    let mut revision = PageRevisionModel {
+        revision_type: PageRevisionType::Create,
+        wikitext_hash: vec![1; 16],
+        compiled_hash: Some(vec![2; 16]),
+        compiled_generator: str!("ftml"),
+        title: str!("Start"),
+        slug: str!("start"),
+        ..fixtures::page_revision(1, 10, 100, 0)
+    };
+
+    // Only full views fetch text
+    assert_eq!(
+        text_hashes(ViewDetail::Full, &revision).expect("No text hashes"),
+        Some((&[1; 16][..], &[2; 16][..])),
+    );
+    assert_eq!(
+        text_hashes(ViewDetail::Minimal, &revision).expect("No text hashes"),
+        None,
+    );
+
+    // Missing compiled HTML only matters if it's being fetched
+    revision.compiled_hash = None;
+    assert!(matches!(
+        text_hashes(ViewDetail::Full, &revision),
+        Err(Error::NotFound),
+    ));
+    assert_eq!(
+        text_hashes(ViewDetail::Minimal, &revision).expect("No text hashes"),
+        None,
+    );
+
+    // The minimal payload omits the heavy fields
+    let site = fixtures::site(1);
+    let page = fixtures::page(1, 10, "start");
+    let output = GetPageViewOutput {
+        viewer: Viewer {
+            site,
+            redirect_site: None,
+            user_session: None,
+        },
+        options: PageOptions::parse(""),
+        page,
+        page_revision: revision,
+        locale: str!("en"),
+        redirect_page: None,
+        wikitext: None,
+        compiled_html: None,
+        actions: None,
+        breadcrumbs: vec![],
+        rating: ScoreValue::Integer(0),
+    };
+
+    let json = serde_json::to_value(&output).expect("Unable to serialize view");
+    assert!(json.get("page").is_some(), "Page metadata missing");
+    assert!(
+        json.get("pageRevision").is_some(),
+        "Revision metadata missing"
+    );
+    assert!(json.get("wikitext").is_none(), "Wikitext included");
+    assert!(json.get("compiledHtml").is_none(), "Compiled HTML included");
+}
+
+#[test]
+fn viewer_permissions() {
+    use crate::constants::ADMIN_USER_ID;
+    use crate::models::sea_orm_active_enums::SiteRole;
+    use time::{Duration, OffsetDateTime};
+
+    let anonymous = UserPermissions::for_user(None, None);
+    let regular = UserPermissions::for_user(Some(ADMIN_USER_ID + 100), None);
+    let member =
+        UserPermissions::for_user(Some(ADMIN_USER_ID + 100), Some(SiteRole::Member));
+    let moderator =
+        UserPermissions::for_user(Some(ADMIN_USER_ID + 100), Some(SiteRole::Moderator));
+    let admin =
+        UserPermissions::for_user(Some(ADMIN_USER_ID + 100), Some(SiteRole::Admin));
+    let platform_admin = UserPermissions::for_user(Some(ADMIN_USER_ID), None);
+
+    // Roles grant increasing permissions
+    assert_eq!(anonymous, UserPermissions::default());
+    assert_eq!(regular, member);
+    assert!(regular.edit && !regular.delete && !regular.moderate && !regular.admin);
+    assert!(moderator.edit && moderator.delete && moderator.moderate && !moderator.admin);
+    assert!(admin.edit && admin.delete && admin.moderate && admin.admin);
+    assert_eq!(admin, platform_admin);
+
+    // Anonymous viewers have no permissions, whatever their role
+    assert_eq!(
+        UserPermissions::for_user(None, Some(SiteRole::Admin)),
+        anonymous
+    );
+
+    // Only moderators may see changes to deleted pages
+    assert!(matches!(
+        anonymous.check_moderate(),
+        Err(Error::InsufficientPermissions),
+    ));
+    assert!(matches!(
+        regular.check_moderate(),
+        Err(Error::InsufficientPermissions),
+    ));
+    assert!(moderator.check_moderate().is_ok());
+    assert!(admin.check_moderate().is_ok());
+
+    // Admin-only pages are hidden from everyone else
+    assert!(!anonymous.can_view("admin:manage"));
+    assert!(!regular.can_view("admin:manage"));
+    assert!(admin.can_view("admin:manage"));
+    assert!(!moderator.can_view("admin:manage"));
+
+    // Including when the slug is written differently
+    assert!(!regular.can_view("Admin:Manage"));
+    assert!(!regular.can_view("ADMIN:manage"));
+    assert!(admin.can_view("Admin:Manage"));
+
+    // Other pages are visible to all
+    for permissions in [anonymous, regular, admin] {
+        assert!(permissions.can_view("scp-001"));
+        assert!(permissions.can_view("fragment:scp-001-1"));
+        assert!(permissions.can_view("administration"));
+    }
+
+    // Scheduled pages are hidden from non-moderators until published
+    let now = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let publish_at = Some(now + Duration::days(1));
+    let after = now + Duration::days(2);
+
+    assert!(!anonymous.can_view_scheduled(publish_at, now));
+    assert!(anonymous.can_view_scheduled(publish_at, after));
+    assert!(anonymous.can_view_scheduled(None, now));
+    assert!(!regular.can_view_scheduled(publish_at, now));
+    assert!(regular.can_view_scheduled(publish_at, after));
+    assert!(regular.can_view_scheduled(None, now));
+
+    for permissions in [moderator, admin] {
+        assert!(permissions.can_view_scheduled(publish_at, now));
+        assert!(permissions.can_view_scheduled(publish_at, after));
+    }
+
+    // Expired pages are hidden from all but administrators
+    let expires_at = Some(now + Duration::days(1));
+
+    for permissions in [anonymous, regular, admin] {
+        assert!(permissions.can_view_expired(expires_at, now));
+        assert!(permissions.can_view_expired(None, after));
+    }
+
+    assert!(!anonymous.can_view_expired(expires_at, after));
+    assert!(!regular.can_view_expired(expires_at, after));
+    assert!(admin.can_view_expired(expires_at, after));
+
+    // Page actions are only included for those who can take them
+    assert_eq!(anonymous.page_actions(), None);
+    assert_eq!(
+        regular.page_actions(),
+        Some(PageActions {
+            edit: true,
+            delete: false,
+        }),
+    );
+    assert_eq!(
+        admin.page_actions(),
+        Some(PageActions {
+            edit: true,
+            delete: true,
+        }),
+    );
+}
+
+#[test]
+fn batch_views() {
+    use async_std::task::block_on;
+
+    let results = block_on(collect_views(
+        vec!["scp-001", "missing", "scp-002"],
+        BATCH_VIEW_CONCURRENCY,
+        |slug| async move {
+            match slug {
+                "missing" => Err(Error::NotFound),
+                _ => Ok(str!(slug)),
+            }
+        },
+    ))
+    .expect("Unable to collect page views");
+
+    let results: Vec<_> = results
+        .into_iter()
+        .map(|result| result.map_err(|view| (view.status, view.message_key)))
+        .collect();
+
+    assert_eq!(
+        results,
+        vec![
+            Ok(str!("scp-001")),
+            Err((404, "error-404.page")),
+            Ok(str!("scp-002")),
+        ],
+        "Batch view results don't match expected",
+    );
+
+    // Unexpected errors fail the whole batch
+    let result = block_on(collect_views(
+        vec!["scp-001", "broken"],
+        BATCH_VIEW_CONCURRENCY,
+        |slug| async move {
+            match slug {
+                "broken" => Err(Error::Inconsistent),
+                _ => Ok(str!(slug)),
+            }
+        },
+    ));
+    assert!(result.is_err(), "Unexpected error didn't fail the batch");
+}
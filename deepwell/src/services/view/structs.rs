@@ -19,21 +19,68 @@
  */
 
 use super::options::PageOptions;
+use crate::constants::ADMIN_USER_ID;
 use crate::models::page::Model as PageModel;
 use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::sea_orm_active_enums::SiteRole;
 use crate::models::session::Model as SessionModel;
 use crate::models::site::Model as SiteModel;
 use crate::models::user::Model as UserModel;
+use crate::services::page::{is_expired, is_published};
+use crate::services::score::ScoreValue;
+use crate::services::{Error, Result};
+use crate::utils::get_category_name;
+use crate::web::{check_not_blank, Validate, ValidationResult};
+use schemars::JsonSchema;
+use time::OffsetDateTime;
+use wikidot_normalize::normalize;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct GetPageView {
     pub domain: String,
     pub session_token: Option<String>,
     pub route: Option<PageRoute>,
+
+    #[serde(default)]
+    pub detail: ViewDetail,
 }
 
-#[derive(Deserialize, Debug)]
+impl Validate for GetPageView {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("domain", &self.domain)?;
+
+        if let Some(route) = &self.route {
+            check_not_blank("route.slug", &route.slug)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How much page data to include in a view.
+#[derive(Deserialize, JsonSchema, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ViewDetail {
+    /// Only page and revision metadata, without any text.
+    ///
+    /// This is suitable for prefetching, or other cases where
+    /// the page is not going to be rendered.
+    Minimal,
+
+    /// All page data, including its wikitext and compiled HTML.
+    #[default]
+    Full,
+}
+
+impl ViewDetail {
+    #[inline]
+    pub fn includes_text(self) -> bool {
+        self == ViewDetail::Full
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct PageRoute {
     pub slug: String,
@@ -49,9 +96,65 @@ pub struct GetPageViewOutput {
 
     pub page: PageModel,
     pub page_revision: PageRevisionModel,
+    pub locale: String,
+    pub redirect_page: Option<String>,
+
+    /// Only included for `ViewDetail::Full`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wikitext: Option<String>,
+
+    /// Only included for `ViewDetail::Full`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compiled_html: Option<String>,
+
+    /// Only included if the viewer may take any actions on the page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actions: Option<PageActions>,
+
+    /// The chain of ancestor pages, starting from the root.
+    pub breadcrumbs: Vec<Breadcrumb>,
+    pub rating: ScoreValue,
+}
+
+/// The result of one page view in a batch.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum PageViewResult {
+    Page(Box<GetPageViewOutput>),
+    Error(ErrorView),
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Breadcrumb {
+    pub page_id: i64,
+    pub slug: String,
+    pub title: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveRoute {
+    pub domain: String,
+
+    /// The raw request path, for instance `/scp-001/norender/true`.
+    pub path: String,
+}
+
+impl Validate for ResolveRoute {
+    fn validate(&self) -> ValidationResult {
+        check_not_blank("domain", &self.domain)
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveRouteOutput {
+    pub site: SiteModel,
+    pub redirect_site: Option<String>,
+    pub slug: String,
+    pub extra: String,
     pub redirect_page: Option<String>,
-    pub wikitext: String,
-    pub compiled_html: String,
 }
 
 #[derive(Serialize, Debug)]
@@ -62,10 +165,148 @@ pub struct Viewer {
     pub user_session: Option<UserSession>,
 }
 
+impl Viewer {
+    #[inline]
+    pub fn permissions(&self) -> UserPermissions {
+        match &self.user_session {
+            Some(user_session) => user_session.user_permissions,
+            None => UserPermissions::for_user(None, None),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteView {
+    pub site: SiteModel,
+    pub redirect_site: Option<String>,
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UserSession {
     pub session: SessionModel,
     pub user: UserModel,
-    pub user_permissions: (),
+    pub user_permissions: UserPermissions,
+}
+
+/// What a viewer is permitted to do on a site.
+#[derive(Serialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPermissions {
+    pub edit: bool,
+    pub delete: bool,
+    pub moderate: bool,
+    pub admin: bool,
+}
+
+impl UserPermissions {
+    /// Gets the permissions for a viewer from their role on the site.
+    ///
+    /// Anonymous viewers are `None`, and may not do anything but view.
+    /// Any logged-in user may edit, while the other permissions depend on
+    /// their role. The platform administrator has every permission on all sites.
+    pub fn for_user(user_id: Option<i64>, role: Option<SiteRole>) -> Self {
+        match (user_id, role) {
+            (None, _) => UserPermissions::default(),
+            (Some(ADMIN_USER_ID), _) | (Some(_), Some(SiteRole::Admin)) => {
+                UserPermissions {
+                    edit: true,
+                    delete: true,
+                    moderate: true,
+                    admin: true,
+                }
+            }
+            (Some(_), Some(SiteRole::Moderator)) => UserPermissions {
+                edit: true,
+                delete: true,
+                moderate: true,
+                admin: false,
+            },
+            (Some(_), Some(SiteRole::Member) | None) => UserPermissions {
+                edit: true,
+                delete: false,
+                moderate: false,
+                admin: false,
+            },
+        }
+    }
+
+    /// Ensures the viewer may moderate the site.
+    ///
+    /// Yields `Error::InsufficientPermissions` if they may not.
+    pub fn check_moderate(self) -> Result<()> {
+        if self.moderate {
+            Ok(())
+        } else {
+            tide::log::error!("Viewer does not have moderator permissions");
+            Err(Error::InsufficientPermissions)
+        }
+    }
+
+    /// Determines if the page with this slug may be viewed.
+    ///
+    /// Pages in the `admin` category are only visible to administrators.
+    /// The slug is normalized first, so that differently-written slugs
+    /// for the same page cannot be used to get around this.
+    pub fn can_view(self, slug: &str) -> bool {
+        let mut slug = str!(slug);
+        normalize(&mut slug);
+        self.admin || get_category_name(&slug) != "admin"
+    }
+
+    /// Determines if a page scheduled to be published at `publish_at` may be viewed.
+    ///
+    /// Until it is published, a page is only visible to site moderators.
+    pub fn can_view_scheduled(
+        self,
+        publish_at: Option<OffsetDateTime>,
+        now: OffsetDateTime,
+    ) -> bool {
+        self.moderate || is_published(publish_at, now)
+    }
+
+    /// Determines if a page which expires at `expires_at` may be viewed.
+    ///
+    /// Once it has expired, a page is only visible to administrators.
+    pub fn can_view_expired(
+        self,
+        expires_at: Option<OffsetDateTime>,
+        now: OffsetDateTime,
+    ) -> bool {
+        self.admin || !is_expired(expires_at, now)
+    }
+
+    /// Gets the actions the viewer may take on a page, if any.
+    pub fn page_actions(self) -> Option<PageActions> {
+        if self.edit || self.delete {
+            Some(PageActions {
+                edit: self.edit,
+                delete: self.delete,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Actions on a page which the viewer is permitted to take.
+///
+/// The frontend uses this to decide which page controls to show.
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PageActions {
+    pub edit: bool,
+    pub delete: bool,
+}
+
+/// A description of an expected failure in a view, for rendering an error page.
+///
+/// The `message_key` is a Fluent message ID in the `base` bundle.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorView {
+    pub status: u16,
+    pub message_key: &'static str,
+    pub detail: Option<String>,
 }
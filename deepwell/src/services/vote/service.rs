@@ -21,6 +21,8 @@
 
 use super::prelude::*;
 use crate::models::page_vote::{self, Entity as PageVote, Model as PageVoteModel};
+use crate::models::sea_orm_active_enums::{FeatureFlag, VoteType};
+use crate::services::{FeatureFlagService, PageService, SiteService};
 use sea_orm::IntoActiveModel;
 
 #[derive(Debug)]
@@ -48,17 +50,29 @@ impl VoteService {
             value,
         );
 
+        // Check voting is enabled, and the vote is valid for this site
+        let page = PageService::get_direct(ctx, page_id).await?;
+        FeatureFlagService::check(ctx, page.site_id, FeatureFlag::PageVotes).await?;
+
+        let vote_type = Self::get_site_vote_type(ctx, page.site_id).await?;
+        if !vote_type.accepts_value(value) {
+            tide::log::error!(
+                "Vote value {value} is not valid for vote type {vote_type:?}"
+            );
+            return Err(Error::BadRequest);
+        }
+
         // Get previous vote, if any
         let key = GetVote { page_id, user_id };
         if let Some(vote) = Self::get_optional(ctx, key).await? {
             // If it's the same value, no new vote is needed
-            if vote.value == value {
+            if !vote_changed(Some(vote.value), value) {
                 return Ok(None);
             }
 
             // Otherwise, delete so we can insert the new one
             let mut model = vote.into_active_model();
-            model.deleted_at = Set(Some(now()));
+            model.deleted_at = Set(Some(ctx.now()));
             model.update(txn).await?;
         }
 
@@ -74,6 +88,25 @@ impl VoteService {
         Ok(Some(vote))
     }
 
+    /// Gets the kind of votes which can be cast on this page.
+    ///
+    /// This is configured per-site.
+    pub async fn get_vote_type(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<VoteType> {
+        let page = PageService::get_direct(ctx, page_id).await?;
+        Self::get_site_vote_type(ctx, page.site_id).await
+    }
+
+    async fn get_site_vote_type(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<VoteType> {
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        Ok(site.vote_type)
+    }
+
     #[inline]
     pub async fn get(ctx: &ServiceContext<'_>, key: GetVote) -> Result<PageVoteModel> {
         find_or_error(Self::get_optional(ctx, key)).await
@@ -120,7 +153,7 @@ impl VoteService {
             vote.disabled_by = Set(None);
         } else {
             // Set "disabled" field.
-            vote.disabled_at = Set(Some(now()));
+            vote.disabled_at = Set(Some(ctx.now()));
             vote.disabled_by = Set(Some(acting_user_id));
         }
 
@@ -134,7 +167,7 @@ impl VoteService {
 
         let txn = ctx.transaction();
         let mut vote = Self::get(ctx, key).await?.into_active_model();
-        vote.deleted_at = Set(Some(now()));
+        vote.deleted_at = Set(Some(ctx.now()));
 
         let model = vote.update(txn).await?;
         Ok(model)
@@ -221,3 +254,131 @@ impl VoteService {
             .add_option(disabled_condition)
     }
 }
+
+/// Determines if casting a vote with `value` changes the user's current vote.
+///
+/// Users may only have one active vote per page, so re-casting
+/// the same vote does nothing, rather than counting twice.
+fn vote_changed(previous: Option<VoteValue>, value: VoteValue) -> bool {
+    previous != Some(value)
+}
+
+#[test]
+fn cast_votes() {
+    use crate::api::build_test_server_state;
+    use crate::models::page::Model as PageModel;
+    use crate::models::site::Model as SiteModel;
+    use crate::models::site_feature_flag::Model as SiteFeatureFlagModel;
+    use async_std::task;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait};
+    use std::sync::Arc;
+    use time::OffsetDateTime;
+
+    let epoch = OffsetDateTime::UNIX_EPOCH;
+    let page = PageModel {
+        page_id: 10,
+        created_at: epoch,
+        updated_at: None,
+        deleted_at: None,
+        deletion_reason: None,
+        from_wikidot: false,
+        site_id: 1,
+        page_category_id: 1,
+        slug: str!("scp-001"),
+        locale: None,
+        discussion_thread_id: None,
+        keywords: vec![],
+        publish_at: None,
+        expires_at: None,
+    };
+    let site = SiteModel {
+        site_id: 1,
+        created_at: epoch,
+        updated_at: None,
+        deleted_at: None,
+        from_wikidot: false,
+        slug: str!("test"),
+        name: str!("Test Wiki"),
+        tagline: String::new(),
+        description: String::new(),
+        locale: str!("en"),
+        default_page: str!("start"),
+        default_category: str!("_default"),
+        slug_allow_categories: true,
+        slug_pattern: None,
+        require_revision_comments: false,
+        allow_anonymous_edits: false,
+        vote_type: VoteType::UpsDowns,
+        retain_compiled_html: false,
+        disabled_blocks: vec![],
+        robots_txt: None,
+        custom_domain: None,
+    };
+    let vote = |page_vote_id, value| PageVoteModel {
+        page_vote_id,
+        created_at: epoch,
+        deleted_at: None,
+        disabled_at: None,
+        disabled_by: None,
+        from_wikidot: false,
+        page_id: 10,
+        user_id: 5,
+        value,
+    };
+
+    // Results for each query, in the order VoteService::add() makes them.
+    // The page, site, and feature flags are cached after the first vote.
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        // First vote: page, flags, site, no previous vote, inserted vote
+        .append_query_results([[page]])
+        .append_query_results([Vec::<SiteFeatureFlagModel>::new()])
+        .append_query_results([[site]])
+        .append_query_results([vec![], vec![vote(100, 1)]])
+        // Same vote again: previous vote
+        .append_query_results([[vote(100, 1)]])
+        // Changed vote: previous vote, deleted vote, inserted vote
+        .append_query_results([[vote(100, 1)], [vote(100, 1)], [vote(101, -1)]])
+        .into_connection();
+
+    let state = task::block_on(build_test_server_state(database));
+    let (first, again, changed) = task::block_on(async {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::from_raw(&state, &txn);
+        let cast = |value| {
+            VoteService::add(
+                &ctx,
+                CreateVote {
+                    page_id: 10,
+                    user_id: 5,
+                    value,
+                },
+            )
+        };
+
+        let first = cast(1).await?;
+        let again = cast(1).await?;
+        let changed = cast(-1).await?;
+        txn.commit().await?;
+        Ok::<_, Error>((first, again, changed))
+    })
+    .expect("Unable to cast votes");
+
+    assert_eq!(first.map(|vote| vote.page_vote_id), Some(100));
+    assert_eq!(again, None, "Double vote was cast");
+    assert_eq!(changed.map(|vote| vote.value), Some(-1));
+
+    // Only the first and changed votes were inserted,
+    // and the changed vote replaced the previous one
+    let state = Arc::try_unwrap(state).expect("Server state still in use");
+    let log = format!("{:?}", state.database.into_transaction_log());
+    assert_eq!(
+        log.matches("INSERT INTO").count(),
+        2,
+        "Wrong votes cast: {log}"
+    );
+    assert_eq!(
+        log.matches("UPDATE").count(),
+        1,
+        "Wrong votes deleted: {log}"
+    );
+}
@@ -18,6 +18,8 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::web::Validate;
+
 pub type VoteValue = i16;
 
 #[derive(Deserialize, Debug, Copy, Clone)]
@@ -28,6 +30,8 @@ pub struct CreateVote {
     pub value: VoteValue,
 }
 
+impl Validate for CreateVote {}
+
 #[derive(Deserialize, Debug, Copy, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct GetVote {
@@ -35,6 +39,8 @@ pub struct GetVote {
     pub user_id: i64,
 }
 
+impl Validate for GetVote {}
+
 #[derive(Deserialize, Debug, Copy, Clone)]
 #[serde(rename_all = "camelCase", tag = "type", content = "id")]
 pub enum VoteHistoryKind {
@@ -53,6 +59,8 @@ pub struct GetVoteHistory {
     pub limit: u64,
 }
 
+impl Validate for GetVoteHistory {}
+
 #[derive(Deserialize, Debug, Copy, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CountVoteHistory {
@@ -63,6 +71,8 @@ pub struct CountVoteHistory {
     pub disabled: Option<bool>,
 }
 
+impl Validate for CountVoteHistory {}
+
 #[derive(Deserialize, Debug, Copy, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct VoteAction {
@@ -71,3 +81,5 @@ pub struct VoteAction {
     pub enable: bool,
     pub acting_user_id: i64,
 }
+
+impl Validate for VoteAction {}
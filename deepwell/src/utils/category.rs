@@ -47,7 +47,6 @@ pub fn split_category_name(slug: &str) -> (&str, &str) {
 
 /// Retrieves the category portion of a slug, if it exists.
 #[inline]
-#[allow(dead_code)] // TEMP
 pub fn get_category(slug: &str) -> Option<&str> {
     split_category(slug).0
 }
@@ -67,8 +66,21 @@ pub fn trim_default(slug: &str) -> &str {
     // "_default:blah:page-name") then this will
     // mangle the category name.
 
+    trim_category(slug, "_default")
+}
+
+/// Trims off the given default category if present.
+///
+/// This is like `trim_default()`, but for sites which have configured
+/// a different name for their default category. The explicit `_default`
+/// category is always trimmed.
+pub fn trim_category<'a>(slug: &'a str, default_category: &str) -> &'a str {
     match split_category_name(slug) {
-        ("_default", page_slug) => page_slug,
+        (category, page_slug)
+            if category == "_default" || category == default_category =>
+        {
+            page_slug
+        }
         (_, _) => slug,
     }
 }
@@ -186,3 +198,26 @@ fn test_trim_default() {
     check!("archived:_default:start", "archived:_default:start");
     check!("_default:archived:start", "_default:archived:start");
 }
+
+#[test]
+fn test_trim_category() {
+    macro_rules! check {
+        ($input:expr, $default_category:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                trim_category($input, $default_category),
+                $expected,
+                "Actual trimmed slug doesn't match expected",
+            )
+        };
+    }
+
+    check!("apple", "_default", "apple");
+    check!("_default:start", "_default", "start");
+    check!("component:wide-modal", "_default", "component:wide-modal");
+    check!("apple", "main", "apple");
+    check!("main:start", "main", "start");
+    check!("_default:start", "main", "start");
+    check!("component:wide-modal", "main", "component:wide-modal");
+    check!("archived:main:start", "main", "archived:main:start");
+    check!("main:archived:start", "main", "main:archived:start");
+}
@@ -19,7 +19,9 @@
  */
 
 use crate::services::{Error, Result};
+use async_std::task;
 use std::future::Future;
+use std::time::Duration;
 
 pub async fn find_or_error<F, T>(future: F) -> Result<T>
 where
@@ -27,3 +29,81 @@ where
 {
     future.await?.ok_or(Error::NotFound)
 }
+
+/// Runs an operation, retrying it if it fails with a transient error.
+///
+/// The operation is attempted up to `attempts` times in total, sleeping
+/// in between each, starting with `delay` and doubling after every failure.
+/// Errors which are not transient (see `Error::is_transient()`) are
+/// returned immediately.
+///
+/// This must only be used for idempotent operations, such as reads.
+pub async fn retry_with_backoff<F, Fut, T>(
+    attempts: u32,
+    mut delay: Duration,
+    mut operation: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+
+    loop {
+        match operation().await {
+            Err(error) if attempt < attempts && error.is_transient() => {
+                tide::log::warn!(
+                    "Transient error on attempt {attempt} of {attempts}, retrying in {delay:?}: {error}",
+                );
+
+                task::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[test]
+fn retry() {
+    use sea_orm::DbErr;
+    use std::cell::Cell;
+
+    macro_rules! check {
+        ($attempts:expr, $failures:expr, $error:expr, $ok:expr, $calls:expr $(,)?) => {{
+            let calls = Cell::new(0);
+            let calls_ref = &calls;
+            let result = task::block_on(retry_with_backoff(
+                $attempts,
+                Duration::from_millis(1),
+                move || async move {
+                    let calls = calls_ref;
+                    calls.set(calls.get() + 1);
+                    if calls.get() <= $failures {
+                        Err($error)
+                    } else {
+                        Ok(calls.get())
+                    }
+                },
+            ));
+
+            assert_eq!(result.is_ok(), $ok, "Retry result doesn't match expected");
+            assert_eq!(calls.get(), $calls, "Call count doesn't match expected");
+        }};
+    }
+
+    // Succeeds first try
+    check!(3, 0, Error::Database(DbErr::ConnectionAcquire), true, 1);
+
+    // Transient failure, then success
+    check!(3, 1, Error::Database(DbErr::ConnectionAcquire), true, 2);
+    check!(3, 2, Error::Database(DbErr::ConnectionAcquire), true, 3);
+
+    // Too many transient failures
+    check!(3, 5, Error::Database(DbErr::ConnectionAcquire), false, 3);
+
+    // Non-transient failures are not retried
+    check!(3, 1, Error::NotFound, false, 1);
+    check!(3, 1, Error::Conflict, false, 1);
+}
@@ -27,3 +27,31 @@ pub fn validate_locale(locale_str: &str) -> Result<LanguageIdentifier> {
         Error::BadRequest
     })
 }
+
+/// Validates a locale, returning it in canonical form.
+///
+/// For instance, `en-us` becomes `en-US`.
+pub fn canonicalize_locale(locale_str: &str) -> Result<String> {
+    validate_locale(locale_str).map(|locale| locale.to_string())
+}
+
+#[test]
+fn test_canonicalize_locale() {
+    macro_rules! check {
+        ($input:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                canonicalize_locale($input).ok().as_deref(),
+                $expected,
+                "Actual canonical locale doesn't match expected",
+            )
+        };
+    }
+
+    check!("en", Some("en"));
+    check!("fr-CA", Some("fr-CA"));
+    check!("en-us", Some("en-US"));
+    check!("ZH-hans", Some("zh-Hans"));
+    check!("x", None);
+    check!("12", None);
+    check!("en-!", None);
+}
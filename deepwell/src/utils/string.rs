@@ -34,3 +34,27 @@ pub fn regex_replace_in_place(string: &mut String, pattern: &Regex, replacement:
         string.replace_range(range, replacement);
     }
 }
+
+/// Escapes the characters which are special in XML text and attribute values.
+pub fn escape_xml(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+
+    for ch in input.chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&apos;"),
+            _ => output.push(ch),
+        }
+    }
+
+    output
+}
+
+#[test]
+fn test_escape_xml() {
+    assert_eq!(escape_xml("apple"), "apple");
+    assert_eq!(escape_xml("a&b<c>'d\""), "a&amp;b&lt;c&gt;&apos;d&quot;");
+}
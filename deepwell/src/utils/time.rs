@@ -18,9 +18,71 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::sync::{Arc, Mutex};
+#[cfg(test)]
+use std::time::Duration as StdDuration;
 use time::OffsetDateTime;
 
 #[inline]
 pub fn now() -> OffsetDateTime {
     OffsetDateTime::now_utc()
 }
+
+/// A source of the current time.
+///
+/// Normally this reads the system time, but it can be fixed to a
+/// particular instant so that time-dependent logic can be tested.
+/// Clones of a fixed clock share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct Clock {
+    fixed: Option<Arc<Mutex<OffsetDateTime>>>,
+}
+
+impl Clock {
+    #[inline]
+    pub fn system() -> Self {
+        Clock::default()
+    }
+
+    #[cfg(test)]
+    pub fn fixed(time: OffsetDateTime) -> Self {
+        Clock {
+            fixed: Some(Arc::new(Mutex::new(time))),
+        }
+    }
+
+    pub fn now(&self) -> OffsetDateTime {
+        match self.fixed {
+            None => now(),
+            Some(ref time) => *time.lock().expect("Clock mutex poisoned"),
+        }
+    }
+
+    /// Moves a fixed clock forward by the given amount.
+    ///
+    /// # Panics
+    /// Panics if this clock uses the system time.
+    #[cfg(test)]
+    pub fn advance(&self, duration: StdDuration) {
+        let time = self
+            .fixed
+            .as_ref()
+            .expect("Cannot advance the system clock");
+        *time.lock().expect("Clock mutex poisoned") += duration;
+    }
+}
+
+#[test]
+fn clock() {
+    let start = OffsetDateTime::from_unix_timestamp(1675166400).unwrap();
+    let clock = Clock::fixed(start);
+    let clone = clock.clone();
+    assert_eq!(clock.now(), start);
+
+    clock.advance(StdDuration::from_secs(60));
+    assert_eq!(clock.now(), start + StdDuration::from_secs(60));
+    assert_eq!(clone.now(), clock.now(), "Cloned clock has different time");
+
+    let system = Clock::system();
+    assert!(system.now() > start);
+}
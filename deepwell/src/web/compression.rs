@@ -0,0 +1,296 @@
+/*
+ * web/compression.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Compression of large responses, negotiated via `Accept-Encoding`.
+//!
+//! Only bodies of a known length above the configured threshold are compressed,
+//! so streaming responses such as server-sent events are passed through as-is.
+
+use crate::config::Config;
+use brotli::CompressorWriter;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{self, Write};
+use tide::{Body, Middleware, Next, Request, Response};
+
+const ACCEPT_ENCODING: &str = "Accept-Encoding";
+const CONTENT_ENCODING: &str = "Content-Encoding";
+const VARY: &str = "Vary";
+
+/// Brotli settings, chosen to favor speed since responses are compressed per request.
+const BROTLI_BUFFER_SIZE: usize = 4096;
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW_SIZE: u32 = 22;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /// Picks the encoding to use from an `Accept-Encoding` header value, if any.
+    ///
+    /// The encoding with the highest quality value is chosen,
+    /// preferring brotli if both are equally acceptable.
+    pub fn negotiate(accept_encoding: &str) -> Option<Self> {
+        let mut brotli = None;
+        let mut gzip = None;
+        let mut wildcard = None;
+
+        for item in accept_encoding.split(',') {
+            let mut parts = item.split(';').map(str::trim);
+            let name = parts.next().unwrap_or("");
+            let quality = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .map(|value| value.parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+
+            match name.to_ascii_lowercase().as_str() {
+                "br" => brotli = Some(quality),
+                "gzip" => gzip = Some(quality),
+                "*" => wildcard = Some(quality),
+                _ => (),
+            }
+        }
+
+        // Encodings not listed get the wildcard's quality, if present
+        let brotli = brotli.or(wildcard).unwrap_or(0.0);
+        let gzip = gzip.or(wildcard).unwrap_or(0.0);
+
+        if brotli > 0.0 && brotli >= gzip {
+            Some(Encoding::Brotli)
+        } else if gzip > 0.0 {
+            Some(Encoding::Gzip)
+        } else {
+            None
+        }
+    }
+
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Encoding::Brotli => {
+                let mut writer = CompressorWriter::new(
+                    Vec::new(),
+                    BROTLI_BUFFER_SIZE,
+                    BROTLI_QUALITY,
+                    BROTLI_WINDOW_SIZE,
+                );
+                writer.write_all(data)?;
+                Ok(writer.into_inner())
+            }
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct CompressionPolicy {
+    threshold: usize,
+}
+
+impl CompressionPolicy {
+    #[inline]
+    pub fn new(threshold: usize) -> Self {
+        CompressionPolicy { threshold }
+    }
+
+    #[inline]
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.compression_threshold)
+    }
+
+    /// Compresses the response body, if it is large enough.
+    async fn apply(
+        &self,
+        encoding: Encoding,
+        response: &mut Response,
+    ) -> tide::Result<()> {
+        // Already encoded, or unknown length (i.e. streaming)
+        if response.header(CONTENT_ENCODING).is_some() {
+            return Ok(());
+        }
+
+        match response.len() {
+            Some(length) if length > self.threshold => (),
+            _ => return Ok(()),
+        }
+
+        let body = response.take_body();
+        let mime = body.mime().clone();
+        let data = body.into_bytes().await?;
+        let compressed = encoding.compress(&data)?;
+
+        tide::log::debug!(
+            "Compressed response body with {} ({} -> {} bytes)",
+            encoding.name(),
+            data.len(),
+            compressed.len(),
+        );
+
+        let mut body = Body::from_bytes(compressed);
+        body.set_mime(mime);
+        response.set_body(body);
+        response.insert_header(CONTENT_ENCODING, encoding.name());
+        Ok(())
+    }
+}
+
+#[tide::utils::async_trait]
+impl<State> Middleware<State> for CompressionPolicy
+where
+    State: Clone + Send + Sync + 'static,
+{
+    async fn handle(&self, req: Request<State>, next: Next<'_, State>) -> tide::Result {
+        let encoding = req
+            .header(ACCEPT_ENCODING)
+            .and_then(|values| Encoding::negotiate(values.last().as_str()));
+
+        let mut response = next.run(req).await;
+
+        // Since the body depends on the request's encodings, caches must be aware of it.
+        response.append_header(VARY, ACCEPT_ENCODING);
+
+        if let Some(encoding) = encoding {
+            self.apply(encoding, &mut response).await?;
+        }
+
+        Ok(response)
+    }
+}
+
+#[test]
+fn negotiate() {
+    macro_rules! check {
+        ($accept_encoding:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                Encoding::negotiate($accept_encoding),
+                $expected,
+                "Negotiated encoding for {:?} doesn't match expected",
+                $accept_encoding,
+            );
+        };
+    }
+
+    check!("", None);
+    check!("identity", None);
+    check!("gzip", Some(Encoding::Gzip));
+    check!("br", Some(Encoding::Brotli));
+    check!("gzip, deflate, br", Some(Encoding::Brotli));
+    check!("gzip;q=1.0, br;q=0.5", Some(Encoding::Gzip));
+    check!("br;q=0, gzip", Some(Encoding::Gzip));
+    check!("gzip;q=0", None);
+    check!("*", Some(Encoding::Brotli));
+    check!("*, br;q=0", Some(Encoding::Gzip));
+}
+
+#[test]
+fn compression() {
+    use super::testing::header_str;
+    use async_std::task;
+    use brotli::Decompressor;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use tide::http::{Method, Request as HttpRequest, Response as HttpResponse, Url};
+
+    let large = "x".repeat(4096);
+    let small = "x".repeat(16);
+
+    let mut app = tide::new();
+    app.with(CompressionPolicy::new(1024));
+    app.at("/large").get({
+        let large = large.clone();
+        move |_| {
+            let large = large.clone();
+            async move { Ok(large) }
+        }
+    });
+    app.at("/small").get({
+        let small = small.clone();
+        move |_| {
+            let small = small.clone();
+            async move { Ok(small) }
+        }
+    });
+
+    let send = |path: &str, accept_encoding: Option<&str>| {
+        let url = Url::parse("http://localhost").unwrap().join(path).unwrap();
+        let mut req = HttpRequest::new(Method::Get, url);
+
+        if let Some(accept_encoding) = accept_encoding {
+            req.insert_header(ACCEPT_ENCODING, accept_encoding);
+        }
+
+        let response: HttpResponse = task::block_on(app.respond(req)).unwrap();
+        let mut response = Response::from(response);
+        let body = task::block_on(response.take_body().into_bytes()).unwrap();
+        (response, body)
+    };
+
+    // Large response, client supports gzip
+    let (response, body) = send("/large", Some("gzip"));
+    assert_eq!(
+        header_str(&response, CONTENT_ENCODING).as_deref(),
+        Some("gzip"),
+    );
+    assert!(body.len() < large.len(), "Compressed body is not smaller");
+
+    let mut decompressed = String::new();
+    GzDecoder::new(body.as_slice())
+        .read_to_string(&mut decompressed)
+        .expect("Unable to decompress body");
+    assert_eq!(decompressed, large);
+
+    // Large response, client supports brotli
+    let (response, body) = send("/large", Some("gzip, br"));
+    assert_eq!(
+        header_str(&response, CONTENT_ENCODING).as_deref(),
+        Some("br")
+    );
+    assert!(body.len() < large.len(), "Compressed body is not smaller");
+
+    let mut decompressed = String::new();
+    Decompressor::new(body.as_slice(), BROTLI_BUFFER_SIZE)
+        .read_to_string(&mut decompressed)
+        .expect("Unable to decompress body");
+    assert_eq!(decompressed, large);
+
+    // Large response, client doesn't support compression
+    let (response, body) = send("/large", None);
+    assert_eq!(header_str(&response, CONTENT_ENCODING), None);
+    assert_eq!(body, large.as_bytes());
+
+    // Small response is left as-is
+    let (response, body) = send("/small", Some("gzip"));
+    assert_eq!(header_str(&response, CONTENT_ENCODING), None);
+    assert_eq!(body, small.as_bytes());
+}
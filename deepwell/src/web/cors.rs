@@ -0,0 +1,212 @@
+/*
+ * web/cors.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Cross-origin resource sharing (CORS) handling.
+//!
+//! Browser frontends on other origins need these headers in order to
+//! read responses from DEEPWELL. Only origins which are explicitly
+//! configured are permitted, by default none are.
+
+use crate::config::Config;
+use tide::http::Method;
+use tide::{Middleware, Next, Request, Response, StatusCode};
+
+const ORIGIN: &str = "Origin";
+const REQUEST_METHOD: &str = "Access-Control-Request-Method";
+const ALLOW_ORIGIN: &str = "Access-Control-Allow-Origin";
+const ALLOW_METHODS: &str = "Access-Control-Allow-Methods";
+const ALLOW_HEADERS: &str = "Access-Control-Allow-Headers";
+const MAX_AGE: &str = "Access-Control-Max-Age";
+const VARY: &str = "Vary";
+
+#[derive(Debug, Clone, Default)]
+pub struct CorsPolicy {
+    allowed_origins: Vec<String>,
+    allowed_methods: String,
+    allowed_headers: String,
+    max_age_secs: u64,
+}
+
+impl CorsPolicy {
+    pub fn new(
+        allowed_origins: Vec<String>,
+        allowed_methods: &[String],
+        allowed_headers: &[String],
+        max_age_secs: u64,
+    ) -> Self {
+        CorsPolicy {
+            allowed_origins,
+            allowed_methods: allowed_methods.join(", "),
+            allowed_headers: allowed_headers.join(", "),
+            max_age_secs,
+        }
+    }
+
+    #[inline]
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config.cors_allowed_origins.clone(),
+            &config.cors_allowed_methods,
+            &config.cors_allowed_headers,
+            config.cors_max_age.as_secs(),
+        )
+    }
+
+    /// Determines if this origin is permitted to make cross-origin requests.
+    ///
+    /// The special origin `*` permits any origin.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+    }
+
+    /// Adds the headers permitting the given origin to read the response.
+    ///
+    /// Nothing is added if the origin is not permitted.
+    pub fn apply(&self, origin: Option<&str>, response: &mut Response) {
+        // Since the value depends on the origin, caches must be aware of it.
+        response.append_header(VARY, ORIGIN);
+
+        if let Some(origin) = origin {
+            if self.allows_origin(origin) {
+                response.insert_header(ALLOW_ORIGIN, origin);
+            } else {
+                tide::log::debug!(
+                    "Not adding CORS headers for disallowed origin {origin}"
+                );
+            }
+        }
+    }
+
+    /// Builds the response for a preflight (`OPTIONS`) request.
+    pub fn preflight(&self, origin: Option<&str>) -> Response {
+        let mut response = Response::new(StatusCode::NoContent);
+        self.apply(origin, &mut response);
+
+        if response.header(ALLOW_ORIGIN).is_some() {
+            response.insert_header(ALLOW_METHODS, self.allowed_methods.as_str());
+            response.insert_header(ALLOW_HEADERS, self.allowed_headers.as_str());
+            response.insert_header(MAX_AGE, self.max_age_secs.to_string());
+        }
+
+        response
+    }
+}
+
+#[tide::utils::async_trait]
+impl<State> Middleware<State> for CorsPolicy
+where
+    State: Clone + Send + Sync + 'static,
+{
+    async fn handle(&self, req: Request<State>, next: Next<'_, State>) -> tide::Result {
+        let origin = req
+            .header(ORIGIN)
+            .map(|values| values.last().as_str().to_owned());
+
+        // Answer preflight requests directly, without routing
+        let is_preflight =
+            req.method() == Method::Options && req.header(REQUEST_METHOD).is_some();
+
+        if is_preflight {
+            return Ok(self.preflight(origin.as_deref()));
+        }
+
+        let mut response = next.run(req).await;
+        self.apply(origin.as_deref(), &mut response);
+        Ok(response)
+    }
+}
+
+#[test]
+fn cors() {
+    use super::testing::header_str;
+    use async_std::task;
+    use tide::http::{Request as HttpRequest, Response as HttpResponse, Url};
+
+    let policy = CorsPolicy::new(
+        vec![str!("https://wikijump.com")],
+        &[str!("GET"), str!("PUT")],
+        &[str!("Content-Type")],
+        60,
+    );
+
+    let mut app = tide::new();
+    app.with(policy);
+    app.at("/ping").get(|_| async { Ok("Pong!") });
+
+    let send = |method, origin: Option<&str>, preflight| {
+        let url = Url::parse("http://localhost/ping").unwrap();
+        let mut req = HttpRequest::new(method, url);
+
+        if let Some(origin) = origin {
+            req.insert_header(ORIGIN, origin);
+        }
+
+        if preflight {
+            req.insert_header(REQUEST_METHOD, "GET");
+        }
+
+        let response: HttpResponse = task::block_on(app.respond(req)).unwrap();
+        Response::from(response)
+    };
+
+    // Allowed origin
+    let response = send(Method::Get, Some("https://wikijump.com"), false);
+    assert_eq!(response.status(), StatusCode::Ok);
+    assert_eq!(
+        header_str(&response, ALLOW_ORIGIN).as_deref(),
+        Some("https://wikijump.com"),
+    );
+
+    let response = send(Method::Options, Some("https://wikijump.com"), true);
+    assert_eq!(response.status(), StatusCode::NoContent);
+    assert_eq!(
+        header_str(&response, ALLOW_ORIGIN).as_deref(),
+        Some("https://wikijump.com"),
+    );
+    assert_eq!(
+        header_str(&response, ALLOW_METHODS).as_deref(),
+        Some("GET, PUT")
+    );
+    assert_eq!(
+        header_str(&response, ALLOW_HEADERS).as_deref(),
+        Some("Content-Type"),
+    );
+    assert_eq!(header_str(&response, MAX_AGE).as_deref(), Some("60"));
+
+    // Disallowed origin
+    let response = send(Method::Get, Some("https://evil.example.com"), false);
+    assert_eq!(response.status(), StatusCode::Ok);
+    assert_eq!(header_str(&response, ALLOW_ORIGIN), None);
+
+    let response = send(Method::Options, Some("https://evil.example.com"), true);
+    assert_eq!(header_str(&response, ALLOW_ORIGIN), None);
+    assert_eq!(header_str(&response, ALLOW_METHODS), None);
+
+    // No origin (not a cross-origin request)
+    let response = send(Method::Get, None, false);
+    assert_eq!(response.status(), StatusCode::Ok);
+    assert_eq!(header_str(&response, ALLOW_ORIGIN), None);
+
+    // Default policy allows nothing
+    let policy = CorsPolicy::default();
+    assert!(!policy.allows_origin("https://wikijump.com"));
+}
@@ -18,20 +18,39 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+mod compression;
 mod connection_type;
+mod cors;
 mod fetch_direction;
 mod file_details;
+mod openapi;
 mod page_details;
 mod page_order;
+mod pagination;
 mod provided_value;
 mod reference;
+mod schema;
+mod security_headers;
 mod unwrap;
+mod validate;
 
+#[cfg(test)]
+mod testing;
+
+pub use self::compression::CompressionPolicy;
 pub use self::connection_type::ConnectionType;
+pub use self::cors::CorsPolicy;
 pub use self::fetch_direction::FetchDirection;
 pub use self::file_details::FileDetailsQuery;
+pub use self::openapi::openapi_document;
 pub use self::page_details::PageDetailsQuery;
 pub use self::page_order::{PageOrder, PageOrderColumn};
+pub use self::pagination::Pagination;
 pub use self::provided_value::ProvidedValue;
 pub use self::reference::Reference;
+pub use self::schema::input_schemas;
+pub use self::security_headers::SecurityHeaders;
 pub use self::unwrap::HttpUnwrap;
+pub use self::validate::{
+    body_json_validated, check_not_blank, Validate, ValidationError, ValidationResult,
+};
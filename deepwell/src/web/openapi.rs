@@ -0,0 +1,726 @@
+/*
+ * web/openapi.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Generation of an OpenAPI 3 document describing the HTTP API.
+//!
+//! Routes registered with tide cannot be inspected afterwards, so the
+//! operations here are listed by hand, and must be kept in sync with `api.rs`.
+//! The `all_routes` test checks that every route there is listed.
+//!
+//! Routes accepting any method are described as `GET`.
+//!
+//! TODO: Response bodies do not have schemas yet.
+
+use super::schema::input_components;
+use crate::info;
+use serde_json::{json, Map, Value as JsonValue};
+
+/// An API operation, that is, a method on a route.
+#[derive(Debug)]
+struct Operation {
+    method: &'static str,
+
+    /// The route, with parameters in OpenAPI form, e.g. `/page/direct/{page_id}`.
+    path: &'static str,
+
+    /// The name of the endpoint function.
+    id: &'static str,
+
+    /// The name of the type for the request body, if any.
+    input: Option<&'static str>,
+}
+
+macro_rules! operations {
+    ($(($method:expr, $path:expr, $id:ident, $input:expr)),+ $(,)?) => {
+        &[
+            $(
+                Operation {
+                    method: $method,
+                    path: $path,
+                    id: stringify!($id),
+                    input: $input,
+                },
+            )+
+        ]
+    };
+}
+
+const OPERATIONS: &[Operation] = operations![
+    // Miscellaneous
+    ("get", "/ping", ping, None),
+    ("get", "/version", version, None),
+    ("get", "/version/full", full_version, None),
+    ("get", "/hostname", hostname, None),
+    ("get", "/config", config_dump, None),
+    ("get", "/schema", schema_retrieve, None),
+    ("get", "/openapi.json", openapi_retrieve, None),
+    ("get", "/normalize/{input}", normalize_method, None),
+    ("get", "/teapot", teapot, None),
+    // Localization
+    ("get", "/locale/{locale}", locale_get, None),
+    (
+        "put",
+        "/message/{locale}/{message_key}",
+        message_put,
+        Some("MessageArguments")
+    ),
+    // View
+    ("put", "/view/page", view_page, Some("GetPageView")),
+    ("put", "/view/pages", view_pages, None),
+    ("put", "/view/resolve", view_resolve, Some("ResolveRoute")),
+    // Authentication
+    ("post", "/auth/login", auth_login, Some("LoginUser")),
+    ("delete", "/auth/logout", auth_logout, None),
+    ("post", "/auth/mfa", auth_mfa_verify, Some("LoginUserMfa")),
+    ("get", "/auth/session/get", auth_session_retrieve, None),
+    (
+        "post",
+        "/auth/session/renew",
+        auth_session_renew,
+        Some("RenewSession")
+    ),
+    ("post", "/auth/session/refresh", auth_session_refresh, None),
+    (
+        "delete",
+        "/auth/session/others",
+        auth_session_invalidate_others,
+        Some("InvalidateOtherSessions")
+    ),
+    (
+        "put",
+        "/auth/session/others/get",
+        auth_session_retrieve_others,
+        Some("GetOtherSessions")
+    ),
+    ("post", "/auth/mfa/install", auth_mfa_setup, Some("GetUser")),
+    (
+        "delete",
+        "/auth/mfa/install",
+        auth_mfa_disable,
+        Some("MultiFactorConfigure")
+    ),
+    (
+        "post",
+        "/auth/mfa/resetRecovery",
+        auth_mfa_reset_recovery,
+        Some("MultiFactorConfigure")
+    ),
+    // Site
+    ("put", "/site", site_put, Some("UpdateSite")),
+    ("delete", "/site", site_delete, Some("DeleteSite")),
+    ("put", "/site/get", site_retrieve, Some("GetSite")),
+    ("post", "/site/create", site_create, Some("CreateSite")),
+    ("put", "/site/export", site_export, Some("ExportSite")),
+    (
+        "post",
+        "/site/import",
+        site_import,
+        Some("ImportSiteExport")
+    ),
+    (
+        "put",
+        "/site/provenance",
+        site_provenance_retrieve,
+        Some("GetSite")
+    ),
+    (
+        "put",
+        "/site/sitemap",
+        site_sitemap_retrieve,
+        Some("GetSitemap")
+    ),
+    ("put", "/site/robots", site_robots_retrieve, Some("GetSite")),
+    (
+        "put",
+        "/site/feed",
+        site_feed_retrieve,
+        Some("GetRecentChangesFeed")
+    ),
+    ("post", "/site/rerender", site_rerender, Some("GetSite")),
+    (
+        "post",
+        "/site/domain/custom",
+        site_custom_domain_post,
+        Some("CreateCustomDomain")
+    ),
+    (
+        "delete",
+        "/site/domain/custom",
+        site_custom_domain_delete,
+        None
+    ),
+    (
+        "get",
+        "/site/domain/custom/get",
+        site_custom_domain_retrieve,
+        None
+    ),
+    (
+        "get",
+        "/site/domain/custom/all",
+        site_custom_domain_list_all,
+        None
+    ),
+    (
+        "post",
+        "/site/domain/custom/verification",
+        site_custom_domain_verification_post,
+        Some("GenerateDomainVerification")
+    ),
+    (
+        "put",
+        "/site/domain/custom/verify",
+        site_custom_domain_verify,
+        None
+    ),
+    (
+        "get",
+        "/site/fromDomain/{domain}",
+        site_get_from_domain,
+        None
+    ),
+    (
+        "put",
+        "/site/features",
+        site_feature_flag_put,
+        Some("SetFeatureFlag")
+    ),
+    (
+        "get",
+        "/site/features/{site_id}",
+        site_feature_flags_retrieve,
+        None
+    ),
+    (
+        "put",
+        "/site/member",
+        site_member_role_put,
+        Some("SetSiteRole")
+    ),
+    (
+        "put",
+        "/site/member/get",
+        site_member_role_retrieve,
+        Some("GetSiteRole")
+    ),
+    // Category
+    ("get", "/category", category_get, Some("GetCategory")),
+    ("get", "/category/site", category_all_get, Some("GetSite")),
+    (
+        "put",
+        "/category/rebuild",
+        category_rebuild,
+        Some("GetSite")
+    ),
+    // Page
+    ("post", "/page", page_edit, Some("EditPage")),
+    ("delete", "/page", page_delete, Some("DeletePage")),
+    ("put", "/page/get", page_retrieve, Some("GetPage")),
+    ("post", "/page/create", page_create, Some("CreatePage")),
+    ("get", "/page/direct/{page_id}", page_get_direct, None),
+    ("post", "/page/move", page_move, Some("MovePage")),
+    ("put", "/page/rerender", page_rerender, None),
+    ("post", "/page/restore", page_restore, Some("RestorePage")),
+    (
+        "post",
+        "/page/restore/slug",
+        page_restore_by_slug,
+        Some("RestorePageBySlug")
+    ),
+    (
+        "put",
+        "/page/recent",
+        page_recent_changes,
+        Some("GetRecentChanges")
+    ),
+    (
+        "get",
+        "/page/recent/stream/{site_id}",
+        page_recent_changes_stream,
+        None
+    ),
+    (
+        "put",
+        "/page/deleted",
+        page_deleted_retrieve,
+        Some("GetDeletedPages")
+    ),
+    ("put", "/page/schedule", page_schedule, Some("SchedulePage")),
+    (
+        "put",
+        "/page/expiry",
+        page_set_expiry,
+        Some("SetPageExpiry")
+    ),
+    (
+        "get",
+        "/page/duplicates/{site_id}",
+        page_duplicates_retrieve,
+        None
+    ),
+    (
+        "put",
+        "/page/historical",
+        page_historical_retrieve,
+        Some("FindHistoricalSlug")
+    ),
+    (
+        "put",
+        "/page/keyword",
+        page_keyword_retrieve,
+        Some("FindPagesByKeyword")
+    ),
+    (
+        "put",
+        "/page/excerpt",
+        page_excerpt_retrieve,
+        Some("GetPageExcerpt")
+    ),
+    // Page locks
+    (
+        "post",
+        "/page/lock",
+        page_lock_acquire,
+        Some("AcquirePageLock")
+    ),
+    (
+        "delete",
+        "/page/lock",
+        page_lock_release,
+        Some("ReleasePageLock")
+    ),
+    (
+        "put",
+        "/page/lock/get",
+        page_lock_retrieve,
+        Some("GetPageLock")
+    ),
+    ("get", "/page/events/{page_id}", page_events, None),
+    // Page revisions
+    (
+        "put",
+        "/page/revision",
+        page_revision_put,
+        Some("UpdatePageRevision")
+    ),
+    (
+        "get",
+        "/page/revision/get",
+        page_revision_retrieve,
+        Some("GetPageRevision")
+    ),
+    (
+        "get",
+        "/page/revision/count",
+        page_revision_count,
+        Some("GetPage")
+    ),
+    (
+        "post",
+        "/page/revision/rollback",
+        page_rollback,
+        Some("RollbackPage")
+    ),
+    (
+        "put",
+        "/page/revision/view",
+        page_revision_view,
+        Some("GetPageRevisionView")
+    ),
+    (
+        "put",
+        "/page/revision/range",
+        page_revision_range_retrieve,
+        Some("GetPageRevisionRange")
+    ),
+    // Page drafts
+    (
+        "post",
+        "/page/draft",
+        page_draft_create,
+        Some("CreatePageDraft")
+    ),
+    (
+        "post",
+        "/page/draft/publish",
+        page_draft_publish,
+        Some("PublishPageDraft")
+    ),
+    // Page links
+    (
+        "put",
+        "/page/links/from",
+        page_links_from_retrieve,
+        Some("GetLinksFrom")
+    ),
+    (
+        "put",
+        "/page/links/to",
+        page_links_to_retrieve,
+        Some("GetLinksTo")
+    ),
+    (
+        "put",
+        "/page/links/to/missing",
+        page_links_to_missing_retrieve,
+        Some("GetLinksToMissing")
+    ),
+    (
+        "put",
+        "/page/urls/from",
+        page_links_external_from,
+        Some("GetLinksExternalFrom")
+    ),
+    (
+        "put",
+        "/page/urls/to",
+        page_links_external_to,
+        Some("GetLinksExternalTo")
+    ),
+    // Page parents
+    ("put", "/page/parent", parent_put, Some("ParentDescription")),
+    (
+        "delete",
+        "/page/parent",
+        parent_delete,
+        Some("ParentDescription")
+    ),
+    (
+        "put",
+        "/page/parent/get",
+        parent_retrieve,
+        Some("ParentDescription")
+    ),
+    (
+        "put",
+        "/page/parent/{relationship_type}",
+        parent_relationships_retrieve,
+        Some("GetPage")
+    ),
+    (
+        "put",
+        "/page/children",
+        parent_children_retrieve,
+        Some("GetPage")
+    ),
+    // Files
+    ("post", "/file", file_edit, None),
+    ("delete", "/file", file_delete, None),
+    ("get", "/file/get", file_retrieve, Some("GetFile")),
+    ("post", "/file/upload", file_create, None),
+    ("post", "/file/move", file_move, None),
+    ("post", "/file/restore", file_restore, None),
+    // File revisions
+    (
+        "put",
+        "/file/revision",
+        file_revision_put,
+        Some("UpdateFileRevision")
+    ),
+    (
+        "get",
+        "/file/revision/get",
+        file_revision_retrieve,
+        Some("GetFileRevision")
+    ),
+    (
+        "put",
+        "/file/revision/count",
+        file_revision_count,
+        Some("GetFile")
+    ),
+    (
+        "put",
+        "/file/revision/range/{direction}",
+        file_revision_range_retrieve,
+        Some("GetFileRevisionRange")
+    ),
+    // Text
+    ("put", "/text", text_put, None),
+    ("put", "/text/verify", text_verify_all, None),
+    ("get", "/text/verify/{hash}", text_verify, None),
+    ("get", "/text/{hash}", text_get, None),
+    // User
+    ("put", "/user", user_put, Some("UpdateUser")),
+    ("delete", "/user", user_delete, Some("DeleteUser")),
+    ("put", "/user/get", user_retrieve, Some("GetUser")),
+    ("put", "/user/list", user_list_retrieve, Some("UserFilter")),
+    ("put", "/user/avatar", user_avatar_put, None),
+    ("post", "/user/create", user_create, Some("CreateUser")),
+    ("post", "/user/import", user_import, None),
+    (
+        "post",
+        "/user/addNameChange",
+        user_add_name_change,
+        Some("GetUser")
+    ),
+    (
+        "post",
+        "/user/email/change",
+        user_email_change_post,
+        Some("BeginEmailChange")
+    ),
+    (
+        "post",
+        "/user/email/confirm",
+        user_email_confirm_post,
+        Some("ConfirmEmailChange")
+    ),
+    // User bot information
+    ("put", "/user/bot/get", user_bot_retrieve, Some("GetUser")),
+    (
+        "post",
+        "/user/bot/create",
+        user_bot_create,
+        Some("CreateBotUser")
+    ),
+    (
+        "put",
+        "/user/bot/owner",
+        user_bot_owner_put,
+        Some("CreateBotOwner")
+    ),
+    (
+        "delete",
+        "/user/bot/owner",
+        user_bot_owner_delete,
+        Some("DeleteBotOwner")
+    ),
+    // Votes
+    ("put", "/vote", vote_put, Some("CreateVote")),
+    ("delete", "/vote", vote_delete, Some("GetVote")),
+    ("put", "/vote/get", vote_retrieve, Some("GetVote")),
+    ("put", "/vote/action", vote_action, Some("VoteAction")),
+    (
+        "put",
+        "/vote/list",
+        vote_list_retrieve,
+        Some("GetVoteHistory")
+    ),
+    (
+        "put",
+        "/vote/count",
+        vote_count_retrieve,
+        Some("CountVoteHistory")
+    ),
+    // Filters
+    ("post", "/filter", filter_create, Some("AddFilter")),
+    ("put", "/filter", filter_put, Some("EditFilter")),
+    ("delete", "/filter", filter_delete, Some("ModifyFilter")),
+    (
+        "post",
+        "/filter/restore",
+        filter_restore,
+        Some("ModifyFilter")
+    ),
+    (
+        "put",
+        "/filter/list",
+        filter_list_retrieve,
+        Some("GetFilterPage")
+    ),
+    (
+        "put",
+        "/filter/count",
+        filter_count_retrieve,
+        Some("GetSite")
+    ),
+    (
+        "delete",
+        "/filter/many",
+        filter_delete_many,
+        Some("ModifyFilters")
+    ),
+    (
+        "post",
+        "/filter/restore/many",
+        filter_restore_many,
+        Some("ModifyFilters")
+    ),
+    (
+        "put",
+        "/filter/export",
+        filter_export,
+        Some("ExportFilters")
+    ),
+    (
+        "post",
+        "/filter/import",
+        filter_import,
+        Some("ImportFilters")
+    ),
+    // Audit log
+    ("put", "/audit", audit_log_retrieve, Some("GetAuditLog")),
+];
+
+/// Builds the OpenAPI document for the API.
+pub fn openapi_document() -> JsonValue {
+    let components = input_components();
+    let mut paths = Map::new();
+
+    for operation in OPERATIONS {
+        let path = paths
+            .entry(operation.path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("Path item is not an object");
+
+        path.insert(
+            str!(operation.method),
+            operation_object(
+                operation,
+                components.contains_key(operation.input.unwrap_or("")),
+            ),
+        );
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "DEEPWELL",
+            "version": info::PKG_VERSION,
+        },
+        "paths": paths,
+        "components": {
+            "schemas": components,
+        },
+    })
+}
+
+/// Builds the object describing a single operation.
+///
+/// If the input type has no schema, the request body is described as any JSON object.
+fn operation_object(operation: &Operation, has_schema: bool) -> JsonValue {
+    let mut object = json!({
+        "operationId": operation.id,
+        "responses": {
+            "default": {
+                "description": "Endpoint response",
+            },
+        },
+    });
+
+    // Path parameters, e.g. {page_id}
+    let parameters: Vec<JsonValue> = operation
+        .path
+        .split('/')
+        .filter_map(|part| part.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            })
+        })
+        .collect();
+
+    if !parameters.is_empty() {
+        object["parameters"] = json!(parameters);
+    }
+
+    if let Some(input) = operation.input {
+        let schema = if has_schema {
+            json!({ "$ref": format!("#/components/schemas/{input}") })
+        } else {
+            json!({ "type": "object", "title": input })
+        };
+
+        object["requestBody"] = json!({
+            "required": true,
+            "content": {
+                "application/json": {
+                    "schema": schema,
+                },
+            },
+        });
+    }
+
+    object
+}
+
+#[test]
+fn openapi_paths() {
+    let document = openapi_document();
+
+    // The view page endpoint is listed, with its input schema
+    let operation = &document["paths"]["/view/page"]["put"];
+    assert_eq!(operation["operationId"], "view_page");
+    assert_eq!(
+        operation["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/GetPageView",
+    );
+    assert!(
+        document["components"]["schemas"]["GetPageView"]["properties"]["domain"]
+            .is_object(),
+        "GetPageView schema missing domain field",
+    );
+
+    // Both methods on the same route are listed
+    assert!(document["paths"]["/page"]["post"].is_object());
+    assert!(document["paths"]["/page"]["delete"].is_object());
+
+    // Path parameters are described
+    let operation = &document["paths"]["/page/direct/{page_id}"]["get"];
+    assert_eq!(operation["parameters"][0]["name"], "page_id");
+}
+
+#[test]
+fn all_routes() {
+    const API_SOURCE: &str = include_str!("../api.rs");
+
+    let routes = API_SOURCE
+        .split_once("fn build_routes")
+        .expect("No route builder in api.rs")
+        .1;
+
+    let mut count = 0;
+    for route in routes.split("app.at(\"").skip(1) {
+        let (path, rest) = route.split_once('"').expect("Unterminated route path");
+        let methods = rest.split(';').next().unwrap();
+
+        // Convert tide parameters to OpenAPI form, e.g. :page_id to {page_id}
+        let path = path
+            .split('/')
+            .map(|part| match part.strip_prefix(':') {
+                Some(name) => format!("{{{name}}}"),
+                None => str!(part),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        for method in ["get", "put", "post", "delete", "all"] {
+            if !methods.contains(&format!(".{method}(")) {
+                continue;
+            }
+
+            count += 1;
+            assert!(
+                OPERATIONS.iter().any(|operation| operation.path == path
+                    && (method == "all" || operation.method == method)),
+                "Route {} {} missing from OpenAPI operations",
+                method.to_uppercase(),
+                path,
+            );
+        }
+    }
+
+    assert_eq!(count, OPERATIONS.len(), "OpenAPI operations not in api.rs");
+}
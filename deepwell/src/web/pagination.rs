@@ -0,0 +1,102 @@
+/*
+ * web/pagination.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use sea_orm::QuerySelect;
+
+/// Describes which page of results to retrieve.
+///
+/// Page numbers begin at zero.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Pagination {
+    pub page: u64,
+    pub page_size: u64,
+}
+
+impl Pagination {
+    /// Gets the number of results before this page.
+    #[inline]
+    pub fn offset(self) -> u64 {
+        self.page.saturating_mul(self.page_size)
+    }
+
+    /// Limits a query to only the results on this page.
+    ///
+    /// The query must have a stable order for pages to be consistent.
+    pub fn apply<Q: QuerySelect>(self, query: Q) -> Q {
+        query.offset(self.offset()).limit(self.page_size)
+    }
+}
+
+#[test]
+fn pagination() {
+    use crate::models::filter::{self, Entity as Filter};
+    use sea_orm::{DbBackend, EntityTrait, QueryOrder, QueryTrait};
+
+    let items: Vec<u64> = (0..60).collect();
+    let page_size = 25;
+
+    // Walking through every page yields each item once, in order
+    let mut seen = Vec::new();
+    let mut page_lengths = Vec::new();
+
+    for page in 0.. {
+        let pagination = Pagination { page, page_size };
+        let results: Vec<u64> = items
+            .iter()
+            .copied()
+            .skip(pagination.offset() as usize)
+            .take(pagination.page_size as usize)
+            .collect();
+
+        if results.is_empty() {
+            break;
+        }
+
+        page_lengths.push(results.len());
+        seen.extend(results);
+    }
+
+    assert_eq!(seen, items, "Pages overlap or leave gaps");
+    assert_eq!(page_lengths, [25, 25, 10], "Last page not partial");
+
+    // Pages are applied to queries as an offset and limit
+    let pagination = Pagination {
+        page: 2,
+        page_size: 25,
+    };
+
+    let sql = pagination
+        .apply(Filter::find().order_by_asc(filter::Column::FilterId))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(
+        sql.ends_with(r#"ORDER BY "filter"."filter_id" ASC LIMIT 25 OFFSET 50"#),
+        "Wrong pagination: {sql}",
+    );
+
+    // Far pages don't overflow
+    let pagination = Pagination {
+        page: u64::MAX,
+        page_size,
+    };
+    assert_eq!(pagination.offset(), u64::MAX, "Offset did not saturate");
+}
@@ -18,6 +18,8 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use sea_orm::{ActiveValue, Value};
+
 /// Denotes that a field is optional in a struct.
 ///
 /// This is meant to be used when doing `UPDATE` operations,
@@ -43,6 +45,39 @@ impl<T> ProvidedValue<T> {
             ProvidedValue::Unset => None,
         }
     }
+
+    /// Applies this value to a field in an `ActiveModel`, if it was provided.
+    ///
+    /// If the value is `Unset`, then the field is left untouched.
+    /// This is the usual case when performing partial updates.
+    #[inline]
+    pub fn update_field(self, field: &mut ActiveValue<T>)
+    where
+        T: Into<Value>,
+    {
+        if let ProvidedValue::Set(value) = self {
+            *field = ActiveValue::Set(value);
+        }
+    }
+
+    /// Replaces a value with this one, if it was provided.
+    ///
+    /// For nullable fields, `Set(None)` clears the value,
+    /// whereas `Unset` leaves it untouched.
+    ///
+    /// Returns `true` if the value was changed.
+    pub fn update_value(self, value: &mut T) -> bool
+    where
+        T: PartialEq,
+    {
+        match self {
+            ProvidedValue::Set(new_value) if *value != new_value => {
+                *value = new_value;
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
 impl<T> From<ProvidedValue<T>> for Option<T> {
@@ -84,3 +119,88 @@ fn provided_value_deserialize() {
         ProvidedValue::Set(Some(str!("value"))),
     );
 }
+
+#[test]
+fn provided_value_update_value() {
+    macro_rules! check {
+        ($value:expr, $provided:expr, $expected:expr, $changed:expr $(,)?) => {{
+            let mut value: Option<&str> = $value;
+            let changed = ProvidedValue::update_value($provided, &mut value);
+
+            assert_eq!(value, $expected, "Actual value doesn't match expected");
+            assert_eq!(
+                changed, $changed,
+                "Actual change status doesn't match expected"
+            );
+        }};
+    }
+
+    // Preserving alt title
+    check!(Some("Title"), ProvidedValue::Unset, Some("Title"), false);
+    check!(None, ProvidedValue::Unset, None, false);
+
+    // Clearing alt title
+    check!(Some("Title"), ProvidedValue::Set(None), None, true);
+    check!(None, ProvidedValue::Set(None), None, false);
+
+    // Changing alt title
+    check!(None, ProvidedValue::Set(Some("Title")), Some("Title"), true);
+    check!(
+        Some("Old"),
+        ProvidedValue::Set(Some("Title")),
+        Some("Title"),
+        true
+    );
+    check!(
+        Some("Title"),
+        ProvidedValue::Set(Some("Title")),
+        Some("Title"),
+        false
+    );
+}
+
+#[test]
+fn provided_value_update_field() {
+    macro_rules! check {
+        ($field:expr, $value:expr, $expected:expr $(,)?) => {{
+            let mut field = $field;
+            ProvidedValue::update_field($value, &mut field);
+
+            assert_eq!(
+                field, $expected,
+                "Actual active model field doesn't match expected",
+            );
+        }};
+    }
+
+    check!(
+        ActiveValue::<i32>::NotSet,
+        ProvidedValue::Unset,
+        ActiveValue::NotSet,
+    );
+    check!(
+        ActiveValue::Unchanged(1),
+        ProvidedValue::Unset,
+        ActiveValue::Unchanged(1),
+    );
+    check!(
+        ActiveValue::<i32>::NotSet,
+        ProvidedValue::Set(5),
+        ActiveValue::Set(5),
+    );
+    check!(
+        ActiveValue::Unchanged(1),
+        ProvidedValue::Set(5),
+        ActiveValue::Set(5),
+    );
+    check!(
+        ActiveValue::Unchanged(Some(str!("value"))),
+        ProvidedValue::Set(None),
+        ActiveValue::Set(None),
+    );
+    check!(
+        ActiveValue::Unchanged(Some(str!("value"))),
+        ProvidedValue::Unset,
+        ActiveValue::Unchanged(Some(str!("value"))),
+    );
+}
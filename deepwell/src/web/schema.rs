@@ -0,0 +1,108 @@
+/*
+ * web/schema.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! JSON Schema for the inputs of API endpoints.
+//!
+//! This gives frontend and third-party consumers of the API
+//! a typed contract for what each endpoint accepts.
+//!
+//! TODO: Only some inputs have schemas so far. Add more as types derive
+//!       `JsonSchema`, and include endpoint outputs as well.
+
+use crate::services::page::{
+    CreatePage, FindHistoricalSlug, FindPagesByKeyword, GetPageExcerpt, RestorePage,
+    RestorePageBySlug,
+};
+use crate::services::view::GetPageView;
+use schemars::gen::SchemaSettings;
+use schemars::schema::{RootSchema, Schema};
+use schemars::{schema_for, Map};
+use std::collections::BTreeMap;
+
+/// Invokes the given macro with the list of all endpoint input types with schemas.
+macro_rules! input_types {
+    ($callback:ident) => {
+        $callback![
+            CreatePage,
+            FindHistoricalSlug,
+            FindPagesByKeyword,
+            GetPageExcerpt,
+            GetPageView,
+            RestorePage,
+            RestorePageBySlug,
+        ]
+    };
+}
+
+/// Builds the JSON Schema for each endpoint input, by type name.
+pub fn input_schemas() -> BTreeMap<&'static str, RootSchema> {
+    macro_rules! schemas {
+        ($($type:ty),+ $(,)?) => {{
+            let mut schemas = BTreeMap::new();
+            $(
+                schemas.insert(stringify!($type), schema_for!($type));
+            )+
+            schemas
+        }};
+    }
+
+    input_types!(schemas)
+}
+
+/// Builds the schemas for all endpoint inputs and the types they use,
+/// as referenced from `#/components/schemas/` in an OpenAPI document.
+pub fn input_components() -> Map<String, Schema> {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+
+    macro_rules! components {
+        ($($type:ty),+ $(,)?) => {{
+            $(
+                gen.subschema_for::<$type>();
+            )+
+        }};
+    }
+
+    input_types!(components);
+    gen.take_definitions()
+}
+
+#[test]
+fn page_view_schema() {
+    let schemas = input_schemas();
+    let schema = &schemas["GetPageView"];
+    let object = schema
+        .schema
+        .object
+        .as_ref()
+        .expect("GetPageView schema is not an object");
+
+    assert!(
+        object.properties.contains_key("domain"),
+        "Domain field missing from schema",
+    );
+    assert!(
+        object.required.contains("domain"),
+        "Domain field not required in schema",
+    );
+    assert!(
+        !object.required.contains("detail"),
+        "Defaulted field required in schema",
+    );
+}
@@ -0,0 +1,176 @@
+/*
+ * web/security_headers.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Security headers, such as `Content-Security-Policy`, added to all responses.
+//!
+//! These are configured in the `[security.headers]` section, and may be
+//! overridden for routes under a given path prefix, such as for embeddable content.
+
+use crate::config::Config;
+use tide::{Middleware, Next, Request, Response};
+
+#[derive(Debug, Clone, Default)]
+pub struct SecurityHeaders {
+    headers: Vec<(String, String)>,
+    route_headers: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl SecurityHeaders {
+    #[inline]
+    pub fn new(
+        headers: Vec<(String, String)>,
+        route_headers: Vec<(String, Vec<(String, String)>)>,
+    ) -> Self {
+        SecurityHeaders {
+            headers,
+            route_headers,
+        }
+    }
+
+    #[inline]
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config.security_headers.clone(),
+            config.security_route_headers.clone(),
+        )
+    }
+
+    /// Gets the headers to add for a request path.
+    ///
+    /// Overrides from the longest matching route prefix are applied,
+    /// with an empty value removing the header entirely.
+    pub fn headers_for(&self, path: &str) -> Vec<(&str, &str)> {
+        let mut headers: Vec<(&str, &str)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+
+        let overrides = self
+            .route_headers
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len());
+
+        if let Some((_, overrides)) = overrides {
+            for (name, value) in overrides {
+                headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+                headers.push((name, value));
+            }
+        }
+
+        headers.retain(|(_, value)| !value.is_empty());
+        headers
+    }
+
+    /// Adds the headers for this path to the response.
+    ///
+    /// Headers already set by the route are left as-is.
+    pub fn apply(&self, path: &str, response: &mut Response) {
+        for (name, value) in self.headers_for(path) {
+            if response.header(name).is_none() {
+                response.insert_header(name, value);
+            }
+        }
+    }
+}
+
+#[tide::utils::async_trait]
+impl<State> Middleware<State> for SecurityHeaders
+where
+    State: Clone + Send + Sync + 'static,
+{
+    async fn handle(&self, req: Request<State>, next: Next<'_, State>) -> tide::Result {
+        let path = str!(req.url().path());
+        let mut response = next.run(req).await;
+        self.apply(&path, &mut response);
+        Ok(response)
+    }
+}
+
+#[test]
+fn security_headers() {
+    use super::testing::header_str;
+    use async_std::task;
+    use tide::http::{Method, Request as HttpRequest, Response as HttpResponse, Url};
+
+    const CSP: &str = "default-src 'none'; frame-ancestors 'none'";
+
+    let policy = SecurityHeaders::new(
+        vec![
+            (str!("Content-Security-Policy"), str!(CSP)),
+            (str!("X-Frame-Options"), str!("DENY")),
+        ],
+        vec![(
+            str!("/embed"),
+            vec![
+                (str!("Content-Security-Policy"), str!("frame-ancestors *")),
+                (str!("X-Frame-Options"), str!("")),
+            ],
+        )],
+    );
+
+    let mut app = tide::new();
+    app.with(policy);
+    app.at("/view/page").put(|_| async { Ok("Page") });
+    app.at("/embed/page").get(|_| async { Ok("Embedded page") });
+    app.at("/custom").get(|_| async {
+        let mut response = Response::new(200);
+        response.insert_header("X-Frame-Options", "SAMEORIGIN");
+        Ok(response)
+    });
+
+    let send = |method, path: &str| {
+        let url = Url::parse("http://localhost").unwrap().join(path).unwrap();
+        let req = HttpRequest::new(method, url);
+        let response: HttpResponse = task::block_on(app.respond(req)).unwrap();
+        Response::from(response)
+    };
+
+    // Page responses have the configured headers
+    let response = send(Method::Put, "/view/page");
+    assert_eq!(
+        header_str(&response, "Content-Security-Policy").as_deref(),
+        Some(CSP),
+    );
+    assert_eq!(
+        header_str(&response, "X-Frame-Options").as_deref(),
+        Some("DENY"),
+    );
+
+    // Route overrides replace or remove headers
+    let response = send(Method::Get, "/embed/page");
+    assert_eq!(
+        header_str(&response, "Content-Security-Policy").as_deref(),
+        Some("frame-ancestors *"),
+    );
+    assert_eq!(header_str(&response, "X-Frame-Options"), None);
+
+    // Headers set by the route itself are kept
+    let response = send(Method::Get, "/custom");
+    assert_eq!(
+        header_str(&response, "X-Frame-Options").as_deref(),
+        Some("SAMEORIGIN"),
+    );
+    assert_eq!(
+        header_str(&response, "Content-Security-Policy").as_deref(),
+        Some(CSP),
+    );
+}
@@ -0,0 +1,194 @@
+/*
+ * web/validate.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Validation of endpoint input before it is passed to services.
+
+use serde::de::DeserializeOwned;
+use std::fmt::{self, Display};
+use tide::{Error as TideError, Request, StatusCode};
+
+pub type ValidationResult = Result<(), ValidationError>;
+
+/// Trait for input structures which can be checked for basic correctness.
+///
+/// This is only for checks which need no other context, such as a field
+/// being blank. Anything requiring database access belongs in services.
+///
+/// Input with no such constraints can use the default implementation,
+/// which accepts anything that deserializes.
+pub trait Validate {
+    fn validate(&self) -> ValidationResult {
+        Ok(())
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate(&self) -> ValidationResult {
+        self.iter().try_for_each(Validate::validate)
+    }
+}
+
+/// Describes which field of an input structure is invalid, and why.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub message: &'static str,
+}
+
+impl ValidationError {
+    #[inline]
+    pub fn new(field: &'static str, message: &'static str) -> Self {
+        ValidationError { field, message }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+impl From<ValidationError> for TideError {
+    fn from(error: ValidationError) -> TideError {
+        TideError::from_str(StatusCode::BadRequest, error.to_string())
+    }
+}
+
+/// Ensures that the given string field has non-whitespace content.
+pub fn check_not_blank(field: &'static str, value: &str) -> ValidationResult {
+    if value.trim().is_empty() {
+        Err(ValidationError::new(field, "must not be blank"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Reads the JSON body of the request, and then validates it.
+///
+/// The request is rejected with an HTTP 400 if the body cannot be
+/// deserialized, or naming the invalid field if validation fails.
+pub async fn body_json_validated<S, T>(req: &mut Request<S>) -> Result<T, TideError>
+where
+    T: DeserializeOwned + Validate,
+{
+    let input: T = req.body_json().await.map_err(|mut error| {
+        error.set_status(StatusCode::BadRequest);
+        error
+    })?;
+
+    if let Err(error) = input.validate() {
+        tide::log::warn!("Request body failed validation: {error}");
+        return Err(error.into());
+    }
+
+    Ok(input)
+}
+
+#[test]
+fn not_blank() {
+    assert_eq!(check_not_blank("name", "apple"), Ok(()));
+    assert_eq!(check_not_blank("name", " apple "), Ok(()));
+    assert_eq!(
+        check_not_blank("name", ""),
+        Err(ValidationError::new("name", "must not be blank")),
+    );
+    assert_eq!(
+        check_not_blank("name", " \t\n"),
+        Err(ValidationError::new("name", "must not be blank")),
+    );
+}
+
+#[test]
+fn endpoint_validation() {
+    use crate::services::view::GetPageView;
+    use async_std::task;
+    use tide::http::{Method, Request as HttpRequest, Url};
+
+    fn build_request(body: &str) -> Request<()> {
+        let url = Url::parse("http://localhost/view/page").unwrap();
+        let mut req = HttpRequest::new(Method::Put, url);
+        req.set_body(body);
+        req.into()
+    }
+
+    macro_rules! check_err {
+        ($body:expr, $message:expr $(,)?) => {{
+            let mut req = build_request($body);
+            let error = task::block_on(body_json_validated::<_, GetPageView>(&mut req))
+                .expect_err("Invalid input was accepted");
+
+            assert_eq!(error.status(), StatusCode::BadRequest);
+
+            if let Some(message) = $message {
+                assert_eq!(error.to_string(), message);
+            }
+        }};
+    }
+
+    // Valid
+    let mut req = build_request(r#"{"domain": "scp-wiki.wikijump.com"}"#);
+    let input = task::block_on(body_json_validated::<_, GetPageView>(&mut req))
+        .expect("Valid input was rejected");
+    assert_eq!(input.domain, "scp-wiki.wikijump.com");
+
+    // Missing or blank domain
+    check_err!(r#"{}"#, None::<&str>);
+    check_err!(r#"{"domain": ""}"#, Some("domain: must not be blank"));
+    check_err!(r#"{"domain": "   "}"#, Some("domain: must not be blank"));
+    check_err!(
+        r#"{"domain": "scp-wiki.wikijump.com", "route": {"slug": "", "extra": ""}}"#,
+        Some("route.slug: must not be blank"),
+    );
+}
+
+#[test]
+fn edit_validation() {
+    use crate::services::page::EditPage;
+    use crate::web::ProvidedValue;
+    use async_std::task;
+    use tide::http::{Method, Request as HttpRequest, Url};
+
+    fn read(body: &str) -> Result<EditPage<'static>, TideError> {
+        let url = Url::parse("http://localhost/page").unwrap();
+        let mut req = HttpRequest::new(Method::Post, url);
+        req.set_body(body);
+
+        let mut req: Request<()> = req.into();
+        task::block_on(body_json_validated(&mut req))
+    }
+
+    // Fields which aren't being edited aren't checked
+    let input = read(r#"{"siteId": 1, "page": "apple", "revisionComments": ""}"#)
+        .expect("Valid edit was rejected");
+    assert_eq!(input.body.title, ProvidedValue::Unset);
+
+    let input = read(
+        r#"{"siteId": 1, "page": "apple", "revisionComments": "", "title": "Apple"}"#,
+    )
+    .expect("Valid edit was rejected");
+    assert_eq!(input.body.title, ProvidedValue::Set(str!("Apple")));
+
+    // But those which are must be valid
+    let error =
+        read(r#"{"siteId": 1, "page": "apple", "revisionComments": "", "title": " "}"#)
+            .expect_err("Blank title was accepted");
+    assert_eq!(error.status(), StatusCode::BadRequest);
+    assert_eq!(error.to_string(), "title: must not be blank");
+}
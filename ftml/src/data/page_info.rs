@@ -64,7 +64,6 @@ pub struct PageInfo<'a> {
 
 impl PageInfo<'_> {
     /// Generate a dummy PageInfo instance for tests.
-    #[cfg(test)]
     pub fn dummy() -> Self {
         PageInfo {
             page: cow!("some-page"),
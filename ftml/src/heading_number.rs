@@ -0,0 +1,113 @@
+/*
+ * heading_number.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Hierarchical numbering of headings, such as `1`, `1.1`, and `1.2`.
+//!
+//! This is used by both the table of contents and the HTML renderer,
+//! so that the numbers they produce for each heading are the same.
+
+/// Assigns section numbers to headings, in document order.
+///
+/// Headings are numbered by their nesting, not their absolute level.
+/// So a heading which skips levels (e.g. an H3 directly under an H1)
+/// is numbered as a direct child (`1.1`, not `1.0.1`).
+#[derive(Debug, Default, Clone)]
+pub struct HeadingNumbers {
+    /// The depth and count of each heading in the current chain.
+    stack: Vec<(usize, u32)>,
+}
+
+impl HeadingNumbers {
+    #[inline]
+    pub fn new() -> Self {
+        HeadingNumbers::default()
+    }
+
+    /// Gets the number for the next heading, at the given (zero-indexed) depth.
+    pub fn next(&mut self, depth: usize) -> String {
+        // Close any deeper sections, remembering the last one
+        // in case this heading continues at its position.
+        let mut previous = None;
+        while let Some(&(last_depth, count)) = self.stack.last() {
+            if last_depth <= depth {
+                break;
+            }
+
+            previous = Some(count);
+            self.stack.pop();
+        }
+
+        match self.stack.last_mut() {
+            // Sibling of the previous heading
+            Some((last_depth, count)) if *last_depth == depth => *count += 1,
+
+            // New subsection, or continuing a closed one
+            _ => {
+                let count = previous.map(|count| count + 1).unwrap_or(1);
+                self.stack.push((depth, count));
+            }
+        }
+
+        let mut number = String::new();
+        for (index, (_, count)) in self.stack.iter().enumerate() {
+            if index > 0 {
+                number.push('.');
+            }
+
+            str_write!(&mut number, "{count}");
+        }
+
+        number
+    }
+}
+
+#[test]
+fn heading_numbers() {
+    macro_rules! check {
+        ($depths:expr, $expected:expr $(,)?) => {{
+            let mut numbers = HeadingNumbers::new();
+            let actual: Vec<String> =
+                $depths.iter().map(|&depth| numbers.next(depth)).collect();
+            let expected: &[&str] = &$expected;
+
+            assert_eq!(
+                actual, expected,
+                "Heading numbers for depths {:?} don't match expected",
+                $depths,
+            );
+        }};
+    }
+
+    check!([0usize; 0], []);
+    check!([0, 0, 0], ["1", "2", "3"]);
+    check!([0, 1, 1, 0, 1], ["1", "1.1", "1.2", "2", "2.1"]);
+    check!(
+        [0, 1, 2, 2, 1, 0],
+        ["1", "1.1", "1.1.1", "1.1.2", "1.2", "2"]
+    );
+
+    // Skipped levels nest directly
+    check!([0, 2, 2, 1], ["1", "1.1", "1.2", "1.3"]);
+    check!([0, 2, 1, 2], ["1", "1.1", "1.2", "1.2.1"]);
+
+    // Starting below the top level
+    check!([1, 1, 0, 1], ["1", "2", "3", "3.1"]);
+    check!([2, 1, 0], ["1", "2", "3"]);
+}
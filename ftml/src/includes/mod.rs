@@ -136,7 +136,11 @@ where
         let replace_with = match fetched.content {
             // Take fetched content, replace variables
             Some(mut content) => {
-                replace_variables(content.to_mut(), &variables);
+                replace_variables(
+                    content.to_mut(),
+                    &variables,
+                    settings.blank_missing_include_variables,
+                );
                 content
             }
 
@@ -158,7 +162,7 @@ where
     Ok((output, pages))
 }
 
-fn replace_variables(content: &mut String, variables: &VariableMap) {
+fn replace_variables(content: &mut String, variables: &VariableMap, blank_missing: bool) {
     let mut matches = Vec::new();
 
     // Find all variables
@@ -166,8 +170,10 @@ fn replace_variables(content: &mut String, variables: &VariableMap) {
         let mtch = capture.get(0).unwrap();
         let name = &capture["name"];
 
-        if let Some(value) = variables.get(name) {
-            matches.push((value, mtch.range()));
+        match variables.get(name) {
+            Some(value) => matches.push((value.as_ref(), mtch.range())),
+            None if blank_missing => matches.push(("", mtch.range())),
+            None => debug!("No value for include variable '{name}', leaving as-is"),
         }
     }
 
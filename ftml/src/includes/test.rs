@@ -18,8 +18,10 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::{include, DebugIncluder, PageRef};
+use super::{include, DebugIncluder, FetchedPage, IncludeRef, Includer, PageRef};
 use crate::settings::{WikitextMode, WikitextSettings};
+use std::borrow::Cow;
+use void::Void;
 
 #[test]
 fn includes() {
@@ -270,3 +272,75 @@ fn includes() {
         vec![],
     );
 }
+
+#[test]
+fn include_variables() {
+    /// Includer where every page has the same parameterized contents.
+    #[derive(Debug)]
+    struct TemplateIncluder;
+
+    impl<'t> Includer<'t> for TemplateIncluder {
+        type Error = Void;
+
+        fn include_pages(
+            &mut self,
+            includes: &[IncludeRef<'t>],
+        ) -> Result<Vec<FetchedPage<'t>>, Void> {
+            let pages = includes
+                .iter()
+                .map(|include| FetchedPage {
+                    page_ref: include.page_ref().clone(),
+                    content: Some(Cow::Borrowed("Hello {$name}, from {$place}!")),
+                })
+                .collect();
+
+            Ok(pages)
+        }
+
+        fn no_such_include(&mut self, _: &PageRef<'t>) -> Result<Cow<'t, str>, Void> {
+            panic!("All pages exist");
+        }
+    }
+
+    let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! test {
+        ($text:expr, $expected:expr $(,)?) => {{
+            let result = include($text, &settings, TemplateIncluder, || panic!());
+            let (output, _) = result.expect("Fetching pages failed");
+
+            println!("Input:  '{}'", $text);
+            println!("Output: '{}'", &output);
+            println!();
+
+            assert_eq!(output, $expected, "Included output doesn't match expected");
+        }};
+    }
+
+    // Missing variables left as-is
+    test!(
+        "[[include-messy greeting name=Alice|place=Site-19]]",
+        "Hello Alice, from Site-19!",
+    );
+    test!(
+        "[[include-messy greeting name=Alice]]",
+        "Hello Alice, from {$place}!",
+    );
+    test!(
+        "[[include-messy greeting]]",
+        "Hello {$name}, from {$place}!"
+    );
+
+    // Missing variables removed
+    settings.blank_missing_include_variables = true;
+
+    test!(
+        "[[include-messy greeting name=Alice|place=Site-19]]",
+        "Hello Alice, from Site-19!",
+    );
+    test!(
+        "[[include-messy greeting name=Alice]]",
+        "Hello Alice, from !"
+    );
+    test!("[[include-messy greeting]]", "Hello , from !");
+}
@@ -113,6 +113,7 @@ mod test;
 #[macro_use]
 mod macros;
 
+mod heading_number;
 mod id_prefix;
 mod next_index;
 mod non_empty_vec;
@@ -178,6 +178,9 @@ pub enum ParseErrorKind {
     /// This block does not allow score (`_`) invocation.
     BlockDisallowsScore,
 
+    /// This block has been disabled in the settings, and so was not rendered.
+    BlockDisabled,
+
     /// This block does not specify a name.
     BlockMissingName,
 
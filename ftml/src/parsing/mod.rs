@@ -57,7 +57,7 @@ use self::rule::impls::RULE_PAGE;
 use self::string::parse_string;
 use self::strip::{strip_newlines, strip_whitespace};
 use crate::data::PageInfo;
-use crate::next_index::{NextIndex, TableOfContentsIndex};
+use crate::heading_number::HeadingNumbers;
 use crate::settings::WikitextSettings;
 use crate::tokenizer::Tokenization;
 use crate::tree::{
@@ -92,9 +92,6 @@ where
         bibliographies,
     } = parse_internal(page_info, settings, tokenization);
 
-    // For producing table of contents indexes
-    let mut incrementer = Incrementer(0);
-
     info!("Finished paragraph gathering, matching on consumption");
     match result {
         Ok(ParseSuccess {
@@ -107,15 +104,30 @@ where
                 errors.len(),
             );
 
+            // Each entry keeps its index, since that is its heading's anchor,
+            // even if earlier entries are excluded for being too deep.
+            // Similarly, they are numbered before any are excluded.
+            //
             // process_depths() wants a "list type", so we map in a () for each.
+            let mut heading_numbers = HeadingNumbers::new();
             let table_of_contents_depths = table_of_contents_depths
                 .into_iter()
-                .map(|(depth, contents)| (depth, (), contents));
+                .map(|(depth, contents)| {
+                    if settings.number_headings {
+                        let number = heading_numbers.next(depth);
+                        (depth, format!("{number} {contents}"))
+                    } else {
+                        (depth, contents)
+                    }
+                })
+                .enumerate()
+                .filter(|(_, (depth, _))| toc_depth_included(settings, *depth))
+                .map(|(index, (depth, contents))| (depth, (), (index, contents)));
 
             // Convert TOC depth lists
             let table_of_contents = process_depths((), table_of_contents_depths)
                 .into_iter()
-                .map(|(_, items)| build_toc_list_element(&mut incrementer, items))
+                .map(|(_, items)| build_toc_list_element(items))
                 .collect::<Vec<_>>();
 
             // Add a footnote block at the end,
@@ -196,16 +208,21 @@ where
 
 // Helper functions
 
-fn build_toc_list_element(
-    incr: &mut Incrementer,
-    list: DepthList<(), String>,
-) -> Element<'static> {
+/// Determines if a heading at this (zero-indexed) depth is listed in the table of contents.
+fn toc_depth_included(settings: &WikitextSettings, depth: usize) -> bool {
+    match settings.max_toc_depth {
+        Some(max_depth) => depth < usize::from(max_depth),
+        None => true,
+    }
+}
+
+fn build_toc_list_element(list: DepthList<(), (usize, String)>) -> Element<'static> {
     let build_item = |item| match item {
         DepthItem::List(_, list) => ListItem::SubList {
-            element: Box::new(build_toc_list_element(incr, list)),
+            element: Box::new(build_toc_list_element(list)),
         },
-        DepthItem::Item(name) => {
-            let anchor = format!("#toc{}", incr.next());
+        DepthItem::Item((index, name)) => {
+            let anchor = format!("#toc{index}");
             let link = Element::Link {
                 ltype: LinkType::TableOfContents,
                 link: LinkLocation::Url(Cow::Owned(anchor)),
@@ -230,19 +247,6 @@ fn build_toc_list_element(
     }
 }
 
-// Incrementer for TOC
-
-#[derive(Debug)]
-struct Incrementer(usize);
-
-impl NextIndex<TableOfContentsIndex> for Incrementer {
-    fn next(&mut self) -> usize {
-        let index = self.0;
-        self.0 += 1;
-        index
-    }
-}
-
 // Parse internal result
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -55,29 +55,46 @@ impl<'t> Arguments<'t> {
         self.inner.remove(&key)
     }
 
+    /// Gets a boolean argument, or the given default if it is absent.
     pub fn get_bool(
         &mut self,
         parser: &Parser<'_, 't>,
         key: &'t str,
-    ) -> Result<Option<bool>, ParseError> {
+        default: bool,
+    ) -> Result<bool, ParseError> {
         match self.get(key) {
-            Some(argument) => match parse_boolean(argument) {
-                Ok(value) => Ok(Some(value)),
-                Err(_) => Err(make_err!(parser)),
+            Some(argument) => match parse_boolean(&argument) {
+                Ok(value) => Ok(value),
+                Err(_) => {
+                    warn!("Invalid boolean value for argument '{key}': '{argument}'");
+                    Err(make_err!(parser))
+                }
             },
-            None => Ok(None),
+            None => Ok(default),
         }
     }
 
+    #[inline]
+    pub fn get_u32(
+        &mut self,
+        parser: &Parser<'_, 't>,
+        key: &'t str,
+    ) -> Result<Option<u32>, ParseError> {
+        self.get_value(parser, key)
+    }
+
     pub fn get_value<T: FromStr>(
         &mut self,
         parser: &Parser<'_, 't>,
         key: &'t str,
     ) -> Result<Option<T>, ParseError> {
         match self.get(key) {
-            Some(argument) => match argument.parse() {
+            Some(argument) => match argument.trim().parse() {
                 Ok(value) => Ok(Some(value)),
-                Err(_) => Err(make_err!(parser)),
+                Err(_) => {
+                    warn!("Invalid value for argument '{key}': '{argument}'");
+                    Err(make_err!(parser))
+                }
             },
             None => Ok(None),
         }
@@ -117,3 +134,63 @@ impl<'t> Arguments<'t> {
         map
     }
 }
+
+#[test]
+fn typed_arguments() {
+    use crate::data::PageInfo;
+    use crate::settings::WikitextMode;
+
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let tokens = crate::tokenize("Test input");
+    let parser = Parser::new(&tokens, &page_info, &settings);
+
+    let mut arguments = Arguments::new();
+    arguments.insert("folded", cow!("yes"));
+    arguments.insert("Hover", cow!("F"));
+    arguments.insert("broken", cow!("maybe"));
+    arguments.insert("depth", cow!(" 4 "));
+    arguments.insert("negative", cow!("-1"));
+    arguments.insert("word", cow!("four"));
+
+    // Booleans
+    assert_eq!(
+        arguments.get_bool(&parser, "folded", false).ok(),
+        Some(true)
+    );
+    assert_eq!(arguments.get_bool(&parser, "hover", true).ok(), Some(false));
+    assert_eq!(
+        arguments.get_bool(&parser, "missing", true).ok(),
+        Some(true)
+    );
+    assert_eq!(
+        arguments.get_bool(&parser, "missing", false).ok(),
+        Some(false),
+    );
+    assert_eq!(
+        arguments
+            .get_bool(&parser, "broken", false)
+            .map_err(|error| error.kind())
+            .err(),
+        Some(ParseErrorKind::BlockMalformedArguments),
+    );
+
+    // Integers
+    assert_eq!(arguments.get_u32(&parser, "depth").ok(), Some(Some(4)));
+    assert_eq!(arguments.get_u32(&parser, "missing").ok(), Some(None));
+    assert_eq!(
+        arguments
+            .get_u32(&parser, "negative")
+            .map_err(|error| error.kind())
+            .err(),
+        Some(ParseErrorKind::BlockMalformedArguments),
+    );
+    assert!(arguments.get_u32(&parser, "word").is_err());
+
+    // Arguments are consumed when retrieved
+    assert!(arguments.is_empty());
+    assert_eq!(
+        arguments.get_bool(&parser, "folded", false).ok(),
+        Some(false)
+    );
+}
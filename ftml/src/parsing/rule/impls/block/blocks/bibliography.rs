@@ -45,7 +45,7 @@ fn parse_fn<'r, 't>(
     let mut arguments = parser.get_head_map(&BLOCK_BIBLIOGRAPHY, in_head)?;
 
     let title = arguments.get("title");
-    let hide = arguments.get_bool(parser, "hide")?.unwrap_or(false);
+    let hide = arguments.get_bool(parser, "hide", false)?;
 
     // Get body content. The contents should only be a definition list, but
     // we use the regular elements parser to make it easy on us. If we find
@@ -68,10 +68,17 @@ fn parse_fn<'r, 't>(
             Element::DefinitionList(items) => {
                 for DefinitionListItem {
                     key_string,
-                    value_elements,
+                    mut value_elements,
+                    extra_value_elements,
                     ..
                 } in items
                 {
+                    // Further definitions of a reference are each put on a new line
+                    for elements in extra_value_elements {
+                        value_elements.push(Element::LineBreak);
+                        value_elements.extend(elements);
+                    }
+
                     bibliography.add(key_string, value_elements);
                 }
             }
@@ -51,7 +51,7 @@ fn parse_fn<'r, 't>(
     // Get folding arguments
     //
     // We invert this first argument since "folded=no" means "start_open=yes"
-    let start_open = !arguments.get_bool(parser, "folded")?.unwrap_or(true);
+    let start_open = !arguments.get_bool(parser, "folded", true)?;
     let (show_top, show_bottom) = match arguments.get("hideLocation") {
         Some(value) => parse_hide_location(&value, parser)?,
         None => (true, false),
@@ -51,7 +51,7 @@ fn parse_fn<'r, 't>(
     let (value, mut arguments) = parser.get_head_name_map(&BLOCK_DATE, in_head)?;
     let format = arguments.get("format");
     let arg_timezone = arguments.get("tz");
-    let hover = arguments.get_bool(parser, "hover")?.unwrap_or(true);
+    let hover = arguments.get_bool(parser, "hover", true)?;
 
     // For now: we don't support strftime-like formats because the time crate doesn't
     if format.is_some() {
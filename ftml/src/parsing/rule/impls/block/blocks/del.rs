@@ -26,10 +26,19 @@ pub const BLOCK_DEL: BlockRule = BlockRule {
     accepts_star: false,
     accepts_score: false,
     accepts_newlines: false,
-    parse_fn,
+    parse_fn: parse_inline,
 };
 
-fn parse_fn<'r, 't>(
+pub const BLOCK_DEL_BLOCK: BlockRule = BlockRule {
+    name: "block-del-block",
+    accepts_names: &["del-block", "deletion-block"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: true,
+    parse_fn: parse_block,
+};
+
+fn parse_inline<'r, 't>(
     parser: &mut Parser<'r, 't>,
     name: &'t str,
     flag_star: bool,
@@ -41,11 +50,36 @@ fn parse_fn<'r, 't>(
     assert!(!flag_score, "Deletion doesn't allow score flag");
     assert_block_name(&BLOCK_DEL, name);
 
-    let arguments = parser.get_head_map(&BLOCK_DEL, in_head)?;
+    parse_deletion(parser, &BLOCK_DEL, in_head, false)
+}
 
-    // Get body content, without paragraphs
-    let (elements, errors, paragraph_safe) =
-        parser.get_body_elements(&BLOCK_DEL, false)?.into();
+fn parse_block<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing deletion block with paragraphs (name '{name}', in-head {in_head})");
+    assert!(!flag_star, "Deletion doesn't allow star flag");
+    assert!(!flag_score, "Deletion doesn't allow score flag");
+    assert_block_name(&BLOCK_DEL_BLOCK, name);
+
+    parse_deletion(parser, &BLOCK_DEL_BLOCK, in_head, true)
+}
+
+fn parse_deletion<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    block_rule: &BlockRule,
+    in_head: bool,
+    wrap_paragraphs: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    let arguments = parser.get_head_map(block_rule, in_head)?;
+
+    // Get body content, with paragraphs if this is the block form
+    let (elements, errors, paragraph_safe) = parser
+        .get_body_elements(block_rule, wrap_paragraphs)?
+        .into();
 
     // Build and return element
     let element = Element::Container(Container::new(
@@ -54,5 +88,8 @@ fn parse_fn<'r, 't>(
         arguments.to_attribute_map(parser.settings()),
     ));
 
+    // The block form contains paragraphs, so it is never paragraph safe
+    let paragraph_safe = paragraph_safe && !wrap_paragraphs;
+
     ok!(paragraph_safe; element, errors)
 }
@@ -110,7 +110,7 @@ fn parse_footnote_block<'r, 't>(
     let mut arguments = parser.get_head_map(&BLOCK_FOOTNOTE_BLOCK, in_head)?;
 
     let title = arguments.get("title");
-    let hide = arguments.get_bool(parser, "hide")?.unwrap_or(false);
+    let hide = arguments.get_bool(parser, "hide", false)?;
 
     if !arguments.is_empty() {
         warn!("Invalid argument keys found");
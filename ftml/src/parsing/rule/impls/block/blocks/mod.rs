@@ -123,7 +123,7 @@ pub use self::checkbox::BLOCK_CHECKBOX;
 pub use self::code::BLOCK_CODE;
 pub use self::collapsible::BLOCK_COLLAPSIBLE;
 pub use self::date::BLOCK_DATE;
-pub use self::del::BLOCK_DEL;
+pub use self::del::{BLOCK_DEL, BLOCK_DEL_BLOCK};
 pub use self::div::BLOCK_DIV;
 pub use self::embed::BLOCK_EMBED;
 pub use self::equation_ref::BLOCK_EQUATION_REF;
@@ -34,9 +34,7 @@ fn parse_fn<'r, 't>(
     info!("Parsing categories module");
     assert_module_name(&MODULE_CATEGORIES, name);
 
-    let include_hidden = arguments
-        .get_bool(parser, "includeHidden")?
-        .unwrap_or(false);
+    let include_hidden = arguments.get_bool(parser, "includeHidden", false)?;
 
     ok!(false; Module::Categories { include_hidden })
 }
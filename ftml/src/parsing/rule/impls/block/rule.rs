@@ -130,6 +130,16 @@ where
         return Err(parser.make_err(ParseErrorKind::BlockDisallowsScore));
     }
 
+    // Check if this block has been disabled.
+    //
+    // The error is created here so it points at the start of the block.
+    let disabled_error = if parser.settings().is_block_disabled(block.accepts_names) {
+        warn!("Block '{}' is disabled, will not be rendered", block.name);
+        Some(parser.make_err(ParseErrorKind::BlockDisabled))
+    } else {
+        None
+    };
+
     parser.get_optional_space()?;
 
     // Run the parse function until the end.
@@ -137,5 +147,16 @@ where
     // This is responsible for parsing any arguments,
     // and terminating the block (the ']]' token),
     // then processing the body (if any) and tail block.
-    (block.parse_fn)(parser, name, flag_star, flag_score, in_head)
+    let result = (block.parse_fn)(parser, name, flag_star, flag_score, in_head);
+
+    // If disabled, we still parse the block in full so it is consumed,
+    // but discard what it produced.
+    match disabled_error {
+        None => result,
+        Some(error) => {
+            let (_, mut errors, _) = result?.into();
+            errors.push(error);
+            ok!(Elements::None, errors)
+        }
+    }
 }
@@ -59,10 +59,16 @@ fn parse_definition_list<'r, 't>(
     let mut errors = Vec::new();
     let mut _paragraph_safe = false;
 
-    // Definition list needs at least one item
-    let (item, at_end) = parse_item(parser)?.chain(&mut errors, &mut _paragraph_safe);
-
-    items.push(item);
+    // Definition list needs at least one item,
+    // and it must have a term for any further definitions to belong to.
+    let (entry, at_end) = parse_item(parser)?.chain(&mut errors, &mut _paragraph_safe);
+
+    match entry {
+        ListEntry::Item(item) => items.push(item),
+        ListEntry::Definition(_) => {
+            return Err(parser.make_err(ParseErrorKind::RuleFailed));
+        }
+    }
 
     // Collect remainder, halting if there's a failure
     if !at_end {
@@ -73,9 +79,18 @@ fn parse_definition_list<'r, 't>(
                 Ok(success) => {
                     debug!("Retrieved definition list item");
 
-                    let (item, at_end) = success.chain(&mut errors, &mut _paragraph_safe);
+                    let (entry, at_end) =
+                        success.chain(&mut errors, &mut _paragraph_safe);
+
+                    match entry {
+                        ListEntry::Item(item) => items.push(item),
+                        ListEntry::Definition(value_elements) => {
+                            // There is always a previous item, see above
+                            let item = items.last_mut().unwrap();
+                            item.extra_value_elements.push(value_elements);
+                        }
+                    }
 
-                    items.push(item);
                     parser.update(sub_parser);
 
                     if at_end {
@@ -96,7 +111,7 @@ fn parse_definition_list<'r, 't>(
 
 fn parse_item<'r, 't>(
     parser: &mut Parser<'r, 't>,
-) -> ParseResult<'r, 't, (DefinitionListItem<'t>, bool)> {
+) -> ParseResult<'r, 't, (ListEntry<'t>, bool)> {
     debug!("Trying to parse a definition list item pair");
 
     let mut errors = Vec::new();
@@ -104,6 +119,9 @@ fn parse_item<'r, 't>(
 
     // The pattern for a definition list row is:
     // : key : value \n
+    //
+    // Or, for a further definition of the previous key:
+    // : : value \n
 
     // Ensure the start of the line
     if !parser.start_of_line() {
@@ -120,6 +138,25 @@ fn parse_item<'r, 't>(
 
     parser.step_n(2)?;
 
+    // Check if this is a further definition, without a key
+    if matches!(
+        parser.next_two_tokens(),
+        (Token::Colon, Some(Token::Whitespace)),
+    ) {
+        parser.step_n(2)?;
+
+        let (value_elements, should_break) =
+            parse_value(parser)?.chain(&mut errors, &mut _paragraph_safe);
+
+        // An empty definition isn't valid
+        if value_elements.is_empty() {
+            return Err(parser.make_err(ParseErrorKind::RuleFailed));
+        }
+
+        let entry = ListEntry::Definition(value_elements);
+        return ok!(false; (entry, should_break), errors);
+    }
+
     // Gather key elements until colon
     let start_token = parser.current();
     let mut key_elements = collect_consume(
@@ -144,6 +181,29 @@ fn parse_item<'r, 't>(
         .slice_partial(start_token, end_token)
         .trim();
 
+    let (value_elements, should_break) =
+        parse_value(parser)?.chain(&mut errors, &mut _paragraph_safe);
+
+    // Build and return
+    let item = DefinitionListItem {
+        key_string: cow!(key_string),
+        key_elements,
+        value_elements,
+        extra_value_elements: Vec::new(),
+    };
+
+    ok!(false; (ListEntry::Item(item), should_break), errors)
+}
+
+/// Gathers the value elements of a row, until the end of the line.
+///
+/// Also returns whether the definition list definitely ends after this row.
+fn parse_value<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+) -> ParseResult<'r, 't, (Vec<Element<'t>>, bool)> {
+    let mut errors = Vec::new();
+    let mut _paragraph_safe = false;
+
     // Gather value elements until end of line
     let (mut value_elements, last) = collect_consume_keep(
         parser,
@@ -166,13 +226,15 @@ fn parse_item<'r, 't>(
     };
 
     strip_whitespace(&mut value_elements);
+    ok!(false; (value_elements, should_break), errors)
+}
 
-    // Build and return
-    let item = DefinitionListItem {
-        key_string: cow!(key_string),
-        key_elements,
-        value_elements,
-    };
+/// A row in a definition list.
+#[derive(Debug)]
+enum ListEntry<'t> {
+    /// A new key and its definition.
+    Item(DefinitionListItem<'t>),
 
-    ok!(false; (item, should_break), errors)
+    /// A further definition for the previous key.
+    Definition(Vec<Element<'t>>),
 }
@@ -20,17 +20,21 @@
 
 use super::builder::HtmlBuilder;
 use super::escape::escape;
+use super::highlight::CodeHighlighter;
+use super::hooks::HtmlHooks;
+use super::math::MathRenderer;
 use super::meta::{HtmlMeta, HtmlMetaType};
 use super::output::HtmlOutput;
 use super::random::Random;
 use crate::data::PageRef;
 use crate::data::{Backlinks, PageInfo};
+use crate::heading_number::HeadingNumbers;
 use crate::info;
 use crate::next_index::{NextIndex, TableOfContentsIndex};
 use crate::render::Handle;
 use crate::settings::WikitextSettings;
 use crate::tree::{
-    Bibliography, BibliographyList, Element, LinkLocation, VariableScopes,
+    Bibliography, BibliographyList, Element, Heading, LinkLocation, VariableScopes,
 };
 use crate::url::is_url;
 use std::borrow::Cow;
@@ -38,6 +42,14 @@ use std::collections::HashMap;
 use std::fmt::{self, Write};
 use std::num::NonZeroUsize;
 
+/// Fields from the syntax tree which are referenced during rendering.
+#[derive(Debug, Copy, Clone)]
+pub struct TreeFields<'e, 't> {
+    pub table_of_contents: &'e [Element<'t>],
+    pub footnotes: &'e [Vec<Element<'t>>],
+    pub bibliographies: &'e BibliographyList<'t>,
+}
+
 #[derive(Debug)]
 pub struct HtmlContext<'i, 'h, 'e, 't>
 where
@@ -49,6 +61,7 @@ where
     info: &'i PageInfo<'i>,
     handle: &'h Handle,
     settings: &'e WikitextSettings,
+    hooks: HtmlHooks<'e>,
     random: Random,
 
     //
@@ -73,8 +86,10 @@ where
     //
     code_snippet_index: NonZeroUsize,
     table_of_contents_index: usize,
+    heading_numbers: HeadingNumbers,
     equation_index: NonZeroUsize,
     footnote_index: NonZeroUsize,
+    size_exceeded: bool,
 }
 
 impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
@@ -83,9 +98,8 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
         info: &'i PageInfo<'i>,
         handle: &'h Handle,
         settings: &'e WikitextSettings,
-        table_of_contents: &'e [Element<'t>],
-        footnotes: &'e [Vec<Element<'t>>],
-        bibliographies: &'e BibliographyList<'t>,
+        hooks: HtmlHooks<'e>,
+        tree_fields: TreeFields<'e, 't>,
         wikitext_len: usize,
     ) -> Self {
         // Heuristic for improving rendering performance by avoiding reallocating.
@@ -106,6 +120,12 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
             }
         };
 
+        let TreeFields {
+            table_of_contents,
+            footnotes,
+            bibliographies,
+        } = tree_fields;
+
         // Build and return
         HtmlContext {
             body: String::with_capacity(capacity),
@@ -114,6 +134,7 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
             info,
             handle,
             settings,
+            hooks,
             random: Random::default(),
             variables: VariableScopes::new(),
             table_of_contents,
@@ -122,8 +143,10 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
             pages_exists: HashMap::new(),
             code_snippet_index: NonZeroUsize::new(1).unwrap(),
             table_of_contents_index: 0,
+            heading_numbers: HeadingNumbers::new(),
             equation_index: NonZeroUsize::new(1).unwrap(),
             footnote_index: NonZeroUsize::new(1).unwrap(),
+            size_exceeded: false,
         }
     }
 
@@ -173,6 +196,16 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
         self.settings
     }
 
+    #[inline]
+    pub fn math_renderer(&self) -> Option<&'e dyn MathRenderer> {
+        self.hooks.math_renderer
+    }
+
+    #[inline]
+    pub fn code_highlighter(&self) -> Option<&'e dyn CodeHighlighter> {
+        self.hooks.code_highlighter
+    }
+
     #[inline]
     pub fn handle(&self) -> &'h Handle {
         self.handle
@@ -226,6 +259,18 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
         index
     }
 
+    /// Gets the section number for this heading, if headings are being numbered.
+    ///
+    /// See [`WikitextSettings::number_headings`](crate::settings::WikitextSettings::number_headings).
+    pub fn next_heading_number(&mut self, heading: Heading) -> Option<String> {
+        if self.settings.number_headings && heading.has_toc {
+            let depth = usize::from(heading.level.value()) - 1;
+            Some(self.heading_numbers.next(depth))
+        } else {
+            None
+        }
+    }
+
     pub fn next_table_of_contents_index(&mut self) -> usize {
         let index = self.table_of_contents_index;
         self.table_of_contents_index += 1;
@@ -312,19 +357,46 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
         &mut self.body
     }
 
+    /// Whether the output has grown beyond the maximum size in the settings.
+    ///
+    /// Once this is the case, nothing more is added to the output.
+    pub fn size_exceeded(&mut self) -> bool {
+        if !self.size_exceeded {
+            if let Some(max_size) = self.settings.max_html_size {
+                if self.body.len() > max_size {
+                    warn!(
+                        "Rendered HTML exceeded maximum size ({} > {})",
+                        self.body.len(),
+                        max_size,
+                    );
+
+                    self.size_exceeded = true;
+                }
+            }
+        }
+
+        self.size_exceeded
+    }
+
     #[inline]
     pub fn push_raw(&mut self, ch: char) {
-        self.buffer().push(ch);
+        if !self.size_exceeded() {
+            self.buffer().push(ch);
+        }
     }
 
     #[inline]
     pub fn push_raw_str(&mut self, s: &str) {
-        self.buffer().push_str(s);
+        if !self.size_exceeded() {
+            self.buffer().push_str(s);
+        }
     }
 
     #[inline]
     pub fn push_escaped(&mut self, s: &str) {
-        escape(self.buffer(), s);
+        if !self.size_exceeded() {
+            escape(self.buffer(), s);
+        }
     }
 
     #[inline]
@@ -354,6 +426,10 @@ impl<'i, 'h, 'e, 't> From<HtmlContext<'i, 'h, 'e, 't>> for HtmlOutput {
 impl<'i, 'h, 'e, 't> Write for HtmlContext<'i, 'h, 'e, 't> {
     #[inline]
     fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.size_exceeded() {
+            return Ok(());
+        }
+
         self.buffer().write_str(s)
     }
 }
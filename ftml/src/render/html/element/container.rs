@@ -43,6 +43,12 @@ pub fn render_container_internal(ctx: &mut HtmlContext, container: &Container) {
     // Get HTML tag type for this type of container
     let tag_spec = container.ctype().html_tag(ctx);
 
+    // Get section number, if this is a numbered heading
+    let heading_number = match container.ctype() {
+        ContainerType::Header(heading) => ctx.next_heading_number(heading),
+        _ => None,
+    };
+
     // Get correct ID, based on the render setting
     let random_id = choose_id(ctx, &tag_spec);
 
@@ -66,7 +72,18 @@ pub fn render_container_internal(ctx: &mut HtmlContext, container: &Container) {
     };
 
     // Add container internals
-    tag.contents(container.elements());
+    match heading_number {
+        None => tag.contents(container.elements()),
+        Some(ref number) => tag.inner(|ctx| {
+            ctx.html()
+                .span()
+                .attr(attr!("class" => "wj-heading-number"))
+                .contents(number);
+
+            ctx.push_raw(' ');
+            render_elements(ctx, container.elements());
+        }),
+    };
 }
 
 pub fn render_color(ctx: &mut HtmlContext, color: &str, elements: &[Element]) {
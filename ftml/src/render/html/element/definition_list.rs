@@ -28,11 +28,16 @@ pub fn render_definition_list(ctx: &mut HtmlContext, items: &[DefinitionListItem
         for DefinitionListItem {
             key_elements,
             value_elements,
+            extra_value_elements,
             ..
         } in items
         {
             ctx.html().dt().contents(key_elements);
             ctx.html().dd().contents(value_elements);
+
+            for value_elements in extra_value_elements {
+                ctx.html().dd().contents(value_elements);
+            }
         }
     });
 }
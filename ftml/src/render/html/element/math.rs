@@ -19,22 +19,9 @@
  */
 
 use super::prelude::*;
-use cfg_if::cfg_if;
+use crate::render::html::math::MathDisplay;
 use std::num::NonZeroUsize;
 
-cfg_if! {
-    if #[cfg(feature = "mathml")] {
-        use latex2mathml::{latex_to_mathml, DisplayStyle};
-    } else {
-        /// Mocked version of the enum from `latex2mathml`.
-        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-        enum DisplayStyle {
-            Block,
-            Inline,
-        }
-    }
-}
-
 pub fn render_math_block(ctx: &mut HtmlContext, name: Option<&str>, latex_source: &str) {
     info!(
         "Rendering math block (name '{}', source '{}')",
@@ -44,12 +31,12 @@ pub fn render_math_block(ctx: &mut HtmlContext, name: Option<&str>, latex_source
 
     let index = ctx.next_equation_index();
 
-    render_latex(ctx, name, Some(index), latex_source, DisplayStyle::Block);
+    render_latex(ctx, name, Some(index), latex_source, MathDisplay::Block);
 }
 
 pub fn render_math_inline(ctx: &mut HtmlContext, latex_source: &str) {
     info!("Rendering math inline (source '{latex_source}'");
-    render_latex(ctx, None, None, latex_source, DisplayStyle::Inline);
+    render_latex(ctx, None, None, latex_source, MathDisplay::Inline);
 }
 
 fn render_latex(
@@ -57,12 +44,11 @@ fn render_latex(
     name: Option<&str>,
     index: Option<NonZeroUsize>,
     latex_source: &str,
-    display: DisplayStyle,
+    display: MathDisplay,
 ) {
-    // error_type is unused if MathML is disabled
-    let (html_tag, wj_type, _error_type) = match display {
-        DisplayStyle::Block => ("div", "wj-math-block", "wj-error-block"),
-        DisplayStyle::Inline => ("span", "wj-math-inline", "wj-error-inline"),
+    let (html_tag, wj_type, error_type) = match display {
+        MathDisplay::Block => ("div", "wj-math-block", "wj-error-block"),
+        MathDisplay::Inline => ("span", "wj-math-inline", "wj-error-inline"),
     };
 
     // Outer container
@@ -109,29 +95,33 @@ fn render_latex(
                 ))
                 .contents(latex_source);
 
-            // Add generated MathML
-            cfg_if! {
-                if #[cfg(feature = "mathml")] {
-                    match latex_to_mathml(latex_source, display) {
-                        Ok(mathml) => {
-                            info!("Processed LaTeX -> MathML");
-
-                            // Inject MathML elements
-                            ctx.html()
-                                .element("wj-math-ml")
-                                .attr(attr!("class" => "wj-math-ml"))
-                                .inner(|ctx| ctx.push_raw_str(&mathml));
-                        }
-                        Err(error) => {
-                            warn!("Error processing LaTeX -> MathML: {error}");
-                            let error = str!(error);
-
-                            ctx.html()
-                                .span()
-                                .attr(attr!("class" => _error_type))
-                                .contents(error);
-                        }
+            // Add typeset output, or a placeholder if there's no renderer
+            match ctx.math_renderer() {
+                Some(renderer) => match renderer.render_latex(latex_source, display) {
+                    Ok(output) => {
+                        info!("Typeset LaTeX source");
+
+                        let element_name = renderer.element_name();
+                        ctx.html()
+                            .element(element_name)
+                            .attr(attr!("class" => element_name))
+                            .inner(|ctx| ctx.push_raw_str(&output));
                     }
+                    Err(error) => {
+                        warn!("Error typesetting LaTeX source: {error}");
+
+                        ctx.html()
+                            .span()
+                            .attr(attr!("class" => error_type))
+                            .contents(error);
+                    }
+                },
+                None => {
+                    debug!("No math renderer, emitting placeholder");
+
+                    ctx.html()
+                        .element("wj-math-placeholder")
+                        .attr(attr!("class" => "wj-math-placeholder"));
                 }
             }
         });
@@ -78,6 +78,11 @@ pub fn render_elements(ctx: &mut HtmlContext, elements: &[Element]) {
     info!("Rendering elements (length {})", elements.len());
 
     for element in elements {
+        // Stop rendering once the output is too large
+        if ctx.size_exceeded() {
+            break;
+        }
+
         render_element(ctx, element);
     }
 }
@@ -95,9 +95,24 @@ pub fn render_code(ctx: &mut HtmlContext, language: Option<&str>, contents: &str
                         .contents(language.unwrap_or(""));
                 });
 
-            // Code block containing highlighted contents
-            ctx.html().pre().inner(|ctx| {
-                ctx.html().code().contents(contents);
+            // Code block containing highlighted contents,
+            // or plain text if it couldn't be highlighted
+            let highlighted = match (ctx.code_highlighter(), language) {
+                (Some(highlighter), Some(language)) => {
+                    highlighter.highlight(language, contents)
+                }
+                _ => None,
+            };
+
+            ctx.html().pre().inner(|ctx| match highlighted.as_deref() {
+                Some(html) => {
+                    debug!("Inserting highlighted code");
+                    ctx.html().code().inner(|ctx| ctx.push_raw_str(html));
+                }
+                None => {
+                    debug!("No highlighting available, inserting plain text");
+                    ctx.html().code().contents(contents);
+                }
             });
         });
 }
@@ -0,0 +1,43 @@
+/*
+ * render/html/error.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::error::Error;
+use std::fmt::{self, Display};
+
+/// Error returned when rendered HTML grows beyond the configured maximum size.
+///
+/// See [`WikitextSettings::max_html_size`](crate::settings::WikitextSettings::max_html_size).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HtmlSizeError {
+    /// The maximum size of the HTML body, in bytes.
+    pub limit: usize,
+}
+
+impl Display for HtmlSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Rendered HTML exceeded the maximum size of {} bytes",
+            self.limit,
+        )
+    }
+}
+
+impl Error for HtmlSizeError {}
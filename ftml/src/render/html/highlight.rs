@@ -0,0 +1,36 @@
+/*
+ * render/html/highlight.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Pluggable syntax highlighting for code blocks.
+//!
+//! If no highlighter is given, or it doesn't support the code block's
+//! language, the contents are emitted as escaped plain text instead.
+
+use std::fmt::Debug;
+
+/// A backend which highlights source code into HTML.
+pub trait CodeHighlighter: Debug {
+    /// Highlights the contents of a code block written in the given language.
+    ///
+    /// The output is inserted as-is within the block's `<code>` element,
+    /// so it must be safe to include in the page.
+    /// If the language isn't supported, this returns `None`.
+    fn highlight(&self, language: &str, contents: &str) -> Option<String>;
+}
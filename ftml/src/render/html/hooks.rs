@@ -0,0 +1,45 @@
+/*
+ * render/html/hooks.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::highlight::CodeHighlighter;
+use super::math::{MathRenderer, DEFAULT_MATH_RENDERER};
+
+/// External backends the HTML renderer calls into for some elements.
+#[derive(Debug, Copy, Clone)]
+pub struct HtmlHooks<'a> {
+    /// Typesets LaTeX in math blocks.
+    ///
+    /// If `None`, then a placeholder element is emitted in its place.
+    pub math_renderer: Option<&'a dyn MathRenderer>,
+
+    /// Highlights the contents of code blocks.
+    ///
+    /// If `None`, then the contents are emitted as plain text.
+    pub code_highlighter: Option<&'a dyn CodeHighlighter>,
+}
+
+impl Default for HtmlHooks<'_> {
+    fn default() -> Self {
+        HtmlHooks {
+            math_renderer: DEFAULT_MATH_RENDERER,
+            code_highlighter: None,
+        }
+    }
+}
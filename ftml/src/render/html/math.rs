@@ -0,0 +1,95 @@
+/*
+ * render/html/math.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Pluggable typesetting of LaTeX in math blocks.
+//!
+//! The HTML renderer always emits the LaTeX source, but the typeset
+//! output comes from a [`MathRenderer`], so that the backend can be chosen
+//! by the caller. If no renderer is given, a placeholder is emitted instead,
+//! which can be typeset client-side.
+
+use cfg_if::cfg_if;
+use std::fmt::Debug;
+
+/// Whether math is being typeset as a block or inline with text.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MathDisplay {
+    Block,
+    Inline,
+}
+
+/// A backend which typesets LaTeX source into HTML, such as MathML.
+pub trait MathRenderer: Debug {
+    /// The name of the element wrapping this renderer's output.
+    ///
+    /// This is also used as its class.
+    fn element_name(&self) -> &'static str {
+        "wj-math-rendered"
+    }
+
+    /// Typesets the LaTeX source into HTML.
+    ///
+    /// The output is inserted as-is, so it must be safe to include in the page.
+    /// If the source cannot be typeset, a message describing why is returned,
+    /// which is shown in place of the output.
+    fn render_latex(
+        &self,
+        latex_source: &str,
+        display: MathDisplay,
+    ) -> Result<String, String>;
+}
+
+cfg_if! {
+    if #[cfg(feature = "mathml")] {
+        use latex2mathml::{latex_to_mathml, DisplayStyle};
+
+        /// Math renderer producing MathML, using `latex2mathml`.
+        #[derive(Debug, Copy, Clone, Default)]
+        pub struct MathMlRenderer;
+
+        impl MathRenderer for MathMlRenderer {
+            fn element_name(&self) -> &'static str {
+                "wj-math-ml"
+            }
+
+            fn render_latex(
+                &self,
+                latex_source: &str,
+                display: MathDisplay,
+            ) -> Result<String, String> {
+                let display = match display {
+                    MathDisplay::Block => DisplayStyle::Block,
+                    MathDisplay::Inline => DisplayStyle::Inline,
+                };
+
+                latex_to_mathml(latex_source, display).map_err(|error| str!(error))
+            }
+        }
+
+        /// The math renderer used if none is specified.
+        pub const DEFAULT_MATH_RENDERER: Option<&dyn MathRenderer> = Some(&MathMlRenderer);
+    } else {
+        /// The math renderer used if none is specified.
+        ///
+        /// Without the `mathml` feature there is no built-in renderer,
+        /// so placeholders are emitted.
+        pub const DEFAULT_MATH_RENDERER: Option<&dyn MathRenderer> = None;
+    }
+}
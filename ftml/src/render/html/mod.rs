@@ -26,21 +26,32 @@ mod attributes;
 mod builder;
 mod context;
 mod element;
+mod error;
 mod escape;
+mod highlight;
+mod hooks;
+mod math;
 mod meta;
 mod output;
 mod random;
 mod render;
 
+pub use self::error::HtmlSizeError;
+pub use self::highlight::CodeHighlighter;
+pub use self::hooks::HtmlHooks;
+pub use self::math::{MathDisplay, MathRenderer, DEFAULT_MATH_RENDERER};
 pub use self::meta::{HtmlMeta, HtmlMetaType};
 pub use self::output::HtmlOutput;
 
+#[cfg(feature = "mathml")]
+pub use self::math::MathMlRenderer;
+
 #[cfg(test)]
 use super::prelude;
 
 use self::attributes::AddedAttributes;
-use self::context::HtmlContext;
-use crate::data::PageInfo;
+use self::context::{HtmlContext, TreeFields};
+use crate::data::{Backlinks, PageInfo};
 use crate::render::{Handle, Render};
 use crate::settings::WikitextSettings;
 use crate::tree::SyntaxTree;
@@ -48,15 +59,32 @@ use crate::tree::SyntaxTree;
 #[derive(Debug)]
 pub struct HtmlRender;
 
-impl Render for HtmlRender {
-    type Output = HtmlOutput;
+impl HtmlRender {
+    /// Render an abstract syntax tree into HTML, failing if the output is too large.
+    ///
+    /// If `max_html_size` in the settings is exceeded, rendering stops and
+    /// this returns an error rather than the partial output.
+    #[inline]
+    pub fn try_render(
+        &self,
+        tree: &SyntaxTree,
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> Result<HtmlOutput, HtmlSizeError> {
+        self.try_render_with_hooks(tree, page_info, settings, HtmlHooks::default())
+    }
 
-    fn render(
+    /// Render an abstract syntax tree into HTML, using the given hooks.
+    ///
+    /// See [`HtmlHooks`] for the backends which can be provided,
+    /// such as for typesetting math or highlighting code.
+    pub fn try_render_with_hooks(
         &self,
         tree: &SyntaxTree,
         page_info: &PageInfo,
         settings: &WikitextSettings,
-    ) -> HtmlOutput {
+        hooks: HtmlHooks,
+    ) -> Result<HtmlOutput, HtmlSizeError> {
         info!(
             "Rendering HTML (site {}, page {}, category {})",
             page_info.site.as_ref(),
@@ -71,9 +99,12 @@ impl Render for HtmlRender {
             page_info,
             &Handle,
             settings,
-            &tree.table_of_contents,
-            &tree.footnotes,
-            &tree.bibliographies,
+            hooks,
+            TreeFields {
+                table_of_contents: &tree.table_of_contents,
+                footnotes: &tree.footnotes,
+                bibliographies: &tree.bibliographies,
+            },
             tree.wikitext_len,
         );
 
@@ -83,7 +114,38 @@ impl Render for HtmlRender {
             .attr(attr!("class" => "wj-body"))
             .contents(&tree.elements);
 
+        // Discard the partial output if it's too large
+        if ctx.size_exceeded() {
+            let limit = settings.max_html_size.unwrap_or_default();
+            return Err(HtmlSizeError { limit });
+        }
+
         // Build and return HtmlOutput
-        ctx.into()
+        Ok(ctx.into())
+    }
+}
+
+impl Render for HtmlRender {
+    type Output = HtmlOutput;
+
+    /// Render an abstract syntax tree into HTML.
+    ///
+    /// If the output exceeds `max_html_size` in the settings,
+    /// then the body is only an error message.
+    /// Use [`HtmlRender::try_render`] to handle this case directly.
+    fn render(
+        &self,
+        tree: &SyntaxTree,
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> HtmlOutput {
+        self.try_render(tree, page_info, settings)
+            .unwrap_or_else(|error| HtmlOutput {
+                body: format!(
+                    "<wj-body class=\"wj-body\"><div class=\"wj-error-block\">{error}</div></wj-body>",
+                ),
+                meta: vec![],
+                backlinks: Backlinks::new(),
+            })
     }
 }
@@ -180,6 +180,7 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
             for DefinitionListItem {
                 key_elements,
                 value_elements,
+                extra_value_elements,
                 ..
             } in items
             {
@@ -187,6 +188,11 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
                 ctx.push(' ');
                 render_elements(ctx, value_elements);
                 ctx.add_newline();
+
+                for value_elements in extra_value_elements {
+                    render_elements(ctx, value_elements);
+                    ctx.add_newline();
+                }
             }
 
             ctx.add_newline();
@@ -22,6 +22,8 @@ mod interwiki;
 
 pub use self::interwiki::{InterwikiSettings, DEFAULT_INTERWIKI, EMPTY_INTERWIKI};
 
+use std::collections::HashSet;
+
 const DEFAULT_MINIFY_CSS: bool = true;
 
 /// Settings to tweak behavior in the ftml parser and renderer.
@@ -48,6 +50,15 @@ pub struct WikitextSettings {
     /// It is off by default.
     pub use_include_compatibility: bool,
 
+    /// Whether variables missing from an include are replaced with nothing.
+    ///
+    /// When a page is included, each `{$name}` in it is replaced with the
+    /// value given for `name` in the include block. If no such value was given,
+    /// then by default the `{$name}` is left as-is. If this is true, it is
+    /// removed instead.
+    #[serde(default)]
+    pub blank_missing_include_variables: bool,
+
     /// Whether IDs should have true values, or be excluded or randomly generated.
     ///
     /// In the latter case, IDs can be used for navigation, for instance
@@ -90,6 +101,43 @@ pub struct WikitextSettings {
     ///   any beyond that are considered part of the link.
     /// * By convention, prefixes should be all-lowercase.
     pub interwiki: InterwikiSettings,
+
+    /// Which blocks are not permitted.
+    ///
+    /// Any name the block accepts may be used here, for instance either
+    /// `tabview` or `tabs`, and this disables the block under all its names.
+    /// Names must be lowercase.
+    ///
+    /// A disabled block is still parsed, but it produces a warning
+    /// and renders nothing.
+    #[serde(default)]
+    pub disabled_blocks: HashSet<String>,
+
+    /// The maximum size of the rendered HTML body, in bytes.
+    ///
+    /// Small inputs can expand into very large outputs, for instance through
+    /// nested includes. If the HTML renderer exceeds this size, it stops
+    /// rendering and produces an error instead.
+    ///
+    /// If `None`, then there is no limit. This is the default.
+    #[serde(default)]
+    pub max_html_size: Option<usize>,
+
+    /// The deepest heading level to include in the table of contents.
+    ///
+    /// For instance, `Some(3)` includes only headings from H1 to H3.
+    /// Deeper headings are still given anchors, but are not listed.
+    ///
+    /// If `None`, then all headings are included. This is the default.
+    #[serde(default)]
+    pub max_toc_depth: Option<u8>,
+
+    /// Whether to number headings hierarchically, for instance `1`, `1.1`, `1.2`.
+    ///
+    /// Only headings with table of contents entries are numbered,
+    /// and the entries themselves are given the same numbers.
+    #[serde(default)]
+    pub number_headings: bool,
 }
 
 impl WikitextSettings {
@@ -101,44 +149,71 @@ impl WikitextSettings {
                 mode,
                 enable_page_syntax: true,
                 use_include_compatibility: false,
+                blank_missing_include_variables: false,
                 use_true_ids: true,
                 isolate_user_ids: false,
                 minify_css: DEFAULT_MINIFY_CSS,
                 allow_local_paths: true,
                 interwiki,
+                disabled_blocks: HashSet::new(),
+                max_html_size: None,
+                max_toc_depth: None,
+                number_headings: false,
             },
             WikitextMode::Draft => WikitextSettings {
                 mode,
                 enable_page_syntax: true,
                 use_include_compatibility: false,
+                blank_missing_include_variables: false,
                 use_true_ids: false,
                 isolate_user_ids: false,
                 minify_css: DEFAULT_MINIFY_CSS,
                 allow_local_paths: true,
                 interwiki,
+                disabled_blocks: HashSet::new(),
+                max_html_size: None,
+                max_toc_depth: None,
+                number_headings: false,
             },
             WikitextMode::ForumPost | WikitextMode::DirectMessage => WikitextSettings {
                 mode,
                 enable_page_syntax: false,
                 use_include_compatibility: false,
+                blank_missing_include_variables: false,
                 use_true_ids: false,
                 isolate_user_ids: false,
                 minify_css: DEFAULT_MINIFY_CSS,
                 allow_local_paths: false,
                 interwiki,
+                disabled_blocks: HashSet::new(),
+                max_html_size: None,
+                max_toc_depth: None,
+                number_headings: false,
             },
             WikitextMode::List => WikitextSettings {
                 mode,
                 enable_page_syntax: true,
                 use_include_compatibility: false,
+                blank_missing_include_variables: false,
                 use_true_ids: false,
                 isolate_user_ids: false,
                 minify_css: DEFAULT_MINIFY_CSS,
                 allow_local_paths: true,
                 interwiki,
+                disabled_blocks: HashSet::new(),
+                max_html_size: None,
+                max_toc_depth: None,
+                number_headings: false,
             },
         }
     }
+
+    /// Determines if a block with the given accepted names has been disabled.
+    pub fn is_block_disabled(&self, names: &[&str]) -> bool {
+        names
+            .iter()
+            .any(|name| self.disabled_blocks.contains(*name))
+    }
 }
 
 /// What mode parsing and rendering is done in.
@@ -0,0 +1,104 @@
+/*
+ * test/highlight.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::data::PageInfo;
+use crate::render::html::{CodeHighlighter, HtmlHooks, HtmlRender};
+use crate::settings::{WikitextMode, WikitextSettings};
+
+/// Highlighter which only knows one language, wrapping each line in a span.
+#[derive(Debug)]
+struct StubHighlighter;
+
+impl CodeHighlighter for StubHighlighter {
+    fn highlight(&self, language: &str, contents: &str) -> Option<String> {
+        if language != "stub" {
+            return None;
+        }
+
+        let mut html = String::new();
+        for line in contents.lines() {
+            html.push_str("<span class=\"line\">");
+            html.push_str(line);
+            html.push_str("</span>");
+        }
+
+        Some(html)
+    }
+}
+
+#[test]
+fn highlight_code() {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! check {
+        ($input:expr, $highlighter:expr, $substring:expr $(,)?) => {{
+            let mut text = str!($input);
+            crate::preprocess(&mut text);
+
+            let tokens = crate::tokenize(&text);
+            let result = crate::parse(&tokens, &page_info, &settings);
+            let (tree, _errors) = result.into();
+            let html_output = HtmlRender
+                .try_render_with_hooks(
+                    &tree,
+                    &page_info,
+                    &settings,
+                    HtmlHooks {
+                        code_highlighter: $highlighter,
+                        ..HtmlHooks::default()
+                    },
+                )
+                .expect("HTML output too large");
+
+            println!();
+            println!("Input:  {:?}", $input);
+            println!("Output: {:?}", html_output.body);
+
+            assert!(
+                html_output.body.contains($substring),
+                "HTML output for {:?} doesn't contain expected substring {:?}",
+                $input,
+                $substring,
+            );
+        }};
+    }
+
+    check!(
+        "[[code type=\"stub\"]]\nfoo\nbar\n[[/code]]",
+        Some(&StubHighlighter),
+        "<pre><code><span class=\"line\">foo</span><span class=\"line\">bar</span></code></pre>",
+    );
+    check!(
+        "[[code type=\"unknown\"]]\n<foo>\n[[/code]]",
+        Some(&StubHighlighter),
+        "<pre><code>&lt;foo&gt;</code></pre>",
+    );
+    check!(
+        "[[code]]\n<foo>\n[[/code]]",
+        Some(&StubHighlighter),
+        "<pre><code>&lt;foo&gt;</code></pre>",
+    );
+    check!(
+        "[[code type=\"stub\"]]\n<foo>\n[[/code]]",
+        None,
+        "<pre><code>&lt;foo&gt;</code></pre>",
+    );
+}
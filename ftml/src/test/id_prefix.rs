@@ -24,6 +24,7 @@ use crate::tree::{
     AttributeMap, Container, ContainerType, Element, ImageSource, ListItem, ListType,
 };
 use std::borrow::Cow;
+use std::collections::HashSet;
 
 #[test]
 fn isolate_user_ids() {
@@ -55,10 +56,15 @@ fn isolate_user_ids() {
         enable_page_syntax: true,
         use_true_ids: true,
         use_include_compatibility: false,
+        blank_missing_include_variables: false,
         isolate_user_ids: true,
         minify_css: false,
         allow_local_paths: true,
         interwiki: EMPTY_INTERWIKI.clone(),
+        disabled_blocks: HashSet::new(),
+        max_html_size: None,
+        max_toc_depth: None,
+        number_headings: false,
     };
 
     fn append_footnote_block(mut elements: Vec<Element>) -> Vec<Element> {
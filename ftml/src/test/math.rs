@@ -0,0 +1,146 @@
+/*
+ * test/math.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::data::PageInfo;
+use crate::render::html::{HtmlHooks, HtmlRender, MathDisplay, MathRenderer};
+use crate::settings::{WikitextMode, WikitextSettings};
+use crate::tree::{Element, SyntaxTree};
+use std::borrow::Cow;
+
+/// Math renderer which just echoes the source, to check how output is inserted.
+#[derive(Debug)]
+struct StubRenderer;
+
+impl MathRenderer for StubRenderer {
+    fn render_latex(
+        &self,
+        latex_source: &str,
+        display: MathDisplay,
+    ) -> Result<String, String> {
+        if latex_source == "fail" {
+            return Err(str!("stub failure"));
+        }
+
+        let tag = match display {
+            MathDisplay::Block => "stub-block",
+            MathDisplay::Inline => "stub-inline",
+        };
+
+        Ok(format!("<{tag}>{latex_source}</{tag}>"))
+    }
+}
+
+fn parse(input: &str) -> SyntaxTree<'static> {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let mut text = str!(input);
+    crate::preprocess(&mut text);
+
+    let tokens = crate::tokenize(&text);
+    let result = crate::parse(&tokens, &page_info, &settings);
+    let (tree, _errors) = result.into();
+    tree.to_owned()
+}
+
+#[test]
+fn parse_math() {
+    macro_rules! check {
+        ($input:expr, $expected:expr $(,)?) => {{
+            let tree = parse($input);
+            let element = tree.elements.first().expect("No elements");
+
+            assert_eq!(
+                element, &$expected,
+                "Math element didn't match expected for {:?}",
+                $input,
+            );
+        }};
+    }
+
+    check!(
+        "[[math]]\nx^2 + y^2\n[[/math]]",
+        Element::Math {
+            name: None,
+            latex_source: Cow::Borrowed("x^2 + y^2"),
+        },
+    );
+    check!(
+        "[[math circle]]\nx^2 + y^2 = r^2\n[[/math]]",
+        Element::Math {
+            name: Some(Cow::Borrowed("circle")),
+            latex_source: Cow::Borrowed("x^2 + y^2 = r^2"),
+        },
+    );
+}
+
+#[test]
+fn render_math() {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! check {
+        ($input:expr, $renderer:expr, $substring:expr $(,)?) => {{
+            let tree = parse($input);
+            let html_output = HtmlRender
+                .try_render_with_hooks(
+                    &tree,
+                    &page_info,
+                    &settings,
+                    HtmlHooks {
+                        math_renderer: $renderer,
+                        code_highlighter: None,
+                    },
+                )
+                .expect("HTML output too large");
+
+            println!();
+            println!("Input:  {:?}", $input);
+            println!("Output: {:?}", html_output.body);
+
+            assert!(
+                html_output.body.contains($substring),
+                "HTML output for {:?} doesn't contain expected substring {:?}",
+                $input,
+                $substring,
+            );
+        }};
+    }
+
+    check!(
+        "[[math]]\nx^2\n[[/math]]",
+        Some(&StubRenderer),
+        "<wj-math-rendered class=\"wj-math-rendered\"><stub-block>x^2</stub-block></wj-math-rendered>",
+    );
+    check!(
+        "A [[$ y^2 $]] B",
+        Some(&StubRenderer),
+        "<wj-math-rendered class=\"wj-math-rendered\"><stub-inline>y^2</stub-inline></wj-math-rendered>",
+    );
+    check!(
+        "[[math]]\nfail\n[[/math]]",
+        Some(&StubRenderer),
+        "<span class=\"wj-error-block\">stub failure</span>",
+    );
+    check!(
+        "[[math]]\nx^2\n[[/math]]",
+        None,
+        "<wj-math-placeholder class=\"wj-math-placeholder\"></wj-math-placeholder>",
+    );
+}
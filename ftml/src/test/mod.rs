@@ -19,8 +19,10 @@
  */
 
 mod ast;
+mod highlight;
 mod id_prefix;
 mod includer;
 mod large;
+mod math;
 mod prop;
 mod settings;
@@ -21,6 +21,7 @@
 use crate::data::PageInfo;
 use crate::render::{html::HtmlRender, Render};
 use crate::settings::{WikitextMode, WikitextSettings};
+use crate::tree::{Element, LinkLabel, LinkLocation, ListItem, SyntaxTree};
 
 #[test]
 fn settings() {
@@ -100,3 +101,229 @@ fn settings() {
         [true, true, false, false, true],
     );
 }
+
+#[test]
+fn disabled_blocks() {
+    use crate::parsing::ParseErrorKind;
+
+    let page_info = PageInfo::dummy();
+    let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+    settings.disabled_blocks.insert(str!("iframe"));
+    settings.disabled_blocks.insert(str!("tabs"));
+
+    macro_rules! check {
+        ($input:expr, $substring:expr, $disabled:expr $(,)?) => {{
+            let tokens = crate::tokenize($input);
+            let result = crate::parse(&tokens, &page_info, &settings);
+            let (tree, errors) = result.into();
+            let html_output = HtmlRender.render(&tree, &page_info, &settings);
+            let warned = errors
+                .iter()
+                .any(|error| error.kind() == ParseErrorKind::BlockDisabled);
+
+            println!();
+            println!("Input:  {:?}", $input);
+            println!("Output: {:?}", html_output.body);
+            println!("Errors: {:#?}", errors);
+
+            assert_eq!(
+                html_output.body.contains($substring),
+                !$disabled,
+                "HTML for {} block did not match",
+                if $disabled { "disabled" } else { "enabled" },
+            );
+
+            assert_eq!(warned, $disabled, "Disabled block warning did not match");
+        }};
+    }
+
+    check!("[[iframe https://example.com/]]", "<iframe", true);
+    check!(
+        "[[tabview]]\n[[tab Apple]]\nCherry\n[[/tab]]\n[[/tabview]]",
+        "Cherry",
+        true,
+    );
+    check!("[[span class=\"apple\"]]Cherry[[/span]]", "Cherry", false);
+    check!("[[div]]\nCherry\n[[/div]]", "Cherry", false);
+}
+
+#[test]
+fn max_html_size() {
+    use crate::render::html::HtmlSizeError;
+
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    // Small input which expands into much larger output
+    let input = "+ A\n\n".repeat(500);
+    let tokens = crate::tokenize(&input);
+    let result = crate::parse(&tokens, &page_info, &settings);
+    let (tree, _errors) = result.into();
+
+    // No limit by default
+    let html_output = HtmlRender
+        .try_render(&tree, &page_info, &settings)
+        .expect("Rendering failed without a size limit");
+
+    let size = html_output.body.len();
+    assert!(size > input.len(), "Output did not expand on input");
+
+    // Limit large enough for the output
+    let limited_settings = WikitextSettings {
+        max_html_size: Some(size),
+        ..settings.clone()
+    };
+    let html_output = HtmlRender
+        .try_render(&tree, &page_info, &limited_settings)
+        .expect("Rendering failed below the size limit");
+    assert_eq!(html_output.body.len(), size);
+
+    // Limit smaller than the output
+    let limited_settings = WikitextSettings {
+        max_html_size: Some(1000),
+        ..settings.clone()
+    };
+    let error = HtmlRender
+        .try_render(&tree, &page_info, &limited_settings)
+        .expect_err("Rendering succeeded above the size limit");
+    assert_eq!(error, HtmlSizeError { limit: 1000 });
+
+    let html_output = HtmlRender.render(&tree, &page_info, &limited_settings);
+    assert!(
+        html_output.body.contains("wj-error-block"),
+        "Size limit error not rendered",
+    );
+    assert!(
+        !html_output.body.contains("<h1"),
+        "Partial output was rendered",
+    );
+}
+
+/// Gets the anchor and label of each link in the tree's table of contents, in order.
+fn toc_entries(tree: &SyntaxTree) -> Vec<(String, String)> {
+    fn collect(element: &Element, entries: &mut Vec<(String, String)>) {
+        if let Element::List { items, .. } = element {
+            for item in items {
+                match item {
+                    ListItem::Elements { elements, .. } => {
+                        for element in elements {
+                            if let Element::Link {
+                                link: LinkLocation::Url(anchor),
+                                label: LinkLabel::Text(name),
+                                ..
+                            } = element
+                            {
+                                entries.push((anchor.to_string(), name.to_string()));
+                            }
+                        }
+                    }
+                    ListItem::SubList { element } => collect(element, entries),
+                }
+            }
+        }
+    }
+
+    let mut entries = Vec::new();
+    for element in &tree.table_of_contents {
+        collect(element, &mut entries);
+    }
+    entries
+}
+
+#[test]
+fn max_toc_depth() {
+    let page_info = PageInfo::dummy();
+    let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let input = "+ One\n\n++ Two\n\n+++ Three\n\n++++ Four\n\n+ Five";
+
+    macro_rules! check {
+        ($max_toc_depth:expr, $expected:expr $(,)?) => {{
+            settings.max_toc_depth = $max_toc_depth;
+
+            let tokens = crate::tokenize(input);
+            let result = crate::parse(&tokens, &page_info, &settings);
+            let (tree, _errors) = result.into();
+
+            let entries = toc_entries(&tree);
+            let expected: Vec<(String, String)> = $expected
+                .iter()
+                .map(|(anchor, name): &(&str, &str)| (str!(anchor), str!(name)))
+                .collect();
+
+            assert_eq!(
+                entries, expected,
+                "Table of contents entries don't match expected",
+            );
+
+            // All headings are still anchored
+            let html_output = HtmlRender.render(&tree, &page_info, &settings);
+            for id in ["toc0", "toc1", "toc2", "toc3", "toc4"] {
+                assert!(
+                    html_output.body.contains(&format!("id=\"{id}\"")),
+                    "Heading anchor {id} missing from output",
+                );
+            }
+        }};
+    }
+
+    // No limit
+    check!(
+        None,
+        [
+            ("#toc0", "One"),
+            ("#toc1", "Two"),
+            ("#toc2", "Three"),
+            ("#toc3", "Four"),
+            ("#toc4", "Five"),
+        ],
+    );
+
+    // Only H1 and H2
+    check!(
+        Some(2),
+        [("#toc0", "One"), ("#toc1", "Two"), ("#toc4", "Five")],
+    );
+
+    // Only H1
+    check!(Some(1), [("#toc0", "One"), ("#toc4", "Five")]);
+}
+
+#[test]
+fn number_headings() {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings {
+        number_headings: true,
+        ..WikitextSettings::from_mode(WikitextMode::Page)
+    };
+
+    let input = "+ A\n\n++ B\n\n+++ C\n\n++ D\n\n++* Unlisted\n\n+ E\n\n+++ F";
+    let tokens = crate::tokenize(input);
+    let result = crate::parse(&tokens, &page_info, &settings);
+    let (tree, _errors) = result.into();
+
+    // Headings in the output
+    let html_output = HtmlRender.render(&tree, &page_info, &settings);
+    let numbers: Vec<&str> = html_output
+        .body
+        .split("<span class=\"wj-heading-number\">")
+        .skip(1)
+        .map(|part| part.split_once("</span>").unwrap().0)
+        .collect();
+
+    assert_eq!(numbers, ["1", "1.1", "1.1.1", "1.2", "2", "2.1"]);
+    assert!(html_output.body.contains("1.1.1</span> C"));
+    assert!(!html_output.body.contains("</span> Unlisted"));
+
+    // Table of contents entries have the same numbers
+    let labels: Vec<String> = toc_entries(&tree)
+        .into_iter()
+        .map(|(_, label)| label)
+        .collect();
+
+    assert_eq!(labels, ["1 A", "1.1 B", "1.1.1 C", "1.2 D", "2 E", "2.1 F"]);
+
+    // Numbering is off by default
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let html_output = HtmlRender.render(&tree, &page_info, &settings);
+    assert!(!html_output.body.contains("wj-heading-number"));
+}
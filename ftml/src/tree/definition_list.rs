@@ -33,6 +33,14 @@ pub struct DefinitionListItem<'t> {
 
     #[serde(rename = "value")]
     pub value_elements: Vec<Element<'t>>,
+
+    /// Any further definitions for this term, after the first.
+    #[serde(
+        rename = "extra_values",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub extra_value_elements: Vec<Vec<Element<'t>>>,
 }
 
 impl DefinitionListItem<'_> {
@@ -41,6 +49,11 @@ impl DefinitionListItem<'_> {
             key_string: string_to_owned(&self.key_string),
             key_elements: elements_to_owned(&self.key_elements),
             value_elements: elements_to_owned(&self.value_elements),
+            extra_value_elements: self
+                .extra_value_elements
+                .iter()
+                .map(|elements| elements_to_owned(elements))
+                .collect(),
         }
     }
 }
@@ -128,6 +128,7 @@ error-api =
   .FORBIDDEN = You are not authorized to perform this action.
   .NOT_FOUND = The requested resource was not found.
   .CONFLICT = The requested resource is in conflict with another resource.
+  .RATE_LIMITED = You are making too many requests. Please wait and try again.
 
   .ACCOUNT_ALREADY_VERIFIED = This account has already been verified.
   .ACCOUNT_NO_EMAIL = This account does not have an email address.
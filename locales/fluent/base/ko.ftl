@@ -125,6 +125,7 @@ error-api =
   .FORBIDDEN = 이 동작을 수행할 권한이 없습니다.
   .NOT_FOUND = 요청한 리소스를 찾을 수 없습니다.
   .CONFLICT = 요청한 리소스가 다른 리소스와 충돌합니다.
+  .RATE_LIMITED = 요청이 너무 많습니다. 잠시 후 다시 시도해 주세요.
 
   .ACCOUNT_ALREADY_VERIFIED = 이 계정은 이미 인증되었습니다.
   .ACCOUNT_NO_EMAIL = 이 계정에는 등록된 이메일 주소가 없습니다.
@@ -128,6 +128,7 @@ error-api =
   .FORBIDDEN = Nie jesteś upoważniony do wykonania tej czynności.
   .NOT_FOUND = Żądana treść nie została odnaleziona.
   .CONFLICT = Żądana treść jest w konflikcie z inną treścią.
+  .RATE_LIMITED = Wysyłasz zbyt wiele żądań. Poczekaj chwilę i spróbuj ponownie.
 
   .ACCOUNT_ALREADY_VERIFIED = Konto zostało już wcześniej zweryfikowane.
   .ACCOUNT_NO_EMAIL = Konto nie ma załączonego adresu email.
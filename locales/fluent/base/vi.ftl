@@ -125,6 +125,7 @@ error-api =
   .FORBIDDEN = Bạn không có quyền thực hiện hành động này.
   .NOT_FOUND = Không thể tìm thấy tài nguyên này.
   .CONFLICT = Tài nguyên bạn yêu cầu đang có xung đột với tài nguyên khác.
+  .RATE_LIMITED = Bạn đang gửi quá nhiều yêu cầu. Vui lòng đợi rồi thử lại.
 
   .ACCOUNT_ALREADY_VERIFIED = Tài khoản này đã được xác minh trước đó.
   .ACCOUNT_NO_EMAIL = Tài khoản này không có địa chỉ email.
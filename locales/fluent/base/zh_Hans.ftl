@@ -124,6 +124,7 @@ error-api =
   .FORBIDDEN = 您没有权限执行此操作。
   .NOT_FOUND = 未找到所请求的来源。
   .CONFLICT = 所请求的来源与另一来源冲突。
+  .RATE_LIMITED = 您的请求过于频繁，请稍后再试。
 
   .ACCOUNT_ALREADY_VERIFIED = 此账号已认证。
   .ACCOUNT_NO_EMAIL = 此账号无电子邮箱。
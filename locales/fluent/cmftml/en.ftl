@@ -45,6 +45,8 @@ cmftml-lint =
 
   .block-disallows-score = Block '{ $slice }' doesn't support a score invocation. (starting '_' character)
 
+  .block-disabled = Block '{ $slice }' is disabled on this site, and will not be shown.
+
   .block-missing-name = Block '{ $slice }' requires a name/value, but none is specified.
 
   .block-missing-close-brackets = This block is missing closing ']]' brackets.
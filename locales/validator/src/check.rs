@@ -18,14 +18,34 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::fix::fix_source;
 use crate::messages::Catalog;
+use crate::options::{read_used_keys, Options};
 use fluent_bundle::FluentResource;
 use fluent_syntax::ast;
 use std::path::Path;
 use std::{fs, process};
 use unic_langid::LanguageIdentifier;
 
-pub fn run<P: AsRef<Path>>(directory: P) {
+pub fn run<P: AsRef<Path>>(directory: P, options: &Options) {
+    let success = check_directory(directory, options);
+
+    // Exit with result
+    if success {
+        println!();
+        println!("Everything looks in order.");
+        process::exit(0);
+    } else {
+        eprintln!();
+        eprintln!("Some validation issues found! See above.");
+        process::exit(1);
+    }
+}
+
+/// Reads and checks all the Fluent files in the localization directory.
+///
+/// Returns `true` if no validation issues were found.
+pub fn check_directory<P: AsRef<Path>>(directory: P, options: &Options) -> bool {
     let directory = directory.as_ref();
     let mut success = true;
 
@@ -127,31 +147,79 @@ pub fn run<P: AsRef<Path>>(directory: P) {
             };
 
             // Traverse resource, add keys to mapping
+            let mut has_junk = false;
+
             for entry in resource.entries() {
                 match entry {
                     ast::Entry::Message(message) => catalog.add_message(locale.clone(), message),
-                    ast::Entry::Term(term) => catalog.add_term(term),
+                    ast::Entry::Term(term) => catalog.add_term(locale.clone(), term),
                     ast::Entry::Junk { content } => {
                         fail!("Fluent file contains unknown data: {}", content);
+                        has_junk = true;
                     }
                     _ => (),
                 }
             }
+
+            // Sort and normalize file, if requested
+            if options.fix && !has_junk {
+                let fixed = fix_source(&source);
+
+                if fixed != source {
+                    match fs::write(&path, fixed) {
+                        Ok(()) => println!("+++ Fixed {}", path.display()),
+                        Err(error) => {
+                            fail!("Unable to write Fluent file {}: {}", path.display(), error);
+                        }
+                    }
+                }
+            }
         }
     }
 
     // Built catalog, check for validity
     catalog.print_summary();
-    success &= catalog.check();
+    success &= catalog.check(options);
+    success
+}
 
-    // Exit with result
-    if success {
-        println!();
-        println!("Everything looks in order.");
-        process::exit(0);
-    } else {
-        eprintln!();
-        eprintln!("Some validation issues found! See above.");
-        process::exit(1);
-    }
+#[test]
+fn whitespace() {
+    let lenient = Options::default();
+    let strict = Options {
+        strict: true,
+        ..Default::default()
+    };
+
+    // Stray whitespace is only a warning, unless in strict mode
+    assert!(check_directory("test/whitespace", &lenient));
+    assert!(!check_directory("test/whitespace", &strict));
+}
+
+#[test]
+fn unused_keys() {
+    let strict = |used_keys| Options {
+        strict: true,
+        used_keys: Some(used_keys),
+        ..Default::default()
+    };
+
+    // Only checked if the used keys are known
+    assert!(check_directory("test/unused", &Options::default()));
+
+    // The manifest doesn't list one of the messages
+    let used_keys = read_used_keys("test/unused-keys.txt");
+    assert!(!used_keys.contains("old-banner"));
+    assert!(!check_directory("test/unused", &strict(used_keys.clone())));
+
+    // With every message used
+    let mut used_keys = used_keys;
+    used_keys.insert(str!("old-banner"));
+    assert!(check_directory("test/unused", &strict(used_keys)));
+}
+
+#[test]
+fn dangling_terms() {
+    // The translation references a term only defined for the primary locale
+    assert!(!check_directory("test/terms", &Options::default()));
 }
@@ -0,0 +1,222 @@
+/*
+ * fix.rs
+ *
+ * wikijump-locales-validator - Validate Wikijump's Fluent localization files
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Rewrites Fluent files so that entries are sorted and consistently spaced.
+//!
+//! Files are split into sections, each beginning with a group comment or
+//! a standalone comment. Entries are only sorted within their section, and
+//! comments attached to an entry are kept with it.
+//!
+//! This works on the lines of the file rather than its syntax tree,
+//! so that the text of each message is preserved as it was written.
+//! It should only be run on files which parse without errors.
+
+#[derive(Debug, Default)]
+struct Section<'a> {
+    /// The comment lines which begin this section, if any.
+    header: Vec<&'a str>,
+    entries: Vec<Entry<'a>>,
+
+    /// Whether entries in this section are separated by blank lines.
+    spaced: bool,
+}
+
+#[derive(Debug)]
+struct Entry<'a> {
+    key: &'a str,
+    lines: Vec<String>,
+}
+
+/// Gets the number of `#` characters a comment line begins with.
+fn comment_level(line: &str) -> usize {
+    line.chars().take_while(|&c| c == '#').count()
+}
+
+/// Determines if a line continues the previous entry, rather than beginning one.
+///
+/// Most continuation lines are indented, but the variants and closing brace of
+/// a select expression, as well as attributes, may begin at the start of a line.
+fn is_continuation(line: &str) -> bool {
+    line.starts_with(char::is_whitespace)
+        || line.starts_with('}')
+        || line.starts_with('[')
+        || line.starts_with("*[")
+        || line.starts_with('.')
+}
+
+/// Normalizes the first line of an entry, e.g. `key=value` to `key = value`.
+fn normalize_entry_line(line: &str) -> (&str, String) {
+    match line.split_once('=') {
+        Some((key, value)) => {
+            let key = key.trim();
+            let value = value.trim();
+
+            let line = if value.is_empty() {
+                format!("{} =", key)
+            } else {
+                format!("{} = {}", key, value)
+            };
+
+            (key, line)
+        }
+        None => (line.trim(), str!(line)),
+    }
+}
+
+/// Sorts the entries in Fluent source and normalizes its spacing.
+pub fn fix_source(source: &str) -> String {
+    let mut sections = vec![Section::default()];
+    let mut comment: Vec<&str> = Vec::new();
+    let mut blank_lines = 0;
+
+    // Begins a new section, with the pending comment as its header.
+    macro_rules! new_section {
+        () => {{
+            sections.push(Section {
+                header: comment.drain(..).collect(),
+                ..Default::default()
+            });
+        }};
+    }
+
+    for line in source.lines() {
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            // A comment followed by a blank line is not attached to an entry
+            if comment.is_empty() {
+                blank_lines += 1;
+            } else {
+                new_section!();
+            }
+        } else if line.starts_with('#') {
+            // Comments of different kinds are not part of the same block
+            if let Some(previous) = comment.last() {
+                if comment_level(previous) != comment_level(line) {
+                    new_section!();
+                }
+            }
+
+            comment.push(line);
+        } else if is_continuation(line) {
+            // Continuation of the previous entry, such as an attribute or variant
+            let section = sections.last_mut().unwrap();
+            if let Some(entry) = section.entries.last_mut() {
+                if blank_lines > 0 {
+                    entry.lines.push(String::new());
+                }
+
+                entry.lines.push(str!(line));
+            }
+
+            blank_lines = 0;
+        } else {
+            // Group and resource comments are never attached to entries
+            if comment.first().map(|line| comment_level(line)).unwrap_or(0) > 1 {
+                new_section!();
+            }
+
+            let (key, first_line) = normalize_entry_line(line);
+            let mut lines: Vec<String> = comment.drain(..).map(String::from).collect();
+            lines.push(first_line);
+
+            let section = sections.last_mut().unwrap();
+            if blank_lines > 0 && !section.entries.is_empty() {
+                section.spaced = true;
+            }
+
+            section.entries.push(Entry { key, lines });
+            blank_lines = 0;
+        }
+    }
+
+    if !comment.is_empty() {
+        new_section!();
+    }
+
+    // Sort and write each section
+    let mut output = String::new();
+    let mut first_section = true;
+
+    for mut section in sections {
+        if section.header.is_empty() && section.entries.is_empty() {
+            continue;
+        }
+
+        if !first_section {
+            output.push('\n');
+        }
+        first_section = false;
+
+        for line in &section.header {
+            output.push_str(line);
+            output.push('\n');
+        }
+
+        if !section.header.is_empty() && !section.entries.is_empty() {
+            output.push('\n');
+        }
+
+        section.entries.sort_by(|a, b| a.key.cmp(b.key));
+
+        for (i, entry) in section.entries.iter().enumerate() {
+            if section.spaced && i > 0 {
+                output.push('\n');
+            }
+
+            for line in &entry.lines {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+    }
+
+    output
+}
+
+#[test]
+fn fix() {
+    use std::fs;
+
+    let unsorted = fs::read_to_string("test/fix/unsorted.ftl").unwrap();
+    let sorted = fs::read_to_string("test/fix/sorted.ftl").unwrap();
+
+    assert_eq!(fix_source(&unsorted), sorted, "Fixed output doesn't match");
+    assert_eq!(
+        fix_source(&sorted),
+        sorted,
+        "Fixing again changed the output"
+    );
+}
+
+#[test]
+fn fix_select() {
+    use std::fs;
+
+    let unsorted = fs::read_to_string("test/fix/select-unsorted.ftl").unwrap();
+    let sorted = fs::read_to_string("test/fix/select-sorted.ftl").unwrap();
+
+    assert_eq!(fix_source(&unsorted), sorted, "Fixed output doesn't match");
+    assert_eq!(
+        fix_source(&sorted),
+        sorted,
+        "Fixing again changed the output"
+    );
+}
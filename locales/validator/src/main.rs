@@ -25,8 +25,14 @@ extern crate str_macro;
 extern crate unic_langid;
 
 mod check;
+mod fix;
 mod messages;
+mod options;
+
+use self::options::Options;
+use std::env;
 
 fn main() {
-    check::run("../fluent");
+    let options = Options::from_args(env::args().skip(1));
+    check::run("../fluent", &options);
 }
@@ -18,6 +18,7 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::options::Options;
 use fluent_syntax::ast;
 use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
@@ -43,7 +44,7 @@ const USED_FLUENT_FUNCTIONS: [&str; 1] = ["NUMBER"];
 #[derive(Debug, Default, Clone)]
 pub struct Catalog {
     locales: HashMap<LanguageIdentifier, Messages>,
-    terms: HashSet<String>,
+    terms: HashMap<LanguageIdentifier, HashSet<String>>,
 }
 
 impl Catalog {
@@ -64,15 +65,16 @@ impl Catalog {
         }
     }
 
-    pub fn add_term(&mut self, term: &ast::Term<&str>) {
+    pub fn add_term(&mut self, locale: LanguageIdentifier, term: &ast::Term<&str>) {
         let base_key = term.id.name;
+        let terms = self.terms.entry(locale).or_default();
 
         // There is always a value, so no if let.
-        self.terms.insert(str!(base_key));
+        terms.insert(str!(base_key));
 
         for ast::Attribute { id, .. } in &term.attributes {
             let key = format!("{}.{}", base_key, id.name);
-            self.terms.insert(key);
+            terms.insert(key);
         }
     }
 
@@ -87,13 +89,15 @@ impl Catalog {
         println!();
         println!("Found terms:");
 
-        for term in &self.terms {
-            println!("* {}", term);
+        for (locale, terms) in &self.terms {
+            for term in terms {
+                println!("* {} ({})", term, locale);
+            }
         }
     }
 
     #[must_use]
-    pub fn check(&self) -> bool {
+    pub fn check(&self, options: &Options) -> bool {
         let mut success = true;
 
         macro_rules! fail {
@@ -105,6 +109,18 @@ impl Catalog {
             }};
         }
 
+        // Only fails in strict mode
+        macro_rules! warn {
+            ($($arg:tt)*) => {{
+                if options.strict {
+                    fail!($($arg)*);
+                } else {
+                    eprint!("?? ");
+                    eprintln!($($arg)*);
+                }
+            }};
+        }
+
         println!();
         println!(
             "Running checks, comparing to primary locale {}...",
@@ -118,13 +134,30 @@ impl Catalog {
             }
         };
 
+        if let Some(used_keys) = &options.used_keys {
+            println!("+ Checking for unused message keys");
+
+            for key in primary.keys() {
+                let base_key = key.split('.').next().unwrap_or(key);
+
+                if !used_keys.contains(key) && !used_keys.contains(base_key) {
+                    warn!("Message key is never used: {}", key);
+                }
+            }
+        }
+
         if self.locales.len() == 1 {
             println!("+ (no locales to check)");
         }
 
+        let no_terms = HashSet::new();
+
         for (locale, messages) in &self.locales {
             println!("+ Checking locale {}", locale);
 
+            // Terms are not shared between locales, so each must define its own
+            let terms = self.terms.get(locale).unwrap_or(&no_terms);
+
             for (key, usages) in messages.iter() {
                 // Ensure all paths match ones in the primary
                 let primary_usages = match primary.get(key) {
@@ -146,8 +179,8 @@ impl Catalog {
                 }
 
                 for term in &usages.terms {
-                    if !self.terms.contains(term) {
-                        fail!("Nonexistent term referenced: {}", term);
+                    if !terms.contains(term) {
+                        fail!("Nonexistent term referenced in {}: -{}", key, term);
                     }
                 }
 
@@ -156,6 +189,16 @@ impl Catalog {
                         fail!("Variable reference not found in parent: {}", variable);
                     }
                 }
+
+                // Check surrounding whitespace, which can break layouts
+
+                if usages.leading_whitespace != primary_usages.leading_whitespace {
+                    warn!("Leading whitespace differs from parent: {}", key);
+                }
+
+                if usages.trailing_whitespace != primary_usages.trailing_whitespace {
+                    warn!("Trailing whitespace differs from parent: {}", key);
+                }
             }
         }
 
@@ -195,12 +238,24 @@ pub struct MessageUsages {
     messages: Vec<String>,
     terms: Vec<String>,
     variables: Vec<String>,
+    leading_whitespace: bool,
+    trailing_whitespace: bool,
 }
 
 impl MessageUsages {
     pub fn from_elements(elements: &[ast::PatternElement<&str>]) -> Self {
         let mut usages = Self::default();
         usages.add_elements(elements);
+        usages.leading_whitespace = elements
+            .first()
+            .and_then(element_text)
+            .map(|text| text.starts_with(char::is_whitespace))
+            .unwrap_or(false);
+        usages.trailing_whitespace = elements
+            .last()
+            .and_then(element_text)
+            .map(|text| text.ends_with(char::is_whitespace))
+            .unwrap_or(false);
         usages
     }
 
@@ -242,9 +297,30 @@ impl MessageUsages {
             StringLiteral { .. } | NumberLiteral { .. } => (),
             FunctionReference { id, .. } => self.functions.push(str!(id.name)),
             MessageReference { id, .. } => self.messages.push(str!(id.name)),
-            TermReference { id, .. } => self.terms.push(str!(id.name)),
+            TermReference { id, attribute, .. } => {
+                let term = match attribute {
+                    Some(attribute) => format!("{}.{}", id.name, attribute.name),
+                    None => str!(id.name),
+                };
+
+                self.terms.push(term);
+            }
             VariableReference { id, .. } => self.variables.push(str!(id.name)),
             Placeable { expression } => self.add_expression(expression),
         }
     }
 }
+
+/// Gets the literal text of a pattern element, if it has any.
+///
+/// Fluent strips plain spaces around a pattern, but other whitespace
+/// is kept, as are spaces in string literals like `{ " " }`.
+fn element_text<'a>(element: &ast::PatternElement<&'a str>) -> Option<&'a str> {
+    match element {
+        ast::PatternElement::TextElement { value } => Some(*value),
+        ast::PatternElement::Placeable {
+            expression: ast::Expression::Inline(ast::InlineExpression::StringLiteral { value }),
+        } => Some(*value),
+        _ => None,
+    }
+}
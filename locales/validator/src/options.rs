@@ -0,0 +1,87 @@
+/*
+ * options.rs
+ *
+ * wikijump-locales-validator - Validate Wikijump's Fluent localization files
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Settings for how the validator runs, taken from the command line.
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// Whether to fail on issues which are otherwise only warnings.
+    ///
+    /// Enabled with `--strict`.
+    pub strict: bool,
+
+    /// The message keys actually used by the application, if known.
+    ///
+    /// If set, keys in the localization files which are not in this
+    /// list are reported as unused. Attribute keys (e.g. `message.title`)
+    /// are also considered used if their message is listed.
+    ///
+    /// Set with `--used-keys <manifest>`, where the manifest has one key per line.
+    pub used_keys: Option<HashSet<String>>,
+
+    /// Whether to rewrite Fluent files with entries sorted and spacing normalized.
+    ///
+    /// Files which have parse errors are left as-is.
+    /// Enabled with `--fix`.
+    pub fix: bool,
+}
+
+impl Options {
+    pub fn from_args<I>(args: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--strict" => options.strict = true,
+                "--fix" => options.fix = true,
+                "--used-keys" => {
+                    let path = args.next().expect("No manifest path for --used-keys");
+                    options.used_keys = Some(read_used_keys(path));
+                }
+                _ => panic!("Unknown argument: {}", arg),
+            }
+        }
+
+        options
+    }
+}
+
+/// Reads a manifest of used message keys, one per line.
+///
+/// Blank lines and lines starting with `#` are ignored.
+pub fn read_used_keys<P: AsRef<Path>>(path: P) -> HashSet<String> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)
+        .unwrap_or_else(|error| panic!("Unable to read {}: {}", path.display(), error));
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
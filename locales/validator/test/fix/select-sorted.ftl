@@ -0,0 +1,19 @@
+## Counts
+
+files-uploaded =
+    { $count ->
+        [one] One file uploaded
+       *[other] { $count } files uploaded
+    }
+items-selected = { $count ->
+    [one] One item selected
+   *[other] { $count } items selected
+}
+    .title = { $count ->
+        [one] Item
+       *[other] Items
+    }
+pages-count = { $count ->
+[one] One page
+*[other] { $count } pages
+}
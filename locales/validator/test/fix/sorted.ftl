@@ -0,0 +1,29 @@
+### Strings for the example component.
+
+## Terms
+
+-service-name = Wikijump
+
+## Buttons
+
+cancel-button = Cancel
+# Shown on the login page.
+login-button = Log in
+    .title = Log in to { -service-name }
+save-button = Save
+
+## Errors
+
+error-details =
+    Something went wrong.
+
+    Please try again later.
+
+forbidden = You cannot view this page
+
+not-found = Page not found
+
+# Standalone comments begin a new group of messages.
+
+apple = Apple
+zebra = Zebra
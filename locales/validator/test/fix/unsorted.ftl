@@ -0,0 +1,30 @@
+### Strings for the example component.
+
+## Terms
+
+-service-name = Wikijump
+
+## Buttons
+
+save-button=Save
+# Shown on the login page.
+login-button = Log in
+    .title = Log in to { -service-name }
+cancel-button = Cancel   
+
+## Errors
+
+not-found = Page not found
+
+
+forbidden = You cannot view this page
+    
+error-details =
+    Something went wrong.
+
+    Please try again later.
+
+# Standalone comments begin a new group of messages.
+
+zebra = Zebra
+apple = Apple
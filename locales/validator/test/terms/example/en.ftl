@@ -0,0 +1,3 @@
+-brand = Wikijump
+
+welcome = Welcome to { -brand }!
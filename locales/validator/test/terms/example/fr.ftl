@@ -0,0 +1,3 @@
+-marque = Wikijump
+
+welcome = Bienvenue sur { -brand } !
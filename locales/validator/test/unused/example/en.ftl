@@ -0,0 +1,4 @@
+greeting = Hello!
+login-button = Log in
+    .title = Log in to your account
+old-banner = This site is moving!
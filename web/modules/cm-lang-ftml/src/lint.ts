@@ -34,6 +34,7 @@ const warningConfig: Record<string, "info" | "warning" | "error" | null> = {
   "no-such-block": "error",
   "block-disallows-star": "warning",
   "block-disallows-score": "warning",
+  "block-disabled": "warning",
   "block-missing-name": "error",
   "block-missing-close-brackets": "error",
   "block-malformed-arguments": "error",